@@ -1,9 +1,11 @@
-use std::{collections::{BTreeMap, HashMap}, sync::Arc};
+use std::{collections::BTreeMap, sync::Arc};
 use my_web_app::CountFileMetaColumnData;
+use serde::{Deserialize, Serialize};
 
 use std::fmt;
 
 use crate::component_reduction_main::ReductionViewData;
+use crate::metadata_cache::{EvictionPolicy, MetadataCache, DEFAULT_METADATA_CACHE_CAPACITY};
 
 //TODO: Possibility of a struct, mapping int <-> cell. can share this
 
@@ -13,7 +15,7 @@ use crate::component_reduction_main::ReductionViewData;
 pub struct BiscviData {
 
     pub reductions: BTreeMap<String, AsyncData<ReductionViewData>>,  //converted from ReductionResponse
-    pub metadatas: HashMap<PerCellDataSource, AsyncData<CountFileMetaColumnData>>,
+    pub metadatas: MetadataCache,
 
 }
 impl BiscviData {
@@ -24,7 +26,7 @@ impl BiscviData {
     pub fn new() -> BiscviData {
         BiscviData {
             reductions: BTreeMap::new(),
-            metadatas: HashMap::new(),
+            metadatas: MetadataCache::new(DEFAULT_METADATA_CACHE_CAPACITY, EvictionPolicy::LeastRecentlyUsed),
         }
     }
 
@@ -56,10 +58,19 @@ impl BiscviData {
 
 ////////////////////////////////////////////////////////////
 /// List of data for each cell, e.g. metadata or feature counts
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum PerCellDataSource {
     Metadata(String),       // metadata column
     Counts(String, String), // count table name, feature name
+    ClusterProportionScore {    // fraction of the k nearest neighbors of each cell in a category; computed locally
+        base_column: String,
+        category_index: usize,
+        k: usize,
+    },
+    ExpressionRatio {   // log((A+1)/(B+1)) of two features of the default count table; computed locally
+        gene_a: String,
+        gene_b: String,
+    },
 }
 
 impl std::fmt::Display for PerCellDataSource {
@@ -74,6 +85,12 @@ impl std::fmt::Display for PerCellDataSource {
             PerCellDataSource::Counts(x,y) => {
                 write!(f, "Counts({},{})", x,y)
             },
+            PerCellDataSource::ClusterProportionScore { base_column, category_index, k } => {
+                write!(f, "ClusterProportionScore({},{},{})", base_column, category_index, k)
+            },
+            PerCellDataSource::ExpressionRatio { gene_a, gene_b } => {
+                write!(f, "ExpressionRatio({},{})", gene_a, gene_b)
+            },
         }
     }
 
@@ -82,14 +99,15 @@ impl std::fmt::Display for PerCellDataSource {
 
 
 ////////////////////////////////////////////////////////////
-/// Data that is not loaded, loading, or loaded. Designed for yew;
+/// Data that is not loaded, loading, loaded, or failed to load. Designed for yew;
 /// this means that data is considered equal iff it is stored
 /// in the same position in memory
 #[derive(Debug)]
 pub enum AsyncData<T> {
     NotLoaded,
     Loading,
-    Loaded(Arc<T>)
+    Loaded(Arc<T>),
+    Error(String),  // gave up loading; the reason
 }
 impl<T> AsyncData<T> {
 
@@ -118,6 +136,9 @@ impl<T> Clone for AsyncData<T> {
             AsyncData::Loading => {
                 AsyncData::Loading
             },
+            AsyncData::Error(e) => {
+                AsyncData::Error(e.clone())
+            },
         }        
     }
 }
@@ -150,6 +171,12 @@ impl<T> PartialEq for AsyncData<T> {
                     _ => false
                 }
             },
+            AsyncData::Error(this) => {
+                match other {
+                    AsyncData::Error(other) => this == other,
+                    _ => false
+                }
+            },
         }
     }
 
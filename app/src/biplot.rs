@@ -0,0 +1,29 @@
+////////////////////////////////////////////////////////////
+/// Number of loadings drawn as arrows, those of the largest magnitude
+pub const BIPLOT_NUM_ARROWS: usize = 10;
+
+
+////////////////////////////////////////////////////////////
+/// Read PCA loadings from a CSV with rows "gene,pc1,pc2". Rows whose weights are not
+/// numbers, such as a header, are skipped
+pub fn parse_loadings_csv(csv: &str) -> Vec<(String,f32,f32)> {
+    csv.lines().filter_map(|line| {
+        let mut fields = line.split(',').map(|f| f.trim().trim_matches('"'));
+        let gene = fields.next()?;
+        let pc1: f32 = fields.next()?.parse().ok()?;
+        let pc2: f32 = fields.next()?.parse().ok()?;
+        if gene.is_empty() || !pc1.is_finite() || !pc2.is_finite() {
+            return None;
+        }
+        Some((gene.to_string(), pc1, pc2))
+    }).collect()
+}
+
+
+////////////////////////////////////////////////////////////
+/// Keep the BIPLOT_NUM_ARROWS loadings of the largest magnitude, the largest first
+pub fn top_loadings(mut loadings: Vec<(String,f32,f32)>) -> Vec<(String,f32,f32)> {
+    loadings.sort_by(|(_, a1, a2), (_, b1, b2)| (b1*b1 + b2*b2).total_cmp(&(a1*a1 + a2*a2)));
+    loadings.truncate(BIPLOT_NUM_ARROWS);
+    loadings
+}
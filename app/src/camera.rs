@@ -1,14 +1,47 @@
+use serde::{Deserialize, Serialize};
+
 use crate::component_reduction_main::ReductionViewData;
 
 
+////////////////////////////////////////////////////////////
+/// Zoom, relative to when all points fit in view, at which the details of each level are shown
+const ZOOM_LEVEL_CLUSTER: f32 = 2.0;
+const ZOOM_LEVEL_CELL: f32 = 8.0;
+
+
+////////////////////////////////////////////////////////////
+/// How close the camera is, deciding what details are shown. When all points are in view, labels
+/// of clusters are shown; when close enough to tell cells apart, their IDs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ZoomLevel {
+    Overview,
+    Cluster,
+    Cell,
+}
+impl ZoomLevel {
+
+    ////////////////////////////////////////////////////////////
+    /// Factor the size of points is scaled by
+    pub fn point_size_factor(&self) -> f32 {
+        match self {
+            ZoomLevel::Overview => 0.75,
+            ZoomLevel::Cluster => 1.0,
+            ZoomLevel::Cell => 1.5,
+        }
+    }
+}
+
+
 ////////////////////////////////////////////////////////////
 /// A camera for 2D scenes
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Camera2D {
     pub x: f32,
     pub y: f32,
     pub zoom_x: f32,
     pub zoom_y: f32,
+    pub aspect_ratio: f32,  // canvas height/width
+    pub fit_zoom_x: f32,    // zoom when all points were last fitted in view
 }
 impl Camera2D {
 
@@ -20,6 +53,19 @@ impl Camera2D {
             y: 0.0,
             zoom_x: 1.0,
             zoom_y: 1.0,
+            aspect_ratio: 1.0,
+            fit_zoom_x: 1.0,
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Set the aspect ratio (height/width) of the canvas. Camera coordinates span [-1,1]
+    /// along both axes, so zoom_y must be scaled for world-space circles to appear circular
+    pub fn set_aspect_ratio(&mut self, aspect_ratio: f32) {
+        if aspect_ratio > 0.0 && aspect_ratio.is_finite() {
+            self.aspect_ratio = aspect_ratio;
+            self.zoom_y = self.zoom_x / self.aspect_ratio;
         }
     }
 
@@ -52,9 +98,55 @@ impl Camera2D {
         let world_dx = umap.max_x - umap.min_x;
         let world_dy = umap.max_y - umap.min_y;
 
+        //Same scale along both axes; the axis that needs the most space decides
         let margin = 0.9;
-        self.zoom_x = margin/(world_dx/2.0);
-        self.zoom_y = margin/(world_dy/2.0);
+        self.zoom_x = (margin/(world_dx/2.0)).min(margin*self.aspect_ratio/(world_dy/2.0));
+        self.zoom_y = self.zoom_x / self.aspect_ratio;
+        if self.zoom_x > 0.0 && self.zoom_x.is_finite() {
+            self.fit_zoom_x = self.zoom_x;
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Center the camera on a rectangle in world coordinates, zoomed so that all of it is visible.
+    /// Unlike fit_reduction, this does not change the zoom level that point sizes are relative to
+    pub fn fit_rect(&mut self, rect: &Rectangle2D) {
+        let (x1, x2) = rect.range_x();
+        let (y1, y2) = rect.range_y();
+        self.x = (x1 + x2)/2.0;
+        self.y = (y1 + y2)/2.0;
+
+        let world_dx = x2 - x1;
+        let world_dy = y2 - y1;
+
+        //Same scale along both axes; the axis that needs the most space decides
+        let zoom_x = (1.0/(world_dx/2.0)).min(self.aspect_ratio/(world_dy/2.0));
+        if zoom_x > 0.0 && zoom_x.is_finite() {
+            self.zoom_x = zoom_x;
+            self.zoom_y = self.zoom_x / self.aspect_ratio;
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// How much the camera is zoomed in compared to when all points were fitted in view
+    pub fn zoom_scale(&self) -> f32 {
+        self.zoom_x / self.fit_zoom_x
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Level of detail to show at the current zoom
+    pub fn zoom_level(&self) -> ZoomLevel {
+        let zoom_scale = self.zoom_scale();
+        if zoom_scale < ZOOM_LEVEL_CLUSTER {
+            ZoomLevel::Overview
+        } else if zoom_scale < ZOOM_LEVEL_CELL {
+            ZoomLevel::Cluster
+        } else {
+            ZoomLevel::Cell
+        }
     }
 
 
@@ -74,7 +166,7 @@ impl Camera2D {
 
         //Apply zoom
         self.zoom_x *= scale;
-        self.zoom_y *= scale;
+        self.zoom_y = self.zoom_x / self.aspect_ratio;
 
         //Correct position
         self.x = wx - (wx-self.x)*zoom1_x/self.zoom_x;
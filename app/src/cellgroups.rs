@@ -0,0 +1,55 @@
+use my_web_app::{CountFileMetaColumnData, ReductionResponse};
+
+use crate::component_reduction_main::{convert_from_response_to_reduction_data, ReductionViewData};
+
+
+////////////////////////////////////////////////////////////
+/// Cells of a reduction grouped by category, with one point per group at the centroid
+#[derive(Debug, Clone)]
+pub struct CellGroups {
+    pub centroids: ReductionViewData,
+    pub num_categories: usize,    // in the column, also those without cells
+    pub categories: Vec<usize>,   // category of each group
+    pub members: Vec<Vec<usize>>, // cells of each group
+}
+
+
+////////////////////////////////////////////////////////////
+/// Group cells by a categorical column. Categories without cells are left out.
+/// None if the column is not categorical
+pub fn group_by_category(reduction: &ReductionViewData, column: &CountFileMetaColumnData) -> Option<CellGroups> {
+    let (codes, category_names) = if let CountFileMetaColumnData::Categorical(codes, category_names) = column {
+        (codes, category_names)
+    } else {
+        return None;
+    };
+
+    let mut members: Vec<Vec<usize>> = vec![Vec::new(); category_names.len()];
+    for (i, c) in codes.iter().take(reduction.num_point).enumerate() {
+        if let Some(m) = members.get_mut(*c as usize) {
+            m.push(i);
+        }
+    }
+
+    let mut categories = Vec::new();
+    let mut list_members = Vec::new();
+    let mut resp = ReductionResponse {
+        x: Vec::new(),
+        y: Vec::new(),
+        version: reduction.version,
+    };
+    for (category, m) in members.into_iter().enumerate().filter(|(_, m)| !m.is_empty()) {
+        let n = m.len() as f32;
+        resp.x.push(m.iter().map(|i| reduction.data[i*2+0]).sum::<f32>() / n);
+        resp.y.push(m.iter().map(|i| reduction.data[i*2+1]).sum::<f32>() / n);
+        categories.push(category);
+        list_members.push(m);
+    }
+
+    Some(CellGroups {
+        centroids: convert_from_response_to_reduction_data(resp).ok()?,
+        num_categories: category_names.len(),
+        categories: categories,
+        members: list_members,
+    })
+}
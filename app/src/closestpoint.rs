@@ -18,7 +18,9 @@ type IndexedPoint = (f32,f32,usize);
 /// distance are not relevant.
 pub struct ClosestPointIndex2D {
     sectors: HashMap<SectorID, Vec<IndexedPoint>>,
-    max_dist: f32
+    sector_range: (SectorID, SectorID),   // smallest and largest sector IDs along each axis
+    max_dist: f32,
+    pub max_distance_threshold: f32,  // points further away than this are not returned as closest. Can be smaller than the buckets
 }
 impl ClosestPointIndex2D {
 
@@ -27,7 +29,9 @@ impl ClosestPointIndex2D {
     pub fn new() -> ClosestPointIndex2D {
         ClosestPointIndex2D {
             sectors: HashMap::new(),
-            max_dist: 1.0 //do not do 0.0 to avoid division by 0
+            sector_range: ((0,0), (0,0)),
+            max_dist: 1.0, //do not do 0.0 to avoid division by 0
+            max_distance_threshold: f32::INFINITY,
         }
     }
 
@@ -58,6 +62,12 @@ impl ClosestPointIndex2D {
             let y: f32 = umap.data[i*2+1];
 
             let sector_id = self.get_sector_id(x,y);
+            let ((min_x, min_y), (max_x, max_y)) = self.sector_range;
+            self.sector_range = if i == 0 {
+                (sector_id, sector_id)
+            } else {
+                ((min_x.min(sector_id.0), min_y.min(sector_id.1)), (max_x.max(sector_id.0), max_y.max(sector_id.1)))
+            };
 
             /*
             possible speedup
@@ -124,7 +134,7 @@ impl ClosestPointIndex2D {
             }
 
             //See if this point is close enough
-            if max < self.max_dist*self.max_dist {  // can remove this extra test
+            if max < self.max_dist*self.max_dist && max <= self.max_distance_threshold*self.max_distance_threshold {
                 Some(return_i)
             } else {
                 None
@@ -135,6 +145,79 @@ impl ClosestPointIndex2D {
         }
     }
 
+
+
+    ////////////////////////////////////////////////////////////
+    /// Find the k points closest to the given point, closest first. Sectors are scanned in
+    /// rings of increasing size until no unscanned point can be closer than the k:th found
+    pub fn get_k_nearest(&self, x:f32, y:f32, k: usize) -> Vec<usize> {
+        if k == 0 || self.sectors.is_empty() {
+            return Vec::new();
+        }
+
+        //No point is further away than the outermost sector
+        let (sector_mid_x,sector_mid_y) = self.get_sector_id(x,y);
+        let ((min_x, min_y), (max_x, max_y)) = self.sector_range;
+        let max_ring = (sector_mid_x - min_x).abs()
+            .max((max_x - sector_mid_x).abs())
+            .max((sector_mid_y - min_y).abs())
+            .max((max_y - sector_mid_y).abs());
+
+        let mut list_cand: Vec<(f32, usize)> = Vec::new();
+        for ring in 0..=max_ring {
+            for sector_x in (sector_mid_x-ring)..(sector_mid_x+ring+1) {
+                for sector_y in (sector_mid_y-ring)..(sector_mid_y+ring+1) {
+                    //Only the border of the ring; the inside was scanned before
+                    if (sector_x - sector_mid_x).abs() != ring && (sector_y - sector_mid_y).abs() != ring {
+                        continue;
+                    }
+                    if let Some(sector) = self.sectors.get(&(sector_x, sector_y)) {
+                        for (px,py,i) in sector.iter() {
+                            list_cand.push((dist2(x,y,  *px,*py), *i));
+                        }
+                    }
+                }
+            }
+
+            //Points in sectors outside this ring are at least this far away
+            if list_cand.len() >= k {
+                list_cand.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+                list_cand.truncate(k);
+                let min_dist_outside = (ring as f32)*self.max_dist;
+                if list_cand[k-1].0 <= min_dist_outside*min_dist_outside {
+                    break;
+                }
+            }
+        }
+
+        list_cand.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+        list_cand.into_iter().take(k).map(|(_d, i)| i).collect()
+    }
+
+
+
+    ////////////////////////////////////////////////////////////
+    /// Find all points within the max distance of the given point
+    pub fn get_points_within(&self, x:f32, y:f32) -> Vec<usize> {
+        let max_dist2 = self.max_dist*self.max_dist;
+
+        //Scan all sectors around the point
+        let (sector_mid_x,sector_mid_y) = self.get_sector_id(x,y);
+        let mut list_points = Vec::new();
+        for sector_x in (sector_mid_x-1)..(sector_mid_x+2) {
+            for sector_y in (sector_mid_y-1)..(sector_mid_y+2) {
+                if let Some(sector) = self.sectors.get(&(sector_x, sector_y)) {
+                    for (px,py,i) in sector.iter() {
+                        if dist2(x,y,  *px,*py) < max_dist2 {
+                            list_points.push(*i);
+                        }
+                    }
+                }
+            }
+        }
+        list_points
+    }
+
 }
 
 
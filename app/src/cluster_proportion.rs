@@ -0,0 +1,42 @@
+use my_web_app::CountFileMetaColumnData;
+
+use crate::closestpoint::ClosestPointIndex2D;
+use crate::component_reduction_main::ReductionViewData;
+
+
+////////////////////////////////////////////////////////////
+/// Number of neighbors used when coloring by the proportion of neighbors in a category
+pub const DEFAULT_PROPORTION_K: usize = 15;
+
+
+////////////////////////////////////////////////////////////
+/// For each cell, the fraction of its k nearest neighbors in the reduction that belong to
+/// the given category. The cell itself is not counted. None if the column is not categorical
+pub fn compute_cluster_proportion(reduction: &ReductionViewData, base_data: &CountFileMetaColumnData, category_index: usize, k: usize) -> Option<Vec<f32>> {
+    let codes = if let CountFileMetaColumnData::Categorical(codes, _) = base_data {
+        codes
+    } else {
+        return None;
+    };
+
+    //Buckets of about 1% of the reduction size, as for finding hovered cells
+    let span = (reduction.max_x - reduction.min_x).max(reduction.max_y - reduction.min_y);
+    let mut index = ClosestPointIndex2D::new();
+    index.build_point_index(reduction, if span > 0.0 { span*0.01 } else { 1.0 });
+
+    let num_point = reduction.num_point.min(codes.len());
+    let values = (0..num_point).map(|i| {
+        let neighbors: Vec<usize> = index.get_k_nearest(reduction.data[i*2+0], reduction.data[i*2+1], k + 1)
+            .into_iter()
+            .filter(|j| *j != i)
+            .take(k)
+            .collect();
+        if neighbors.is_empty() {
+            0.0
+        } else {
+            let num_in_category = neighbors.iter().filter(|j| codes.get(**j).map(|c| *c as usize) == Some(category_index)).count();
+            num_in_category as f32 / neighbors.len() as f32
+        }
+    }).collect();
+    Some(values)
+}
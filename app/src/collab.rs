@@ -0,0 +1,210 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use js_sys::Promise;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::window;
+use web_sys::{MessageEvent, RtcDataChannel, RtcDataChannelEvent, RtcDataChannelState, RtcIceGatheringState, RtcPeerConnection, RtcSdpType, RtcSessionDescriptionInit};
+use yew::Callback;
+
+
+////////////////////////////////////////////////////////////
+/// Label of the data channel shared by peers
+const COLLAB_CHANNEL_LABEL: &str = "biscvi-collab";
+
+////////////////////////////////////////////////////////////
+/// How long to wait for ICE candidates to be gathered before giving up, in milliseconds.
+/// There is no signaling server, so all candidates must be in the SDP that is shared
+const ICE_GATHERING_TIMEOUT_MS: i32 = 5000;
+
+////////////////////////////////////////////////////////////
+/// Interval to check if ICE gathering is done, in milliseconds
+const ICE_GATHERING_POLL_MS: i32 = 100;
+
+
+////////////////////////////////////////////////////////////
+/// Event sent between peers of a collaborative session
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum CollabEvent {
+    CameraUpdate {
+        x: f32,
+        y: f32,
+        zoom_x: f32,
+        zoom_y: f32,
+    },
+    SelectionUpdate {
+        cells: Vec<usize>,
+    },
+    CursorUpdate {
+        peer_id: u32,   // random id of the sending peer
+        x: f32,         // world coordinates
+        y: f32,
+    },
+}
+
+
+////////////////////////////////////////////////////////////
+/// Connection to one peer over a WebRTC data channel.
+///
+/// There is no signaling server: the peer starting the session gets an offer SDP, which is
+/// passed to the other peer by the user. The other peer answers with an answer SDP, which is
+/// passed back the same way
+pub struct CollabSession {
+    pub peer: RtcPeerConnection,
+    channel: Rc<RefCell<Option<RtcDataChannel>>>,
+    _on_message: Rc<Closure<dyn Fn(MessageEvent)>>,
+    _on_datachannel: Option<Closure<dyn Fn(RtcDataChannelEvent)>>,
+}
+impl CollabSession {
+
+    ////////////////////////////////////////////////////////////
+    /// Start a session as the offering peer. The data channel is created here
+    pub fn start(on_event: Callback<CollabEvent>) -> Result<CollabSession, JsValue> {
+        let peer = RtcPeerConnection::new()?;
+        let on_message = Rc::new(make_message_handler(on_event));
+
+        let channel = peer.create_data_channel(COLLAB_CHANNEL_LABEL);
+        channel.set_onmessage(Some(on_message.as_ref().as_ref().unchecked_ref()));
+
+        Ok(CollabSession {
+            peer: peer,
+            channel: Rc::new(RefCell::new(Some(channel))),
+            _on_message: on_message,
+            _on_datachannel: None,
+        })
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Join a session as the answering peer. The data channel is received once connected
+    pub fn join(on_event: Callback<CollabEvent>) -> Result<CollabSession, JsValue> {
+        let peer = RtcPeerConnection::new()?;
+        let on_message = Rc::new(make_message_handler(on_event));
+        let channel: Rc<RefCell<Option<RtcDataChannel>>> = Rc::new(RefCell::new(None));
+
+        let channel_for_handler = channel.clone();
+        let on_message_for_handler = on_message.clone();
+        let on_datachannel = Closure::wrap(Box::new(move |e: RtcDataChannelEvent| {
+            let channel = e.channel();
+            channel.set_onmessage(Some(on_message_for_handler.as_ref().as_ref().unchecked_ref()));
+            *channel_for_handler.borrow_mut() = Some(channel);
+        }) as Box<dyn Fn(RtcDataChannelEvent)>);
+        peer.set_ondatachannel(Some(on_datachannel.as_ref().unchecked_ref()));
+
+        Ok(CollabSession {
+            peer: peer,
+            channel: channel,
+            _on_message: on_message,
+            _on_datachannel: Some(on_datachannel),
+        })
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Send an event to the peer. Events are dropped until the channel is open
+    pub fn send(&self, event: &CollabEvent) {
+        if let Some(channel) = self.channel.borrow().as_ref() {
+            if channel.ready_state() == RtcDataChannelState::Open {
+                let msg = serde_json::to_string(event).expect("Could not convert to json");
+                if let Err(e) = channel.send_with_str(&msg) {
+                    log::warn!("Could not send collab event: {:?}", e);
+                }
+            }
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Is the data channel open?
+    pub fn is_connected(&self) -> bool {
+        self.channel.borrow().as_ref().map(|c| c.ready_state() == RtcDataChannelState::Open).unwrap_or(false)
+    }
+
+}
+impl Drop for CollabSession {
+    fn drop(&mut self) {
+        if let Some(channel) = self.channel.borrow().as_ref() {
+            channel.set_onmessage(None);
+        }
+        self.peer.set_ondatachannel(None);
+        self.peer.close();
+    }
+}
+
+
+////////////////////////////////////////////////////////////
+/// Handler for messages on the data channel. Messages that cannot be parsed are ignored
+fn make_message_handler(on_event: Callback<CollabEvent>) -> Closure<dyn Fn(MessageEvent)> {
+    Closure::wrap(Box::new(move |e: MessageEvent| {
+        if let Some(text) = e.data().as_string() {
+            match serde_json::from_str::<CollabEvent>(&text) {
+                Ok(event) => on_event.emit(event),
+                Err(err) => log::warn!("Invalid collab event {}: {}", text, err),
+            }
+        }
+    }) as Box<dyn Fn(MessageEvent)>)
+}
+
+
+////////////////////////////////////////////////////////////
+/// Create the offer SDP, to be given to the peer joining
+pub async fn create_offer(peer: RtcPeerConnection) -> Result<String, JsValue> {
+    let offer = JsFuture::from(peer.create_offer()).await?;
+    let offer_sdp = js_sys::Reflect::get(&offer, &JsValue::from_str("sdp"))?.as_string().unwrap_or_default();
+    set_local_description(&peer, RtcSdpType::Offer, &offer_sdp).await
+}
+
+
+////////////////////////////////////////////////////////////
+/// Create the answer SDP for an offer, to be given back to the peer who started the session
+pub async fn create_answer(peer: RtcPeerConnection, offer_sdp: String) -> Result<String, JsValue> {
+    let offer = RtcSessionDescriptionInit::new(RtcSdpType::Offer);
+    offer.set_sdp(&offer_sdp);
+    JsFuture::from(peer.set_remote_description(&offer)).await?;
+
+    let answer = JsFuture::from(peer.create_answer()).await?;
+    let answer_sdp = js_sys::Reflect::get(&answer, &JsValue::from_str("sdp"))?.as_string().unwrap_or_default();
+    set_local_description(&peer, RtcSdpType::Answer, &answer_sdp).await
+}
+
+
+////////////////////////////////////////////////////////////
+/// Complete the connection using the answer from the joining peer
+pub async fn accept_answer(peer: RtcPeerConnection, answer_sdp: String) -> Result<(), JsValue> {
+    let answer = RtcSessionDescriptionInit::new(RtcSdpType::Answer);
+    answer.set_sdp(&answer_sdp);
+    JsFuture::from(peer.set_remote_description(&answer)).await?;
+    Ok(())
+}
+
+
+////////////////////////////////////////////////////////////
+/// Set the local description, then wait for ICE gathering so that the SDP returned has all candidates
+async fn set_local_description(peer: &RtcPeerConnection, sdp_type: RtcSdpType, sdp: &str) -> Result<String, JsValue> {
+    let desc = RtcSessionDescriptionInit::new(sdp_type);
+    desc.set_sdp(sdp);
+    JsFuture::from(peer.set_local_description(&desc)).await?;
+
+    let mut waited = 0;
+    while peer.ice_gathering_state() != RtcIceGatheringState::Complete && waited < ICE_GATHERING_TIMEOUT_MS {
+        sleep_ms(ICE_GATHERING_POLL_MS).await;
+        waited += ICE_GATHERING_POLL_MS;
+    }
+
+    peer.local_description()
+        .map(|d| d.sdp())
+        .ok_or_else(|| JsValue::from_str("No local description"))
+}
+
+
+////////////////////////////////////////////////////////////
+/// Wait for some time
+async fn sleep_ms(ms: i32) {
+    let promise = Promise::new(&mut |resolve, _reject| {
+        let _ = window().expect("no window").set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, ms);
+    });
+    let _ = JsFuture::from(promise).await;
+}
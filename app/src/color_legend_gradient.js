@@ -9,4 +9,17 @@ gradient.addColorStop(1, "black");
 ctx.fillStyle = gradient;
 ctx.fillRect(5, 0, 15, 180);
 return(ctx);
-}
\ No newline at end of file
+}
+
+export function diverging_gradient(ctx) {
+const gradient = ctx.createLinearGradient(0, 0, 0, 180);
+
+// Highest values on top, the center in the middle
+gradient.addColorStop(0, "red");
+gradient.addColorStop(0.5, "white");
+gradient.addColorStop(1, "blue");
+
+ctx.fillStyle = gradient;
+ctx.fillRect(5, 0, 15, 180);
+return(ctx);
+}
@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+
+use crate::component_reduction_main::Color3f;
+
+
+////////////////////////////////////////////////////////////
+/// Color of cells not listed in an imported color table
+pub const COLOR_UNASSIGNED: Color3f = (0.8, 0.8, 0.8);
+
+
+////////////////////////////////////////////////////////////
+/// Result of importing a color table
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorTable {
+    pub colors: Vec<Color3f>,   // for each cell
+    pub num_matched: usize,     // rows with a known cell ID
+    pub num_skipped: usize,     // rows with an unknown cell ID, or that could not be parsed. Includes any header
+}
+
+
+////////////////////////////////////////////////////////////
+/// Parse a two-column CSV of cell ID (barcode) and hex color, e.g. "AAACCTGAGAAGGCCT-1,#FF0000".
+/// A header line is skipped as it has no valid color
+pub fn parse_color_table(csv: &[u8], cell_ids: &Vec<String>) -> ColorTable {
+    let index_of_cell: HashMap<&str, usize> = cell_ids.iter()
+        .enumerate()
+        .map(|(i, id)| (id.as_str(), i))
+        .collect();
+
+    let mut colors = vec![COLOR_UNASSIGNED; cell_ids.len()];
+    let mut num_matched = 0;
+    let mut num_skipped = 0;
+    for line in String::from_utf8_lossy(csv).lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut fields = line.split(',').map(|f| f.trim().trim_matches('"'));
+        let (Some(cell_id), Some(color)) = (fields.next(), fields.next()) else {
+            num_skipped += 1;
+            continue;
+        };
+        match (index_of_cell.get(cell_id), parse_hex_color(color)) {
+            (Some(i), Some(color)) => {
+                colors[*i] = color;
+                num_matched += 1;
+            },
+            _ => {
+                num_skipped += 1;
+            }
+        }
+    }
+
+    ColorTable {
+        colors: colors,
+        num_matched: num_matched,
+        num_skipped: num_skipped,
+    }
+}
+
+
+////////////////////////////////////////////////////////////
+/// Parse a color on the form #RRGGBB. The # is optional
+pub fn parse_hex_color(s: &str) -> Option<Color3f> {
+    let s = s.strip_prefix('#').unwrap_or(s);
+    if s.len() != 6 || !s.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&s[i..i+2], 16).ok().map(|v| v as f32 / 255.0);
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
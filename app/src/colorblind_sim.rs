@@ -0,0 +1,69 @@
+use crate::component_reduction_main::Color3f;
+
+
+////////////////////////////////////////////////////////////
+/// Simulated color vision, to check how the reduction looks to colorblind users
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorSimulationMode {
+    Normal,
+    DeuteranopiaSim,
+    ProtanopiaSim,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Simulation of protanopia (no red cones), from Machado, Oliveira & Fernandes 2009, severity 1.0
+pub const PROTANOPIA_MATRIX: [[f32; 3]; 3] = [
+    [ 0.152286,  1.052583, -0.204868],
+    [ 0.114503,  0.786281,  0.099216],
+    [-0.003882, -0.048116,  1.051998],
+];
+
+////////////////////////////////////////////////////////////
+/// Simulation of deuteranopia (no green cones), from Machado, Oliveira & Fernandes 2009, severity 1.0
+pub const DEUTERANOPIA_MATRIX: [[f32; 3]; 3] = [
+    [ 0.367322,  0.860646, -0.227968],
+    [ 0.280085,  0.672501,  0.047413],
+    [-0.011820,  0.042940,  0.968881],
+];
+
+
+impl ColorSimulationMode {
+
+    ////////////////////////////////////////////////////////////
+    /// Matrix applied to RGB colors, if any
+    pub fn matrix(&self) -> Option<&'static [[f32; 3]; 3]> {
+        match self {
+            ColorSimulationMode::Normal => None,
+            ColorSimulationMode::DeuteranopiaSim => Some(&DEUTERANOPIA_MATRIX),
+            ColorSimulationMode::ProtanopiaSim => Some(&PROTANOPIA_MATRIX),
+        }
+    }
+
+}
+
+
+////////////////////////////////////////////////////////////
+/// Color as seen with the given color vision
+pub fn simulate_color(mode: ColorSimulationMode, col: Color3f) -> Color3f {
+    let Some(m) = mode.matrix() else {
+        return col;
+    };
+    let row = |r: &[f32; 3]| (r[0]*col.0 + r[1]*col.1 + r[2]*col.2).clamp(0.0, 1.0);
+    (row(&m[0]), row(&m[1]), row(&m[2]))
+}
+
+
+////////////////////////////////////////////////////////////
+/// Replace the color of each vertex (x, y, z, r, g, b, ...) with how it is seen with the given color vision
+pub fn simulate_vertex_colors(vec_vertex: &mut Vec<f32>, vec_vertex_size: usize, mode: ColorSimulationMode) {
+    if mode.matrix().is_none() {
+        return;
+    }
+    for v in vec_vertex.chunks_exact_mut(vec_vertex_size) {
+        let (r, g, b) = simulate_color(mode, (v[3], v[4], v[5]));
+        v[3] = r;
+        v[4] = g;
+        v[5] = b;
+    }
+}
@@ -0,0 +1,102 @@
+////////////////////////////////////////////////////////////
+/// Transform applied to numeric values before they are mapped to colors
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorScaleTransform {
+    Linear,
+    Log1p,
+    Sqrt,
+    Arcsinh(f32), // cofactor
+}
+impl ColorScaleTransform {
+
+    ////////////////////////////////////////////////////////////
+    /// All transforms that can be selected, in the order shown to the user
+    pub fn list_all() -> Vec<ColorScaleTransform> {
+        vec![
+            ColorScaleTransform::Linear,
+            ColorScaleTransform::Log1p,
+            ColorScaleTransform::Sqrt,
+            ColorScaleTransform::Arcsinh(5.0),
+        ]
+    }
+
+    ////////////////////////////////////////////////////////////
+    /// Name of the transform, also used as value in the dropdown
+    pub fn name(&self) -> String {
+        match self {
+            ColorScaleTransform::Linear => "linear".to_string(),
+            ColorScaleTransform::Log1p => "log".to_string(),
+            ColorScaleTransform::Sqrt => "sqrt".to_string(),
+            ColorScaleTransform::Arcsinh(_) => "arcsinh".to_string(),
+        }
+    }
+
+    ////////////////////////////////////////////////////////////
+    /// Get a transform from its name
+    pub fn from_name(name: &str) -> Option<ColorScaleTransform> {
+        ColorScaleTransform::list_all().into_iter().find(|t| t.name() == name)
+    }
+
+    ////////////////////////////////////////////////////////////
+    /// Transform one value. Negative values are handled symmetrically
+    pub fn apply(&self, v: f32) -> f32 {
+        match self {
+            ColorScaleTransform::Linear => v,
+            ColorScaleTransform::Log1p => v.signum() * v.abs().ln_1p(),
+            ColorScaleTransform::Sqrt => v.signum() * v.abs().sqrt(),
+            ColorScaleTransform::Arcsinh(cofactor) => (v / cofactor).asinh(),
+        }
+    }
+
+    ////////////////////////////////////////////////////////////
+    /// Inverse of the transform, to go from color scale back to values
+    pub fn invert(&self, v: f32) -> f32 {
+        match self {
+            ColorScaleTransform::Linear => v,
+            ColorScaleTransform::Log1p => v.signum() * v.abs().exp_m1(),
+            ColorScaleTransform::Sqrt => v.signum() * v * v,
+            ColorScaleTransform::Arcsinh(cofactor) => v.sinh() * cofactor,
+        }
+    }
+
+    ////////////////////////////////////////////////////////////
+    /// Transform the range of values. The transforms are monotonic,
+    /// so the range is given by the transformed end points
+    pub fn apply_range(&self, min: f32, max: f32) -> (f32, f32) {
+        (self.apply(min), self.apply(max))
+    }
+
+    ////////////////////////////////////////////////////////////
+    /// Values for legend ticks, evenly spaced on the color scale
+    pub fn legend_ticks(&self, min: f32, max: f32, num_ticks: usize) -> Vec<f32> {
+        let (tmin, tmax) = self.apply_range(min, max);
+        if num_ticks < 2 {
+            return vec![min];
+        }
+        (0..num_ticks).map(|i| {
+            let t = tmin + (tmax - tmin) * (i as f32) / ((num_ticks - 1) as f32);
+            self.invert(t)
+        }).collect()
+    }
+
+}
+
+
+////////////////////////////////////////////////////////////
+/// Diverging color scale: blue at -1, white at 0, red at 1
+pub fn diverging_color(t: f32) -> (f32,f32,f32) {
+    let t = if t.is_finite() { t.clamp(-1.0, 1.0) } else { 0.0 };
+    if t < 0.0 {
+        (1.0 + t, 1.0 + t, 1.0)
+    } else {
+        (1.0, 1.0 - t, 1.0 - t)
+    }
+}
+
+
+////////////////////////////////////////////////////////////
+/// Range around a center that covers the given range, so that the center is in the middle of the color scale
+pub fn symmetric_range(min: f32, max: f32, center: f32) -> (f32, f32) {
+    let half = (min - center).abs().max((max - center).abs());
+    (center - half, center + half)
+}
@@ -0,0 +1,128 @@
+use std::collections::HashSet;
+
+use my_web_app::CountFileMetaColumnData;
+
+
+////////////////////////////////////////////////////////////
+/// Summary statistics of a metadata column, shown next to the column name
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnSummary {
+    pub n_unique: usize,
+    pub min_val: Option<f32>,
+    pub max_val: Option<f32>,
+    pub n_missing: usize,
+    pub n_total: usize,
+}
+impl ColumnSummary {
+
+
+    ////////////////////////////////////////////////////////////
+    /// Compute the summary for any type of column data. Sparse data has zeros for the cells left out,
+    /// up to num_cells
+    pub fn build(inp: &CountFileMetaColumnData, num_cells: usize) -> ColumnSummary {
+        match inp {
+            ///// Categorial data. Codes outside the list of categories are missing values
+            CountFileMetaColumnData::Categorical(list_data, list_cats) => {
+                let mut seen: HashSet<u32> = HashSet::new();
+                let mut n_missing = 0;
+                for v in list_data.iter() {
+                    if (*v as usize) < list_cats.len() {
+                        seen.insert(*v);
+                    } else {
+                        n_missing += 1;
+                    }
+                }
+
+                ColumnSummary {
+                    n_unique: seen.len(),
+                    min_val: None,
+                    max_val: None,
+                    n_missing: n_missing,
+                    n_total: list_data.len(),
+                }
+            },
+
+            ///// Dense numeric array of data
+            CountFileMetaColumnData::Numeric(list_data) => {
+                summarize_continuous_data(list_data)
+            },
+
+            ///// Sparse numeric array or data
+            CountFileMetaColumnData::SparseNumeric(_list_indices, list_data) => {
+                let mut summary = summarize_continuous_data(list_data);
+                let n_zeros = num_cells.saturating_sub(list_data.len());
+                if n_zeros > 0 {
+                    if !list_data.iter().any(|v| *v == 0.0) {
+                        summary.n_unique += 1;
+                    }
+                    summary.min_val = Some(summary.min_val.map_or(0.0, |m| m.min(0.0)));
+                    summary.max_val = Some(summary.max_val.map_or(0.0, |m| m.max(0.0)));
+                    summary.n_total += n_zeros;
+                }
+                summary
+            },
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Fraction of missing values, in percent
+    pub fn percent_missing(&self) -> f32 {
+        if self.n_total == 0 {
+            0.0
+        } else {
+            100.0 * (self.n_missing as f32) / (self.n_total as f32)
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Short text for the badge next to the column name
+    pub fn badge_text(&self) -> String {
+        format!("{}", self.n_unique)
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Longer text, for the tooltip
+    pub fn tooltip_text(&self) -> String {
+        let mut s = format!("{} unique values", self.n_unique);
+        if let (Some(min_val), Some(max_val)) = (self.min_val, self.max_val) {
+            s.push_str(&format!("\nRange: {} ... {}", min_val, max_val));
+        }
+        s.push_str(&format!("\nMissing: {:.1}%", self.percent_missing()));
+        s
+    }
+
+}
+
+
+
+////////////////////////////////////////////////////////////
+/// Summarize continuous data. NaN counts as a missing value
+fn summarize_continuous_data(list_data: &Vec<f32>) -> ColumnSummary {
+    let mut seen: HashSet<u32> = HashSet::new();
+    let mut n_missing = 0;
+    let mut min_val: Option<f32> = None;
+    let mut max_val: Option<f32> = None;
+
+    for v in list_data.iter() {
+        if v.is_nan() {
+            n_missing += 1;
+        } else {
+            //Compare bit patterns; +0 and -0 would otherwise be counted twice
+            let v = if *v == 0.0 { 0.0 } else { *v };
+            seen.insert(v.to_bits());
+            min_val = Some(min_val.map_or(v, |m| m.min(v)));
+            max_val = Some(max_val.map_or(v, |m| m.max(v)));
+        }
+    }
+
+    ColumnSummary {
+        n_unique: seen.len(),
+        min_val,
+        max_val,
+        n_missing,
+        n_total: list_data.len(),
+    }
+}
@@ -0,0 +1,322 @@
+use std::sync::Arc;
+
+use my_web_app::CountFileMetaColumnData;
+use web_sys::{HtmlCanvasElement, HtmlInputElement};
+use yew::{html, Callback, Component, Context, Html, InputEvent, MouseEvent, NodeRef, TargetCast, WheelEvent};
+use yew::Properties;
+
+use crate::appstate::AsyncData;
+use crate::camera::Camera2D;
+use crate::component_cross_projection::draw_points_by_category;
+use crate::component_reduction_main::{mouseevent_get_cx, ReductionViewData};
+
+
+////////////////////////////////////////////////////////////
+/// Size of each of the two views, in pixels
+const SPLIT_CANVAS_SIZE: u32 = 300;
+
+
+////////////////////////////////////////////////////////////
+/// Scale a reduction to fit [-1,1] along both axes, keeping its aspect ratio, so that two
+/// layouts in different units can be shown with the same camera and morphed into each other
+pub fn normalize_reduction(reduction: &ReductionViewData) -> ReductionViewData {
+    let (cx, cy) = ((reduction.min_x + reduction.max_x)/2.0, (reduction.min_y + reduction.max_y)/2.0);
+    let half_span = ((reduction.max_x - reduction.min_x).max(reduction.max_y - reduction.min_y)/2.0).max(f32::EPSILON);
+    let data: Vec<f32> = reduction.data.chunks(2).flat_map(|p| [(p[0] - cx)/half_span, (p[1] - cy)/half_span]).collect();
+    ReductionViewData {
+        num_point: reduction.num_point,
+        data: data,
+        max_x: (reduction.max_x - cx)/half_span,
+        max_y: (reduction.max_y - cy)/half_span,
+        min_x: (reduction.min_x - cx)/half_span,
+        min_y: (reduction.min_y - cy)/half_span,
+        version: reduction.version,
+    }
+}
+
+
+////////////////////////////////////////////////////////////
+/// Positions a fraction t of the way from one layout to another, pairing cells by index.
+/// Cells beyond the end of the shorter layout are left out
+pub fn morph_reductions(from: &ReductionViewData, to: &ReductionViewData, t: f32) -> ReductionViewData {
+    let num_point = from.num_point.min(to.num_point);
+    let data: Vec<f32> = from.data[..num_point*2].iter().zip(to.data[..num_point*2].iter())
+        .map(|(a, b)| a + (b - a)*t)
+        .collect();
+    ReductionViewData {
+        num_point: num_point,
+        data: data,
+        max_x: from.max_x.max(to.max_x),
+        max_y: from.max_y.max(to.max_y),
+        min_x: from.min_x.min(to.min_x),
+        min_y: from.min_y.min(to.min_y),
+        version: 0,
+    }
+}
+
+
+////////////////////////////////////////////////////////////
+/// Properties for SplitReductionView
+#[derive(Properties, PartialEq)]
+pub struct SplitProps {
+    pub title: String,
+    pub reduction: AsyncData<ReductionViewData>,
+    pub categories: AsyncData<CountFileMetaColumnData>,   // points are colored by category, if categorical
+    pub camera: Camera2D,
+    pub on_wheel: Callback<(f32, f32, f32)>,   // dy, camera position
+}
+
+
+////////////////////////////////////////////////////////////
+/// One of the views of BatchCorrectionView. The camera is owned by the parent, so that
+/// zooming in one view zooms both
+pub struct SplitReductionView {
+    node_ref: NodeRef,
+}
+
+impl Component for SplitReductionView {
+    type Message = ();
+    type Properties = SplitProps;
+
+    ////////////////////////////////////////////////////////////
+    /// Create this component
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            node_ref: NodeRef::default(),
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Render the view
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let on_wheel = ctx.props().on_wheel.clone();
+        let cb_mousewheel = Callback::from(move |e: WheelEvent| {
+            e.prevent_default();
+            let (cx, cy) = mouseevent_get_cx(&e);
+            on_wheel.emit((e.delta_y() as f32, cx, cy));
+        });
+
+        html! {
+            <div>
+                <div class="bisci-label-left">{ ctx.props().title.clone() }</div>
+                <canvas ref={self.node_ref.clone()} onwheel={cb_mousewheel}
+                    width={SPLIT_CANVAS_SIZE.to_string()}
+                    height={SPLIT_CANVAS_SIZE.to_string()}
+                    style={format!("width: {}px; height: {}px; border: 1px solid black;", SPLIT_CANVAS_SIZE, SPLIT_CANVAS_SIZE)}
+                />
+            </div>
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Called after DOM has been generated
+    fn rendered(&mut self, ctx: &Context<Self>, _first_render: bool) {
+        let props = ctx.props();
+        if let (AsyncData::Loaded(reduction), Some(canvas)) = (&props.reduction, self.node_ref.cast::<HtmlCanvasElement>()) {
+            let categories = match &props.categories {
+                AsyncData::Loaded(data) => match data.as_ref() {
+                    CountFileMetaColumnData::Categorical(cats, names) => Some((cats, names.len())),
+                    _ => None
+                },
+                _ => None
+            };
+            draw_points_by_category(&canvas, reduction, categories, &props.camera);
+        }
+    }
+
+}
+
+
+////////////////////////////////////////////////////////////
+/// Message sent to the event system for updating the page
+#[derive(Debug)]
+pub enum MsgBatchCorrection {
+    MouseWheel(f32, f32, f32),   // dy, camera position
+    SetMorph(f32),
+}
+
+
+////////////////////////////////////////////////////////////
+/// Properties for BatchCorrectionView
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub name_original: String,
+    pub name_corrected: String,
+    pub original_reduction: AsyncData<ReductionViewData>,
+    pub corrected_reduction: AsyncData<ReductionViewData>,
+    pub batches: AsyncData<CountFileMetaColumnData>,   // points are colored by batch, if categorical
+    pub on_close: Callback<()>,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Cells before and after batch correction, side by side with the same camera. The slider
+/// morphs the cells of the left view from the original to the corrected layout, to follow
+/// where each batch ends up
+pub struct BatchCorrectionView {
+    camera: Camera2D,
+    morph: f32,   // 0 for the original layout, 1 for the corrected layout
+    last_reductions: (AsyncData<ReductionViewData>, AsyncData<ReductionViewData>),
+    normalized: Option<(Arc<ReductionViewData>, Arc<ReductionViewData>)>,   // original, corrected
+    morphed: AsyncData<ReductionViewData>,
+}
+impl BatchCorrectionView {
+
+    ////////////////////////////////////////////////////////////
+    /// Normalize the layouts again if they changed
+    fn update_layouts(&mut self, ctx: &Context<Self>) {
+        let reductions = (ctx.props().original_reduction.clone(), ctx.props().corrected_reduction.clone());
+        if reductions == self.last_reductions {
+            return;
+        }
+        self.normalized = match &reductions {
+            (AsyncData::Loaded(original), AsyncData::Loaded(corrected)) => Some((
+                Arc::new(normalize_reduction(original)),
+                Arc::new(normalize_reduction(corrected)),
+            )),
+            _ => None
+        };
+        self.last_reductions = reductions;
+        self.update_morph();
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Compute the layout shown in the left view, for the current position of the slider
+    fn update_morph(&mut self) {
+        self.morphed = match &self.normalized {
+            Some((original, _)) if self.morph <= 0.0 => AsyncData::Loaded(original.clone()),
+            Some((original, corrected)) => AsyncData::new(morph_reductions(original, corrected, self.morph)),
+            None => AsyncData::NotLoaded
+        };
+    }
+
+}
+
+impl Component for BatchCorrectionView {
+    type Message = MsgBatchCorrection;
+    type Properties = Props;
+
+    ////////////////////////////////////////////////////////////
+    /// Create this component
+    fn create(ctx: &Context<Self>) -> Self {
+        //Layouts are normalized to [-1,1]; leave a margin around them
+        let mut camera = Camera2D::new();
+        camera.set_aspect_ratio(1.0);
+        camera.zoom_x = 0.9;
+        camera.zoom_y = 0.9;
+        camera.fit_zoom_x = 0.9;
+
+        let mut view = Self {
+            camera: camera,
+            morph: 0.0,
+            last_reductions: (AsyncData::NotLoaded, AsyncData::NotLoaded),
+            normalized: None,
+            morphed: AsyncData::NotLoaded,
+        };
+        view.update_layouts(ctx);
+        view
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Properties have changed
+    fn changed(&mut self, ctx: &Context<Self>, _old_props: &Self::Properties) -> bool {
+        self.update_layouts(ctx);
+        true
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Handle an update message
+    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+
+            ////////////////////////////////////////////////////////////
+            // Message: Mouse wheel rotated over either view
+            MsgBatchCorrection::MouseWheel(dy, cx, cy) => {
+                let (wx, wy) = self.camera.cam2world(cx, cy);
+                let scale = (10.0f32).powf(dy / 100.0);
+                self.camera.zoom_around(wx, wy, scale);
+                true
+            },
+
+            ////////////////////////////////////////////////////////////
+            // Message: Slider moved
+            MsgBatchCorrection::SetMorph(t) => {
+                self.morph = t.clamp(0.0, 1.0);
+                self.update_morph();
+                true
+            },
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Render the panel
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+
+        let on_close = props.on_close.clone();
+        let cb_close = Callback::from(move |_e: MouseEvent| {
+            on_close.emit(());
+        });
+
+        let cb_wheel = ctx.link().callback(move |(dy, cx, cy): (f32, f32, f32)| {
+            MsgBatchCorrection::MouseWheel(dy, cx, cy)
+        });
+        let cb_morph = ctx.link().batch_callback(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            input.value().parse::<f32>().ok().map(MsgBatchCorrection::SetMorph)
+        });
+
+        //Cells are paired by index, so the reductions should have the same cells
+        let summary = match (&props.original_reduction, &props.corrected_reduction) {
+            (AsyncData::Loaded(original), AsyncData::Loaded(corrected)) if original.num_point != corrected.num_point => {
+                html! { <div>{ format!("Different number of cells ({} vs {}); only the first {} morphed", original.num_point, corrected.num_point, original.num_point.min(corrected.num_point)) }</div> }
+            },
+            (AsyncData::Loaded(_), AsyncData::Loaded(_)) => html! {""},
+            _ => html! { <div>{"Loading reductions..."}</div> }
+        };
+
+        let corrected = match &self.normalized {
+            Some((_, corrected)) => AsyncData::Loaded(corrected.clone()),
+            None => AsyncData::NotLoaded
+        };
+        let title_left = if self.morph > 0.0 {
+            format!("{}, {:.0}% toward {}", props.name_original, self.morph*100.0, props.name_corrected)
+        } else {
+            props.name_original.clone()
+        };
+
+        html! {
+            <div class="biscvi-compare-panel">
+                <button onclick={cb_close}>{"Close"}</button>
+                { summary }
+                <div class="biscvi-split-views">
+                    <SplitReductionView
+                        title={title_left}
+                        reduction={self.morphed.clone()}
+                        categories={props.batches.clone()}
+                        camera={self.camera.clone()}
+                        on_wheel={cb_wheel.clone()}
+                    />
+                    <SplitReductionView
+                        title={props.name_corrected.clone()}
+                        reduction={corrected}
+                        categories={props.batches.clone()}
+                        camera={self.camera.clone()}
+                        on_wheel={cb_wheel}
+                    />
+                </div>
+                <div class="bisci-label-left">
+                    {"Morph "}
+                    <input type="range" min="0" max="1" step="0.01" value={self.morph.to_string()} oninput={cb_morph}/>
+                </div>
+            </div>
+        }
+    }
+
+}
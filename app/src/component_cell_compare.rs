@@ -0,0 +1,156 @@
+use web_sys::HtmlInputElement;
+use yew::{html, Callback, Component, Context, Event, Html, MouseEvent};
+use yew::Properties;
+use wasm_bindgen::JsCast;
+
+
+////////////////////////////////////////////////////////////
+/// Values of one numeric column for the two cells being compared.
+/// Normalized values are divided by the largest absolute value of the column
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellComparisonRow {
+    pub name: String,
+    pub value_a: f32,
+    pub value_b: f32,
+    pub norm_a: f32,
+    pub norm_b: f32,
+}
+impl CellComparisonRow {
+
+    ////////////////////////////////////////////////////////////
+    /// Difference between the cells, on the normalized scale
+    pub fn abs_diff(&self) -> f32 {
+        (self.norm_a - self.norm_b).abs()
+    }
+
+}
+
+
+////////////////////////////////////////////////////////////
+/// Message sent to the event system for updating the page
+#[derive(Debug)]
+pub enum MsgCellCompare {
+    SetThreshold(f32),
+}
+
+
+////////////////////////////////////////////////////////////
+/// Properties for CellCompareView
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub cell_a: usize,
+    pub cell_b: usize,
+    pub list_rows: Vec<CellComparisonRow>,
+    pub num_loading: usize,   // columns not yet received from the server
+    pub on_close: Callback<()>,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Side panel comparing the numeric metadata of two cells. Bars for the first
+/// cell go left of a center axis, bars for the second cell go right
+pub struct CellCompareView {
+    threshold: f32,
+}
+
+impl Component for CellCompareView {
+    type Message = MsgCellCompare;
+    type Properties = Props;
+
+    ////////////////////////////////////////////////////////////
+    /// Create this component
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            threshold: 0.1,
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Handle an update message
+    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            MsgCellCompare::SetThreshold(t) => {
+                self.threshold = t;
+                true
+            },
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Render the panel
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+
+        //Only show columns that differ enough, most different first
+        let mut list_rows: Vec<&CellComparisonRow> = props.list_rows.iter().filter(|r| r.abs_diff() > self.threshold).collect();
+        list_rows.sort_by(|a, b| b.abs_diff().partial_cmp(&a.abs_diff()).unwrap_or(std::cmp::Ordering::Equal));
+
+        //Layout of the chart
+        let row_h = 20.0;
+        let label_w = 120.0;
+        let half_w = 90.0;
+        let center_x = label_w + half_w;
+        let svg_w = label_w + 2.0*half_w + 10.0;
+        let svg_h = (list_rows.len() as f32)*row_h + 20.0;
+
+        let mut list_svg: Vec<Html> = Vec::new();
+        for (i, row) in list_rows.iter().enumerate() {
+            let y = 20.0 + (i as f32)*row_h;
+            let len_a = row.norm_a.abs().min(1.0)*half_w;
+            let len_b = row.norm_b.abs().min(1.0)*half_w;
+            list_svg.push(html! {
+                <g>
+                    <text x={format!("{}", label_w - 5.0)} y={format!("{}", y + row_h*0.7)} text-anchor="end" font-size="12">{ row.name.clone() }</text>
+                    <rect x={format!("{}", center_x - len_a)} y={format!("{}", y + 2.0)} width={format!("{}", len_a)} height={format!("{}", row_h - 4.0)} fill="#0099FF">
+                        <title>{ format!("{}: {}", row.name, row.value_a) }</title>
+                    </rect>
+                    <rect x={format!("{}", center_x)} y={format!("{}", y + 2.0)} width={format!("{}", len_b)} height={format!("{}", row_h - 4.0)} fill="#FF9900">
+                        <title>{ format!("{}: {}", row.name, row.value_b) }</title>
+                    </rect>
+                </g>
+            });
+        }
+
+        let cb_threshold = ctx.link().callback(move |e: Event | {
+            let target: HtmlInputElement = e.target().expect("Event should have a target when dispatched").unchecked_into();
+            MsgCellCompare::SetThreshold(target.value().parse().unwrap_or(0.0))
+        });
+
+        let on_close = props.on_close.clone();
+        let cb_close = Callback::from(move |_e: MouseEvent | {
+            on_close.emit(());
+        });
+
+        let loading_text = if props.num_loading > 0 {
+            format!("Loading {} columns...", props.num_loading)
+        } else {
+            "".to_string()
+        };
+
+        html! {
+            <div class="biscvi-compare-panel">
+                <div style="display: flex; justify-content: space-between;">
+                    <span class="bisci-label-left">
+                        <span style="color: #0099FF;">{ format!("Cell {}", props.cell_a) }</span>
+                        {" vs "}
+                        <span style="color: #FF9900;">{ format!("Cell {}", props.cell_b) }</span>
+                    </span>
+                    <button onclick={cb_close}>{"Close"}</button>
+                </div>
+                <div class="bisci-label-left">
+                    {"Min difference "}
+                    <input type="range" min="0" max="1" step="0.01" value={format!("{}", self.threshold)} onchange={cb_threshold}/>
+                    { format!("{:.2}", self.threshold) }
+                </div>
+                <div class="bisci-label-left">{ loading_text }</div>
+                <svg width={format!("{}", svg_w)} height={format!("{}", svg_h)}>
+                    <line x1={format!("{}", center_x)} y1="15" x2={format!("{}", center_x)} y2={format!("{}", svg_h)} stroke="gray"/>
+                    { list_svg }
+                </svg>
+            </div>
+        }
+    }
+
+}
@@ -0,0 +1,100 @@
+use yew::{html, Component, Context, Html, MouseEvent};
+use yew::Properties;
+
+
+////////////////////////////////////////////////////////////
+/// Value of one column for the hovered cell
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellMetadataRow {
+    pub name: String,
+    pub value: String,
+    pub is_sparse_zero: bool,   // not listed in a sparse column; hidden unless all rows are shown
+}
+
+
+////////////////////////////////////////////////////////////
+/// Message sent to the event system for updating the page
+#[derive(Debug)]
+pub enum MsgCellMetadata {
+    ToggleShowAll,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Properties for CellMetadataView
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub cell_name: String,   // ID of the cell if known, otherwise its index
+    pub list_rows: Vec<CellMetadataRow>,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Floating table with the value of each loaded column for the hovered cell
+pub struct CellMetadataView {
+    show_all: bool,
+}
+
+impl Component for CellMetadataView {
+    type Message = MsgCellMetadata;
+    type Properties = Props;
+
+    ////////////////////////////////////////////////////////////
+    /// Create this component
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            show_all: false,
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Handle an update message
+    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            MsgCellMetadata::ToggleShowAll => {
+                self.show_all = !self.show_all;
+                true
+            },
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Render the table
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+
+        let list_rows = props.list_rows.iter()
+            .filter(|row| self.show_all || !row.is_sparse_zero)
+            .map(|row| html! {
+                <tr>
+                    <td>{ row.name.clone() }</td>
+                    <td>{ row.value.clone() }</td>
+                </tr>
+            })
+            .collect::<Html>();
+
+        //Only offer to show all if something is hidden
+        let num_hidden = props.list_rows.iter().filter(|row| row.is_sparse_zero).count();
+        let toggle = if num_hidden > 0 {
+            let text = if self.show_all { "Hide zeros".to_string() } else { format!("Show all ({} zeros)", num_hidden) };
+            html! {
+                <button onclick={ctx.link().callback(|_e: MouseEvent| MsgCellMetadata::ToggleShowAll)}>{ text }</button>
+            }
+        } else {
+            html! {""}
+        };
+
+        html! {
+            <div class="biscvi-cell-metadata-panel">
+                <div class="bisci-label-left">{ props.cell_name.clone() }</div>
+                <table>
+                    { list_rows }
+                </table>
+                { toggle }
+            </div>
+        }
+    }
+
+}
@@ -0,0 +1,135 @@
+use yew::context::ContextHandle;
+use yew::{html, Callback, Component, Context, Html, MouseEvent};
+use yew::Properties;
+
+use crate::selection_context::SelectionContext;
+
+
+////////////////////////////////////////////////////////////
+/// Values of the loaded columns for one cell, in the order of the columns of the table
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellTableRow {
+    pub cell: usize,
+    pub name: String,   // ID of the cell if known, otherwise its index
+    pub values: Vec<String>,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Message sent to the event system for updating the page
+#[derive(Debug)]
+pub enum MsgCellTable {
+    ContextChanged(SelectionContext),
+}
+
+
+////////////////////////////////////////////////////////////
+/// Properties for CellTableView
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub columns: Vec<String>,
+    pub rows: Vec<CellTableRow>,   // possibly only the first of the selected cells
+    #[prop_or_default]
+    pub distribution_distance: Option<String>,   // description of the last distance computed between groups of cells
+    pub on_close: Callback<()>,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Panel with a table of the selected cells. The row of the cell hovered in any other view is
+/// highlighted, and hovering a row hovers its cell; both go through the SelectionContext
+pub struct CellTableView {
+    selection_context: Option<(SelectionContext, ContextHandle<SelectionContext>)>,
+}
+
+impl Component for CellTableView {
+    type Message = MsgCellTable;
+    type Properties = Props;
+
+    ////////////////////////////////////////////////////////////
+    /// Create this component
+    fn create(ctx: &Context<Self>) -> Self {
+        Self {
+            selection_context: ctx.link().context(ctx.link().callback(MsgCellTable::ContextChanged)),
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Handle an update message
+    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            MsgCellTable::ContextChanged(selection_context) => {
+                let hovered_changed = self.selection_context.as_ref().map(|(c, _)| c.hovered) != Some(selection_context.hovered);
+                if let Some((current, _)) = &mut self.selection_context {
+                    *current = selection_context;
+                }
+                hovered_changed
+            },
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Render the panel
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+
+        let on_close = props.on_close.clone();
+        let cb_close = Callback::from(move |_e: MouseEvent| {
+            on_close.emit(());
+        });
+
+        let (hovered, num_selected, set_hovered) = match &self.selection_context {
+            Some((selection_context, _)) => (selection_context.hovered, selection_context.selection.len(), selection_context.set_hovered.clone()),
+            None => (None, props.rows.len(), Callback::noop()),
+        };
+
+        let list_header = props.columns.iter().map(|name| html! {
+            <th>{ name.clone() }</th>
+        }).collect::<Html>();
+
+        let list_rows = props.rows.iter().map(|row| {
+            let cell = row.cell;
+            let set_hovered_enter = set_hovered.clone();
+            let cb_enter = Callback::from(move |_e: MouseEvent| {
+                set_hovered_enter.emit(Some(cell));
+            });
+            let set_hovered_leave = set_hovered.clone();
+            let cb_leave = Callback::from(move |_e: MouseEvent| {
+                set_hovered_leave.emit(None);
+            });
+            let list_values = row.values.iter().map(|value| html! {
+                <td>{ value.clone() }</td>
+            }).collect::<Html>();
+            html! {
+                <tr class={if hovered == Some(cell) { "biscvi-cell-table-hovered" } else { "" }} onmouseenter={cb_enter} onmouseleave={cb_leave}>
+                    <td>{ row.name.clone() }</td>
+                    { list_values }
+                </tr>
+            }
+        }).collect::<Html>();
+
+        let title = if num_selected > props.rows.len() {
+            format!("{} cells selected, showing the first {}", num_selected, props.rows.len())
+        } else {
+            format!("{} cells selected", num_selected)
+        };
+
+        html! {
+            <div class="biscvi-compare-panel">
+                <button onclick={cb_close}>{"Close"}</button>
+                <div class="bisci-label-left">{ title }</div>
+                { props.distribution_distance.as_ref().map(|text| html! { <div>{ text.clone() }</div> }).unwrap_or_default() }
+                <table class="biscvi-cell-table">
+                    <tr>
+                        <th>{"Cell"}</th>
+                        { list_header }
+                    </tr>
+                    { list_rows }
+                </table>
+            </div>
+        }
+    }
+
+}
@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+
+use my_web_app::CountFileMetaColumnData;
+use wasm_bindgen::JsCast;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
+use yew::{html, Callback, Component, Context, Html, MouseEvent, NodeRef};
+use yew::Properties;
+
+use crate::appstate::AsyncData;
+use crate::heatmap::{group_mean_zscores, single_linkage_order, viridis, HEATMAP_Z_LIMIT};
+
+
+////////////////////////////////////////////////////////////
+/// Size of the parts of the heatmap, in pixels
+const HEATMAP_CELL_W: f64 = 60.0;
+const HEATMAP_CELL_H: f64 = 14.0;
+const HEATMAP_LABEL_W: f64 = 100.0;
+const HEATMAP_HEADER_H: f64 = 16.0;
+
+
+////////////////////////////////////////////////////////////
+/// Properties for CellStateHeatmapView
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub group_a: Vec<usize>,
+    pub group_b: Vec<usize>,
+    pub group_names: (String, String),
+    pub gene_list: Vec<String>,
+    pub gene_data: HashMap<String, AsyncData<CountFileMetaColumnData>>,   // expression of the genes of gene_list, if requested
+    pub num_point: usize,
+    pub on_close: Callback<()>,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Panel with a heatmap of the mean expression of genes in two groups of cells, e.g. before and after
+/// a transition. Values are z-scores over all cells; similar genes are placed next to each other
+pub struct CellStateHeatmapView {
+    node_ref: NodeRef,
+}
+
+impl CellStateHeatmapView {
+
+    ////////////////////////////////////////////////////////////
+    /// Z-scores of the genes with data, in the order they are drawn
+    fn get_rows(&self, ctx: &Context<Self>) -> Vec<(String, Vec<f32>)> {
+        let props = ctx.props();
+        let groups = [&props.group_a, &props.group_b];
+        let rows: Vec<(String, Vec<f32>)> = props.gene_list.iter().filter_map(|gene| {
+            match props.gene_data.get(gene) {
+                Some(AsyncData::Loaded(data)) => group_mean_zscores(data, &groups, props.num_point).map(|z| (gene.clone(), z)),
+                _ => None
+            }
+        }).collect();
+        let order = single_linkage_order(&rows.iter().map(|(_, z)| z.clone()).collect());
+        order.into_iter().map(|i| rows[i].clone()).collect()
+    }
+
+}
+
+impl Component for CellStateHeatmapView {
+    type Message = ();
+    type Properties = Props;
+
+    ////////////////////////////////////////////////////////////
+    /// Create this component
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            node_ref: NodeRef::default(),
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Render the panel
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+
+        let on_close = props.on_close.clone();
+        let cb_close = Callback::from(move |_e: MouseEvent| {
+            on_close.emit(());
+        });
+
+        let num_loaded = props.gene_list.iter().filter(|gene| matches!(props.gene_data.get(*gene), Some(AsyncData::Loaded(_)))).count();
+        let num_failed = props.gene_list.iter().filter(|gene| matches!(props.gene_data.get(*gene), Some(AsyncData::Error(_)))).count();
+        let status = if props.gene_list.is_empty() {
+            html! { <div>{"Color by genes to add them to the heatmap"}</div> }
+        } else if num_loaded + num_failed < props.gene_list.len() {
+            html! { <div>{ format!("Loading {} of {} genes...", props.gene_list.len() - num_loaded - num_failed, props.gene_list.len()) }</div> }
+        } else if num_failed > 0 {
+            html! { <div>{ format!("Could not load {} of {} genes", num_failed, props.gene_list.len()) }</div> }
+        } else {
+            html! {""}
+        };
+
+        html! {
+            <div class="biscvi-compare-panel">
+                <button onclick={cb_close}>{"Close"}</button>
+                <div class="bisci-label-left">
+                    { format!("{} ({} cells) vs {} ({} cells)", props.group_names.0, props.group_a.len(), props.group_names.1, props.group_b.len()) }
+                </div>
+                { status }
+                <canvas ref={self.node_ref.clone()} width={format!("{}", HEATMAP_LABEL_W + 2.0*HEATMAP_CELL_W)} height="0"/>
+            </div>
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Called after DOM has been generated
+    fn rendered(&mut self, ctx: &Context<Self>, _first_render: bool) {
+        if let Some(canvas) = self.node_ref.cast::<HtmlCanvasElement>() {
+            let rows = self.get_rows(ctx);
+            let group_names = &ctx.props().group_names;
+            draw_heatmap(&canvas, &rows, [&group_names.0, &group_names.1]);
+        }
+    }
+
+}
+
+
+////////////////////////////////////////////////////////////
+/// Draw one row per gene and one column per group, with group names above
+fn draw_heatmap(canvas: &HtmlCanvasElement, rows: &Vec<(String, Vec<f32>)>, group_names: [&String; 2]) {
+    let height = if rows.is_empty() { 0.0 } else { HEATMAP_HEADER_H + rows.len() as f64*HEATMAP_CELL_H };
+    canvas.set_height(height as u32);
+
+    let context = canvas
+        .get_context("2d")
+        .unwrap()
+        .unwrap()
+        .dyn_into::<CanvasRenderingContext2d>()
+        .unwrap();
+    context.clear_rect(0.0, 0.0, canvas.width() as f64, canvas.height() as f64);
+    context.set_font("10px sans-serif");
+    context.set_text_baseline("middle");
+
+    context.set_fill_style_str("black");
+    context.set_text_align("center");
+    for (j, name) in group_names.iter().enumerate() {
+        let _ = context.fill_text(name, HEATMAP_LABEL_W + (j as f64 + 0.5)*HEATMAP_CELL_W, HEATMAP_HEADER_H/2.0);
+    }
+
+    for (i, (gene, zscores)) in rows.iter().enumerate() {
+        let y = HEATMAP_HEADER_H + i as f64*HEATMAP_CELL_H;
+        context.set_fill_style_str("black");
+        context.set_text_align("right");
+        let _ = context.fill_text(gene, HEATMAP_LABEL_W - 4.0, y + HEATMAP_CELL_H/2.0);
+
+        for (j, z) in zscores.iter().enumerate() {
+            let col = viridis((z + HEATMAP_Z_LIMIT)/(2.0*HEATMAP_Z_LIMIT));
+            context.set_fill_style_str(&format!("rgb({}, {}, {})", col.0*255.0, col.1*255.0, col.2*255.0));
+            context.fill_rect(HEATMAP_LABEL_W + j as f64*HEATMAP_CELL_W, y, HEATMAP_CELL_W - 1.0, HEATMAP_CELL_H - 1.0);
+        }
+    }
+}
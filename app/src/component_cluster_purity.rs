@@ -0,0 +1,122 @@
+use yew::{html, Callback, Component, Context, Html, MouseEvent};
+use yew::Properties;
+
+use crate::component_reduction_main::{get_palette_for_categories, rgbvec2string};
+use crate::purity::ClusterPurity;
+
+
+////////////////////////////////////////////////////////////
+/// Properties for ClusterPurityView
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub predicted_col: String,
+    pub gt_col: String,
+    pub purity: Option<ClusterPurity>,   // None while the columns are loading, or if not categorical
+    pub on_close: Callback<()>,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Side panel with a stacked bar for each predicted cluster, split by the proportion of
+/// each ground-truth label. A pure cluster has a single segment
+pub struct ClusterPurityView {
+}
+
+impl Component for ClusterPurityView {
+    type Message = ();
+    type Properties = Props;
+
+    ////////////////////////////////////////////////////////////
+    /// Create this component
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Render the panel
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+
+        let on_close = props.on_close.clone();
+        let cb_close = Callback::from(move |_e: MouseEvent| {
+            on_close.emit(());
+        });
+
+        let chart = if let Some(purity) = &props.purity {
+
+            //Layout of the chart
+            let row_h = 20.0;
+            let label_w = 100.0;
+            let bar_w = 120.0;
+            let svg_w = label_w + bar_w + 50.0;
+            let svg_h = (purity.cluster_names.len() as f32)*row_h + 10.0;
+            let palette = get_palette_for_categories(purity.label_names.len());
+
+            let mut list_svg: Vec<Html> = Vec::new();
+            for (i, cluster_name) in purity.cluster_names.iter().enumerate() {
+                let y = 5.0 + (i as f32)*row_h;
+                let total: usize = purity.counts[i].iter().sum();
+                list_svg.push(html! {
+                    <text x={format!("{}", label_w - 5.0)} y={format!("{}", y + row_h*0.7)} text-anchor="end" font-size="12">{ cluster_name.clone() }</text>
+                });
+
+                //One segment per label, in label order
+                let mut x = label_w;
+                for (label_i, count) in purity.counts[i].iter().enumerate() {
+                    if *count == 0 {
+                        continue;
+                    }
+                    let w = bar_w*(*count as f32)/(total as f32);
+                    let color = palette.get(label_i).cloned().unwrap_or((0.5, 0.5, 0.5));
+                    list_svg.push(html! {
+                        <rect x={format!("{}", x)} y={format!("{}", y + 2.0)} width={format!("{}", w)} height={format!("{}", row_h - 4.0)} fill={rgbvec2string(color)}>
+                            <title>{ format!("{}: {} cells ({:.0}%)", purity.label_names[label_i], count, 100.0*(*count as f32)/(total as f32)) }</title>
+                        </rect>
+                    });
+                    x += w;
+                }
+
+                if let Some(p) = purity.purity(i) {
+                    list_svg.push(html! {
+                        <text x={format!("{}", label_w + bar_w + 5.0)} y={format!("{}", y + row_h*0.7)} font-size="12">{ format!("{:.0}%", 100.0*p) }</text>
+                    });
+                }
+            }
+
+            //Legend of the ground-truth labels
+            let list_legend = purity.label_names.iter().enumerate().map(|(label_i, name)| {
+                let color = palette.get(label_i).cloned().unwrap_or((0.5, 0.5, 0.5));
+                html! {
+                    <div>
+                        <span class="biscvi-purity-swatch" style={format!("background-color: {};", rgbvec2string(color))}></span>
+                        { name.clone() }
+                    </div>
+                }
+            }).collect::<Html>();
+
+            html! {
+                <div>
+                    <svg width={format!("{}", svg_w)} height={format!("{}", svg_h)}>
+                        { for list_svg }
+                    </svg>
+                    { list_legend }
+                </div>
+            }
+        } else {
+            html! { <div>{"Loading columns..."}</div> }
+        };
+
+        html! {
+            <div class="biscvi-compare-panel">
+                <button onclick={cb_close}>{"Close"}</button>
+                <div class="bisci-label-left">
+                    { format!("Purity of {} against {}", props.predicted_col, props.gt_col) }
+                </div>
+                { chart }
+            </div>
+        }
+    }
+
+}
@@ -0,0 +1,87 @@
+use web_sys::HtmlTextAreaElement;
+use yew::{html, Callback, Component, Context, Html, MouseEvent, NodeRef};
+use yew::Properties;
+
+
+////////////////////////////////////////////////////////////
+/// Properties for CollabPanel
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub local_sdp: Option<String>,   // offer or answer to give to the other peer
+    pub num_peers: usize,
+    pub num_connected: usize,
+    pub on_start: Callback<()>,
+    pub on_join: Callback<String>,    // offer from the peer who started the session
+    pub on_accept: Callback<String>,  // answer from the peer who joined
+}
+
+
+////////////////////////////////////////////////////////////
+/// Panel to set up a collaborative session. SDPs are exchanged by copy-paste, e.g. over chat
+pub struct CollabPanel {
+    remote_sdp_ref: NodeRef,
+}
+
+impl Component for CollabPanel {
+    type Message = ();
+    type Properties = Props;
+
+    ////////////////////////////////////////////////////////////
+    /// Create this component
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            remote_sdp_ref: NodeRef::default(),
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Render the panel
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+
+        let on_start = props.on_start.clone();
+        let cb_start = Callback::from(move |_e: MouseEvent | {
+            on_start.emit(());
+        });
+
+        //The SDP pasted is read when a button is clicked
+        let on_join = props.on_join.clone();
+        let remote_sdp_ref = self.remote_sdp_ref.clone();
+        let cb_join = Callback::from(move |_e: MouseEvent | {
+            if let Some(textarea) = remote_sdp_ref.cast::<HtmlTextAreaElement>() {
+                on_join.emit(textarea.value());
+            }
+        });
+
+        let on_accept = props.on_accept.clone();
+        let remote_sdp_ref = self.remote_sdp_ref.clone();
+        let cb_accept = Callback::from(move |_e: MouseEvent | {
+            if let Some(textarea) = remote_sdp_ref.cast::<HtmlTextAreaElement>() {
+                on_accept.emit(textarea.value());
+            }
+        });
+
+        let local_sdp = props.local_sdp.clone().unwrap_or_default();
+
+        html! {
+            <div class="biscvi-collab-panel">
+                <div class="bisci-label-left">
+                    { format!("Collaboration: {} of {} peers connected", props.num_connected, props.num_peers) }
+                </div>
+                <div>
+                    <button onclick={cb_start}>{"Start session"}</button>
+                </div>
+                <div class="bisci-label-left">{"Give this to the other peer:"}</div>
+                <textarea readonly=true rows="3" value={local_sdp}/>
+                <div class="bisci-label-left">{"Paste from the other peer:"}</div>
+                <textarea rows="3" ref={self.remote_sdp_ref.clone()}/>
+                <div>
+                    <button onclick={cb_join}>{"Join with offer"}</button>
+                    <button onclick={cb_accept}>{"Accept answer"}</button>
+                </div>
+            </div>
+        }
+    }
+
+}
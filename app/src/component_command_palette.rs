@@ -0,0 +1,219 @@
+use web_sys::HtmlInputElement;
+use yew::{html, Callback, Component, Context, Html, InputEvent, KeyboardEvent, MouseEvent, TargetCast};
+use yew::Properties;
+
+use crate::core_model::Msg;
+
+
+////////////////////////////////////////////////////////////
+/// Action that can be run from the command palette
+pub struct Command {
+    pub name: String,
+    pub description: String,
+    pub action: Box<dyn Fn() -> Msg>,
+}
+impl Command {
+
+    ////////////////////////////////////////////////////////////
+    /// Create a command
+    pub fn new(name: &str, description: &str, action: impl Fn() -> Msg + 'static) -> Command {
+        Command {
+            name: name.to_string(),
+            description: description.to_string(),
+            action: Box::new(action),
+        }
+    }
+
+}
+impl PartialEq for Command {
+    //Actions cannot be compared; commands with the same name are assumed to do the same thing
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.description == other.description
+    }
+}
+
+
+////////////////////////////////////////////////////////////
+/// Score how well a query matches a name. The characters of the query must appear in
+/// the name in order, ignoring case. Higher is better; consecutive characters and
+/// matches early in the name score more. None if there is no match
+pub fn fuzzy_match(query: &str, name: &str) -> Option<i32> {
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut last_match: Option<usize> = None;
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = name[pos..].iter().position(|c| *c == q)? + pos;
+        score += if last_match == Some(found.wrapping_sub(1)) { 5 } else { 1 };
+        if found == 0 || name[found-1] == ' ' {
+            score += 3;   // start of a word
+        }
+        last_match = Some(found);
+        pos = found + 1;
+    }
+    Some(score*100 - last_match.unwrap_or(0) as i32)
+}
+
+
+////////////////////////////////////////////////////////////
+/// Message sent to the event system for updating the palette
+#[derive(Debug)]
+pub enum MsgPalette {
+    SetQuery(String),
+    MoveSelection(i32),
+    RunSelected,
+    Run(usize),
+}
+
+
+////////////////////////////////////////////////////////////
+/// Properties for CommandPalette
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub commands: Vec<Command>,
+    pub on_command: Callback<Msg>,
+    pub on_close: Callback<()>,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Searchable list of all actions of the app, opened using Ctrl+K
+pub struct CommandPalette {
+    query: String,
+    selected: usize,   // position in the filtered list
+}
+impl CommandPalette {
+
+    ////////////////////////////////////////////////////////////
+    /// Commands matching the query, best match first. Returns positions in the list of all commands
+    fn filtered_commands(&self, ctx: &Context<Self>) -> Vec<usize> {
+        let mut matches: Vec<(usize, i32)> = ctx.props().commands.iter().enumerate()
+            .filter_map(|(i, cmd)| fuzzy_match(&self.query, &cmd.name).map(|score| (i, score)))
+            .collect();
+        matches.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        matches.iter().map(|(i, _)| *i).collect()
+    }
+
+}
+
+impl Component for CommandPalette {
+    type Message = MsgPalette;
+    type Properties = Props;
+
+    ////////////////////////////////////////////////////////////
+    /// Create this component
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            query: String::new(),
+            selected: 0,
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Handle an update message
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+
+            ////////////////////////////////////////////////////////////
+            // Message: Search text edited
+            MsgPalette::SetQuery(query) => {
+                self.query = query;
+                self.selected = 0;
+                true
+            },
+
+            ////////////////////////////////////////////////////////////
+            // Message: Arrow keys pressed
+            MsgPalette::MoveSelection(step) => {
+                let num_match = self.filtered_commands(ctx).len();
+                if num_match > 0 {
+                    self.selected = (self.selected as i32 + step).clamp(0, num_match as i32 - 1) as usize;
+                }
+                true
+            },
+
+            ////////////////////////////////////////////////////////////
+            // Message: Enter pressed
+            MsgPalette::RunSelected => {
+                if let Some(i) = self.filtered_commands(ctx).get(self.selected) {
+                    ctx.link().send_message(MsgPalette::Run(*i));
+                }
+                false
+            },
+
+            ////////////////////////////////////////////////////////////
+            // Message: Run a command, then close the palette
+            MsgPalette::Run(i) => {
+                if let Some(cmd) = ctx.props().commands.get(i) {
+                    ctx.props().on_command.emit((cmd.action)());
+                }
+                ctx.props().on_close.emit(());
+                false
+            },
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Render the palette
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+
+        let cb_input = ctx.link().callback(|e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            MsgPalette::SetQuery(input.value())
+        });
+
+        let on_close = props.on_close.clone();
+        let link = ctx.link().clone();
+        let cb_key = Callback::from(move |e: KeyboardEvent| {
+            match e.key().as_str() {
+                "ArrowDown" => {
+                    e.prevent_default();
+                    link.send_message(MsgPalette::MoveSelection(1));
+                },
+                "ArrowUp" => {
+                    e.prevent_default();
+                    link.send_message(MsgPalette::MoveSelection(-1));
+                },
+                "Enter" => link.send_message(MsgPalette::RunSelected),
+                "Escape" => on_close.emit(()),
+                _ => {}
+            }
+        });
+
+        //Clicking outside the palette closes it
+        let on_close = props.on_close.clone();
+        let cb_backdrop = Callback::from(move |_e: MouseEvent| {
+            on_close.emit(());
+        });
+        let cb_stop = Callback::from(|e: MouseEvent| {
+            e.stop_propagation();
+        });
+
+        let list_items = self.filtered_commands(ctx).iter().enumerate().map(|(pos, i)| {
+            let cmd = &props.commands[*i];
+            let i = *i;
+            let class = if pos == self.selected { "biscvi-palette-item biscvi-palette-selected" } else { "biscvi-palette-item" };
+            html! {
+                <div class={class} onclick={ctx.link().callback(move |_e: MouseEvent| MsgPalette::Run(i))}>
+                    <div class="biscvi-palette-name">{ cmd.name.clone() }</div>
+                    <div class="biscvi-palette-description">{ cmd.description.clone() }</div>
+                </div>
+            }
+        }).collect::<Html>();
+
+        html! {
+            <div class="biscvi-palette-backdrop" onclick={cb_backdrop}>
+                <div class="biscvi-palette" onclick={cb_stop}>
+                    <input type="text" placeholder="Type a command" value={self.query.clone()} oninput={cb_input} onkeydown={cb_key} autofocus=true/>
+                    <div class="biscvi-palette-list">
+                        { list_items }
+                    </div>
+                </div>
+            </div>
+        }
+    }
+
+}
@@ -0,0 +1,306 @@
+use std::sync::Arc;
+
+use my_web_app::CountFileMetaColumnData;
+use wasm_bindgen::JsCast;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
+use yew::{html, Callback, Component, Context, Html, MouseEvent, NodeRef};
+use yew::Properties;
+
+use crate::appstate::AsyncData;
+use crate::colorscale::diverging_color;
+use crate::correlation::{dense_numeric_values, pearson_correlation};
+
+
+////////////////////////////////////////////////////////////
+/// Size of the parts of the grid, in pixels
+const MATRIX_CELL_SIZE: f64 = 24.0;
+const MATRIX_LABEL_W: f64 = 100.0;
+
+////////////////////////////////////////////////////////////
+/// Size of the scatter plot, in pixels, and the margin for its axis labels
+const SCATTER_SIZE: f64 = 220.0;
+const SCATTER_MARGIN: f64 = 30.0;
+
+////////////////////////////////////////////////////////////
+/// At most this many cells are drawn in the scatter plot, evenly spread over all cells
+const MAX_SCATTER_POINTS: usize = 5000;
+
+
+////////////////////////////////////////////////////////////
+/// Message sent to the event system for updating the page
+#[derive(Debug)]
+pub enum MsgCorrelationMatrix {
+    ClickGrid(f64, f64),   // position on the canvas, in pixels
+}
+
+
+////////////////////////////////////////////////////////////
+/// Properties for CorrelationMatrixView
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub columns: Vec<(String, AsyncData<CountFileMetaColumnData>)>,   // numeric columns, loaded or not
+    pub num_point: usize,
+    pub on_close: Callback<()>,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Panel with the Pearson correlation of each pair of numeric columns, as a grid colored from
+/// blue (-1) over white (0) to red (+1). Clicking a square shows a scatter plot of its two columns
+pub struct CorrelationMatrixView {
+    grid_ref: NodeRef,
+    scatter_ref: NodeRef,
+    input: Vec<(String, Arc<CountFileMetaColumnData>)>,   // loaded columns the matrix was computed from
+    values: Vec<Vec<f32>>,                               // of each loaded column, for all cells
+    matrix: Vec<Vec<Option<f32>>>,
+    scatter_axes: Option<(usize, usize)>,   // columns on the x and y axis
+}
+
+impl CorrelationMatrixView {
+
+    ////////////////////////////////////////////////////////////
+    /// Compute the correlations again if the loaded columns changed. This is slow for many cells,
+    /// so it is not done on every render
+    fn update_matrix(&mut self, ctx: &Context<Self>) {
+        let props = ctx.props();
+        let input: Vec<(String, Arc<CountFileMetaColumnData>)> = props.columns.iter().filter_map(|(name, data)| {
+            match data {
+                AsyncData::Loaded(data) => Some((name.clone(), data.clone())),
+                _ => None
+            }
+        }).collect();
+        let same_input = input.len() == self.input.len() && input.iter().zip(self.input.iter()).all(|(a, b)| a.0 == b.0 && Arc::ptr_eq(&a.1, &b.1));
+        if same_input {
+            return;
+        }
+
+        self.values = input.iter().map(|(_, data)| dense_numeric_values(data, props.num_point).unwrap_or_default()).collect();
+        let all_cells: Vec<usize> = (0..props.num_point).collect();
+        self.matrix = (0..self.values.len()).map(|i| {
+            (0..self.values.len()).map(|j| {
+                if i == j {
+                    Some(1.0)
+                } else {
+                    pearson_correlation(&self.values[i], &self.values[j], &all_cells)
+                }
+            }).collect()
+        }).collect();
+
+        //The grid may now have other columns
+        if self.input.iter().map(|(name, _)| name).ne(input.iter().map(|(name, _)| name)) {
+            self.scatter_axes = None;
+        }
+        self.input = input;
+    }
+
+}
+
+impl Component for CorrelationMatrixView {
+    type Message = MsgCorrelationMatrix;
+    type Properties = Props;
+
+    ////////////////////////////////////////////////////////////
+    /// Create this component
+    fn create(ctx: &Context<Self>) -> Self {
+        let mut view = Self {
+            grid_ref: NodeRef::default(),
+            scatter_ref: NodeRef::default(),
+            input: Vec::new(),
+            values: Vec::new(),
+            matrix: Vec::new(),
+            scatter_axes: None,
+        };
+        view.update_matrix(ctx);
+        view
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Properties have changed
+    fn changed(&mut self, ctx: &Context<Self>, _old_props: &Self::Properties) -> bool {
+        self.update_matrix(ctx);
+        true
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Handle an update message
+    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            MsgCorrelationMatrix::ClickGrid(x, y) => {
+                let col = ((x - MATRIX_LABEL_W)/MATRIX_CELL_SIZE).floor();
+                let row = ((y - MATRIX_LABEL_W)/MATRIX_CELL_SIZE).floor();
+                let n = self.matrix.len() as f64;
+                if col >= 0.0 && row >= 0.0 && col < n && row < n {
+                    self.scatter_axes = Some((col as usize, row as usize));
+                    true
+                } else {
+                    false
+                }
+            },
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Render the panel
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+
+        let on_close = props.on_close.clone();
+        let cb_close = Callback::from(move |_e: MouseEvent| {
+            on_close.emit(());
+        });
+
+        let cb_click_grid = ctx.link().callback(move |e: MouseEvent| {
+            MsgCorrelationMatrix::ClickGrid(e.offset_x() as f64, e.offset_y() as f64)
+        });
+
+        let num_loading = props.columns.iter().filter(|(_, data)| matches!(data, AsyncData::NotLoaded | AsyncData::Loading)).count();
+        let status = if props.columns.len() < 2 {
+            html! { <div>{"At least two numeric columns are needed"}</div> }
+        } else if num_loading > 0 {
+            html! { <div>{ format!("Loading {} of {} columns...", num_loading, props.columns.len()) }</div> }
+        } else {
+            html! { <div>{"Click a square to plot its two columns against each other"}</div> }
+        };
+
+        let size = MATRIX_LABEL_W + self.matrix.len() as f64*MATRIX_CELL_SIZE;
+        let scatter_size = if self.scatter_axes.is_some() { SCATTER_SIZE + SCATTER_MARGIN } else { 0.0 };
+
+        html! {
+            <div class="biscvi-compare-panel">
+                <button onclick={cb_close}>{"Close"}</button>
+                <div class="bisci-label-left">{"Correlation of numeric columns"}</div>
+                { status }
+                <canvas ref={self.grid_ref.clone()} width={size.to_string()} height={size.to_string()} onclick={cb_click_grid} style="cursor: pointer;"/>
+                <canvas ref={self.scatter_ref.clone()} width={scatter_size.to_string()} height={scatter_size.to_string()}/>
+            </div>
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Called after DOM has been generated
+    fn rendered(&mut self, _ctx: &Context<Self>, _first_render: bool) {
+        let names: Vec<&String> = self.input.iter().map(|(name, _)| name).collect();
+        if let Some(canvas) = self.grid_ref.cast::<HtmlCanvasElement>() {
+            draw_matrix(&canvas, &names, &self.matrix, self.scatter_axes);
+        }
+        if let (Some(canvas), Some((col_x, col_y))) = (self.scatter_ref.cast::<HtmlCanvasElement>(), self.scatter_axes) {
+            draw_scatter(&canvas, (names[col_x], &self.values[col_x]), (names[col_y], &self.values[col_y]));
+        }
+    }
+
+}
+
+
+////////////////////////////////////////////////////////////
+/// Get the 2D context of a canvas, cleared
+fn get_cleared_context(canvas: &HtmlCanvasElement) -> CanvasRenderingContext2d {
+    let context = canvas
+        .get_context("2d")
+        .unwrap()
+        .unwrap()
+        .dyn_into::<CanvasRenderingContext2d>()
+        .unwrap();
+    context.clear_rect(0.0, 0.0, canvas.width() as f64, canvas.height() as f64);
+    context.set_font("10px sans-serif");
+    context.set_text_baseline("middle");
+    context
+}
+
+
+////////////////////////////////////////////////////////////
+/// Draw one square per pair of columns, with column names to the left and, rotated, above.
+/// The square plotted is outlined
+fn draw_matrix(canvas: &HtmlCanvasElement, names: &Vec<&String>, matrix: &Vec<Vec<Option<f32>>>, selected: Option<(usize, usize)>) {
+    let context = get_cleared_context(canvas);
+
+    context.set_fill_style_str("black");
+    context.set_text_align("right");
+    for (i, name) in names.iter().enumerate() {
+        let mid = MATRIX_LABEL_W + (i as f64 + 0.5)*MATRIX_CELL_SIZE;
+        let _ = context.fill_text(name, MATRIX_LABEL_W - 4.0, mid);
+
+        context.save();
+        let _ = context.translate(mid, MATRIX_LABEL_W - 4.0);
+        let _ = context.rotate(-std::f64::consts::FRAC_PI_2);
+        context.set_text_align("left");
+        let _ = context.fill_text(name, 0.0, 0.0);
+        context.restore();
+    }
+
+    for (i, row) in matrix.iter().enumerate() {
+        for (j, r) in row.iter().enumerate() {
+            let x = MATRIX_LABEL_W + j as f64*MATRIX_CELL_SIZE;
+            let y = MATRIX_LABEL_W + i as f64*MATRIX_CELL_SIZE;
+            //Constant columns have no correlation; drawn gray
+            let fill = match r {
+                Some(r) => {
+                    let col = diverging_color(*r);
+                    format!("rgb({}, {}, {})", col.0*255.0, col.1*255.0, col.2*255.0)
+                },
+                None => "lightgray".to_string(),
+            };
+            context.set_fill_style_str(&fill);
+            context.fill_rect(x, y, MATRIX_CELL_SIZE - 1.0, MATRIX_CELL_SIZE - 1.0);
+            if let Some(r) = r {
+                context.set_fill_style_str("black");
+                context.set_text_align("center");
+                let _ = context.fill_text(&format!("{:.1}", r), x + MATRIX_CELL_SIZE/2.0, y + MATRIX_CELL_SIZE/2.0);
+            }
+        }
+    }
+
+    if let Some((col, row)) = selected {
+        context.set_stroke_style_str("black");
+        context.set_line_width(2.0);
+        context.stroke_rect(MATRIX_LABEL_W + col as f64*MATRIX_CELL_SIZE, MATRIX_LABEL_W + row as f64*MATRIX_CELL_SIZE, MATRIX_CELL_SIZE - 1.0, MATRIX_CELL_SIZE - 1.0);
+    }
+}
+
+
+////////////////////////////////////////////////////////////
+/// Draw one dot per cell, for a subset of cells, with the column names along the axes
+fn draw_scatter(canvas: &HtmlCanvasElement, x: (&String, &Vec<f32>), y: (&String, &Vec<f32>)) {
+    let context = get_cleared_context(canvas);
+    let (name_x, values_x) = x;
+    let (name_y, values_y) = y;
+    let num_point = values_x.len().min(values_y.len());
+    if num_point == 0 {
+        return;
+    }
+
+    let range = |values: &Vec<f32>| {
+        let (min, max) = values.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), v| (lo.min(*v), hi.max(*v)));
+        (min as f64, (max - min).max(1e-6) as f64)
+    };
+    let (min_x, span_x) = range(values_x);
+    let (min_y, span_y) = range(values_y);
+
+    context.set_fill_style_str("rgba(0, 0, 0, 0.3)");
+    let step = num_point.div_ceil(MAX_SCATTER_POINTS);
+    for i in (0..num_point).step_by(step) {
+        let px = SCATTER_MARGIN + (values_x[i] as f64 - min_x)/span_x*SCATTER_SIZE;
+        let py = SCATTER_SIZE - (values_y[i] as f64 - min_y)/span_y*SCATTER_SIZE;
+        context.fill_rect(px - 1.0, py - 1.0, 2.0, 2.0);
+    }
+
+    context.set_stroke_style_str("black");
+    context.set_line_width(1.0);
+    context.begin_path();
+    context.move_to(SCATTER_MARGIN, 0.0);
+    context.line_to(SCATTER_MARGIN, SCATTER_SIZE);
+    context.line_to(SCATTER_MARGIN + SCATTER_SIZE, SCATTER_SIZE);
+    context.stroke();
+
+    context.set_fill_style_str("black");
+    context.set_text_align("center");
+    let _ = context.fill_text(name_x, SCATTER_MARGIN + SCATTER_SIZE/2.0, SCATTER_SIZE + SCATTER_MARGIN/2.0);
+    context.save();
+    let _ = context.translate(SCATTER_MARGIN/2.0, SCATTER_SIZE/2.0);
+    let _ = context.rotate(-std::f64::consts::FRAC_PI_2);
+    let _ = context.fill_text(name_y, 0.0, 0.0);
+    context.restore();
+}
@@ -0,0 +1,325 @@
+use my_web_app::CountFileMetaColumnData;
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlCanvasElement, WebGlRenderingContext as GL};
+use yew::{html, Callback, Component, Context, Html, MouseEvent, NodeRef, WheelEvent};
+use yew::Properties;
+
+use crate::appstate::AsyncData;
+use crate::camera::{Camera2D, Rectangle2D};
+use crate::component_reduction_correlation::pair_reduction_axis;
+use crate::component_reduction_main::{compile_shader_program, get_palette_for_categories, mouseevent_get_cx, set_camera_uniforms, ReductionViewData, DEFAULT_FRAG_SHADER};
+
+
+////////////////////////////////////////////////////////////
+/// Size of the scatter plot, in pixels
+const CROSS_PROJECTION_CANVAS_SIZE: u32 = 400;
+
+////////////////////////////////////////////////////////////
+/// Color of the points when there is no categorical coloring
+const CROSS_PROJECTION_POINT_COLOR: (f32, f32, f32) = (0.0, 0.3, 0.8);
+
+
+////////////////////////////////////////////////////////////
+/// Message sent to the event system for updating the page
+#[derive(Debug)]
+pub enum MsgCrossProjection {
+    MouseWheel(f32, f32, f32),   // dy, camera position
+    MouseDown(f32, f32),         // camera position
+    MouseMove(f32, f32),
+    MouseUp,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Properties for CrossProjectionView
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub name_umap: String,
+    pub name_pca: String,
+    pub reduction_umap: AsyncData<ReductionViewData>,
+    pub reduction_pca: AsyncData<ReductionViewData>,
+    pub clusters: AsyncData<CountFileMetaColumnData>,   // points are colored by category, if categorical
+    pub on_select: Callback<Vec<usize>>,   // cells within the rectangle drawn
+    pub on_close: Callback<()>,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Scatter plot of the first UMAP coordinate of each cell against its first principal component,
+/// colored by cluster. Shows how the nonlinear embedding reorders cells along the main axis of variance.
+/// Cells in a rectangle drawn on the plot are selected, also in the reductions
+pub struct CrossProjectionView {
+    node_ref: NodeRef,
+    camera: Camera2D,
+    paired: Option<ReductionViewData>,
+    fit_camera: bool,   // set when there is a new pairing to show
+    last_reductions: (AsyncData<ReductionViewData>, AsyncData<ReductionViewData>),
+    current_selection: Option<Rectangle2D>,   // in world coordinates, while dragging
+}
+impl CrossProjectionView {
+
+    ////////////////////////////////////////////////////////////
+    /// Pair the reductions again if they changed
+    fn update_pairing(&mut self, ctx: &Context<Self>) {
+        let reductions = (ctx.props().reduction_umap.clone(), ctx.props().reduction_pca.clone());
+        if reductions == self.last_reductions {
+            return;
+        }
+        self.paired = match &reductions {
+            (AsyncData::Loaded(umap), AsyncData::Loaded(pca)) => pair_reduction_axis(umap, pca, 0),
+            _ => None
+        };
+        self.fit_camera = true;
+        self.last_reductions = reductions;
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Cells whose paired coordinates are within a rectangle
+    fn cells_in_rect(&self, rect: &Rectangle2D) -> Vec<usize> {
+        let Some(paired) = &self.paired else {
+            return Vec::new();
+        };
+        let (x1, x2) = rect.range_x();
+        let (y1, y2) = rect.range_y();
+        (0..paired.num_point).filter(|i| {
+            let (x, y) = (paired.data[i*2+0], paired.data[i*2+1]);
+            x1 <= x && x <= x2 && y1 <= y && y <= y2
+        }).collect()
+    }
+
+}
+
+impl Component for CrossProjectionView {
+    type Message = MsgCrossProjection;
+    type Properties = Props;
+
+    ////////////////////////////////////////////////////////////
+    /// Create this component
+    fn create(ctx: &Context<Self>) -> Self {
+        let mut view = Self {
+            node_ref: NodeRef::default(),
+            camera: Camera2D::new(),
+            paired: None,
+            fit_camera: false,
+            last_reductions: (AsyncData::NotLoaded, AsyncData::NotLoaded),
+            current_selection: None,
+        };
+        view.update_pairing(ctx);
+        view
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Properties have changed
+    fn changed(&mut self, ctx: &Context<Self>, _old_props: &Self::Properties) -> bool {
+        self.update_pairing(ctx);
+        true
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Handle an update message
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+
+            ////////////////////////////////////////////////////////////
+            // Message: Mouse wheel rotated
+            MsgCrossProjection::MouseWheel(dy, cx, cy) => {
+                let (wx, wy) = self.camera.cam2world(cx, cy);
+                let scale = (10.0f32).powf(dy / 100.0);
+                self.camera.zoom_around(wx, wy, scale);
+                true
+            },
+
+            ////////////////////////////////////////////////////////////
+            // Message: Mouse button pressed; start drawing a rectangle
+            MsgCrossProjection::MouseDown(cx, cy) => {
+                let (wx, wy) = self.camera.cam2world(cx, cy);
+                self.current_selection = Some(Rectangle2D { x1: wx, x2: wx, y1: wy, y2: wy });
+                true
+            },
+
+            ////////////////////////////////////////////////////////////
+            // Message: Mouse moved; resize the rectangle, if drawing one
+            MsgCrossProjection::MouseMove(cx, cy) => {
+                if let Some(rect) = &mut self.current_selection {
+                    (rect.x2, rect.y2) = self.camera.cam2world(cx, cy);
+                    true
+                } else {
+                    false
+                }
+            },
+
+            ////////////////////////////////////////////////////////////
+            // Message: Mouse button released; select the cells in the rectangle
+            MsgCrossProjection::MouseUp => {
+                if let Some(rect) = self.current_selection.take() {
+                    ctx.props().on_select.emit(self.cells_in_rect(&rect));
+                }
+                true
+            },
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Render the panel
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+
+        let on_close = props.on_close.clone();
+        let cb_close = Callback::from(move |_e: MouseEvent| {
+            on_close.emit(());
+        });
+
+        let cb_mousewheel = ctx.link().callback(move |e: WheelEvent| {
+            e.prevent_default();
+            let (cx, cy) = mouseevent_get_cx(&e);
+            MsgCrossProjection::MouseWheel(e.delta_y() as f32, cx, cy)
+        });
+        let cb_mousedown = ctx.link().callback(move |e: MouseEvent| {
+            let (cx, cy) = mouseevent_get_cx(&e);
+            MsgCrossProjection::MouseDown(cx, cy)
+        });
+        let cb_mousemove = ctx.link().callback(move |e: MouseEvent| {
+            let (cx, cy) = mouseevent_get_cx(&e);
+            MsgCrossProjection::MouseMove(cx, cy)
+        });
+        let cb_mouseup = ctx.link().callback(move |_e: MouseEvent| {
+            MsgCrossProjection::MouseUp
+        });
+
+        //Cells are paired by index, so the reductions should have the same cells
+        let summary = match (&props.reduction_umap, &props.reduction_pca) {
+            (AsyncData::Loaded(umap), AsyncData::Loaded(pca)) => {
+                if umap.num_point == pca.num_point {
+                    html! { <div>{ format!("{} cells. Drag to select", umap.num_point) }</div> }
+                } else {
+                    html! { <div>{ format!("Different number of cells ({} vs {}); only the first {} shown", umap.num_point, pca.num_point, umap.num_point.min(pca.num_point)) }</div> }
+                }
+            },
+            _ => html! { <div>{"Loading reductions..."}</div> }
+        };
+
+        //Rectangle being drawn
+        let size = CROSS_PROJECTION_CANVAS_SIZE as f32;
+        let html_select = if let Some(rect) = &self.current_selection {
+            let (x1, x2) = rect.range_x();
+            let (y1, y2) = rect.range_y();
+            let (x1, y1) = self.camera.world2cam(x1, y1);
+            let (x2, y2) = self.camera.world2cam(x2, y2);
+            let (x1, x2) = (x1*size/2.0 + size/2.0, x2*size/2.0 + size/2.0);
+            let (y1, y2) = (y1*size/2.0 + size/2.0, y2*size/2.0 + size/2.0);
+            html! {
+                <rect x={x1.to_string()} y={y1.to_string()} width={(x2-x1).to_string()} height={(y2-y1).to_string()} fill-opacity="0.1" fill="blue" stroke-width="2" stroke="black" stroke-dasharray="5,5"/>
+            }
+        } else {
+            html! {""}
+        };
+
+        html! {
+            <div class="biscvi-compare-panel">
+                <button onclick={cb_close}>{"Close"}</button>
+                <div class="bisci-label-left">
+                    { format!("x: {} dimension 1, y: {} dimension 1", props.name_umap, props.name_pca) }
+                </div>
+                { summary }
+                <div style={format!("position: relative; width: {}px; height: {}px;", CROSS_PROJECTION_CANVAS_SIZE, CROSS_PROJECTION_CANVAS_SIZE)}>
+                    <canvas ref={self.node_ref.clone()}
+                        onwheel={cb_mousewheel} onmousedown={cb_mousedown} onmousemove={cb_mousemove} onmouseup={cb_mouseup}
+                        width={CROSS_PROJECTION_CANVAS_SIZE.to_string()}
+                        height={CROSS_PROJECTION_CANVAS_SIZE.to_string()}
+                        style={format!("position: absolute; left: 0; top: 0; width: {}px; height: {}px;", CROSS_PROJECTION_CANVAS_SIZE, CROSS_PROJECTION_CANVAS_SIZE)}
+                    />
+                    <svg style={format!("position: absolute; left: 0; top: 0; width: {}px; height: {}px; pointer-events: none;", CROSS_PROJECTION_CANVAS_SIZE, CROSS_PROJECTION_CANVAS_SIZE)}>
+                        { html_select }
+                    </svg>
+                </div>
+            </div>
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Called after DOM has been generated
+    fn rendered(&mut self, ctx: &Context<Self>, _first_render: bool) {
+
+        //Fit the camera whenever there is a new pairing to show
+        if self.fit_camera {
+            if let Some(paired) = &self.paired {
+                self.camera.set_aspect_ratio(1.0);
+                self.camera.fit_reduction(paired);
+                self.fit_camera = false;
+            }
+        }
+
+        if let (Some(paired), Some(canvas)) = (&self.paired, self.node_ref.cast::<HtmlCanvasElement>()) {
+            let categories = match &ctx.props().clusters {
+                AsyncData::Loaded(data) => match data.as_ref() {
+                    CountFileMetaColumnData::Categorical(cats, names) => Some((cats, names.len())),
+                    _ => None
+                },
+                _ => None
+            };
+            draw_points_by_category(&canvas, paired, categories, &self.camera);
+        }
+    }
+
+}
+
+
+////////////////////////////////////////////////////////////
+/// Draw the coordinates of each cell, colored by category if given
+pub fn draw_points_by_category(canvas: &HtmlCanvasElement, paired: &ReductionViewData, categories: Option<(&Vec<u32>, usize)>, camera: &Camera2D) {
+    let gl: GL = canvas
+        .get_context("webgl")
+        .unwrap()
+        .unwrap()
+        .dyn_into()
+        .unwrap();
+
+    //Same layout as for the reduction: position, then color
+    let palette = categories.map(|(_, num_cats)| get_palette_for_categories(num_cats));
+    let vec_vertex_size = 6;
+    let mut vec_vertex: Vec<f32> = Vec::with_capacity(paired.num_point*vec_vertex_size);
+    for i in 0..paired.num_point {
+        vec_vertex.extend([paired.data[i*2+0], paired.data[i*2+1], 0.0]);
+        let color = match (categories, &palette) {
+            (Some((cats, _)), Some(palette)) if !palette.is_empty() => {
+                let cat = cats.get(i).cloned().unwrap_or(0) as usize;
+                palette[cat % palette.len()]
+            },
+            _ => CROSS_PROJECTION_POINT_COLOR
+        };
+        vec_vertex.extend([color.0, color.1, color.2]);
+    }
+
+    let vertex_buffer = gl.create_buffer().unwrap();
+    let js_vertex = js_sys::Float32Array::from(vec_vertex.as_slice());
+    gl.bind_buffer(GL::ARRAY_BUFFER, Some(&vertex_buffer));
+    gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &js_vertex, GL::STATIC_DRAW);
+
+    let shader_program = compile_shader_program(&gl, include_str!("./umap.vert"), DEFAULT_FRAG_SHADER);
+    gl.use_program(Some(&shader_program));
+
+    let sizeof_float = 4;
+    let a_position = gl.get_attrib_location(&shader_program, "a_position") as u32;
+    gl.enable_vertex_attrib_array(a_position);
+    gl.vertex_attrib_pointer_with_i32(a_position, 3, GL::FLOAT, false, sizeof_float*6, 0);
+    let a_color = gl.get_attrib_location(&shader_program, "a_color") as u32;
+    gl.enable_vertex_attrib_array(a_color);
+    gl.vertex_attrib_pointer_with_i32(a_color, 3, GL::FLOAT, false, sizeof_float*6, sizeof_float*3);
+
+    set_camera_uniforms(&gl, &shader_program, camera, canvas.width() as i32, canvas.height() as i32);
+    gl.uniform1f(gl.get_uniform_location(&shader_program, "u_point_size").as_ref(), 3.0);
+    gl.uniform1f(gl.get_uniform_location(&shader_program, "u_alpha").as_ref(), 1.0);
+
+    gl.clear_color(1.0, 1.0, 1.0, 1.0);
+    gl.clear(GL::COLOR_BUFFER_BIT);
+    gl.draw_arrays(GL::POINTS, 0, paired.num_point as i32);
+
+    gl.disable_vertex_attrib_array(a_position);
+    gl.disable_vertex_attrib_array(a_color);
+    gl.delete_buffer(Some(&vertex_buffer));
+}
@@ -0,0 +1,128 @@
+use my_web_app::GrnResponse;
+use yew::{html, Component, Context, Html};
+use yew::Properties;
+
+use crate::grn_layout::{layout_grn, GrnNode, GrnNodeKind};
+
+
+////////////////////////////////////////////////////////////
+/// Size of the drawing, and the space left around the layout for labels, in pixels
+const GRN_WIDTH: f32 = 300.0;
+const GRN_HEIGHT: f32 = 240.0;
+const GRN_MARGIN: f32 = 30.0;
+
+
+////////////////////////////////////////////////////////////
+/// Properties for GrnView
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub gene: String,
+    pub network: Option<GrnResponse>,   // None while loading
+}
+
+
+////////////////////////////////////////////////////////////
+/// Gene regulatory network around a feature: its regulators above it and its targets below.
+/// Edges are green if activating and red if repressing, thicker for stronger weights
+pub struct GrnView {
+    nodes: Vec<GrnNode>,
+}
+
+impl GrnView {
+
+    ////////////////////////////////////////////////////////////
+    /// Lay out the network again
+    fn update_layout(&mut self, ctx: &Context<Self>) {
+        self.nodes = match &ctx.props().network {
+            Some(network) => layout_grn(&ctx.props().gene, network),
+            None => Vec::new()
+        };
+    }
+
+}
+
+impl Component for GrnView {
+    type Message = ();
+    type Properties = Props;
+
+    ////////////////////////////////////////////////////////////
+    /// Create this component
+    fn create(ctx: &Context<Self>) -> Self {
+        let mut view = Self {
+            nodes: Vec::new(),
+        };
+        view.update_layout(ctx);
+        view
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Properties have changed
+    fn changed(&mut self, ctx: &Context<Self>, _old_props: &Self::Properties) -> bool {
+        self.update_layout(ctx);
+        true
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Render the panel
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+
+        let graph = if props.network.is_none() {
+            html! { <div>{"Loading..."}</div> }
+        } else if self.nodes.len() <= 1 {
+            html! { <div>{"No known regulators or targets"}</div> }
+        } else {
+            //Fit the layout into the drawing, with the same scale along both axes
+            let (min_x, max_x) = self.nodes.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), n| (lo.min(n.x), hi.max(n.x)));
+            let (min_y, max_y) = self.nodes.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), n| (lo.min(n.y), hi.max(n.y)));
+            let scale = ((GRN_WIDTH - 2.0*GRN_MARGIN)/(max_x - min_x).max(1e-3)).min((GRN_HEIGHT - 2.0*GRN_MARGIN)/(max_y - min_y).max(1e-3));
+            let to_pixel = |n: &GrnNode| (
+                GRN_WIDTH/2.0 + (n.x - (min_x + max_x)/2.0)*scale,
+                GRN_HEIGHT/2.0 + (n.y - (min_y + max_y)/2.0)*scale,
+            );
+
+            let max_weight = self.nodes.iter().map(|n| n.weight.abs()).fold(0.0, f32::max);
+            let (cx, cy) = to_pixel(&self.nodes[0]);
+            let edges = self.nodes.iter().skip(1).map(|n| {
+                let (x, y) = to_pixel(n);
+                let width = if max_weight > 0.0 { 0.5 + 2.5*n.weight.abs()/max_weight } else { 1.0 };
+                let class = if n.weight < 0.0 { "biscvi-grn-edge-repress" } else { "biscvi-grn-edge-activate" };
+                html! {
+                    <line x1={cx.to_string()} y1={cy.to_string()} x2={x.to_string()} y2={y.to_string()} class={class} stroke-width={width.to_string()}/>
+                }
+            }).collect::<Html>();
+
+            let nodes = self.nodes.iter().map(|n| {
+                let (x, y) = to_pixel(n);
+                let class = match n.kind {
+                    GrnNodeKind::Center => "biscvi-grn-node biscvi-grn-center",
+                    GrnNodeKind::Regulator => "biscvi-grn-node biscvi-grn-regulator",
+                    GrnNodeKind::Target => "biscvi-grn-node biscvi-grn-target",
+                };
+                html! {
+                    <g class={class}>
+                        <circle cx={x.to_string()} cy={y.to_string()} r="5"/>
+                        <text x={x.to_string()} y={(y - 8.0).to_string()} text-anchor="middle">{ n.name.clone() }</text>
+                    </g>
+                }
+            }).collect::<Html>();
+
+            html! {
+                <svg width={GRN_WIDTH.to_string()} height={GRN_HEIGHT.to_string()}>
+                    { edges }
+                    { nodes }
+                </svg>
+            }
+        };
+
+        html! {
+            <div class="biscvi-grn-panel">
+                <div class="bisci-label-left">{ format!("Regulatory network of {}", props.gene) }</div>
+                { graph }
+            </div>
+        }
+    }
+
+}
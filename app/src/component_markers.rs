@@ -0,0 +1,137 @@
+use my_web_app::FindMarkersResponse;
+use yew::{html, Callback, Component, Context, Html, MouseEvent};
+use yew::Properties;
+
+use crate::appstate::AsyncData;
+
+
+////////////////////////////////////////////////////////////
+/// Number of markers listed in the table. All are shown in the volcano plot
+const NUM_MARKER_ROWS: usize = 50;
+
+////////////////////////////////////////////////////////////
+/// Markers beyond these thresholds are highlighted in the volcano plot
+const MARKER_MAX_P_VALUE: f32 = 0.05;
+const MARKER_MIN_ABS_LOG_FC: f32 = 0.5;
+
+////////////////////////////////////////////////////////////
+/// Smallest p-value plotted; an f32 p-value can round to 0
+const MARKER_MIN_P_VALUE: f32 = 1e-38;
+
+
+////////////////////////////////////////////////////////////
+/// Properties for MarkerView
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub markers: AsyncData<FindMarkersResponse>,
+    pub num_a: usize,   // cells selected
+    pub num_b: usize,   // all other cells
+    pub on_close: Callback<()>,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Side panel with the features that differ the most between the selected cells and the rest,
+/// as a ranked table and a volcano plot
+pub struct MarkerView {
+}
+
+impl Component for MarkerView {
+    type Message = ();
+    type Properties = Props;
+
+    ////////////////////////////////////////////////////////////
+    /// Create this component
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Render the panel
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+
+        let on_close = props.on_close.clone();
+        let cb_close = Callback::from(move |_e: MouseEvent| {
+            on_close.emit(());
+        });
+
+        let content = if let AsyncData::Loaded(res) = &props.markers {
+
+            //Volcano plot: fold change against significance
+            let (svg_w, svg_h, margin) = (300.0, 220.0, 30.0);
+            let max_fc = res.markers.iter().map(|m| m.log_fc.abs()).fold(MARKER_MIN_ABS_LOG_FC, f32::max);
+            let neg_log_p = |p: f32| -(p.max(MARKER_MIN_P_VALUE)).log10();
+            let max_y = res.markers.iter().map(|m| neg_log_p(m.p_value)).fold(-MARKER_MAX_P_VALUE.log10(), f32::max);
+            let to_x = |fc: f32| margin + (fc/max_fc + 1.0)*0.5*(svg_w - 2.0*margin);
+            let to_y = |y: f32| svg_h - margin - y/max_y*(svg_h - 2.0*margin);
+
+            let list_points = res.markers.iter().map(|m| {
+                let significant = m.p_value <= MARKER_MAX_P_VALUE && m.log_fc.abs() >= MARKER_MIN_ABS_LOG_FC;
+                let color = if !significant { "#AAAAAA" } else if m.log_fc > 0.0 { "#D62728" } else { "#1F77B4" };
+                html! {
+                    <circle cx={format!("{}", to_x(m.log_fc))} cy={format!("{}", to_y(neg_log_p(m.p_value)))} r="2" fill={color}>
+                        <title>{ format!("{}: log2 FC {:.2}, p {:.2e}", m.gene, m.log_fc, m.p_value) }</title>
+                    </circle>
+                }
+            }).collect::<Html>();
+
+            let y_threshold = to_y(-MARKER_MAX_P_VALUE.log10());
+            let volcano = html! {
+                <svg width={format!("{}", svg_w)} height={format!("{}", svg_h)}>
+                    <line x1={format!("{}", margin)} y1={format!("{}", svg_h - margin)} x2={format!("{}", svg_w - margin)} y2={format!("{}", svg_h - margin)} stroke="black"/>
+                    <line x1={format!("{}", to_x(0.0))} y1={format!("{}", margin)} x2={format!("{}", to_x(0.0))} y2={format!("{}", svg_h - margin)} stroke="lightgray"/>
+                    <line x1={format!("{}", margin)} y1={format!("{}", y_threshold)} x2={format!("{}", svg_w - margin)} y2={format!("{}", y_threshold)} stroke="lightgray" stroke-dasharray="4"/>
+                    { list_points }
+                    <text x={format!("{}", svg_w/2.0)} y={format!("{}", svg_h - 8.0)} text-anchor="middle" font-size="12">{"log2 fold change"}</text>
+                    <text x="12" y={format!("{}", svg_h/2.0)} text-anchor="middle" font-size="12" transform={format!("rotate(-90, 12, {})", svg_h/2.0)}>{"-log10 p"}</text>
+                    <text x={format!("{}", margin)} y={format!("{}", svg_h - margin + 14.0)} text-anchor="middle" font-size="10">{ format!("{:.1}", -max_fc) }</text>
+                    <text x={format!("{}", svg_w - margin)} y={format!("{}", svg_h - margin + 14.0)} text-anchor="middle" font-size="10">{ format!("{:.1}", max_fc) }</text>
+                </svg>
+            };
+
+            let list_rows = res.markers.iter().take(NUM_MARKER_ROWS).enumerate().map(|(rank, m)| {
+                html! {
+                    <tr>
+                        <td>{ rank + 1 }</td>
+                        <td>{ m.gene.clone() }</td>
+                        <td>{ format!("{:.2}", m.log_fc) }</td>
+                        <td>{ format!("{:.2e}", m.p_value) }</td>
+                    </tr>
+                }
+            }).collect::<Html>();
+
+            html! {
+                <div>
+                    { volcano }
+                    <table>
+                        <tr>
+                            <th>{"#"}</th>
+                            <th>{"Feature"}</th>
+                            <th>{"log2 FC"}</th>
+                            <th>{"p-value"}</th>
+                        </tr>
+                        { list_rows }
+                    </table>
+                </div>
+            }
+        } else if let AsyncData::Error(e) = &props.markers {
+            html! { <div>{ format!("Could not find markers: {}", e) }</div> }
+        } else {
+            html! { <div>{"Finding markers..."}</div> }
+        };
+
+        html! {
+            <div class="biscvi-compare-panel">
+                <button onclick={cb_close}>{"Close"}</button>
+                <div class="bisci-label-left">
+                    { format!("Markers of {} selected cells against {} others", props.num_a, props.num_b) }
+                </div>
+                { content }
+            </div>
+        }
+    }
+
+}
@@ -0,0 +1,117 @@
+use yew::{html, Callback, Component, Context, Html, MouseEvent};
+use yew::Properties;
+
+use crate::component_reduction_main::{rgbvec2string, Color3f};
+use crate::parallel_coords::{parallel_polyline_points, ParallelAxis};
+
+
+////////////////////////////////////////////////////////////
+/// Size of the plot, in pixels. Axis names are drawn below it
+const PARALLEL_W: f32 = 400.0;
+const PARALLEL_H: f32 = 200.0;
+const PARALLEL_MARGIN: f32 = 30.0;
+
+
+////////////////////////////////////////////////////////////
+/// Properties for ParallelCoordsView
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub subset_indices: Vec<usize>,   // cells drawn as lines
+    pub axes: Vec<ParallelAxis>,      // values of each cell of subset_indices, in the same order
+    pub colors: Vec<Color3f>,         // of each cell of subset_indices
+    pub highlighted_cell: Option<usize>,
+    pub on_cell_clicked: Callback<usize>,
+    pub on_close: Callback<()>,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Panel with a parallel coordinates plot of the selected cells: one vertical axis per numeric
+/// column, and one line per cell through its values
+pub struct ParallelCoordsView {
+}
+
+impl Component for ParallelCoordsView {
+    type Message = ();
+    type Properties = Props;
+
+    ////////////////////////////////////////////////////////////
+    /// Create this component
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Render the panel
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+
+        let on_close = props.on_close.clone();
+        let cb_close = Callback::from(move |_e: MouseEvent| {
+            on_close.emit(());
+        });
+
+        let chart = if props.subset_indices.is_empty() {
+            html! { <div>{"Select cells to compare"}</div> }
+        } else if props.axes.len() < 2 {
+            html! { <div>{"Load at least two numeric columns to compare"}</div> }
+        } else {
+            let step = PARALLEL_W / (props.axes.len() - 1) as f32;
+            let list_axes = props.axes.iter().enumerate().map(|(k, axis)| {
+                let x = k as f32*step;
+                html! {
+                    <>
+                        <line x1={format!("{}", x)} y1="0" x2={format!("{}", x)} y2={format!("{}", PARALLEL_H)} stroke="#333333"/>
+                        <text x={format!("{}", x)} y="-4" text-anchor="middle" font-size="10">{ format!("{:.2}", axis.max) }</text>
+                        <text x={format!("{}", x)} y={format!("{}", PARALLEL_H + 12.0)} text-anchor="middle" font-size="10">{ format!("{:.2}", axis.min) }</text>
+                        <text x={format!("{}", x)} y={format!("{}", PARALLEL_H + 24.0)} text-anchor="middle" font-size="12">{ axis.name.clone() }</text>
+                    </>
+                }
+            }).collect::<Html>();
+
+            //The highlighted cell is drawn last, so that it is on top
+            let mut order: Vec<usize> = (0..props.subset_indices.len()).collect();
+            order.sort_by_key(|pos| Some(props.subset_indices[*pos]) == props.highlighted_cell);
+            let list_lines = order.iter().map(|pos| {
+                let cell = props.subset_indices[*pos];
+                let highlighted = Some(cell) == props.highlighted_cell;
+                let color = props.colors.get(*pos).copied().unwrap_or((0.5, 0.5, 0.5));
+                let on_cell_clicked = props.on_cell_clicked.clone();
+                let cb_click = Callback::from(move |_e: MouseEvent| {
+                    on_cell_clicked.emit(cell);
+                });
+                html! {
+                    <polyline points={parallel_polyline_points(&props.axes, *pos, PARALLEL_W, PARALLEL_H)}
+                        fill="none" stroke={rgbvec2string(color)}
+                        stroke-width={if highlighted { "3" } else { "1" }}
+                        stroke-opacity={if highlighted { "1" } else { "0.6" }}
+                        style="cursor: pointer;" onclick={cb_click}>
+                        <title>{ format!("Cell {}", cell) }</title>
+                    </polyline>
+                }
+            }).collect::<Html>();
+
+            html! {
+                <svg width={format!("{}", PARALLEL_W + 2.0*PARALLEL_MARGIN)} height={format!("{}", PARALLEL_H + 2.0*PARALLEL_MARGIN)}>
+                    <g transform={format!("translate({},{})", PARALLEL_MARGIN, PARALLEL_MARGIN/2.0)}>
+                        { list_lines }
+                        { list_axes }
+                    </g>
+                </svg>
+            }
+        };
+
+        html! {
+            <div class="biscvi-compare-panel">
+                <button onclick={cb_close}>{"Close"}</button>
+                <div class="bisci-label-left">
+                    { format!("Profiles of {} cells", props.subset_indices.len()) }
+                </div>
+                { chart }
+            </div>
+        }
+    }
+
+}
@@ -0,0 +1,163 @@
+use std::sync::Arc;
+
+use my_web_app::CountFileMetaColumnData;
+use yew::{html, Callback, Component, Context, Html, MouseEvent};
+use yew::Properties;
+
+use crate::appstate::AsyncData;
+use crate::component_reduction_main::ReductionViewData;
+use crate::correlation::dense_numeric_values;
+use crate::trajectory::{expression_along_path, PseudotimeBin, TrajectoryPath};
+
+
+////////////////////////////////////////////////////////////
+/// Number of bins along the trajectory
+const PSEUDOTIME_NUM_BINS: usize = 50;
+
+////////////////////////////////////////////////////////////
+/// Cells further from the trajectory than this fraction of the reduction size are left out
+const PSEUDOTIME_MAX_DISTANCE: f32 = 0.05;
+
+////////////////////////////////////////////////////////////
+/// Size of the chart, and the space left for the axes, in pixels
+const CHART_WIDTH: f32 = 500.0;
+const CHART_HEIGHT: f32 = 160.0;
+const CHART_MARGIN_LEFT: f32 = 50.0;
+const CHART_MARGIN_BOTTOM: f32 = 25.0;
+const CHART_MARGIN_TOP: f32 = 5.0;
+
+
+////////////////////////////////////////////////////////////
+/// Properties for PseudotimeChartView
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub reduction: AsyncData<ReductionViewData>,
+    pub trajectory: Vec<usize>,   // ordered cells
+    pub column_name: String,
+    pub column: AsyncData<CountFileMetaColumnData>,
+    pub on_close: Callback<()>,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Line chart of the mean of a numeric column, e.g. expression of a gene, along a trajectory.
+/// The shaded area covers one standard deviation on either side of the mean
+pub struct PseudotimeChartView {
+    input: Option<(Arc<ReductionViewData>, Vec<usize>, Arc<CountFileMetaColumnData>)>,   // the bins were computed from
+    bins: Vec<Option<PseudotimeBin>>,
+}
+
+impl PseudotimeChartView {
+
+    ////////////////////////////////////////////////////////////
+    /// Bin the cells again if the reduction, trajectory or column changed. This is slow for
+    /// many cells, so it is not done on every render
+    fn update_bins(&mut self, ctx: &Context<Self>) {
+        let props = ctx.props();
+        let (AsyncData::Loaded(reduction), AsyncData::Loaded(column)) = (&props.reduction, &props.column) else {
+            self.input = None;
+            self.bins.clear();
+            return;
+        };
+        if let Some((last_reduction, last_trajectory, last_column)) = &self.input {
+            if Arc::ptr_eq(last_reduction, reduction) && *last_trajectory == props.trajectory && Arc::ptr_eq(last_column, column) {
+                return;
+            }
+        }
+
+        self.bins = match dense_numeric_values(column, reduction.num_point) {
+            Some(values) => {
+                let path = TrajectoryPath::new(reduction, &props.trajectory);
+                let span = (reduction.max_x - reduction.min_x).max(reduction.max_y - reduction.min_y);
+                expression_along_path(reduction, &path, &values, PSEUDOTIME_NUM_BINS, span*PSEUDOTIME_MAX_DISTANCE)
+            },
+            None => Vec::new()
+        };
+        self.input = Some((reduction.clone(), props.trajectory.clone(), column.clone()));
+    }
+
+}
+
+impl Component for PseudotimeChartView {
+    type Message = ();
+    type Properties = Props;
+
+    ////////////////////////////////////////////////////////////
+    /// Create this component
+    fn create(ctx: &Context<Self>) -> Self {
+        let mut view = Self {
+            input: None,
+            bins: Vec::new(),
+        };
+        view.update_bins(ctx);
+        view
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Properties have changed
+    fn changed(&mut self, ctx: &Context<Self>, _old_props: &Self::Properties) -> bool {
+        self.update_bins(ctx);
+        true
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Render the panel
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+
+        let on_close = props.on_close.clone();
+        let cb_close = Callback::from(move |_e: MouseEvent| {
+            on_close.emit(());
+        });
+
+        //Range of the shaded area, over all bins with cells
+        let filled: Vec<(usize, &PseudotimeBin)> = self.bins.iter().enumerate().filter_map(|(i, bin)| bin.as_ref().map(|bin| (i, bin))).collect();
+        let min_y = filled.iter().map(|(_, bin)| bin.mean - bin.sd).fold(f32::INFINITY, f32::min);
+        let max_y = filled.iter().map(|(_, bin)| bin.mean + bin.sd).fold(f32::NEG_INFINITY, f32::max);
+
+        let chart = if self.input.is_none() {
+            html! { <div>{"Loading..."}</div> }
+        } else if filled.is_empty() {
+            html! { <div>{"No numeric values for cells along the trajectory"}</div> }
+        } else {
+            let plot_w = CHART_WIDTH - CHART_MARGIN_LEFT;
+            let plot_h = CHART_HEIGHT - CHART_MARGIN_BOTTOM - CHART_MARGIN_TOP;
+            let range_y = if max_y > min_y { max_y - min_y } else { 1.0 };
+            let to_x = |i: usize| CHART_MARGIN_LEFT + plot_w*(i as f32 + 0.5)/(self.bins.len() as f32);
+            let to_y = |v: f32| CHART_MARGIN_TOP + plot_h*(1.0 - (v - min_y)/range_y);
+
+            //Mean as a line; the band goes along the upper edge, then back along the lower edge
+            let line = filled.iter().map(|(i, bin)| format!("{},{}", to_x(*i), to_y(bin.mean))).collect::<Vec<_>>().join(" ");
+            let band = filled.iter().map(|(i, bin)| format!("{},{}", to_x(*i), to_y(bin.mean + bin.sd)))
+                .chain(filled.iter().rev().map(|(i, bin)| format!("{},{}", to_x(*i), to_y(bin.mean - bin.sd))))
+                .collect::<Vec<_>>().join(" ");
+
+            let axis_y = CHART_HEIGHT - CHART_MARGIN_BOTTOM;
+            html! {
+                <svg class="biscvi-pseudotime-chart" width={CHART_WIDTH.to_string()} height={CHART_HEIGHT.to_string()}>
+                    <polygon points={band} class="biscvi-pseudotime-band"/>
+                    <polyline points={line} class="biscvi-pseudotime-mean"/>
+                    <line x1={CHART_MARGIN_LEFT.to_string()} y1={axis_y.to_string()} x2={CHART_WIDTH.to_string()} y2={axis_y.to_string()} stroke="black"/>
+                    <line x1={CHART_MARGIN_LEFT.to_string()} y1={CHART_MARGIN_TOP.to_string()} x2={CHART_MARGIN_LEFT.to_string()} y2={axis_y.to_string()} stroke="black"/>
+                    <text x={(CHART_MARGIN_LEFT - 4.0).to_string()} y={to_y(max_y).to_string()} dy="0.3em" text-anchor="end">{ format!("{:.2}", max_y) }</text>
+                    <text x={(CHART_MARGIN_LEFT - 4.0).to_string()} y={to_y(min_y).to_string()} dy="0.3em" text-anchor="end">{ format!("{:.2}", min_y) }</text>
+                    <text x={(CHART_MARGIN_LEFT + plot_w/2.0).to_string()} y={(CHART_HEIGHT - 5.0).to_string()} text-anchor="middle">{"Pseudotime"}</text>
+                </svg>
+            }
+        };
+
+        let num_cells: usize = filled.iter().map(|(_, bin)| bin.num_cells).sum();
+        html! {
+            <div class="biscvi-pseudotime-panel">
+                <button onclick={cb_close}>{"Close"}</button>
+                <div class="bisci-label-left">
+                    { format!("Mean {} along the trajectory, {} cells", props.column_name, num_cells) }
+                </div>
+                { chart }
+            </div>
+        }
+    }
+
+}
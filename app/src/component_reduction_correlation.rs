@@ -0,0 +1,283 @@
+use my_web_app::ReductionResponse;
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlCanvasElement, WebGlRenderingContext as GL};
+use yew::{html, Callback, Component, Context, Html, MouseEvent, NodeRef, WheelEvent};
+use yew::Properties;
+
+use crate::appstate::AsyncData;
+use crate::camera::Camera2D;
+use crate::component_reduction_main::{compile_shader_program, convert_from_response_to_reduction_data, mouseevent_get_cx, set_camera_uniforms, ReductionViewData, DEFAULT_FRAG_SHADER};
+use crate::correlation::pearson_correlation;
+
+
+////////////////////////////////////////////////////////////
+/// Size of the scatter plot, in pixels. It is square so that both axes have the same scale
+const CORRELATION_CANVAS_SIZE: u32 = 400;
+
+////////////////////////////////////////////////////////////
+/// Color of the points and of the diagonal, where both reductions agree
+const CORRELATION_POINT_COLOR: (f32, f32, f32) = (0.0, 0.3, 0.8);
+const CORRELATION_DIAGONAL_COLOR: (f32, f32, f32) = (0.8, 0.0, 0.0);
+
+
+////////////////////////////////////////////////////////////
+/// Pair cells of two reductions by index. The result has the coordinate along the given axis
+/// in the first reduction as x, and in the second reduction as y. Cells beyond the end of the
+/// shorter reduction are left out. None if a reduction has fewer coordinates than points
+pub fn pair_reduction_axis(red_a: &ReductionViewData, red_b: &ReductionViewData, axis: usize) -> Option<ReductionViewData> {
+    let num_point = red_a.num_point.min(red_b.num_point);
+    let resp = ReductionResponse {
+        x: (0..num_point).filter_map(|i| red_a.data.get(i*2 + axis).cloned()).collect(),
+        y: (0..num_point).filter_map(|i| red_b.data.get(i*2 + axis).cloned()).collect(),
+        version: 0,
+    };
+    convert_from_response_to_reduction_data(resp).ok()
+}
+
+
+////////////////////////////////////////////////////////////
+/// Message sent to the event system for updating the page
+#[derive(Debug)]
+pub enum MsgCorrelation {
+    MouseWheel(f32, f32, f32),   // dy, camera position
+    SetAxis(usize),
+}
+
+
+////////////////////////////////////////////////////////////
+/// Properties for ReductionCorrelationView
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub name_a: String,
+    pub name_b: String,
+    pub reduction_a: AsyncData<ReductionViewData>,
+    pub reduction_b: AsyncData<ReductionViewData>,
+    pub on_close: Callback<()>,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Scatter plot of the coordinates of each cell in two reductions, e.g. two UMAP runs,
+/// to check that they are reproducible. Points on the diagonal are placed the same in both
+pub struct ReductionCorrelationView {
+    node_ref: NodeRef,
+    camera: Camera2D,
+    axis: usize,   // 0 for the first dimension, 1 for the second
+    paired: Option<ReductionViewData>,
+    correlation: Option<f32>,   // between the paired coordinates
+    fit_camera: bool,   // set when there is a new pairing to show
+    last_reductions: (AsyncData<ReductionViewData>, AsyncData<ReductionViewData>),
+    last_axis: usize,
+}
+impl ReductionCorrelationView {
+
+    ////////////////////////////////////////////////////////////
+    /// Pair the reductions again if they or the axis changed
+    fn update_pairing(&mut self, ctx: &Context<Self>) {
+        let reductions = (ctx.props().reduction_a.clone(), ctx.props().reduction_b.clone());
+        if reductions == self.last_reductions && self.axis == self.last_axis {
+            return;
+        }
+        self.paired = match &reductions {
+            (AsyncData::Loaded(red_a), AsyncData::Loaded(red_b)) => pair_reduction_axis(red_a, red_b, self.axis),
+            _ => None
+        };
+        self.correlation = self.paired.as_ref().and_then(|paired| {
+            let x: Vec<f32> = paired.data.iter().step_by(2).cloned().collect();
+            let y: Vec<f32> = paired.data.iter().skip(1).step_by(2).cloned().collect();
+            pearson_correlation(&x, &y, &(0..paired.num_point).collect())
+        });
+        self.fit_camera = true;
+        self.last_reductions = reductions;
+        self.last_axis = self.axis;
+    }
+
+}
+
+impl Component for ReductionCorrelationView {
+    type Message = MsgCorrelation;
+    type Properties = Props;
+
+    ////////////////////////////////////////////////////////////
+    /// Create this component
+    fn create(ctx: &Context<Self>) -> Self {
+        let mut view = Self {
+            node_ref: NodeRef::default(),
+            camera: Camera2D::new(),
+            axis: 0,
+            paired: None,
+            correlation: None,
+            fit_camera: false,
+            last_reductions: (AsyncData::NotLoaded, AsyncData::NotLoaded),
+            last_axis: 0,
+        };
+        view.update_pairing(ctx);
+        view
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Properties have changed
+    fn changed(&mut self, ctx: &Context<Self>, _old_props: &Self::Properties) -> bool {
+        self.update_pairing(ctx);
+        true
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Handle an update message
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+
+            ////////////////////////////////////////////////////////////
+            // Message: Mouse wheel rotated
+            MsgCorrelation::MouseWheel(dy, cx, cy) => {
+                let (wx, wy) = self.camera.cam2world(cx, cy);
+                let scale = (10.0f32).powf(dy / 100.0);
+                self.camera.zoom_around(wx, wy, scale);
+                true
+            },
+
+            ////////////////////////////////////////////////////////////
+            // Message: Dimension to compare selected
+            MsgCorrelation::SetAxis(axis) => {
+                self.axis = axis;
+                self.update_pairing(ctx);
+                true
+            },
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Render the panel
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+
+        let on_close = props.on_close.clone();
+        let cb_close = Callback::from(move |_e: MouseEvent| {
+            on_close.emit(());
+        });
+
+        let cb_mousewheel = ctx.link().callback(move |e: WheelEvent| {
+            e.prevent_default();
+            let (cx, cy) = mouseevent_get_cx(&e);
+            MsgCorrelation::MouseWheel(e.delta_y() as f32, cx, cy)
+        });
+
+        let axis_buttons = (0..2).map(|axis| {
+            let class = if axis == self.axis { "btn_top_active" } else { "btn_top_inactive" };
+            html! {
+                <button class={class} onclick={ctx.link().callback(move |_e: MouseEvent| MsgCorrelation::SetAxis(axis))}>
+                    { format!("Dimension {}", axis + 1) }
+                </button>
+            }
+        }).collect::<Html>();
+
+        //Cells are paired by index, so the reductions should have the same cells
+        let summary = match (&props.reduction_a, &props.reduction_b) {
+            (AsyncData::Loaded(red_a), AsyncData::Loaded(red_b)) => {
+                let correlation = self.correlation.map(|r| format!("r = {:.3}", r)).unwrap_or("r undefined".into());
+                if red_a.num_point == red_b.num_point {
+                    html! { <div>{ format!("{} cells, {}", red_a.num_point, correlation) }</div> }
+                } else {
+                    html! { <div>{ format!("Different number of cells ({} vs {}); only the first {} compared, {}", red_a.num_point, red_b.num_point, red_a.num_point.min(red_b.num_point), correlation) }</div> }
+                }
+            },
+            _ => html! { <div>{"Loading reductions..."}</div> }
+        };
+
+        html! {
+            <div class="biscvi-compare-panel">
+                <button onclick={cb_close}>{"Close"}</button>
+                <div class="bisci-label-left">
+                    { format!("x: {}, y: {}", props.name_a, props.name_b) }
+                </div>
+                <div>{ axis_buttons }</div>
+                { summary }
+                <canvas ref={self.node_ref.clone()} onwheel={cb_mousewheel}
+                    width={CORRELATION_CANVAS_SIZE.to_string()}
+                    height={CORRELATION_CANVAS_SIZE.to_string()}
+                    style={format!("width: {}px; height: {}px;", CORRELATION_CANVAS_SIZE, CORRELATION_CANVAS_SIZE)}
+                />
+            </div>
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Called after DOM has been generated
+    fn rendered(&mut self, _ctx: &Context<Self>, _first_render: bool) {
+
+        //Fit the camera whenever there is a new pairing to show
+        if self.fit_camera {
+            if let Some(paired) = &self.paired {
+                self.camera.set_aspect_ratio(1.0);
+                self.camera.fit_reduction(paired);
+                self.fit_camera = false;
+            }
+        }
+
+        if let (Some(paired), Some(canvas)) = (&self.paired, self.node_ref.cast::<HtmlCanvasElement>()) {
+            draw_paired_points(&canvas, paired, &self.camera);
+        }
+    }
+
+}
+
+
+////////////////////////////////////////////////////////////
+/// Draw the paired coordinates of each cell, and the diagonal
+fn draw_paired_points(canvas: &HtmlCanvasElement, paired: &ReductionViewData, camera: &Camera2D) {
+    let gl: GL = canvas
+        .get_context("webgl")
+        .unwrap()
+        .unwrap()
+        .dyn_into()
+        .unwrap();
+
+    //Same layout as for the reduction: position, then color
+    let vec_vertex_size = 6;
+    let mut vec_vertex: Vec<f32> = Vec::with_capacity((paired.num_point + 2)*vec_vertex_size);
+    for i in 0..paired.num_point {
+        vec_vertex.extend([paired.data[i*2+0], paired.data[i*2+1], 0.0]);
+        vec_vertex.extend([CORRELATION_POINT_COLOR.0, CORRELATION_POINT_COLOR.1, CORRELATION_POINT_COLOR.2]);
+    }
+
+    //Diagonal through the range of both axes
+    let lo = paired.min_x.min(paired.min_y);
+    let hi = paired.max_x.max(paired.max_y);
+    for v in [lo, hi] {
+        vec_vertex.extend([v, v, 0.0]);
+        vec_vertex.extend([CORRELATION_DIAGONAL_COLOR.0, CORRELATION_DIAGONAL_COLOR.1, CORRELATION_DIAGONAL_COLOR.2]);
+    }
+
+    let vertex_buffer = gl.create_buffer().unwrap();
+    let js_vertex = js_sys::Float32Array::from(vec_vertex.as_slice());
+    gl.bind_buffer(GL::ARRAY_BUFFER, Some(&vertex_buffer));
+    gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &js_vertex, GL::STATIC_DRAW);
+
+    let shader_program = compile_shader_program(&gl, include_str!("./umap.vert"), DEFAULT_FRAG_SHADER);
+    gl.use_program(Some(&shader_program));
+
+    let sizeof_float = 4;
+    let a_position = gl.get_attrib_location(&shader_program, "a_position") as u32;
+    gl.enable_vertex_attrib_array(a_position);
+    gl.vertex_attrib_pointer_with_i32(a_position, 3, GL::FLOAT, false, sizeof_float*6, 0);
+    let a_color = gl.get_attrib_location(&shader_program, "a_color") as u32;
+    gl.enable_vertex_attrib_array(a_color);
+    gl.vertex_attrib_pointer_with_i32(a_color, 3, GL::FLOAT, false, sizeof_float*6, sizeof_float*3);
+
+    set_camera_uniforms(&gl, &shader_program, camera, canvas.width() as i32, canvas.height() as i32);
+    gl.uniform1f(gl.get_uniform_location(&shader_program, "u_point_size").as_ref(), 3.0);
+    gl.uniform1f(gl.get_uniform_location(&shader_program, "u_alpha").as_ref(), 1.0);
+
+    gl.clear_color(1.0, 1.0, 1.0, 1.0);
+    gl.clear(GL::COLOR_BUFFER_BIT);
+    gl.draw_arrays(GL::POINTS, 0, paired.num_point as i32);
+    gl.draw_arrays(GL::LINES, paired.num_point as i32, 2);
+
+    gl.disable_vertex_attrib_array(a_position);
+    gl.disable_vertex_attrib_array(a_color);
+    gl.delete_buffer(Some(&vertex_buffer));
+}
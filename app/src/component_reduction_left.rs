@@ -1,12 +1,20 @@
 
+use std::collections::HashMap;
 use std::collections::HashSet;
 
-use my_web_app::countfile_struct::CountFileMetaColumnDesc;
-use my_web_app::DatasetDescResponse;
-use yew::{html, Callback, Component, Context, Html, MouseEvent, NodeRef};
+use my_web_app::CountFileMetaColumnData;
+use my_web_app::MetadataSchemaResponse;
+use my_web_app::SampleAnnotation;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::Element;
+use web_sys::HtmlInputElement;
+use yew::{html, Callback, Component, Context, Event, Html, InputEvent, MouseEvent, NodeRef};
 use yew::Properties;
+use yew::TargetCast;
 
 use crate::appstate::{AsyncData, PerCellDataSource};
+use crate::cluster_proportion::DEFAULT_PROPORTION_K;
+use crate::column_summary::ColumnSummary;
 use crate::component_reduction_main::get_palette_for_categories;
 
 
@@ -15,17 +23,57 @@ use crate::component_reduction_main::get_palette_for_categories;
 #[derive(Debug)]
 pub enum MsgMetadata {
     SetColorBy(String),
-    ToggleExpand(String)
+    SetColorByProportion(String, usize),   // column, category
+    ToggleExpand(String),
+    Scroll,
+    ToggleSample(u32),
+    ImportColorTable(HtmlInputElement),
 }
 
 
+////////////////////////////////////////////////////////////
+/// Beyond this many columns, only the rows in view are rendered
+const VIRTUAL_LIST_MIN_COLUMNS: usize = 50;
+
+////////////////////////////////////////////////////////////
+/// Height of each row in the virtual list, in pixels
+const VIRTUAL_ROW_HEIGHT: usize = 28;
+
+////////////////////////////////////////////////////////////
+/// Rows rendered above and below those in view, to hide redrawing while scrolling
+const VIRTUAL_ROW_OVERSCAN: usize = 5;
+
+
 ////////////////////////////////////////////////////////////
 /// Properties for MetadataView
 #[derive(Properties, PartialEq)]
 pub struct Props {
-    pub current_datadesc: AsyncData<DatasetDescResponse>,
+    pub metadata_schema: AsyncData<MetadataSchemaResponse>,
+    pub metadatas: HashMap<PerCellDataSource, AsyncData<CountFileMetaColumnData>>,  //Load state of each column
     pub on_colorbymeta: Callback<PerCellDataSource>,
+    pub on_loadmeta: Callback<PerCellDataSource>,
     pub current_colorby: PerCellDataSource,
+    pub column_summary: HashMap<String, ColumnSummary>,
+
+    #[prop_or(AsyncData::NotLoaded)]
+    pub sample_annotation: AsyncData<SampleAnnotation>,
+    #[prop_or_default]
+    pub sample_cell_counts: Vec<usize>,
+    #[prop_or_default]
+    pub selected_samples: HashSet<u32>,
+    #[prop_or_default]
+    pub on_toggle_sample: Callback<u32>,
+    #[prop_or_default]
+    pub on_import_color_table: Callback<Vec<u8>>,  // content of a CSV file
+
+    #[prop_or_default]
+    pub cell_search_query: String,
+    #[prop_or_default]
+    pub cell_search_pending: bool,
+    #[prop_or_default]
+    pub cell_search_num_found: Option<usize>,
+    #[prop_or_default]
+    pub on_search_cells: Callback<String>,   // called on every keystroke
 }
 
 
@@ -36,6 +84,7 @@ pub struct MetadataView {
 
     pub expanded_meta: HashSet<String>,
     pub selected_meta: HashSet<String>,
+    pub visible_range: (usize, usize),   //Rows in view: first, one past last
 }
 
 impl Component for MetadataView {
@@ -49,6 +98,7 @@ impl Component for MetadataView {
             node_ref: NodeRef::default(),
             expanded_meta: HashSet::new(),
             selected_meta: HashSet::new(),
+            visible_range: (0, 20 + VIRTUAL_ROW_OVERSCAN),
             //last_colorby: PerCellDataSource::Metadata("".into()),  //terrible!
         }
     }
@@ -66,16 +116,63 @@ impl Component for MetadataView {
                 true
             },
 
-            ///// Expand this metadata column to show categories etc
+            ///// Color by the fraction of neighbors of each cell that are in this category
+            MsgMetadata::SetColorByProportion(metadata_name, category_index) => {
+                ctx.props().on_colorbymeta.emit(PerCellDataSource::ClusterProportionScore {
+                    base_column: metadata_name,
+                    category_index: category_index,
+                    k: DEFAULT_PROPORTION_K,
+                });
+                true
+            },
+
+            ///// Expand this metadata column to show categories etc. The data is loaded the first time
             MsgMetadata::ToggleExpand(metadata_name) => {
                 if self.expanded_meta.contains(&metadata_name) {
                     self.expanded_meta.remove(&metadata_name);
                 } else {
+                    ctx.props().on_loadmeta.emit(PerCellDataSource::Metadata(metadata_name.clone()));
                     self.expanded_meta.insert(metadata_name);
                 }
                 true
             },
 
+            ///// Select or deselect a sample
+            MsgMetadata::ToggleSample(sample) => {
+                ctx.props().on_toggle_sample.emit(sample);
+                false
+            },
+
+            ///// A color table file has been chosen. Read it, then pass on the content
+            MsgMetadata::ImportColorTable(input) => {
+                if let Some(file) = input.files().and_then(|files| files.get(0)) {
+                    let on_import = ctx.props().on_import_color_table.clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        match JsFuture::from(file.array_buffer()).await {
+                            Ok(buf) => on_import.emit(js_sys::Uint8Array::new(&buf).to_vec()),
+                            Err(e) => log::warn!("Could not read color table: {:?}", e),
+                        }
+                    });
+                }
+                //Allow the same file to be chosen again
+                input.set_value("");
+                false
+            },
+
+            ///// The list has been scrolled; figure out which rows are in view
+            MsgMetadata::Scroll => {
+                if let Some(div) = self.node_ref.cast::<Element>() {
+                    let first = (div.scroll_top().max(0) as usize) / VIRTUAL_ROW_HEIGHT;
+                    let num_visible = (div.client_height().max(0) as usize) / VIRTUAL_ROW_HEIGHT + 1;
+                    let visible_range = (first.saturating_sub(VIRTUAL_ROW_OVERSCAN), first + num_visible + VIRTUAL_ROW_OVERSCAN);
+                    if visible_range != self.visible_range {
+                        self.visible_range = visible_range;
+                        return true;
+                    }
+                }
+                false
+            },
+
         }
     }
 
@@ -106,19 +203,48 @@ impl Component for MetadataView {
             </svg>
         };
         
-        let current_datadesc = ctx.props().current_datadesc.clone();
+        //Badge with a column summary, if the column has been loaded
+        let make_summary_badge = |meta_name: &String| -> Html {
+            if let Some(summary) = ctx.props().column_summary.get(meta_name) {
+                html! {
+                    <span class="biscvi-column-summary" title={summary.tooltip_text()}>
+                        { summary.badge_text() }
+                    </span>
+                }
+            } else {
+                html! {""}
+            }
+        };
 
         //For each metadata column, produce a control
         let mut list_meta_cat:Vec<Html> = Vec::new();
         let mut list_meta_cont:Vec<Html> = Vec::new();
-        if let AsyncData::Loaded(current_datadesc) = &current_datadesc {
+        if let AsyncData::Loaded(metadata_schema) = &ctx.props().metadata_schema {
 
-            for (meta_name,meta_data) in current_datadesc.meta.iter() {
+            for column in metadata_schema.columns.iter() {
+                let meta_name = &column.name;
                 let meta_name_id = PerCellDataSource::Metadata(meta_name.clone());
 
+                //Spinner while the data of the column is loading
+                let column_data = ctx.props().metadatas.get(&meta_name_id).cloned().unwrap_or(AsyncData::NotLoaded);
+                let loading_spinner = if let AsyncData::Loading = column_data {
+                    html! { <span class="biscvi-spinner"></span> }
+                } else {
+                    html! {""}
+                };
+
                 //////////// Discrete category
-                if let CountFileMetaColumnDesc::Categorical(categories ) = meta_data {
-                    let palette = get_palette_for_categories(categories.len());
+                if column.is_categorical {
+                    let palette = get_palette_for_categories(column.num_categories);
+
+                    //Names of categories are only known once the data is loaded
+                    let categories: Vec<String> = match &column_data {
+                        AsyncData::Loaded(column_data) => match column_data.as_ref() {
+                            CountFileMetaColumnData::Categorical(_, categories) => categories.clone(),
+                            _ => Vec::new(),
+                        },
+                        _ => Vec::new(),
+                    };
 
                     //// Produce a list of all categories
                     let mut list_levels = Vec::new();
@@ -131,6 +257,22 @@ impl Component for MetadataView {
                             let col = palette.get(level_i % palette.len()).unwrap();
 
                             let num_cells = "";
+
+                            //Clicking the name colors by how many neighbors are in this category
+                            let meta_name_copy = meta_name.clone();
+                            let cb_color_by_proportion = ctx.link().callback(move |_e: MouseEvent | {
+                                MsgMetadata::SetColorByProportion(meta_name_copy.clone(), level_i)
+                            });
+                            let proportion_id = PerCellDataSource::ClusterProportionScore {
+                                base_column: meta_name.clone(),
+                                category_index: level_i,
+                                k: DEFAULT_PROPORTION_K,
+                            };
+                            let style_proportion = if ctx.props().current_colorby == proportion_id {
+                                "cursor: pointer; font-weight: bold;"
+                            } else {
+                                "cursor: pointer;"
+                            };
                             
                             list_levels.push(                                
                                 html! {
@@ -147,8 +289,9 @@ impl Component for MetadataView {
                                                 <span style="width: 188px; color: black; font-style: normal; display: inline-block; overflow: hidden; line-height: 1.1em; height: 1.1em; vertical-align: middle; margin-right: 16px;">
                                                     <label style="width: 188px; color: black; font-style: normal; display: inline-block; overflow: hidden; line-height: 1.1em; height: 1.1em; vertical-align: middle; margin-right: 16px;">
                                                         <span style="width: 100%; color: black; font-style: normal; display: flex; overflow: hidden; line-height: 1.1em; height: 1.1em; vertical-align: middle; margin-right: 16px; justify-content: flex-start; padding: 0px;">
-                                                            <span style="overflow: hidden; text-overflow: ellipsis; white-space: nowrap; flex-shrink: 1; min-width: 5px;" class="bisci-label-left">
-                                                                {level_name}
+                                                            <span style="overflow: hidden; text-overflow: ellipsis; white-space: nowrap; flex-shrink: 1; min-width: 5px;" class="bisci-label-left"
+                                                                onclick={cb_color_by_proportion} title="Color by fraction of neighbors in this category">
+                                                                <span style={style_proportion}>{level_name}</span>
                                                             </span>
                                                         </span>
                                                     </label>
@@ -204,6 +347,8 @@ impl Component for MetadataView {
                                     <div style="display:table-cell;">
                                         <input type="checkbox" checked=true />
                                         { meta_name.clone() }
+                                        { make_summary_badge(meta_name) }
+                                        { loading_spinner.clone() }
                                         <span onclick={toggle_expand}>
                                             { arrow_down_svg.clone()}
                                         </span>
@@ -214,15 +359,15 @@ impl Component for MetadataView {
                                         </button>
                                     </div>
                                 </div> 
-                                { list_levels }
                             </div>
                         }
                     );
+                    list_meta_cat.extend(list_levels);
 
                 }
 
                 //////////// Continuous categories
-                if let CountFileMetaColumnDesc::Numeric() = meta_data {
+                if !column.is_categorical {
 
                     let style_colorbutton = if ctx.props().current_colorby == meta_name_id {
                         "background-color:  #FF0000; "
@@ -244,6 +389,8 @@ impl Component for MetadataView {
                                     <div style="display:table-cell;">
                                         <input type="checkbox" checked=true />
                                         { meta_name.clone() }
+                                        { make_summary_badge(meta_name) }
+                                        { loading_spinner }
                                         //<span onclick={toggle_expand}>
                                         //    { arrow_down_svg.clone()}
                                         //</span>
@@ -261,17 +408,116 @@ impl Component for MetadataView {
             }
         }
 
+        //All rows of the list, in order
+        let mut list_rows: Vec<Html> = Vec::new();
+
+        //Colors assigned by another tool, as a CSV of cell ID and color
+        let cb_import = ctx.link().batch_callback(move |e: Event | { 
+            e.target_dyn_into::<HtmlInputElement>().map(|input| MsgMetadata::ImportColorTable(input))
+        });
+        //Search for cells by metadata value. The search runs once the user stops typing
+        let on_search_cells = ctx.props().on_search_cells.clone();
+        let cb_search = Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            on_search_cells.emit(input.value());
+        });
+        let search_status = if ctx.props().cell_search_pending {
+            html! { <span class="biscvi-spinner"></span> }
+        } else if let Some(num_found) = ctx.props().cell_search_num_found {
+            html! { <span class="biscvi-column-summary">{ format!("{} cells", num_found) }</span> }
+        } else {
+            html! {""}
+        };
+        list_rows.push(html! {
+            <div class="biscvi-cell-search">
+                <input type="text" placeholder="Search cells by metadata value" value={ctx.props().cell_search_query.clone()} oninput={cb_search}/>
+                { search_status }
+            </div>
+        });
+
+        list_rows.push(html! {
+            <div>
+                <span class="bisci-label-left" title="CSV file with cell ID and hex color on each row">{"Import colors:"}</span>
+                <input type="file" accept=".csv,text/csv" onchange={cb_import}/>
+            </div>
+        });
+
+        //Samples, if the dataset has any. Click to select
+        if let AsyncData::Loaded(sample_annotation) = &ctx.props().sample_annotation {
+            list_rows.push(html! {
+                <span style="color:blue;font-weight:bold;">
+                    {"Samples:"}
+                </span>
+            });
+            for (sample_i, sample_name) in sample_annotation.sample_names.iter().enumerate() {
+                let sample_i = sample_i as u32;
+                let num_cells = ctx.props().sample_cell_counts.get(sample_i as usize).copied().unwrap_or(0);
+                let style = if ctx.props().selected_samples.contains(&sample_i) {
+                    "cursor: pointer; background-color: #0099FF;"
+                } else {
+                    "cursor: pointer;"
+                };
+                let cb_toggle = ctx.link().callback(move |_e: MouseEvent | { 
+                    MsgMetadata::ToggleSample(sample_i)
+                });
+                list_rows.push(html! {
+                    <div style={style} onclick={cb_toggle}>
+                        <span class="bisci-label-left">{ sample_name.clone() }</span>
+                        <span class="biscvi-column-summary">{ num_cells }</span>
+                    </div>
+                });
+            }
+        }
+
+        list_rows.push(html! {
+            <span style="color:blue;font-weight:bold;">
+                {"Discrete categories:"}
+            </span>
+        });
+        list_rows.extend(list_meta_cat);
+        list_rows.push(html! {
+            <span style="color:blue;font-weight:bold;">
+                {"Continuous categories:"}
+            </span>
+        });
+        list_rows.extend(list_meta_cont);
+
+        //With many columns, only render the rows in view. Spacers keep the scrollbar right
+        let num_columns = if let AsyncData::Loaded(metadata_schema) = &ctx.props().metadata_schema {
+            metadata_schema.columns.len()
+        } else {
+            0
+        };
+        let list_rows = if num_columns > VIRTUAL_LIST_MIN_COLUMNS {
+            let num_rows = list_rows.len();
+            let (first, last) = (self.visible_range.0.min(num_rows), self.visible_range.1.min(num_rows));
+            let row_style = format!("height: {}px; overflow: hidden;", VIRTUAL_ROW_HEIGHT);
+            let list_visible = list_rows.into_iter().skip(first).take(last - first).map(|row| {
+                html! {
+                    <div style={row_style.clone()}>
+                        { row }
+                    </div>
+                }
+            }).collect::<Vec<Html>>();
+            html! {
+                <>
+                    <div style={format!("height: {}px;", first*VIRTUAL_ROW_HEIGHT)}></div>
+                    { list_visible }
+                    <div style={format!("height: {}px;", (num_rows - last)*VIRTUAL_ROW_HEIGHT)}></div>
+                </>
+            }
+        } else {
+            html! { for list_rows }
+        };
+
+        let cb_scroll = ctx.link().callback(move |_e: Event | { 
+            MsgMetadata::Scroll
+        });
+
         html! {
-            <div class="biscvi-dimred-leftdiv">
+            <div class="biscvi-dimred-leftdiv" ref={self.node_ref.clone()} onscroll={cb_scroll}>
                 <div>
-                    <span style="color:blue;font-weight:bold;">
-                        {"Discrete categories:"}
-                    </span>
-                    { list_meta_cat }
-                    <span style="color:blue;font-weight:bold;">
-                        {"Continuous categories:"}
-                    </span>
-                    { list_meta_cont }
+                    { list_rows }
                 </div>
             </div>
         }
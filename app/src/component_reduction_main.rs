@@ -1,28 +1,56 @@
 use core::str;
+use std::collections::HashMap;
+use std::collections::HashSet;
 use std::io::BufRead;
 use std::io::Cursor;
 use std::io::BufReader;
+use std::sync::Arc;
 
 use my_web_app::CountFileMetaColumnData;
+use my_web_app::ReductionDelta;
 use my_web_app::ReductionResponse;
+use my_web_app::SampleAnnotation;
 use serde::Deserialize;
 use serde::Serialize;
 use wasm_bindgen::JsCast;
 use wasm_bindgen::prelude::*;
 use web_sys::window;
-use web_sys::{DomRect, EventTarget, HtmlElement, HtmlCanvasElement, CanvasRenderingContext2d, WebGlRenderingContext as GL};
-use yew::context;
-use yew::{html, Callback, Component, Context, Html, MouseEvent, NodeRef, WheelEvent};
+use web_sys::{DomRect, EventTarget, HtmlElement, HtmlCanvasElement, HtmlImageElement, HtmlInputElement, HtmlSelectElement, CanvasRenderingContext2d, WebGlBuffer, WebGlProgram, WebGlRenderingContext as GL};
+use gloo_timers::callback::Interval;
+use yew::context::ContextHandle;
+use yew::{html, Callback, Component, Context, Event, Html, InputEvent, KeyboardEvent, MouseEvent, NodeRef, TouchEvent, WheelEvent};
 use yew::Properties;
 use std::f64;
+use rand::Rng;
 
 use crate::appstate::AsyncData;
 use crate::appstate::PerCellDataSource;
 use crate::camera::Camera2D;
+use crate::camera::ZoomLevel;
 use crate::camera::Rectangle2D;
+use crate::colorscale::{diverging_color, symmetric_range, ColorScaleTransform};
 use crate::histogram::make_safe_minmax;
 use crate::resize::ComponentSize;
 use crate::closestpoint::ClosestPointIndex2D;
+use crate::cellgroups::{group_by_category, CellGroups};
+use crate::density::{compute_density_grid, compute_point_density, draw_density_background, DensityGrid, DENSITY_GRID_SIZE};
+use crate::palette_gen::{generate_palette, sort_palette_by_hue, HueGradient};
+use crate::pointlod::{choose_lod_cell_size, cluster_points};
+use crate::layout_radial::{layout_radial_cluster, LayoutMode};
+use crate::gpu_relax::{relax_layout_gpu, RELAX_ITERATIONS};
+use crate::correlation::{dense_numeric_values, pearson_correlation};
+use crate::violin::category_violins;
+use crate::perfstats::RenderStats;
+use crate::trajectory::TrajectoryPath;
+use crate::spline::{control_point_distances, SPLINE_SUBDIVISIONS};
+use crate::colorblind_sim::{simulate_color, simulate_vertex_colors, ColorSimulationMode};
+use crate::geojson_mask::{cells_inside_mask, point_in_polygon, PolygonMask};
+use crate::selection_context::SelectionContext;
+use crate::landmarks::{load_landmarks, save_landmarks, Landmark, LANDMARK_GRAB_RADIUS, LANDMARK_PIN_HEIGHT};
+use crate::subsample::{format_count, subsample_indices, subsample_vertices};
+use crate::legend::{choose_legend_corner, LegendPosition};
+use crate::download::download_text;
+use crate::svg_export::build_svg;
 
 
 // see https://github.com/yewstack/yew/blob/master/examples/webgl/src/main.rs
@@ -30,7 +58,7 @@ use crate::closestpoint::ClosestPointIndex2D;
 
 ////////////////////////////////////////////////////////////
 /// RGB color, 0...1
-type Color3f = (f32,f32,f32);
+pub type Color3f = (f32,f32,f32);
 
 ////////////////////////////////////////////////////////////
 /// Vectors, 3d and 4d
@@ -38,10 +66,12 @@ type Vec3 = (f32,f32,f32);
 
 ////////////////////////////////////////////////////////////
 /// Coloring of the reduction
-#[derive(PartialEq, Clone)]
+#[derive(PartialEq, Clone, Debug, Default, Serialize, Deserialize)]
 pub enum ReductionColoring {
+    #[default]
     None,
     ByMeta(PerCellDataSource),   //////////// this datastructure is not really needed => option
+    Custom,   // colors imported from a table
 }
 
 ////////////////////////////////////////////////////////////
@@ -50,11 +80,12 @@ pub enum ReductionColoring {
 pub enum ReductionColoringWithData {
     None,
     ByMeta(PerCellDataSource, AsyncData<CountFileMetaColumnData>), //////////// this datastructure is not really needed => option
+    Custom(Vec<Color3f>),  // color of each cell
 }
 
 ////////////////////////////////////////////////////////////
 /// Coordinates for a reduction
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct ReductionViewData {
     pub num_point: usize,
     pub data: Vec<f32>,
@@ -64,14 +95,98 @@ pub struct ReductionViewData {
     pub max_y: f32,
     pub min_x: f32,
     pub min_y: f32,
+
+    pub version: u32,
 }
     //    keep this in a cache? x,y and xy together??
 
 
 
+////////////////////////////////////////////////////////////
+/// Reason a reduction from the server cannot be used
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReductionError {
+    DimensionMismatch { num_x: usize, num_y: usize },
+    ServerError { message: String },
+    Decode(String),
+}
+impl std::fmt::Display for ReductionError {
+
+    ////////////////////////////////////////////////////////////
+    /// Pretty print ReductionError
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ReductionError::DimensionMismatch { num_x, num_y } => {
+                write!(f, "Reduction has {} x coordinates but {} y coordinates", num_x, num_y)
+            },
+            ReductionError::ServerError { message } => {
+                write!(f, "Server could not compute the reduction: {}", message)
+            },
+            ReductionError::Decode(message) => {
+                write!(f, "Reduction could not be decoded: {}", message)
+            },
+        }
+    }
+}
+impl std::error::Error for ReductionError {}
+
+
+////////////////////////////////////////////////////////////
+/// Interleave x and y coordinates into x,y pairs. Without unsafe code, this is as fast as filling
+/// a preallocated vector; see bench_interleave_coordinates
+pub fn interleave_coordinates(x: &[f32], y: &[f32]) -> Vec<f32> {
+    x.iter().zip(y.iter()).flat_map(|(x,y)| [*x,*y]).collect()
+}
+
+
+////////////////////////////////////////////////////////////
+/// Bounding box of the cells whose value is above the threshold, padded by 10% of its size on each side,
+/// and the number of these cells. None if no cell is above the threshold
+pub fn expressor_bounds(reduction: &ReductionViewData, values: &[f32], threshold: f32) -> Option<(Rectangle2D, usize)> {
+    let mut rect = Rectangle2D { x1: f32::INFINITY, x2: f32::NEG_INFINITY, y1: f32::INFINITY, y2: f32::NEG_INFINITY };
+    let mut num_cells = 0;
+    for (i, v) in values.iter().enumerate().take(reduction.num_point) {
+        if *v > threshold {
+            let (x, y) = (reduction.data[i*2], reduction.data[i*2+1]);
+            rect.x1 = rect.x1.min(x);
+            rect.x2 = rect.x2.max(x);
+            rect.y1 = rect.y1.min(y);
+            rect.y2 = rect.y2.max(y);
+            num_cells += 1;
+        }
+    }
+    if num_cells == 0 {
+        return None;
+    }
+
+    let (pad_x, pad_y) = ((rect.x2 - rect.x1)*0.1, (rect.y2 - rect.y1)*0.1);
+    rect.x1 -= pad_x;
+    rect.x2 += pad_x;
+    rect.y1 -= pad_y;
+    rect.y2 += pad_y;
+    Some((rect, num_cells))
+}
+
+
+////////////////////////////////////////////////////////////
+/// Convert from a MessagePack-encoded reduction server response to a optimized data structure
+pub fn convert_from_msgpack_to_reduction_data(bytes: &[u8]) -> Result<ReductionViewData, ReductionError> {
+    let resp: ReductionResponse = rmp_serde::from_slice(bytes).map_err(|e| ReductionError::Decode(e.to_string()))?;
+    convert_from_response_to_reduction_data(resp)
+}
+
+
 ////////////////////////////////////////////////////////////
 /// Convert from a reduction server response to a optimized data structure
-pub fn convert_from_response_to_reduction_data(resp: ReductionResponse) -> ReductionViewData {
+pub fn convert_from_response_to_reduction_data(resp: ReductionResponse) -> Result<ReductionViewData, ReductionError> {
+
+    //Each point needs both coordinates; anything else would produce garbage below
+    if resp.x.len() != resp.y.len() {
+        return Err(ReductionError::DimensionMismatch {
+            num_x: resp.x.len(),
+            num_y: resp.y.len(),
+        });
+    }
 
     let num_point= resp.x.len();
 
@@ -91,36 +206,49 @@ pub fn convert_from_response_to_reduction_data(resp: ReductionResponse) -> Reduc
         min_y = min_y.min(*v);
     });
 
-    //Convert coordinates to flat list. better to send in this format already?  --- code is likely fairly slow in current design
-    let mut data:Vec<f32> = Vec::with_capacity(num_point*2);
-    unsafe {
-        data.set_len(num_point*2);
-    }
-
-    resp.x.iter().enumerate().for_each(|(i,v)| {
-        data[i*2] = *v;
-    });
-
-    resp.y.iter().enumerate().for_each(|(i,v)| {
-        data[i*2+1] = *v;
-    });
-
-    /*
-    is above faster? it should eliminate a bound check at minimum. but would be great if we could instead do below unsafely
-    for i in 0..num_point {
-        data[i*2] = resp.x[i];
-        data[i*2+1] = resp.y[i];
-    }
-     */
+    //Convert coordinates to flat list. better to send in this format already?
+    let data = interleave_coordinates(&resp.x, &resp.y);
 
-    ReductionViewData {
+    Ok(ReductionViewData {
         num_point: num_point,
         data: data,
         max_x: max_x,
         max_y: max_y,
         min_x: min_x,
-        min_y: min_y
+        min_y: min_y,
+        version: resp.version,
+    })
+}
+
+
+
+////////////////////////////////////////////////////////////
+/// Update a reduction with the points that moved since the last version
+pub fn apply_delta(data: &mut ReductionViewData, delta: ReductionDelta) {
+
+    for ((i, dx), dy) in delta.moved_indices.iter().zip(delta.delta_x.iter()).zip(delta.delta_y.iter()) {
+        let i = *i as usize;
+        if i < data.num_point {
+            data.data[i*2] += dx.to_f32();
+            data.data[i*2+1] += dy.to_f32();
+        }
+    }
+
+    //Points may have moved out of (or away from) the previous range
+    let mut max_x = f32::MIN;
+    let mut max_y = f32::MIN;
+    let mut min_x = f32::MAX;
+    let mut min_y = f32::MAX;
+    for p in data.data.chunks_exact(2) {
+        max_x = max_x.max(p[0]);
+        min_x = min_x.min(p[0]);
+        max_y = max_y.max(p[1]);
+        min_y = min_y.min(p[1]);
     }
+    data.max_x = max_x;
+    data.max_y = max_y;
+    data.min_x = min_x;
+    data.min_y = min_y;
 }
 
 
@@ -130,24 +258,261 @@ pub fn convert_from_response_to_reduction_data(resp: ReductionResponse) -> Reduc
 
 ////////////////////////////////////////////////////////////
 /// Enum for the currently selected tool
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum CurrentTool {
     Zoom,
     ZoomAll,
-    Select
+    Select,
+    Lasso,
+    Explore,
+}
+
+
+////////////////////////////////////////////////////////////
+/// What the mouse is dragging, if anything other than the camera or a selection
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MoveMode {
+    None,
+    Landmark(usize),
+}
+
+
+////////////////////////////////////////////////////////////
+/// Action on the view that other components can request, e.g. from the command palette
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReductionCommand {
+    SelectTool(CurrentTool),
+    ZoomIn,
+    ZoomOut,
+    ToggleZoomLock,
+    ToggleShowVelocity,
+    ToggleRadialLayout,
+    ToggleSubsample,
+    ExportSvg,
+    AddLandmark,
+    ClearLandmarks,
+    ToggleRelaxLayout,
+    ReplayLasso,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Focus + context rendering. If enabled, only points in the middle of the view
+/// are drawn in full; the rest are drawn small and faint to give context
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FocusContextMode {
+    Disabled,
+    Enabled,
 }
 
 
+////////////////////////////////////////////////////////////
+/// Placement of the tool buttons. Collapsed into a dropdown menu if the canvas is narrow
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ToolbarLayout {
+    Expanded,
+    Collapsed,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Distance between tool buttons, and from the rightmost button to the right edge of the canvas, in pixels
+const TOOL_SPACING: usize = 30;
+const TOOLBAR_MARGIN_RIGHT: usize = 40;
+
+
+////////////////////////////////////////////////////////////
+/// Narrowest canvas, in pixels, on which a given number of tools fit side by side. Below it, the toolbar is collapsed
+fn toolbar_collapse_width(num_tools: usize) -> usize {
+    TOOLBAR_MARGIN_RIGHT + TOOL_SPACING*num_tools.saturating_sub(1)
+}
+
+
+////////////////////////////////////////////////////////////
+/// Aggregation of cells. If grouping by a categorical column, one point is drawn
+/// per category, at the centroid of its cells
+#[derive(Debug, Clone, PartialEq)]
+pub enum GroupByMode {
+    None,
+    Cluster(PerCellDataSource),
+}
+
+
+////////////////////////////////////////////////////////////
+/// Width of the violins drawn next to the categories in the legend, in pixels
+const VIOLIN_WIDTH: f64 = 44.0;
+
+
+////////////////////////////////////////////////////////////
+/// At most this many cell IDs are shown when zoomed in, to keep the overlay fast
+const MAX_CELL_ID_LABELS: usize = 200;
+
+////////////////////////////////////////////////////////////
+/// Number of nearest cells kept in view when focusing on a cell
+const FOCUS_NUM_NEIGHBOURS: usize = 50;
+
+////////////////////////////////////////////////////////////
+/// Length of the longest biplot arrow, as a fraction of the smallest side of the reduction
+const BIPLOT_ARROW_EXTENT: f32 = 0.4;
+
+
+////////////////////////////////////////////////////////////
+/// Size of the points drawn for groups of cells, in pixels
+const GROUP_POINT_SIZE: f32 = 20.0;
+
+
+////////////////////////////////////////////////////////////
+/// Half-size of the focus region, in camera coordinates [-1,1]
+const FOCUS_REGION_SIZE: f32 = 0.5;
+
+////////////////////////////////////////////////////////////
+/// Number of particles flowing along a trajectory
+const NUM_TRAJECTORY_PARTICLES: usize = 30;
+
+////////////////////////////////////////////////////////////
+/// Distance a particle moves along a trajectory per animation tick, per unit of camera zoom
+const PARTICLE_SPEED: f32 = 0.5;
+
+////////////////////////////////////////////////////////////
+/// Color of trajectory particles
+const PARTICLE_COLOR: Color3f = (1.0, 0.5, 0.0);
+
+////////////////////////////////////////////////////////////
+/// Size of the canvas in the print layout: an A4 page, in inches, at the given resolution
+const PRINT_PAGE_INCHES: (f32, f32) = (8.27, 11.69);
+const PRINT_DPI: f32 = 300.0;
+
+////////////////////////////////////////////////////////////
+/// CSS pixels per inch, as defined by CSS
+const CSS_DPI: f32 = 96.0;
+
+////////////////////////////////////////////////////////////
+/// Time new cells take to fly in from the edge of the view, in ms
+const ARRIVAL_MS: f64 = 500.0;
+
+////////////////////////////////////////////////////////////
+/// Fraction of the remaining distance moved per animation tick, for the camera and labels
+/// pulled by a gravity well
+const GRAVITY_SPRING: f32 = 0.1;
+
+////////////////////////////////////////////////////////////
+/// Labels closer than this to a gravity well are pushed away, in pixels
+const GRAVITY_LABEL_RADIUS: f32 = 100.0;
+
+////////////////////////////////////////////////////////////
+/// Margin added on each side when zooming to a cluster, as a fraction of its size
+const CLUSTER_ZOOM_PADDING: f32 = 0.2;
+
+////////////////////////////////////////////////////////////
+/// Cells considered when moving the highlight with the arrow keys: the nearest ones, and if none
+/// is in the pressed direction, this many times more
+const BROWSE_CANDIDATES: usize = 64;
+const BROWSE_CANDIDATES_WIDE: usize = 16;
+
+////////////////////////////////////////////////////////////
+/// Largest angle between the pressed direction and the next cell, in radians
+const BROWSE_MAX_ANGLE: f32 = std::f32::consts::FRAC_PI_4;
+
+////////////////////////////////////////////////////////////
+/// The camera pans if the highlighted cell is further from the center than this, in camera coordinates
+const BROWSE_EDGE: f32 = 0.8;
+
+////////////////////////////////////////////////////////////
+/// Built-in fragment shader for points. A custom shader given as a property replaces it,
+/// and must use the same varying color and uniform u_alpha
+pub const DEFAULT_FRAG_SHADER: &str = include_str!("./umap.frag");
+
+////////////////////////////////////////////////////////////
+/// Largest number of points that can be drawn using gl.draw_elements without the
+/// OES_element_index_uint extension, as indices are then 16 bits. Points are currently
+/// drawn using gl.draw_arrays, which has no such limit
+const MAX_UINT16_INDEXED_POINTS: usize = 65535;
+
+////////////////////////////////////////////////////////////
+/// Zoom factor of one zoom in/out command
+const ZOOM_STEP: f32 = 1.5;
+
+////////////////////////////////////////////////////////////
+/// A mouse wheel scrolls about 120 pixels per notch, and would zoom 16 times per notch at the
+/// sensitivity of a trackpad. It is damped by this factor, to zoom about 1.6 times per notch
+const MOUSE_WHEEL_DAMPING: f32 = 6.0;
+
+////////////////////////////////////////////////////////////
+/// Wheel events in pixel mode with smaller steps than this are taken to be from a trackpad
+const TRACKPAD_MAX_DELTA: f64 = 50.0;
+
+////////////////////////////////////////////////////////////
+/// Pixels per line and per page, for wheel events not given in pixels
+const WHEEL_LINE_PIXELS: f64 = 40.0;
+const WHEEL_PAGE_PIXELS: f64 = 800.0;
+
+////////////////////////////////////////////////////////////
+/// Two taps closer in time than this, in ms, are a double tap
+const DOUBLE_TAP_MS: f64 = 300.0;
+
+////////////////////////////////////////////////////////////
+/// Size of the sparkline of cells per sample in the summary card of a group, in pixels
+const CLUSTER_CARD_SPARKLINE_WIDTH: f32 = 100.0;
+const CLUSTER_CARD_SPARKLINE_HEIGHT: f32 = 20.0;
+
+////////////////////////////////////////////////////////////
+/// Time between points when replaying a lasso, in ms
+const LASSO_REPLAY_MS: u32 = 50;
+
+////////////////////////////////////////////////////////////
+/// Number of steps the complete lasso stays on screen after a replay
+const LASSO_REPLAY_HOLD_STEPS: usize = 20;
+
+////////////////////////////////////////////////////////////
+/// Neighborhood over which category colors are blended, as a fraction of the reduction size
+const CATEGORY_GRADIENT_RADIUS: f32 = 0.02;
+
+////////////////////////////////////////////////////////////
+/// Size of the legend, color scale controls included, in pixels. Category counts are drawn outside it
+const LEGEND_WIDTH: usize = 160;
+const LEGEND_HEIGHT: usize = 260;
+
+////////////////////////////////////////////////////////////
+/// Distance from the legend to the top and bottom of the canvas, leaving room for other controls
+const LEGEND_MARGIN_TOP: usize = 55;
+const LEGEND_MARGIN_BOTTOM: usize = 40;
+
+
 ////////////////////////////////////////////////////////////
 /// Message sent to the event system for updating the page
 #[derive(Debug)]
 pub enum MsgReduction {
     MouseMove(f32,f32, bool),
     MouseClick,
-    MouseWheel(f32),
+    ZoomToCluster(usize),
+    MouseWheel(f32, bool),   // scroll in pixels, and if from a trackpad
+    ZoomCenter(f32),
     MouseStartSelect(f32,f32),
     MouseEndSelect(f32,f32),
     SelectCurrentTool(CurrentTool),
+    ToggleZoomLock,
+    SetColorTransform(ColorScaleTransform),
+    ToggleShowVelocity,
+    ToggleFollowSelection,
+    ToggleToolbarMenu,
+    AnimationTick,
+    ExploreStep,
+    ToggleRadialLayout,
+    SetCorrelateWith(Option<PerCellDataSource>),
+    ToggleSubsample,
+    ExportSvg,
+    SubmitAnnotation(usize, String),
+    GravityWell(f32,f32),
+    CloseAnnotation,
+    BrowseCell(f32,f32),   // direction in world coordinates
+    AddLandmark,
+    ClearLandmarks,
+    ToggleRelaxLayout,
+    SelectionContextChanged(SelectionContext),
+    Tap(f64),   // time of a single-finger touch, in ms
+    ReplayLasso,
+    ReplayLassoStep,
+    CloseClusterCard,
 }
 
 
@@ -155,12 +520,166 @@ pub enum MsgReduction {
 /// Properties for ReductionView
 #[derive(Properties, PartialEq)]
 pub struct Props {
-    pub on_cell_hovered: Callback<Option<usize>>,
-    pub on_cell_clicked: Callback<Vec<usize>>,
+    #[prop_or_default]
+    pub on_cell_hovered: Callback<Option<usize>>,   // also sent to the SelectionContext, if there is one
+
+    #[prop_or_default]
+    pub on_cells_hovered: Callback<Vec<usize>>,   // all cells of the hovered group, when grouping; empty if none
+    #[prop_or_default]
+    pub on_group_clicked: Callback<Vec<usize>>,   // all cells of the group whose summary card was opened
+    #[prop_or_default]
+    pub cluster_card_genes: Option<Vec<(String, f32)>>,   // top features of the group in the summary card, once loaded
+
+    #[prop_or_default]
+    pub on_cell_clicked: Callback<Vec<usize>>,   // also sent to the SelectionContext, if there is one
     pub reduction_data: AsyncData<ReductionViewData>, 
     pub color_reduction_by: ReductionColoringWithData,
     pub last_component_size: ComponentSize,
      pub current_colorby: PerCellDataSource,
+
+    #[prop_or_default]
+    pub velocity_data: Vec<(f32,f32)>,   // velocity of each cell, in reduction coordinates
+    #[prop_or(1.0)]
+    pub velocity_scale: f32,
+
+    #[prop_or(0.0)]
+    pub blur_radius: f32,     // neighborhood for smoothing colors, in reduction coordinates. 0 to disable
+    #[prop_or(0.0)]
+    pub smooth_factor: f32,   // 0 = own color, 1 = mean color of neighbors
+    #[prop_or_default]
+    pub category_gradient: bool,   // blend the colors of categories where they meet, instead of discrete colors
+
+    #[prop_or_default]
+    pub tooltip_genes: Option<Vec<(String, f32)>>,  // top features of the hovered cell
+
+    #[prop_or_default]
+    pub global_color_range: Option<(f32,f32)>,  // if set, used instead of the range of the data to normalize colors
+    #[prop_or_default]
+    pub diverging_center: Option<f32>,  // if set, numeric data is colored blue-white-red, white at this value
+    #[prop_or_default]
+    pub sync_color_scale: bool,
+    #[prop_or_default]
+    pub on_toggle_sync_color_scale: Callback<()>,
+
+    #[prop_or_default]
+    pub expression_threshold: Option<f32>,   // if set, numeric data is shown using two colors instead of a scale
+    #[prop_or((1.0, 0.0, 0.0))]
+    pub color_above: Color3f,
+    #[prop_or((0.8, 0.8, 0.8))]
+    pub color_below: Color3f,
+    #[prop_or_default]
+    pub on_set_expression_threshold: Callback<Option<f32>>,
+
+    #[prop_or_default]
+    pub min_expression_filter: Option<f32>,   // if set, cells with a lower numeric value are hidden
+    #[prop_or_default]
+    pub mask_polygon: Option<PolygonMask>,    // if set, cells outside it are hidden
+    #[prop_or_default]
+    pub on_set_expression_filter: Callback<f32>,
+
+    #[prop_or(AsyncData::NotLoaded)]
+    pub sample_annotation: AsyncData<SampleAnnotation>,
+    #[prop_or_default]
+    pub display_subset: Option<Arc<HashSet<usize>>>,   // if set, only these cells are drawn, e.g. the same number from each sample
+    #[prop_or_default]
+    pub selected_samples: HashSet<u32>,    // cells of other samples are faded out
+
+    #[prop_or_default]
+    pub forced_selection: Option<Vec<usize>>,   // set by other components to select cells; emitted through on_cell_clicked
+
+    #[prop_or_default]
+    pub highlighted_cell: Option<usize>,   // marked by another component without selecting it
+
+    #[prop_or(FocusContextMode::Disabled)]
+    pub focus_context: FocusContextMode,
+    #[prop_or(ColorSimulationMode::Normal)]
+    pub color_simulation: ColorSimulationMode,   // show colors as seen by colorblind users
+    #[prop_or_default]
+    pub on_toggle_focus_context: Callback<()>,
+
+    #[prop_or_default]
+    pub trajectory: Option<Vec<usize>>,   // ordered cells; particles flow along the path from first to last
+
+    #[prop_or_default]
+    pub forced_camera: Option<Camera2D>,   // set by other components, e.g. peers of a collaborative session
+    #[prop_or_default]
+    pub on_camera_changed: Callback<Camera2D>,   // not emitted for changes due to forced_camera
+    #[prop_or_default]
+    pub on_tool_changed: Callback<CurrentTool>,
+    #[prop_or_default]
+    pub on_cursor_moved: Callback<(f32,f32)>,   // in world coordinates
+    #[prop_or_default]
+    pub peer_cursors: Vec<(f32,f32)>,   // cursors of other peers, in world coordinates
+
+    #[prop_or_default]
+    pub performance_overlay: bool,   // show frame rate and render times
+
+    #[prop_or_default]
+    pub correlation_columns: Vec<(PerCellDataSource, AsyncData<CountFileMetaColumnData>)>,  // numeric columns that can be correlated with the coloring
+
+    #[prop_or_default]
+    pub max_display_points: Option<usize>,   // if there are more points, only a random subset is drawn
+
+    #[prop_or_else(device_pixel_ratio)]
+    pub pixel_ratio: f32,   // canvas pixels per CSS pixel; below 1 renders faster, above 1 gives print quality
+    #[prop_or_default]
+    pub print_layout: bool,   // A4 page at PRINT_DPI, without tool buttons; overrides the size and pixel ratio
+
+    #[prop_or(LegendPosition::TopLeft)]
+    pub legend_position: LegendPosition,
+
+    #[prop_or_default]
+    pub cell_annotations: HashMap<usize, String>,   // text label of each annotated cell
+    #[prop_or_default]
+    pub on_set_annotation: Callback<(usize, String)>,   // empty text to remove the annotation
+
+    #[prop_or(GroupByMode::None)]
+    pub group_by: GroupByMode,
+
+    #[prop_or(AsyncData::NotLoaded)]
+    pub group_by_data: AsyncData<CountFileMetaColumnData>,   // column of the group_by mode
+
+    #[prop_or(5.0)]
+    pub point_size: f32,   // in pixels when all points fit in view. Points shrink when zooming in
+
+    #[prop_or_default]
+    pub compact_positions: bool,   // upload positions as 16-bit integers, to save GPU memory for large datasets
+
+    #[prop_or(1.0)]
+    pub point_opacity: f32,   // below 1, overlapping points are blended independent of drawing order
+
+    #[prop_or(AsyncData::NotLoaded)]
+    pub violin_data: AsyncData<CountFileMetaColumnData>,   // numeric column; when coloring by categories, its distribution in each is drawn in the legend
+
+    #[prop_or_default]
+    pub confidence: Option<Vec<f32>>,   // 0-1 for each cell, e.g. of a probabilistic cluster assignment. Cells are drawn as opaque as their confidence
+
+    #[prop_or(0.0)]
+    pub density_weight: f32,   // above 0, points in crowded regions are drawn more transparent. Only used if point_opacity is 1
+
+    #[prop_or(100.0)]
+    pub wheel_sensitivity: f32,   // pixels scrolled to zoom 10 times with a trackpad, 10-500. A mouse wheel zooms more slowly
+
+    #[prop_or_default]
+    pub hover_max_distance: Option<f32>,   // in world coordinates. Cells further from the cursor are not hovered
+
+    #[prop_or_default]
+    pub forced_command: Option<(u32, ReductionCommand)>,   // run whenever the sequence number changes
+
+    #[prop_or_default]
+    pub custom_frag_shader: Option<String>,   // GLSL source replacing DEFAULT_FRAG_SHADER. Ignored if it does not compile
+
+    #[prop_or(AsyncData::NotLoaded)]
+    pub cell_ids: AsyncData<Vec<String>>,   // shown next to the cells when zoomed in close
+
+    #[prop_or_default]
+    pub on_zoom_level_changed: Callback<ZoomLevel>,
+
+    #[prop_or_default]
+    pub focus_cell_index: Option<usize>,   // the camera moves to this cell and its neighbours whenever it changes
+
+    #[prop_or_default]
+    pub biplot_arrows: Vec<(String,f32,f32)>,   // gene, loading on PC1, loading on PC2; drawn as arrows from the origin
 }
 
 
@@ -170,11 +689,62 @@ pub struct ReductionView {
     node_refs: Vec<NodeRef>,
     last_pos: (f32,f32),
     last_cell: Option<usize>,
+    browsed_cells: HashSet<usize>,   // reached with the arrow keys since the mouse last hovered a cell; not visited again
+    hovered_cluster_name: Option<String>,   // status text for the category of the hovered cell, when coloring by a categorical column
     closest_point_index: ClosestPointIndex2D,
     current_tool: CurrentTool,
     camera: Camera2D,
     current_selection: Option<Rectangle2D>,
+    lasso_path: Option<Vec<(f32,f32)>>,       // in world coordinates, while drawing a lasso
+    lasso_path_history: Vec<(f32,f32)>,       // cursor positions of the last lasso completed
+    lasso_replay: Option<(usize, Interval)>,  // number of points of the last lasso shown so far, while replaying it
     last_reduction_data: AsyncData<ReductionViewData>,
+    zoom_locked: bool,
+    color_transform: ColorScaleTransform,
+    show_velocity: bool,
+    last_selection: Vec<usize>,
+    follow_selection: bool,
+    camera_target: Option<(f32,f32)>,
+    zoom_target: Option<f32>,   // zoom_x the camera is animated toward
+    animation_running: bool,
+    arrivals: HashMap<usize, PointArrival>,   // cells recently added to the reduction, still flying in
+    arrival_epoch: f64,                       // times given to the shader are relative to this, to fit in a float
+    explore_interval: Option<(i32, Closure<dyn Fn()>)>,
+    layout_mode: LayoutMode,
+    layout_input: (AsyncData<ReductionViewData>, ReductionColoringWithData),
+    layout_data: AsyncData<ReductionViewData>,
+    highlighted_cells: Vec<usize>,
+    correlate_with: Option<PerCellDataSource>,
+    render_stats: RenderStats,
+    last_emitted_camera: Camera2D,
+    particles: Vec<f32>,   // arc length of each particle along the trajectory
+    pixel_ratio: f32,      // of the last render; point sizes are scaled by this to keep their size on screen
+    mask_cache: Option<(PolygonMask, AsyncData<ReductionViewData>, Vec<bool>)>,   // cells inside the mask, for the given reduction
+    show_subsample: bool,
+    display_subsample: ((usize, usize), Vec<usize>),   // (number of points, max points) it was made for; cells to draw
+    svg_export_requested: bool,   // done during the next render, when the colors are known
+    annotating_cell: Option<usize>,   // cell clicked, with an input for its annotation shown
+    gravity_well: Option<(f32,f32)>,   // point middle-clicked, in world coordinates
+    label_offsets: HashMap<usize, (f32,f32)>,   // how far each annotation has been pushed by the gravity well, in pixels
+    has_uint_index: bool,   // OES_element_index_uint enabled; needed for indexed draw calls of more than 65535 points
+    custom_frag_checked: Option<(String, bool)>,   // last custom fragment shader given, and if it compiles
+    cell_groups: Option<CellGroups>,   // set when grouping by a column
+    toolbar_menu_open: bool,   // only used when the toolbar is collapsed
+    density_grid: Option<DensityGrid>,   // of the points of the current reduction, shown in the background
+    point_density: Vec<f32>,   // crowding around each point of the current reduction; computed when first needed
+    violin_cache: Option<(AsyncData<CountFileMetaColumnData>, AsyncData<CountFileMetaColumnData>, Vec<Vec<f32>>)>,   // coloring, numeric column, violin of each category
+    group_index: ClosestPointIndex2D,  // centroids of the groups
+    last_group: Option<usize>,
+    cluster_card: Option<(usize, f32, f32)>,   // group with a summary card shown, and where it was clicked, in camera coordinates
+    last_group_input: (AsyncData<ReductionViewData>, GroupByMode, AsyncData<CountFileMetaColumnData>),
+    landmarks: Vec<Landmark>,   // saved to localStorage whenever changed
+    move_mode: MoveMode,
+    selection_context: Option<(SelectionContext, ContextHandle<SelectionContext>)>,   // set if a provider is above this view
+    last_tap_time: Option<f64>,   // of the last touch, to detect double taps
+    zoom_level: ZoomLevel,   // of the last render
+    label_groups: Option<CellGroups>,   // cells of each category when coloring by a categorical column; labels are put at the centroids
+    last_label_input: (AsyncData<ReductionViewData>, ReductionColoringWithData),
+    overlay_gl: Option<OverlayGl>,   // compiled at the first render
 }
 
 impl Component for ReductionView {
@@ -183,17 +753,143 @@ impl Component for ReductionView {
     
     ////////////////////////////////////////////////////////////
     /// Create this component
-    fn create(_ctx: &Context<Self>) -> Self {
+    fn create(ctx: &Context<Self>) -> Self {
+        let mut closest_point_index = ClosestPointIndex2D::new(); //tricky... adapt to umap size??
+        closest_point_index.max_distance_threshold = ctx.props().hover_max_distance.unwrap_or(f32::INFINITY);
+
         Self {
-            node_refs:vec![NodeRef::default(), NodeRef::default()],
+            node_refs:vec![NodeRef::default(), NodeRef::default(), NodeRef::default(), NodeRef::default(), NodeRef::default()],
             last_pos: (0.0,0.0),
             last_cell: None,
-            closest_point_index: ClosestPointIndex2D::new(), //tricky... adapt to umap size??
+            browsed_cells: HashSet::new(),
+            hovered_cluster_name: None,
+            closest_point_index: closest_point_index,
             current_tool: CurrentTool::Select,
             camera: Camera2D::new(),
             current_selection: None,
+            lasso_path: None,
+            lasso_path_history: Vec::new(),
+            lasso_replay: None,
             last_reduction_data: AsyncData::NotLoaded,
+            zoom_locked: false,
+            color_transform: ColorScaleTransform::Linear,
+            show_velocity: false,
+            last_selection: Vec::new(),
+            follow_selection: false,
+            camera_target: None,
+            zoom_target: None,
+            animation_running: false,
+            arrivals: HashMap::new(),
+            arrival_epoch: 0.0,
+            explore_interval: None,
+            layout_mode: LayoutMode::Original,
+            layout_input: (AsyncData::NotLoaded, ReductionColoringWithData::None),
+            layout_data: AsyncData::NotLoaded,
+            highlighted_cells: Vec::new(),
+            correlate_with: None,
+            render_stats: RenderStats::new(),
+            last_emitted_camera: Camera2D::new(),
+            particles: Vec::new(),
+            pixel_ratio: 1.0,
+            mask_cache: None,
+            show_subsample: true,
+            display_subsample: ((0, 0), Vec::new()),
+            svg_export_requested: false,
+            annotating_cell: None,
+            gravity_well: None,
+            label_offsets: HashMap::new(),
+            has_uint_index: false,
+            custom_frag_checked: None,
+            cell_groups: None,
+            toolbar_menu_open: false,
+            density_grid: None,
+            point_density: Vec::new(),
+            violin_cache: None,
+            group_index: ClosestPointIndex2D::new(),
+            last_group: None,
+            cluster_card: None,
+            last_group_input: (AsyncData::NotLoaded, GroupByMode::None, AsyncData::NotLoaded),
+            landmarks: load_landmarks(),
+            move_mode: MoveMode::None,
+            selection_context: ctx.link().context(ctx.link().callback(MsgReduction::SelectionContextChanged)),
+            last_tap_time: None,
+            zoom_level: ZoomLevel::Overview,
+            label_groups: None,
+            last_label_input: (AsyncData::NotLoaded, ReductionColoringWithData::None),
+            overlay_gl: None,
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Properties have changed
+    fn changed(&mut self, ctx: &Context<Self>, old_props: &Self::Properties) -> bool {
+        self.update_layout(ctx);
+        self.closest_point_index.max_distance_threshold = ctx.props().hover_max_distance.unwrap_or(f32::INFINITY);
+
+        //Selection driven by another component
+        if ctx.props().forced_selection != old_props.forced_selection {
+            if let Some(selection) = &ctx.props().forced_selection {
+                self.last_selection = selection.clone();
+                self.highlighted_cells = selection.clone();
+                emit_selection(ctx, &self.selection_context, selection.clone());
+            }
+        }
+
+        //New trajectory: spread particles evenly along it
+        if ctx.props().trajectory != old_props.trajectory {
+            self.particles.clear();
+            if let (Some(trajectory), AsyncData::Loaded(reduction_data)) = (&ctx.props().trajectory, &self.get_reduction_data(ctx)) {
+                let path = TrajectoryPath::smoothed(reduction_data, trajectory);
+                let length = path.length();
+                if length > 0.0 {
+                    self.particles = (0..NUM_TRAJECTORY_PARTICLES).map(|i| length*(i as f32)/(NUM_TRAJECTORY_PARTICLES as f32)).collect();
+                    self.start_animation(ctx);
+                }
+            }
+        }
+
+        //Command requested by another component
+        if ctx.props().forced_command != old_props.forced_command {
+            if let Some((_, command)) = &ctx.props().forced_command {
+                ctx.link().send_message(match command.clone() {
+                    ReductionCommand::SelectTool(t) => MsgReduction::SelectCurrentTool(t),
+                    ReductionCommand::ZoomIn => MsgReduction::ZoomCenter(ZOOM_STEP),
+                    ReductionCommand::ZoomOut => MsgReduction::ZoomCenter(1.0/ZOOM_STEP),
+                    ReductionCommand::ToggleZoomLock => MsgReduction::ToggleZoomLock,
+                    ReductionCommand::ToggleShowVelocity => MsgReduction::ToggleShowVelocity,
+                    ReductionCommand::ToggleRadialLayout => MsgReduction::ToggleRadialLayout,
+                    ReductionCommand::ToggleSubsample => MsgReduction::ToggleSubsample,
+                    ReductionCommand::ExportSvg => MsgReduction::ExportSvg,
+                    ReductionCommand::AddLandmark => MsgReduction::AddLandmark,
+                    ReductionCommand::ClearLandmarks => MsgReduction::ClearLandmarks,
+                    ReductionCommand::ToggleRelaxLayout => MsgReduction::ToggleRelaxLayout,
+                    ReductionCommand::ReplayLasso => MsgReduction::ReplayLasso,
+                });
+            }
         }
+
+        //Cell to focus on, chosen by another component
+        if ctx.props().focus_cell_index != old_props.focus_cell_index {
+            if let Some(cell) = ctx.props().focus_cell_index {
+                self.focus_cell(ctx, cell);
+            }
+        }
+
+        //Camera driven by another component. Only the zoom along x is taken, as the canvas shape may differ
+        if ctx.props().forced_camera != old_props.forced_camera {
+            if let Some(camera) = &ctx.props().forced_camera {
+                self.camera.x = camera.x;
+                self.camera.y = camera.y;
+                self.camera.zoom_x = camera.zoom_x;
+                self.camera.zoom_y = camera.zoom_x / self.camera.aspect_ratio;
+                self.last_emitted_camera = self.camera.clone();
+            }
+        }
+
+        //The coloring may have changed, and with it the category of the hovered cell
+        self.hovered_cluster_name = self.get_hovered_cluster_name(ctx);
+        true
     }
 
 
@@ -212,9 +908,21 @@ impl Component for ReductionView {
 
                 //Handle pointer in world coordinates
                 let (wx,wy) = self.camera.cam2world(x as f32, y as f32);
+                ctx.props().on_cursor_moved.emit((wx,wy));
+
+                //When grouping, hover whole groups instead of cells
+                if let Some(groups) = &self.cell_groups {
+                    let group = self.group_index.get_closest_point(wx, wy);
+                    if self.last_group != group {
+                        self.last_group = group;
+                        let members = group.map(|g| groups.members[g].clone()).unwrap_or_default();
+                        ctx.props().on_cells_hovered.emit(members);
+                        do_update = true;
+                    }
+                }
 
                 //Handle hovering
-                let cp = self.closest_point_index.get_closest_point(wx, wy);  // sometimes a crash overflow here?? 666
+                let cp = if self.cell_groups.is_some() { None } else { self.closest_point_index.get_closest_point(wx, wy) };  // sometimes a crash overflow here?? 666
                 //log::debug!("p: {:?}",cp);
                 //log::debug!("{} {}",x,y);
 
@@ -231,15 +939,30 @@ impl Component for ReductionView {
                 let point_changed = self.last_cell != point_name;
                 self.last_cell = point_name.clone();
                 if point_changed {
-                    ctx.props().on_cell_hovered.emit(point_name);
+                    self.browsed_cells.clear();
+                    emit_hovered(ctx, &self.selection_context, point_name);
+                    self.hovered_cluster_name = self.get_hovered_cluster_name(ctx);
                     do_update=true;
                 }
 
+                //Drag a landmark
+                if let MoveMode::Landmark(i) = self.move_mode {
+                    if let Some(landmark) = self.landmarks.get_mut(i) {
+                        landmark.world_x = wx;
+                        landmark.world_y = wy;
+                    }
+                    return true;
+                }
+
                 if let Some(sel) = &mut self.current_selection {
                     sel.x2=wx;
                     sel.y2=wy;
                     //log::debug!("sel-move {:?}",sel);
                 }
+                if let Some(path) = &mut self.lasso_path {
+                    path.push((wx, wy));
+                    do_update = true;
+                }
 
                 //Handle panning
                 if self.current_tool == CurrentTool::Zoom && press_left {
@@ -260,48 +983,115 @@ impl Component for ReductionView {
 
             ////////////////////////////////////////////////////////////
             // Message: Mouse wheel rotated
-            MsgReduction::MouseWheel(dy) => {
+            MsgReduction::MouseWheel(dy, is_trackpad) => {
+                if self.zoom_locked {
+                    return false;
+                }
                 let (cx,cy) = self.last_pos;
                 let (wx, wy) = self.camera.cam2world(cx, cy);
-                let scale = (10.0f32).powf(dy / 100.0);
+                let sensitivity = ctx.props().wheel_sensitivity.clamp(10.0, 500.0);
+                let sensitivity = if is_trackpad { sensitivity } else { sensitivity*MOUSE_WHEEL_DAMPING };
+                let scale = (10.0f32).powf(dy / sensitivity);
                 self.camera.zoom_around(wx,wy, scale);
                 true
             },
 
             ////////////////////////////////////////////////////////////
-            // Message: Mouse has clicked
-            MsgReduction::MouseClick => {
-                false
+            // Message: Zoom in or out, keeping the middle of the view in place
+            MsgReduction::ZoomCenter(scale) => {
+                if self.zoom_locked {
+                    return false;
+                }
+                let (wx, wy) = (self.camera.x, self.camera.y);
+                self.camera.zoom_around(wx, wy, scale);
+                true
             },
 
             ////////////////////////////////////////////////////////////
-            // Message: A tool has been selected
-            MsgReduction::SelectCurrentTool(t) => {
+            // Message: Zoom lock toggled
+            MsgReduction::ToggleZoomLock => {
+                self.zoom_locked = !self.zoom_locked;
+                true
+            },
 
-                let reduction_data = &ctx.props().reduction_data;
+            ////////////////////////////////////////////////////////////
+            // Message: Color scale transform selected
+            MsgReduction::SetColorTransform(t) => {
+                self.color_transform = t;
+                true
+            },
 
-                if t==CurrentTool::ZoomAll {
-                    if let AsyncData::Loaded(reduction_data) = reduction_data {
-                        self.camera.fit_reduction(reduction_data);
+            ////////////////////////////////////////////////////////////
+            // Message: Save the current view as SVG
+            MsgReduction::ExportSvg => {
+                self.svg_export_requested = true;
+                true
+            },
+
+            ////////////////////////////////////////////////////////////
+            // Message: Place a landmark in the middle of the view, asking for its name
+            MsgReduction::AddLandmark => {
+                let default_label = format!("Landmark {}", self.landmarks.len() + 1);
+                let label = window().and_then(|w| w.prompt_with_message_and_default("Name of the landmark", &default_label).ok().flatten());
+                let Some(label) = label else {
+                    return false;
+                };
+                let palette = get_palette_for_categories(self.landmarks.len() + 1);
+                let (world_x, world_y) = self.camera.cam2world(0.0, 0.0);
+                self.landmarks.push(Landmark {
+                    world_x: world_x,
+                    world_y: world_y,
+                    label: label.trim().to_string(),
+                    color: palette[self.landmarks.len() % palette.len()],
+                });
+                save_landmarks(&self.landmarks);
+                true
+            },
+
+            ////////////////////////////////////////////////////////////
+            // Message: Screen touched. A second tap soon after the first shows all cells
+            MsgReduction::Tap(now) => {
+                match self.last_tap_time {
+                    Some(last) if now - last < DOUBLE_TAP_MS => {
+                        self.last_tap_time = None;
+                        ctx.link().send_message(MsgReduction::SelectCurrentTool(CurrentTool::ZoomAll));
+                    },
+                    _ => {
+                        self.last_tap_time = Some(now);
                     }
-                } else {
-                    self.current_tool=t;
                 }
+                false
+            },
+
+            ////////////////////////////////////////////////////////////
+            // Message: Close the summary card of a group
+            MsgReduction::CloseClusterCard => {
+                self.cluster_card.take().is_some()
+            },
+
+            ////////////////////////////////////////////////////////////
+            // Message: Replay the drawing of the last lasso, one point at a time
+            MsgReduction::ReplayLasso => {
+                if self.lasso_path_history.is_empty() {
+                    return false;
+                }
+                let link = ctx.link().clone();
+                let interval = Interval::new(LASSO_REPLAY_MS, move || {
+                    link.send_message(MsgReduction::ReplayLassoStep);
+                });
+                //Replacing an ongoing replay drops its interval, which cancels it
+                self.lasso_replay = Some((0, interval));
                 true
             },
 
             ////////////////////////////////////////////////////////////
-            // Message: A selection of a region has started using mouse
-            MsgReduction::MouseStartSelect(cx,cy) => {
-                if self.current_tool==CurrentTool::Select {
-                    let (wx,wy) = self.camera.cam2world(cx as f32, cy as f32);
-                    self.current_selection = Some(Rectangle2D {
-                        x1: wx,
-                        x2: wx,
-                        y1: wy,
-                        y2: wy
-                    });
-                    //log::debug!("sel-start {:?}",self.current_selection);
+            // Message: Show one more point of the lasso being replayed
+            MsgReduction::ReplayLassoStep => {
+                if let Some((num_shown, _)) = &mut self.lasso_replay {
+                    *num_shown += 1;
+                    if *num_shown > self.lasso_path_history.len() + LASSO_REPLAY_HOLD_STEPS {
+                        self.lasso_replay = None;
+                    }
                     true
                 } else {
                     false
@@ -309,26 +1099,375 @@ impl Component for ReductionView {
             },
 
             ////////////////////////////////////////////////////////////
-            // Message: A selection of a region has ended using mouse
-            MsgReduction::MouseEndSelect(cx,cy) => {
-                if let Some(rect) = &mut self.current_selection {
-                    let (wx,wy) = self.camera.cam2world(cx as f32, cy as f32);
-                    rect.x2=wx;
-                    rect.y2=wy;
+            // Message: Hovered or selected cells changed in the shared context
+            MsgReduction::SelectionContextChanged(selection_context) => {
+                if let Some((current, _)) = &mut self.selection_context {
+                    *current = selection_context;
+                }
+                false
+            },
 
-                    let reduction_data = &ctx.props().reduction_data;
+            ////////////////////////////////////////////////////////////
+            // Message: Remove all landmarks
+            MsgReduction::ClearLandmarks => {
+                self.landmarks.clear();
+                self.move_mode = MoveMode::None;
+                save_landmarks(&self.landmarks);
+                true
+            },
 
-                    if let AsyncData::Loaded(reduction_data) = reduction_data {
+            ////////////////////////////////////////////////////////////
+            // Message: Annotation of a cell entered
+            MsgReduction::SubmitAnnotation(cell, text) => {
+                ctx.props().on_set_annotation.emit((cell, text.trim().to_string()));
+                self.annotating_cell = None;
+                true
+            },
 
-                        let (x1,x2) =rect.range_x();
-                        let (y1,y2) =rect.range_y();
+            ////////////////////////////////////////////////////////////
+            // Message: Middle click. Pull the camera to the point under the cursor, and push labels near it away
+            MsgReduction::GravityWell(cx,cy) => {
+                let (wx, wy) = self.camera.cam2world(cx, cy);
+                let well = match (&self.last_cell, &self.get_reduction_data(ctx)) {
+                    (Some(cell), AsyncData::Loaded(reduction_data)) if *cell < reduction_data.num_point => {
+                        (reduction_data.data[cell*2+0], reduction_data.data[cell*2+1])
+                    },
+                    _ => (wx, wy)
+                };
+                self.gravity_well = Some(well);
+                self.camera_target = Some(well);
+                self.start_animation(ctx);
+                false
+            },
 
-                        if x1==x2 && y1==y2 {
-                            log::debug!("this is a click");
-                            if self.current_tool==CurrentTool::Select {
-                                if let Some(cell) = &self.last_cell {
-                                    ctx.props().on_cell_clicked.emit(vec![cell.clone()]);
-                                }
+            ////////////////////////////////////////////////////////////
+            // Message: Annotation input closed without changes
+            MsgReduction::CloseAnnotation => {
+                self.annotating_cell = None;
+                true
+            },
+
+            ////////////////////////////////////////////////////////////
+            // Message: Subsampled display toggled
+            MsgReduction::ToggleSubsample => {
+                self.show_subsample = !self.show_subsample;
+                true
+            },
+
+            ////////////////////////////////////////////////////////////
+            // Message: Velocity layer toggled
+            MsgReduction::ToggleShowVelocity => {
+                self.show_velocity = !self.show_velocity;
+                true
+            },
+
+            ////////////////////////////////////////////////////////////
+            // Message: Follow selection toggled
+            MsgReduction::ToggleFollowSelection => {
+                self.follow_selection = !self.follow_selection;
+                self.start_animation(ctx);
+                true
+            },
+
+            ////////////////////////////////////////////////////////////
+            // Message: Dropdown menu of the collapsed toolbar opened or closed
+            MsgReduction::ToggleToolbarMenu => {
+                self.toolbar_menu_open = !self.toolbar_menu_open;
+                true
+            },
+
+            ////////////////////////////////////////////////////////////
+            // Message: Time to update animations
+            MsgReduction::AnimationTick => {
+                self.animation_running = false;
+
+                //Follow the centroid of the selected cells, or go to where the camera has been sent
+                let mut target = self.camera_target;
+                if self.follow_selection {
+                    if let AsyncData::Loaded(reduction_data) = &self.get_reduction_data(ctx) {
+                        target = get_centroid(reduction_data, &self.last_selection).or(target);
+                    }
+                }
+
+                //Smoothly pan toward the target. A gravity well pulls harder
+                let mut do_update = false;
+                if let Some((cx, cy)) = target {
+                    let lerp = if self.gravity_well.is_some() { GRAVITY_SPRING } else { 0.05 };
+                    let dx = (cx - self.camera.x)*lerp;
+                    let dy = (cy - self.camera.y)*lerp;
+
+                    //Only redraw if the camera moves noticeably, in camera coordinates
+                    if (dx*self.camera.zoom_x).abs() > 1e-4 || (dy*self.camera.zoom_y).abs() > 1e-4 {
+                        self.camera.x += dx;
+                        self.camera.y += dy;
+                        do_update = true;
+                    } else {
+                        self.camera_target = None;
+                    }
+                }
+
+                //Zoom toward the target, by the same fraction of the remaining zoom factor each tick
+                if let Some(zoom) = self.zoom_target {
+                    let log_ratio = (zoom/self.camera.zoom_x).ln();
+                    if log_ratio.abs() > 1e-3 {
+                        self.camera.zoom_x *= (log_ratio*0.05).exp();
+                    } else {
+                        self.camera.zoom_x = zoom;
+                        self.zoom_target = None;
+                    }
+                    self.camera.zoom_y = self.camera.zoom_x / self.camera.aspect_ratio;
+                    do_update = true;
+                }
+
+                //Move particles along the trajectory, wrapping around at the end
+                if !self.particles.is_empty() {
+                    if let (Some(trajectory), AsyncData::Loaded(reduction_data)) = (&ctx.props().trajectory, &self.get_reduction_data(ctx)) {
+                        let length = TrajectoryPath::smoothed(reduction_data, trajectory).length();
+                        let dt = PARTICLE_SPEED*self.camera.zoom_x;
+                        if length > 0.0 {
+                            for t in self.particles.iter_mut() {
+                                *t = (*t + dt) % length;
+                            }
+                        }
+                        do_update = true;
+                    } else {
+                        self.particles.clear();
+                    }
+                }
+
+                //Forget cells once they have arrived; one more frame draws them in place
+                if !self.arrivals.is_empty() {
+                    let now = js_sys::Date::now();
+                    self.arrivals.retain(|_, arrival| now - arrival.born_at < ARRIVAL_MS);
+                    do_update = true;
+                }
+
+                //Move labels away from the gravity well, or back once it is released
+                let labels_moving = self.update_label_offsets(ctx);
+                do_update |= labels_moving;
+
+                if self.follow_selection || self.camera_target.is_some() || self.zoom_target.is_some() || !self.particles.is_empty() || labels_moving || !self.arrivals.is_empty() {
+                    self.start_animation(ctx);
+                }
+                do_update
+            },
+
+            ////////////////////////////////////////////////////////////
+            // Message: Time to move on to another part of the reduction
+            MsgReduction::ExploreStep => {
+                if let Some(target) = self.pick_explore_target(ctx) {
+                    self.camera_target = Some(target);
+                    self.start_animation(ctx);
+                }
+                false
+            },
+
+            ////////////////////////////////////////////////////////////
+            // Message: Radial layout of categories toggled
+            MsgReduction::ToggleRadialLayout => {
+                self.layout_mode = if self.layout_mode==LayoutMode::RadialCluster { LayoutMode::Original } else { LayoutMode::RadialCluster };
+                self.update_layout(ctx);
+                true
+            },
+
+            ////////////////////////////////////////////////////////////
+            // Message: Layout relaxed on the GPU toggled
+            MsgReduction::ToggleRelaxLayout => {
+                self.layout_mode = if self.layout_mode==LayoutMode::Relaxed { LayoutMode::Original } else { LayoutMode::Relaxed };
+                self.update_layout(ctx);
+                true
+            },
+
+            ////////////////////////////////////////////////////////////
+            // Message: Pick the column to correlate with the coloring
+            MsgReduction::SetCorrelateWith(column) => {
+                self.correlate_with = column;
+                true
+            },
+
+            ////////////////////////////////////////////////////////////
+            // Message: Mouse has clicked
+            MsgReduction::MouseClick => {
+                false
+            },
+
+            ////////////////////////////////////////////////////////////
+            // Message: Arrow key pressed. Move the highlight to the nearest cell in that direction
+            MsgReduction::BrowseCell(dx, dy) => {
+                let Some(cell) = self.last_cell else {
+                    return false;
+                };
+                let AsyncData::Loaded(reduction_data) = self.get_reduction_data(ctx) else {
+                    return false;
+                };
+                let Some(next) = find_cell_in_direction(&reduction_data, &self.closest_point_index, cell, (dx, dy), &self.browsed_cells) else {
+                    return false;
+                };
+                self.browsed_cells.insert(cell);
+                self.browsed_cells.insert(next);
+                self.last_cell = Some(next);
+                emit_hovered(ctx, &self.selection_context, Some(next));
+                self.hovered_cluster_name = self.get_hovered_cluster_name(ctx);
+
+                //Pan just enough to keep the cell away from the edge of the view
+                let (cx, cy) = self.camera.world2cam(reduction_data.data[next*2+0], reduction_data.data[next*2+1]);
+                if cx.abs() > BROWSE_EDGE {
+                    self.camera.x += (cx - cx.signum()*BROWSE_EDGE) / self.camera.zoom_x;
+                }
+                if cy.abs() > BROWSE_EDGE {
+                    self.camera.y += (cy - cy.signum()*BROWSE_EDGE) / self.camera.zoom_y;
+                }
+                true
+            },
+
+            ////////////////////////////////////////////////////////////
+            // Message: Zoom in on all cells of a category of the coloring
+            MsgReduction::ZoomToCluster(category_index) => {
+                let AsyncData::Loaded(reduction_data) = self.get_reduction_data(ctx) else {
+                    return false;
+                };
+                let ReductionColoringWithData::ByMeta(_, AsyncData::Loaded(color_data)) = &ctx.props().color_reduction_by else {
+                    return false;
+                };
+                let CountFileMetaColumnData::Categorical(vec_data, _vec_cats) = color_data.as_ref() else {
+                    return false;
+                };
+                if let Some(rect) = category_bounding_box(&reduction_data, vec_data, category_index) {
+                    //Pad so that the outermost cells are not at the edge
+                    let (x1, x2) = rect.range_x();
+                    let (y1, y2) = rect.range_y();
+                    let pad_x = (x2 - x1)*CLUSTER_ZOOM_PADDING;
+                    let pad_y = (y2 - y1)*CLUSTER_ZOOM_PADDING;
+                    self.camera.fit_rect(&Rectangle2D {
+                        x1: x1 - pad_x,
+                        x2: x2 + pad_x,
+                        y1: y1 - pad_y,
+                        y2: y2 + pad_y,
+                    });
+                    true
+                } else {
+                    false
+                }
+            },
+
+            ////////////////////////////////////////////////////////////
+            // Message: A tool has been selected
+            MsgReduction::SelectCurrentTool(t) => {
+
+                let reduction_data = &self.get_reduction_data(ctx);
+
+                if t==CurrentTool::ZoomAll {
+                    if let AsyncData::Loaded(reduction_data) = reduction_data {
+                        self.camera.fit_reduction(reduction_data);
+                    }
+                } else {
+                    ctx.props().on_tool_changed.emit(t.clone());
+                    self.current_tool=t;
+                }
+
+                //Explore mode periodically moves the camera
+                if self.current_tool==CurrentTool::Explore {
+                    self.start_explore(ctx);
+                } else {
+                    self.stop_explore();
+                }
+                true
+            },
+
+            ////////////////////////////////////////////////////////////
+            // Message: A selection of a region has started using mouse
+            MsgReduction::MouseStartSelect(cx,cy) => {
+                //Labels spring back when the gravity well is released
+                if self.gravity_well.take().is_some() {
+                    self.start_animation(ctx);
+                }
+
+                //Pressing on a landmark drags it, whatever the tool
+                if let Some(i) = self.get_landmark_at(ctx, cx, cy) {
+                    self.move_mode = MoveMode::Landmark(i);
+                    return true;
+                }
+
+                if self.current_tool==CurrentTool::Lasso {
+                    let (wx,wy) = self.camera.cam2world(cx as f32, cy as f32);
+                    self.lasso_path = Some(vec![(wx, wy)]);
+                    self.lasso_replay = None;
+                    return true;
+                }
+
+                if self.current_tool==CurrentTool::Select {
+                    let (wx,wy) = self.camera.cam2world(cx as f32, cy as f32);
+                    self.current_selection = Some(Rectangle2D {
+                        x1: wx,
+                        x2: wx,
+                        y1: wy,
+                        y2: wy
+                    });
+                    //log::debug!("sel-start {:?}",self.current_selection);
+                    true
+                } else {
+                    false
+                }
+            },
+
+            ////////////////////////////////////////////////////////////
+            // Message: A selection of a region has ended using mouse
+            MsgReduction::MouseEndSelect(cx,cy) => {
+                if self.move_mode != MoveMode::None {
+                    self.move_mode = MoveMode::None;
+                    save_landmarks(&self.landmarks);
+                    return true;
+                }
+
+                //Select the cells inside the lasso. Its path is kept so that the drawing can be replayed
+                if let Some(path) = self.lasso_path.take() {
+                    if path.len() >= 3 {
+                        if let AsyncData::Loaded(reduction_data) = &self.get_reduction_data(ctx) {
+                            let (x1, x2) = path.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), p| (lo.min(p.0), hi.max(p.0)));
+                            let (y1, y2) = path.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), p| (lo.min(p.1), hi.max(p.1)));
+                            let selected_vert: Vec<usize> = (0..reduction_data.num_point).filter(|i| {
+                                let (px, py) = (reduction_data.data[i*2+0], reduction_data.data[i*2+1]);
+                                px >= x1 && px <= x2 && py >= y1 && py <= y2 && point_in_polygon(px, py, &path)
+                            }).collect();
+                            self.last_selection = selected_vert.clone();
+                            self.highlighted_cells.clear();
+                            self.annotating_cell = None;
+                            emit_selection(ctx, &self.selection_context, selected_vert);
+                        }
+                        self.lasso_path_history = path;
+                    }
+                    return true;
+                }
+
+                let reduction_data = &self.get_reduction_data(ctx);
+                if let Some(rect) = &mut self.current_selection {
+                    let (wx,wy) = self.camera.cam2world(cx as f32, cy as f32);
+                    rect.x2=wx;
+                    rect.y2=wy;
+
+                    if let AsyncData::Loaded(reduction_data) = reduction_data {
+
+                        let (x1,x2) =rect.range_x();
+                        let (y1,y2) =rect.range_y();
+
+                        if x1==x2 && y1==y2 {
+                            log::debug!("this is a click");
+
+                            //When grouping, clicking a group opens its summary card. Clicking elsewhere closes it
+                            if let Some(groups) = &self.cell_groups {
+                                self.cluster_card = self.last_group.map(|g| (g, cx as f32, cy as f32));
+                                if let Some(g) = self.last_group {
+                                    ctx.props().on_group_clicked.emit(groups.members[g].clone());
+                                }
+                            }
+
+                            if self.current_tool==CurrentTool::Select {
+                                if let Some(cell) = &self.last_cell {
+                                    self.last_selection = vec![cell.clone()];
+                                    self.highlighted_cells.clear();
+                                    self.annotating_cell = Some(*cell);
+                                    emit_selection(ctx, &self.selection_context, vec![cell.clone()]);
+                                }
                             }
                         } else {
                             log::debug!("this is a rect select");
@@ -351,7 +1490,10 @@ impl Component for ReductionView {
                             //log::debug!("sel-end {:?}",rect);
                             //log::debug!("sel-en!! {:?}",selected_vert);
 
-                            ctx.props().on_cell_clicked.emit(selected_vert);                            
+                            self.last_selection = selected_vert.clone();
+                            self.highlighted_cells.clear();
+                            self.annotating_cell = None;
+                            emit_selection(ctx, &self.selection_context, selected_vert);                            
                         }
                     }
                     self.current_selection=None;
@@ -369,9 +1511,10 @@ impl Component for ReductionView {
     /// Render this component
     fn view(&self, ctx: &Context<Self>) -> Html {
        let current_legend: PerCellDataSource = ctx.props().current_colorby.clone();
-       let legend_name = match current_legend {
+       let legend_name = match current_legend.clone() {
            PerCellDataSource::Metadata(name) => name,
-           PerCellDataSource::Counts(_,_) => "Error_naming_legend".to_string()
+           PerCellDataSource::Counts(_,_) => "Error_naming_legend".to_string(),
+           PerCellDataSource::ClusterProportionScore { .. } | PerCellDataSource::ExpressionRatio { .. } => column_label(&current_legend),
        };
 
         let cb_mousemoved = ctx.link().callback(move |e: MouseEvent | { 
@@ -383,14 +1526,67 @@ impl Component for ReductionView {
             //there is mouse movement! https://developer.mozilla.org/en-US/docs/Web/API/MouseEvent/movementX 
         });
         
-        let cb_mousewheel = ctx.link().callback(move |e: WheelEvent | { 
-            e.prevent_default();
-            MsgReduction::MouseWheel(e.delta_y() as f32)
+        //If zoom is locked, let the page scroll instead
+        let zoom_locked = self.zoom_locked;
+        let cb_mousewheel = ctx.link().batch_callback(move |e: WheelEvent | { 
+            if zoom_locked {
+                None
+            } else {
+                e.prevent_default();
+                let (dy, is_trackpad) = wheel_delta_pixels(&e);
+                Some(MsgReduction::MouseWheel(dy, is_trackpad))
+            }
         });
 
         let cb_mouseclicked = ctx.link().callback(move |_e: MouseEvent | { 
             MsgReduction::MouseClick
         });
+
+        //Only single-finger taps count toward a double tap; pinches do not
+        let cb_touchstart = ctx.link().batch_callback(move |e: TouchEvent | { 
+            if e.touches().length() == 1 {
+                Some(MsgReduction::Tap(js_sys::Date::now()))
+            } else {
+                None
+            }
+        });
+
+        //Double-click in zoom mode: zoom in on the cluster of the hovered cell
+        let hovered_category = match (&self.current_tool, self.last_cell, &ctx.props().color_reduction_by) {
+            (CurrentTool::Zoom, Some(cell), ReductionColoringWithData::ByMeta(_, AsyncData::Loaded(color_data))) => {
+                if let CountFileMetaColumnData::Categorical(vec_data, _vec_cats) = color_data.as_ref() {
+                    vec_data.get(cell).map(|c| *c as usize)
+                } else {
+                    None
+                }
+            },
+            _ => None
+        };
+        let cb_mousedoubleclicked = ctx.link().batch_callback(move |_e: MouseEvent | { 
+            hovered_category.map(MsgReduction::ZoomToCluster)
+        });
+
+        //Arrow keys move the highlight between cells. World y grows downward, as for the camera.
+        //Escape closes the summary card of a group
+        let has_highlight = self.last_cell.is_some();
+        let has_cluster_card = self.cluster_card.is_some();
+        let cb_keydown = ctx.link().batch_callback(move |e: KeyboardEvent | {
+            let dir = match e.key().as_str() {
+                "Escape" if has_cluster_card => return Some(MsgReduction::CloseClusterCard),
+                "ArrowUp" => (0.0, -1.0),
+                "ArrowDown" => (0.0, 1.0),
+                "ArrowLeft" => (-1.0, 0.0),
+                "ArrowRight" => (1.0, 0.0),
+                _ => return None
+            };
+            if has_highlight {
+                //Do not scroll the page
+                e.prevent_default();
+                Some(MsgReduction::BrowseCell(dir.0, dir.1))
+            } else {
+                None
+            }
+        });
         
         let cb_click_select = ctx.link().callback(move |_e: MouseEvent | { 
             MsgReduction::SelectCurrentTool(CurrentTool::Select)
@@ -404,17 +1600,155 @@ impl Component for ReductionView {
             MsgReduction::SelectCurrentTool(CurrentTool::ZoomAll)
         });
 
+        let cb_click_lasso = ctx.link().callback(move |_e: MouseEvent | {
+            MsgReduction::SelectCurrentTool(CurrentTool::Lasso)
+        });
+
+        let cb_click_explore = ctx.link().callback(move |_e: MouseEvent | { 
+            MsgReduction::SelectCurrentTool(CurrentTool::Explore)
+        });
+
+        let cb_click_zoomlock = ctx.link().callback(move |_e: MouseEvent | { 
+            MsgReduction::ToggleZoomLock
+        });
+
+        let cb_click_velocity = ctx.link().callback(move |_e: MouseEvent | { 
+            MsgReduction::ToggleShowVelocity
+        });
+
+        let cb_click_follow = ctx.link().callback(move |_e: MouseEvent | { 
+            MsgReduction::ToggleFollowSelection
+        });
+
+        let on_toggle_sync_color_scale = ctx.props().on_toggle_sync_color_scale.clone();
+        let cb_click_radial = ctx.link().callback(move |_e: MouseEvent | { 
+            MsgReduction::ToggleRadialLayout
+        });
+
+        let cb_click_relax = ctx.link().callback(move |_e: MouseEvent | { 
+            MsgReduction::ToggleRelaxLayout
+        });
+
+        let on_toggle_focus_context = ctx.props().on_toggle_focus_context.clone();
+        let cb_click_focus_context = Callback::from(move |_e: MouseEvent | { 
+            on_toggle_focus_context.emit(());
+        });
+
+        let cb_click_export_svg = ctx.link().callback(move |_e: MouseEvent | { 
+            MsgReduction::ExportSvg
+        });
+
+        let cb_click_sync_color = Callback::from(move |_e: MouseEvent | { 
+            on_toggle_sync_color_scale.emit(());
+        });
+
+        //Middle button: gravity well. Other buttons select
         let cb_onmousedown = ctx.link().callback(move |e: MouseEvent | { 
             e.prevent_default();
             let (x_cam, y_cam) = mouseevent_get_cx(&e);
-            MsgReduction::MouseStartSelect(x_cam, y_cam)
+            if e.button() == 1 {
+                MsgReduction::GravityWell(x_cam, y_cam)
+            } else {
+                MsgReduction::MouseStartSelect(x_cam, y_cam)
+            }
         });
 
-        let cb_onmouseup = ctx.link().callback(move |e: MouseEvent | { 
+        let cb_onmouseup = ctx.link().batch_callback(move |e: MouseEvent | { 
             e.prevent_default();
             let (x_cam, y_cam) = mouseevent_get_cx(&e);
-            MsgReduction::MouseEndSelect(x_cam, y_cam)
+            if e.button() == 1 {
+                None
+            } else {
+                Some(MsgReduction::MouseEndSelect(x_cam, y_cam))
+            }
+        });
+
+        let cb_color_transform = ctx.link().batch_callback(move |e: Event | { 
+            let target: Option<EventTarget> = e.target();
+            let select: HtmlSelectElement = target.and_then(|t| t.dyn_into::<HtmlSelectElement>().ok()).expect("wrong type");
+            ColorScaleTransform::from_name(&select.value()).map(MsgReduction::SetColorTransform)
+        });
+
+        //Threshold for two-color display. Empty to show the color scale
+        let on_set_expression_threshold = ctx.props().on_set_expression_threshold.clone();
+        let cb_threshold = Callback::from(move |e: Event | { 
+            let input: HtmlInputElement = e.target().expect("Event should have a target when dispatched").unchecked_into();
+            on_set_expression_threshold.emit(input.value().trim().parse::<f32>().ok());
         });
+        let threshold_value = ctx.props().expression_threshold.map(|t| t.to_string()).unwrap_or_default();
+
+        //Slider to hide cells with low values, over the range of the numeric coloring
+        let html_expression_filter = match ctx.props().global_color_range.or_else(|| get_numeric_coloring_range(&ctx.props().color_reduction_by)) {
+            Some((min_val, max_val)) if max_val > min_val => {
+                let on_set_expression_filter = ctx.props().on_set_expression_filter.clone();
+                let cb_expression_filter = Callback::from(move |e: InputEvent | { 
+                    let input: HtmlInputElement = e.target().expect("Event should have a target when dispatched").unchecked_into();
+                    if let Ok(v) = input.value().parse::<f32>() {
+                        on_set_expression_filter.emit(v);
+                    }
+                });
+                let filter_value = ctx.props().min_expression_filter.unwrap_or(min_val);
+                html! {
+                    <div title="Hide cells below this value">
+                        <input type="range" style="width: 80px;"
+                            min={min_val.to_string()} max={max_val.to_string()} step={((max_val - min_val)/100.0).to_string()}
+                            value={filter_value.to_string()} oninput={cb_expression_filter}/>
+                        <div class="bisci-label-left">{ format!("Min {:.2}", filter_value) }</div>
+                    </div>
+                }
+            },
+            _ => html! {""}
+        };
+
+        //Column to correlate with the coloring, and the correlation over visible cells
+        let correlation_columns = ctx.props().correlation_columns.clone();
+        let list_correlation_columns: Vec<PerCellDataSource> = correlation_columns.iter().map(|(name, _)| name.clone()).collect();
+        let cb_correlate_with = ctx.link().callback(move |e: Event | { 
+            let select: HtmlSelectElement = e.target().expect("Event should have a target when dispatched").unchecked_into();
+            let column = select.value().parse::<usize>().ok().and_then(|i| list_correlation_columns.get(i).cloned());
+            MsgReduction::SetCorrelateWith(column)
+        });
+        let list_correlation_html = correlation_columns.iter().enumerate().map(|(i, (name, _))| {
+            html! {
+                <option value={i.to_string()} selected={Some(name)==self.correlate_with.as_ref()}>
+                    {column_label(name)}
+                </option>
+            }
+        }).collect::<Html>();
+        let correlation_badge = match self.get_visible_correlation(ctx) {
+            Some((r, num_cells)) => html! {
+                <span class="biscvi-correlation-badge" title={format!("Pearson correlation over {} visible cells", num_cells)}>
+                    { format!("r = {:.2}", r) }
+                </span>
+            },
+            None => html! {""}
+        };
+
+        //Options for the color scale transform
+        let list_transform_html = ColorScaleTransform::list_all().iter().map(|t| {
+            html! {
+                <option value={t.name()} selected={*t==self.color_transform}>
+                    {t.name()}
+                </option>
+            }
+        }).collect::<Html>();
+
+        //Ticks for the legend of numeric data. Evenly spaced along the color bar
+        let mut list_legend_ticks = Vec::new();
+        let legend_range = ctx.props().global_color_range.or_else(|| get_numeric_coloring_range(&ctx.props().color_reduction_by));
+        let legend_range = match (legend_range, ctx.props().diverging_center) {
+            (Some((min_val, max_val)), Some(center)) => Some(symmetric_range(min_val, max_val, center)),
+            (legend_range, _) => legend_range,
+        };
+        if let Some((min_val, max_val)) = legend_range {
+            let num_ticks = 5;
+            for (i, v) in self.color_transform.legend_ticks(min_val, max_val, num_ticks).iter().enumerate() {
+                let y = 17.0 + 180.0*(1.0 - (i as f32)/((num_ticks-1) as f32));
+                list_legend_ticks.push(html! {
+                    <text x="24" y={y.to_string()} dy="0.3em" style="font-size: 10px;">{format!("{:.2}", v)}</text>
+                });
+            }
+        }
 
         // Render box representing current selection
         let html_select = if let Some(rect) = &self.current_selection {
@@ -425,9 +1759,7 @@ impl Component for ReductionView {
             let (x1,y1) = self.camera.world2cam(x1, y1); //camera is in range [-1,1]
             let (x2,y2) = self.camera.world2cam(x2, y2);
 
-            let canvas = self.node_refs[0].cast::<HtmlCanvasElement>().unwrap();
-            let w = canvas.width() as f32;
-            let h = canvas.height() as f32;
+            let (w, h) = self.get_css_canvas_size(ctx);
 
             let x1 = x1*w/2.0 + w/2.0;
             let x2 = x2*w/2.0 + w/2.0;
@@ -441,76 +1773,634 @@ impl Component for ReductionView {
             html! {""}
         };
 
+        //Lasso being drawn, or the part of the last lasso replayed so far
+        let lasso_points = match (&self.lasso_path, &self.lasso_replay) {
+            (Some(path), _) => Some(&path[..]),
+            (None, Some((num_shown, _))) => Some(&self.lasso_path_history[..(*num_shown).min(self.lasso_path_history.len())]),
+            (None, None) => None
+        };
+        let html_lasso = if let Some(points) = lasso_points {
+            let (w, h) = self.get_css_canvas_size(ctx);
+            let points = points.iter().map(|(wx, wy)| {
+                let (cx, cy) = self.camera.world2cam(*wx, *wy);
+                format!("{},{}", cx*w/2.0 + w/2.0, cy*h/2.0 + h/2.0)
+            }).collect::<Vec<_>>().join(" ");
+            html! {
+                <polyline points={points} class="biscvi-lasso"/>
+            }
+        } else {
+            html! {""}
+        };
+
+        //Cursors of other peers
+        let palette = get_palette_for_categories(ctx.props().peer_cursors.len());
+        let (w, h) = self.get_css_canvas_size(ctx);
+        let html_peer_cursors = ctx.props().peer_cursors.iter().enumerate().map(|(i, (wx, wy))| {
+            let (cx, cy) = self.camera.world2cam(*wx, *wy);
+            let x = cx*w/2.0 + w/2.0;
+            let y = cy*h/2.0 + h/2.0;
+            let color = rgbvec2string(palette[i % palette.len()]);
+            html! {
+                <circle cx={x.to_string()} cy={y.to_string()} r="6" fill={color} fill-opacity="0.7" stroke="black"/>
+            }
+        }).collect::<Html>();
+
+        //Annotations of cells, next to each cell in view
+        let reduction_data = self.get_reduction_data(ctx);
+        let cell_pixel_pos = |cell: usize| -> Option<(f32,f32)> {
+            let AsyncData::Loaded(reduction_data) = &reduction_data else {
+                return None;
+            };
+            if cell >= reduction_data.num_point {
+                return None;
+            }
+            let (cx, cy) = self.camera.world2cam(reduction_data.data[cell*2+0], reduction_data.data[cell*2+1]);
+            if cx.abs() > 1.0 || cy.abs() > 1.0 {
+                return None;
+            }
+            Some((cx*w/2.0 + w/2.0, cy*h/2.0 + h/2.0))
+        };
+        let html_annotations = ctx.props().cell_annotations.iter().filter_map(|(cell, text)| {
+            let (x, y) = cell_pixel_pos(*cell)?;
+            let (dx, dy) = self.label_offsets.get(cell).cloned().unwrap_or((0.0, 0.0));
+            let (x, y) = (x + dx, y + dy);
+            Some(html! {
+                <text x={(x + 6.0).to_string()} y={(y - 6.0).to_string()} class="biscvi-annotation">{ text.clone() }</text>
+            })
+        }).collect::<Html>();
+
+        //Details shown depend on how close the camera is
+        let zoom_level = self.camera.zoom_level();
+
+        //Labels of the categories, at the centroids of their cells. Only when the clusters are not yet apart
+        let html_cluster_labels = match (zoom_level, &self.label_groups, &ctx.props().color_reduction_by) {
+            (ZoomLevel::Overview | ZoomLevel::Cluster, Some(groups), ReductionColoringWithData::ByMeta(_, AsyncData::Loaded(color_data))) if self.cell_groups.is_none() => {
+                if let CountFileMetaColumnData::Categorical(_, vec_cats) = color_data.as_ref() {
+                    let class = if zoom_level == ZoomLevel::Overview { "biscvi-cluster-label" } else { "biscvi-cluster-label biscvi-cluster-label-small" };
+                    groups.categories.iter().enumerate().filter_map(|(i, category)| {
+                        let (cx, cy) = self.camera.world2cam(groups.centroids.data[i*2+0], groups.centroids.data[i*2+1]);
+                        if cx.abs() > 1.0 || cy.abs() > 1.0 {
+                            return None;
+                        }
+                        let name = vec_cats.get(*category)?;
+                        Some(html! {
+                            <text x={(cx*w/2.0 + w/2.0).to_string()} y={(cy*h/2.0 + h/2.0).to_string()} class={class}>{ name.clone() }</text>
+                        })
+                    }).collect::<Html>()
+                } else {
+                    html! {""}
+                }
+            },
+            _ => html! {""}
+        };
+
+        //IDs of the cells in view, when close enough to tell them apart
+        let html_cell_ids = match (zoom_level, &reduction_data, &ctx.props().cell_ids) {
+            (ZoomLevel::Cell, AsyncData::Loaded(reduction_data), AsyncData::Loaded(cell_ids)) if self.cell_groups.is_none() => {
+                (0..reduction_data.num_point.min(cell_ids.len()))
+                    .filter_map(|cell| cell_pixel_pos(cell).map(|pos| (cell, pos)))
+                    .take(MAX_CELL_ID_LABELS)
+                    .map(|(cell, (x, y))| html! {
+                        <text x={(x + 4.0).to_string()} y={(y + 3.0).to_string()} class="biscvi-cell-id">{ cell_ids[cell].clone() }</text>
+                    }).collect::<Html>()
+            },
+            _ => html! {""}
+        };
+
+        //Landmarks, as pins pointing at their position
+        let html_landmarks = self.landmarks.iter().map(|landmark| {
+            let (cx, cy) = self.camera.world2cam(landmark.world_x, landmark.world_y);
+            let x = cx*w/2.0 + w/2.0;
+            let y = cy*h/2.0 + h/2.0;
+            let head_y = y - LANDMARK_PIN_HEIGHT;
+            let color = rgbvec2string(landmark.color);
+            html! {
+                <g class="biscvi-landmark">
+                    <line x1={x.to_string()} y1={y.to_string()} x2={x.to_string()} y2={head_y.to_string()} stroke="black" stroke-width="2"/>
+                    <circle cx={x.to_string()} cy={head_y.to_string()} r="6" fill={color} stroke="black"/>
+                    <text x={(x + 9.0).to_string()} y={(head_y + 4.0).to_string()}>{ landmark.label.clone() }</text>
+                </g>
+            }
+        }).collect::<Html>();
+
+        //Loadings as arrows from the origin, scaled so that the longest spans BIPLOT_ARROW_EXTENT of the reduction
+        let html_biplot = match &reduction_data {
+            AsyncData::Loaded(reduction_data) if !ctx.props().biplot_arrows.is_empty() => {
+                let extent = (reduction_data.max_x - reduction_data.min_x).min(reduction_data.max_y - reduction_data.min_y);
+                let max_norm = ctx.props().biplot_arrows.iter().map(|(_, l1, l2)| (l1*l1 + l2*l2).sqrt()).fold(0.0, f32::max);
+                let scale = if max_norm > 0.0 { BIPLOT_ARROW_EXTENT*extent/max_norm } else { 0.0 };
+
+                let to_pixel = |wx: f32, wy: f32| {
+                    let (cx, cy) = self.camera.world2cam(wx, wy);
+                    (cx*w/2.0 + w/2.0, cy*h/2.0 + h/2.0)
+                };
+                let (x0, y0) = to_pixel(0.0, 0.0);
+                let arrows = ctx.props().biplot_arrows.iter().map(|(gene, l1, l2)| {
+                    let (x1, y1) = to_pixel(l1*scale, l2*scale);
+                    html! {
+                        <g class="biscvi-biplot-arrow">
+                            <line x1={x0.to_string()} y1={y0.to_string()} x2={x1.to_string()} y2={y1.to_string()} marker-end="url(#biplot-arrowhead)"/>
+                            <text x={(x1 + 4.0).to_string()} y={(y1 + 4.0).to_string()}>{ gene.clone() }</text>
+                        </g>
+                    }
+                }).collect::<Html>();
+                html! {
+                    <>
+                        <defs>
+                            <marker id="biplot-arrowhead" viewBox="0 0 10 10" refX="9" refY="5" markerWidth="6" markerHeight="6" orient="auto-start-reverse">
+                                <path d="M 0 0 L 10 5 L 0 10 z" fill="darkred"/>
+                            </marker>
+                        </defs>
+                        { arrows }
+                    </>
+                }
+            },
+            _ => html! {""}
+        };
+
+        //Input to annotate the clicked cell. Enter to save, Escape to close
+        let html_annotation_input = match self.annotating_cell.and_then(|cell| cell_pixel_pos(cell).map(|pos| (cell, pos))) {
+            Some((cell, (x, y))) => {
+                let cb_annotation_key = ctx.link().batch_callback(move |e: KeyboardEvent | {
+                    match e.key().as_str() {
+                        "Enter" => {
+                            let input: HtmlInputElement = e.target().expect("Event should have a target when dispatched").unchecked_into();
+                            Some(MsgReduction::SubmitAnnotation(cell, input.value()))
+                        },
+                        "Escape" => Some(MsgReduction::CloseAnnotation),
+                        _ => None
+                    }
+                });
+                let current_text = ctx.props().cell_annotations.get(&cell).cloned().unwrap_or_default();
+                html! {
+                    <div style={format!("position: absolute; left: {}px; top: {}px; z-index: 2;", x + 10.0, y + 10.0)}>
+                        <input type="text" placeholder="Annotation" value={current_text} onkeydown={cb_annotation_key} autofocus=true/>
+                    </div>
+                }
+            },
+            None => html! {""}
+        };
+
         //Compute current canvas size. Not automatic via CSS
         let window = window().expect("no window");//.document().expect("no document on window");
         let _window_h = window.inner_height().expect("failed to get height").as_f64().unwrap();
         let window_w = window.inner_width().expect("failed to get width").as_f64().unwrap();
-        let canvas_w = (window_w*0.59) as usize;
-        let canvas_h = 500 as usize; //(window_h*0.59) as usize;
-        
-        //Compose the view
-        html! {
-            <div style="display: flex; height: 500px; position: relative;">
-
-                <div style="position: absolute; left:0; top:0; display: flex; ">
+        let (canvas_w, canvas_h) = if ctx.props().print_layout {
+            ((PRINT_PAGE_INCHES.0*CSS_DPI) as usize, (PRINT_PAGE_INCHES.1*CSS_DPI) as usize)
+        } else {
+            ((window_w*0.59) as usize, 500 as usize) //(window_h*0.59) as usize;
+        };
+        let pixel_ratio = get_pixel_ratio(ctx.props());
+        let buffer_w = ((canvas_w as f32)*pixel_ratio).round().max(1.0) as usize;
+        let buffer_h = ((canvas_h as f32)*pixel_ratio).round().max(1.0) as usize;
+
+        //Tool buttons: if active, callback, title, icon
+        let list_tools: Vec<(bool, Callback<MouseEvent>, &str, Html)> = vec![
+            //Select
+            (self.current_tool==CurrentTool::Select, cb_click_select, "Select", html! {
+                <svg data-icon="polygon-filter" height="16" role="img" viewBox="0 0 16 16" width="16"><path d="M14 5c-.24 0-.47.05-.68.13L9.97 2.34c.01-.11.03-.22.03-.34 0-1.1-.9-2-2-2S6 .9 6 2c0 .04.01.08.01.12L2.88 4.21C2.61 4.08 2.32 4 2 4 .9 4 0 4.9 0 6c0 .74.4 1.38 1 1.72v4.55c-.6.35-1 .99-1 1.73 0 1.1.9 2 2 2 .74 0 1.38-.4 1.72-1h4.55c.35.6.98 1 1.72 1 1.1 0 2-.9 2-2 0-.37-.11-.7-.28-1L14 9c1.11-.01 2-.9 2-2s-.9-2-2-2zm-4.01 7c-.73 0-1.37.41-1.71 1H3.73c-.18-.3-.43-.55-.73-.72V7.72c.6-.34 1-.98 1-1.72 0-.04-.01-.08-.01-.12l3.13-2.09c.27.13.56.21.88.21.24 0 .47-.05.68-.13l3.35 2.79c-.01.11-.03.22-.03.34 0 .37.11.7.28 1l-2.29 4z" fill-rule="evenodd"></path></svg>
+            }),
+            //Lasso. Drag around the cells to select
+            (self.current_tool==CurrentTool::Lasso, cb_click_lasso, "Lasso select", html! {
+                <svg data-icon="lasso" height="16" role="img" viewBox="0 0 16 16" width="16"><ellipse cx="8.5" cy="6" rx="6" ry="4.5" fill="none" stroke="black" stroke-width="1.8"/><path d="M4.5 9.5c-1 1.5-.5 3 1 3.5s1.5 1.5.5 2.5" fill="none" stroke="black" stroke-width="1.8"/></svg>
+            }),
+            //Zoom
+            (self.current_tool==CurrentTool::Zoom, cb_click_zoom, "Zoom", html! {
+                <svg data-icon="zoom-in" height="16" role="img" viewBox="0 0 16 16" width="16"><path d="M7.99 5.99v-2c0-.55-.45-1-1-1s-1 .45-1 1v2h-2c-.55 0-1 .45-1 1s.45 1 1 1h2v2c0 .55.45 1 1 1s1-.45 1-1v-2h2c.55 0 1-.45 1-1s-.45-1-1-1h-2zm7.56 7.44l-2.67-2.68a6.94 6.94 0 001.11-3.76c0-3.87-3.13-7-7-7s-7 3.13-7 7 3.13 7 7 7c1.39 0 2.68-.42 3.76-1.11l2.68 2.67a1.498 1.498 0 102.12-2.12zm-8.56-1.44c-2.76 0-5-2.24-5-5s2.24-5 5-5 5 2.24 5 5-2.24 5-5 5z" fill-rule="evenodd"></path></svg>
+            }),
+            //Zoom all
+            (self.current_tool==CurrentTool::ZoomAll, cb_click_zoomall, "Zoom to fit all", html! {
+                <svg data-icon="zoom-in" height="16" width="16" xmlns="http://www.w3.org/2000/svg"><path style="fill:none;stroke:#000;stroke-width:2.01074px;stroke-linecap:butt;stroke-linejoin:miter;stroke-opacity:1" d="M14.733 8.764v5.973H9.586m-8.29-5.973v5.973h5.146m8.29-7.5V1.264H9.587m-8.29 5.973V1.264h5.146"/></svg>
+            }),
+            //Zoom lock. Mouse wheel scrolls the page instead of zooming
+            (self.zoom_locked, cb_click_zoomlock, "Lock zoom", html! {
+                <svg data-icon="lock" height="16" role="img" viewBox="0 0 16 16" width="16"><path d="M13.96 7H12V3.95C12 1.77 10.21 0 8 0S4 1.77 4 3.95V7H1.96c-.55 0-.96.35-.96.9v6.91c0 .54.41 1.19.96 1.19h12c.55 0 1.04-.65 1.04-1.19V7.9c0-.55-.49-.9-1.04-.9zM6 7V3.95c0-1.09.9-1.97 2-1.97s2 .88 2 1.97V7H6z" fill-rule="evenodd"></path></svg>
+            }),
+            //Show velocity of cells
+            (self.show_velocity, cb_click_velocity, "Show velocity", html! {
+                <svg data-icon="trending-up" height="16" role="img" viewBox="0 0 16 16" width="16"><path d="M15 3h-3c-.55 0-1 .45-1 1s.45 1 1 1h.59L9 8.59 6.71 6.29A.997.997 0 006 6c-.28 0-.53.11-.71.29l-5 5a1.003 1.003 0 001.42 1.42L6 8.41l2.29 2.29c.18.19.43.3.71.3s.53-.11.71-.29L14 6.41V7c0 .55.45 1 1 1s1-.45 1-1V4c0-.55-.45-1-1-1z" fill-rule="evenodd"></path></svg>
+            }),
+            //Keep the selected cells centered
+            (self.follow_selection, cb_click_follow, "Follow selection", html! {
+                <svg data-icon="locate" height="16" role="img" viewBox="0 0 16 16" width="16"><path d="M15 7h-1.09c-.43-2.52-2.39-4.48-4.91-4.91V1c0-.55-.45-1-1-1S7 .45 7 1v1.09C4.48 2.52 2.52 4.48 2.09 7H1c-.55 0-1 .45-1 1s.45 1 1 1h1.09c.43 2.52 2.39 4.48 4.91 4.91V15c0 .55.45 1 1 1s1-.45 1-1v-1.09c2.52-.43 4.48-2.39 4.91-4.91H15c.55 0 1-.45 1-1s-.45-1-1-1zm-6 4.82V11c0-.55-.45-1-1-1s-1 .45-1 1v.82C5.57 11.4 4.6 10.43 4.18 9H5c.55 0 1-.45 1-1s-.45-1-1-1h-.82C4.6 5.57 5.57 4.6 7 4.18V5c0 .55.45 1 1 1s1-.45 1-1v-.82c1.43.42 2.4 1.39 2.82 2.82H11c-.55 0-1 .45-1 1s.45 1 1 1h.82c-.42 1.43-1.39 2.4-2.82 2.82z" fill-rule="evenodd"></path></svg>
+            }),
+            //Explore. Camera moves between points of interest
+            (self.current_tool==CurrentTool::Explore, cb_click_explore, "Explore", html! {
+                <svg data-icon="compass" height="16" role="img" viewBox="0 0 16 16" width="16"><path d="M8 0C3.58 0 0 3.58 0 8s3.58 8 8 8 8-3.58 8-8-3.58-8-8-8zm0 14c-3.31 0-6-2.69-6-6s2.69-6 6-6 6 2.69 6 6-2.69 6-6 6zm3.5-9.5L6.5 6.5l-2 5 5-2 2-5zM8 9c-.55 0-1-.45-1-1s.45-1 1-1 1 .45 1 1-.45 1-1 1z" fill-rule="evenodd"></path></svg>
+            }),
+            //Use the same color scale in all panels
+            (ctx.props().sync_color_scale, cb_click_sync_color, "Sync color scale", html! {
+                <svg data-icon="link" height="16" role="img" viewBox="0 0 16 16" width="16"><path d="M4.99 11.99c.28 0 .53-.11.71-.29l6-6a1.003 1.003 0 00-1.42-1.42l-6 6a1.003 1.003 0 00.71 1.71zm3.85-2.02L6.4 12.41l-1 1-.02-.02c-.36.38-.87.61-1.44.61-1.1 0-2-.9-2-2 0-.57.23-1.08.6-1.44l-.02-.02 1-1 2.44-2.44c-.33-.11-.67-.19-1.04-.19-1.3 0-2.43.67-3.1 1.68l-.02-.02-.37.37-.03.03C.51 9.1.02 10.26.02 11.53c0 2.42 1.96 4.45 4.38 4.45 1.27 0 2.43-.49 3.3-1.29l.42-.42.02.02c1.01-.67 1.68-1.8 1.68-3.1 0-.38-.08-.73-.19-1.07zM11.6.02c-1.27 0-2.43.49-3.3 1.29l-.42.42-.02-.02c-1.01.67-1.68 1.8-1.68 3.1 0 .38.08.73.19 1.07l2.44-2.44 1-1 .02.02c.36-.38.87-.61 1.44-.61 1.1 0 2 .9 2 2 0 .57-.23 1.08-.6 1.44l.02.02-1 1-2.44 2.44c.33.11.67.19 1.04.19 1.3 0 2.43-.67 3.1-1.68l.02.02.37-.37.03-.03c.79-.85 1.28-2.01 1.28-3.28 0-2.42-1.96-4.45-4.38-4.45z" fill-rule="evenodd"></path></svg>
+            }),
+            //Place each category in its own sector of a disc
+            (self.layout_mode==LayoutMode::RadialCluster, cb_click_radial, "Radial layout of categories", html! {
+                <svg data-icon="pie-chart" height="16" role="img" viewBox="0 0 16 16" width="16"><path d="M7 1.08c-3.37.5-5.97 3.4-5.97 6.92 0 3.87 3.13 7 6.99 7 3.52 0 6.42-2.61 6.91-6H7V1.08zM8 0v8h8c0-4.42-3.58-8-8-8z" fill-rule="evenodd"></path></svg>
+            }),
+            //Spread out overlapping points
+            (self.layout_mode==LayoutMode::Relaxed, cb_click_relax, "Relax layout", html! {
+                <svg data-icon="scatter-plot" height="16" role="img" viewBox="0 0 16 16" width="16"><circle cx="3" cy="4" r="2"/><circle cx="12" cy="3" r="2"/><circle cx="8" cy="8" r="2"/><circle cx="4" cy="13" r="2"/><circle cx="13" cy="12" r="2"/></svg>
+            }),
+            //Focus + context. Only the middle of the view is drawn in detail
+            (ctx.props().focus_context==FocusContextMode::Enabled, cb_click_focus_context, "Focus + context", html! {
+                <svg data-icon="focus" height="16" role="img" viewBox="0 0 16 16" width="16"><rect x="1" y="1" width="14" height="14" fill="none" stroke="black" stroke-dasharray="2,2"/><rect x="5" y="5" width="6" height="6"/></svg>
+            }),
+            //Save the view as vector graphics
+            (false, cb_click_export_svg, "Export SVG", html! {
+                <svg data-icon="download" height="16" role="img" viewBox="0 0 16 16" width="16"><path d="M7.99-.01c-4.42 0-8 3.58-8 8s3.58 8 8 8 8-3.58 8-8-3.58-8-8-8zm3.71 7.71l-3 3c-.18.18-.43.29-.71.29s-.53-.11-.71-.29l-3-3a1.003 1.003 0 011.42-1.42L7 7.59V3.99c0-.55.45-1 1-1s1 .45 1 1v3.59l1.29-1.29c.18-.19.43-.3.71-.3a1.003 1.003 0 01.7 1.71z" fill-rule="evenodd"></path></svg>
+            }),
+        ];
+
+        //On narrow canvases, the tools are placed in a dropdown menu so that they do not overlap
+        let toolbar_layout = if canvas_w < toolbar_collapse_width(list_tools.len()) { ToolbarLayout::Collapsed } else { ToolbarLayout::Expanded };
+        let html_toolbar = match toolbar_layout {
+            ToolbarLayout::Expanded => {
+                list_tools.into_iter().enumerate().map(|(i, (active, cb, title, icon))| html! {
+                    <div style={get_tool_style(canvas_w.saturating_sub(TOOLBAR_MARGIN_RIGHT + TOOL_SPACING*i), 10, active)} onclick={cb} title={title}>
+                        { icon }
+                    </div>
+                }).collect::<Html>()
+            },
+            ToolbarLayout::Collapsed => {
+                let menu_x = canvas_w.saturating_sub(TOOLBAR_MARGIN_RIGHT);
+                let cb_toggle_menu = ctx.link().callback(move |_e: MouseEvent | { 
+                    MsgReduction::ToggleToolbarMenu
+                });
+
+                //Any click in the menu closes it, after the tool has handled the click
+                let html_menu = if self.toolbar_menu_open {
+                    let cb_close_menu = ctx.link().callback(move |_e: MouseEvent | { 
+                        MsgReduction::ToggleToolbarMenu
+                    });
+                    let list_items = list_tools.into_iter().enumerate().map(|(i, (active, cb, title, icon))| html! {
+                        <div style={get_tool_style(menu_x, 10 + TOOL_SPACING*(i + 1), active)} onclick={cb} title={title}>
+                            { icon }
+                        </div>
+                    }).collect::<Html>();
+                    html! {
+                        <div onclick={cb_close_menu}>
+                            { list_items }
+                        </div>
+                    }
+                } else {
+                    html! {""}
+                };
+
+                html! {
+                    <>
+                        <div style={get_tool_style(menu_x, 10, self.toolbar_menu_open)} onclick={cb_toggle_menu} title="Tools">
+                            <svg data-icon="menu" height="16" role="img" viewBox="0 0 16 16" width="16"><path d="M1 4h14c.55 0 1-.45 1-1s-.45-1-1-1H1c-.55 0-1 .45-1 1s.45 1 1 1zm14 3H1c-.55 0-1 .45-1 1s.45 1 1 1h14c.55 0 1-.45 1-1s-.45-1-1-1zm0 5H1c-.55 0-1 .45-1 1s.45 1 1 1h14c.55 0 1-.45 1-1s-.45-1-1-1z" fill-rule="evenodd"></path></svg>
+                        </div>
+                        { html_menu }
+                    </>
+                }
+            },
+        };
+
+        
+        //Number of cells drawn, if there are too many to draw all
+        let cb_toggle_subsample = ctx.link().callback(move |_e: MouseEvent | { 
+            MsgReduction::ToggleSubsample
+        });
+        let num_point = match &self.get_reduction_data(ctx) {
+            AsyncData::Loaded(reduction_data) => reduction_data.num_point,
+            _ => 0
+        };
+        let html_subsample = match ctx.props().max_display_points {
+            Some(max_points) if num_point > max_points => {
+                let (text, button_text) = if self.show_subsample {
+                    (format!("Showing {} / {} cells", format_count(max_points), format_count(num_point)), "Show all")
+                } else {
+                    (format!("Showing all {} cells", format_count(num_point)), "Subsample")
+                };
+                html! {
+                    <div class="biscvi-subsample-indicator" style={format!("left: {}px; top: {}px;", canvas_w.saturating_sub(300), canvas_h.saturating_sub(30))}>
+                        { text }
+                        <button onclick={cb_toggle_subsample}>{ button_text }</button>
+                    </div>
+                }
+            },
+            _ => html! {""}
+        };
+
+        //What the opacity of cells means, when it shows confidence
+        let html_confidence_legend = if ctx.props().confidence.is_some() {
+            html! {
+                <div class="biscvi-confidence-legend" style={format!("left: {}px; top: 8px;", (canvas_w/2).saturating_sub(60))}>
+                    <div>{"Opacity: confidence"}</div>
+                    <div class="biscvi-confidence-legend-bar"></div>
+                    <div class="biscvi-confidence-legend-ticks"><span>{"0"}</span><span>{"1"}</span></div>
+                </div>
+            }
+        } else {
+            html! {""}
+        };
+
+        let html_cluster_status = if let Some(text) = &self.hovered_cluster_name {
+            html! {
+                <div class="biscvi-cluster-status" style={format!("top: {}px; width: {}px;", canvas_h.saturating_sub(22), canvas_w)}>
+                    { text.clone() }
+                </div>
+            }
+        } else {
+            html! {""}
+        };
+
+        //Place the legend in a corner. If chosen automatically, where it covers the fewest points
+        let legend_position = match ctx.props().legend_position {
+            LegendPosition::Automatic => match &self.get_reduction_data(ctx) {
+                AsyncData::Loaded(reduction_data) => choose_legend_corner(reduction_data, &self.camera, canvas_w as f32, canvas_h as f32),
+                _ => LegendPosition::TopLeft
+            },
+            position => position
+        };
+        let legend_x = if legend_position.is_right() { canvas_w.saturating_sub(8 + LEGEND_WIDTH) } else { 8 };
+        let legend_y = if legend_position.is_bottom() { canvas_h.saturating_sub(LEGEND_MARGIN_BOTTOM + LEGEND_HEIGHT) } else { LEGEND_MARGIN_TOP };
+        let legend_side = if legend_position.is_right() { "right" } else { "left" };
+        //Category counts extend away from the corner
+        let category_counts_y = if legend_position.is_bottom() {
+            format!("bottom: {}px;", LEGEND_HEIGHT + 5)
+        } else {
+            format!("top: {}px;", LEGEND_HEIGHT + 5)
+        };
+
+        //Tooltip for the hovered cell
+        let html_tooltip = if let (Some(_cell), Some(genes), ZoomLevel::Cluster | ZoomLevel::Cell) = (&self.last_cell, &ctx.props().tooltip_genes, zoom_level) {
+            let (x,y) = self.last_pos; //camera is in range [-1,1]
+            let x = (x + 1.0)/2.0*(canvas_w as f32) + 15.0;
+            let y = (y + 1.0)/2.0*(canvas_h as f32) + 15.0;
+            html! {
+                <div class="biscvi-tooltip" style={format!("left: {}px; top: {}px;", x, y)}>
+                    { make_gene_barchart(genes) }
+                </div>
+            }
+        } else {
+            html! {""}
+        };
+
+        //Summary of the clicked group: name, number of cells, top features, and cells per sample
+        let html_cluster_card = match (&self.cluster_card, &self.cell_groups) {
+            (Some((g, x, y)), Some(groups)) if *g < groups.members.len() => {
+                let members = &groups.members[*g];
+                let category = groups.categories[*g];
+                let name = match &ctx.props().group_by_data {
+                    AsyncData::Loaded(column) => match column.as_ref() {
+                        CountFileMetaColumnData::Categorical(_, vec_cats) => vec_cats.get(category).cloned(),
+                        _ => None
+                    },
+                    _ => None
+                }.unwrap_or_else(|| format!("Group {}", category));
+
+                let html_genes = match &ctx.props().cluster_card_genes {
+                    Some(genes) if genes.is_empty() => html! { <div>{"No features expressed"}</div> },
+                    Some(genes) => genes.iter().map(|(gene, v)| html! {
+                        <div>{ format!("{} ({:.2})", gene, v) }</div>
+                    }).collect::<Html>(),
+                    None => html! { <div>{"Loading top features..."}</div> }
+                };
+
+                let html_sparkline = match &ctx.props().sample_annotation {
+                    AsyncData::Loaded(samples) if samples.sample_names.len() > 1 => {
+                        let mut counts = vec![0usize; samples.sample_names.len()];
+                        for cell in members.iter() {
+                            if let Some(c) = samples.sample_id.get(*cell).and_then(|s| counts.get_mut(*s as usize)) {
+                                *c += 1;
+                            }
+                        }
+                        let max_count = counts.iter().copied().max().unwrap_or(0).max(1) as f32;
+                        let step = CLUSTER_CARD_SPARKLINE_WIDTH/((counts.len() - 1) as f32);
+                        let points = counts.iter().enumerate().map(|(i, c)| {
+                            format!("{},{}", (i as f32)*step, CLUSTER_CARD_SPARKLINE_HEIGHT*(1.0 - (*c as f32)/max_count))
+                        }).collect::<Vec<_>>().join(" ");
+                        html! {
+                            <div>
+                                {"Cells per sample "}
+                                <svg width={CLUSTER_CARD_SPARKLINE_WIDTH.to_string()} height={CLUSTER_CARD_SPARKLINE_HEIGHT.to_string()} class="biscvi-sparkline">
+                                    <polyline points={points}/>
+                                </svg>
+                            </div>
+                        }
+                    },
+                    _ => html! {""}
+                };
+
+                let x = (x + 1.0)/2.0*(canvas_w as f32) + 10.0;
+                let y = (y + 1.0)/2.0*(canvas_h as f32) + 10.0;
+                html! {
+                    <div class="biscvi-cluster-card" style={format!("left: {}px; top: {}px;", x, y)}>
+                        <div class="biscvi-cluster-card-title">{ name }</div>
+                        <div>{ format!("{} cells", members.len()) }</div>
+                        { html_genes }
+                        { html_sparkline }
+                    </div>
+                }
+            },
+            _ => html! {""}
+        };
+
+        //Tools are of no use on paper
+        let html_toolbar = if ctx.props().print_layout { html! {""} } else { html_toolbar };
+
+        //Browsers may print a WebGL canvas blank, so a copy is printed instead. It is set once drawn
+        let html_print_snapshot = if ctx.props().print_layout {
+            html! {
+                <img ref={self.node_refs[4].clone()} class="biscvi-print-snapshot" style={format!("position: absolute; left: 1px; top: 1px; width: {}px; height: {}px;", canvas_w, canvas_h)}/>
+            }
+        } else {
+            html! {""}
+        };
+
+        //Compose the view
+        html! {
+            <div style={format!("display: flex; height: {}px; position: relative;", canvas_h)}>
+
+                <div style="position: absolute; left:0; top:0; display: flex; ">
                     <canvas 
                         ref={self.node_refs[0].clone()} 
-                        style="border:1px solid #000000;"
-                        onmousemove={cb_mousemoved} onclick={cb_mouseclicked} onwheel={cb_mousewheel} onmousedown={cb_onmousedown} onmouseup={cb_onmouseup}
-                        width={format!{"{}", canvas_w}}
-                        height={format!{"{}", canvas_h}}
+                        style={format!("border:1px solid #000000; width: {}px; height: {}px;", canvas_w, canvas_h)}
+                        tabindex="0"
+                        onmousemove={cb_mousemoved} onclick={cb_mouseclicked} ondblclick={cb_mousedoubleclicked} onwheel={cb_mousewheel} onmousedown={cb_onmousedown} onmouseup={cb_onmouseup}
+                        ontouchstart={cb_touchstart}
+                        onkeydown={cb_keydown}
+                        width={format!{"{}", buffer_w}}
+                        height={format!{"{}", buffer_h}}
                     />
                 </div>
 
+                { html_print_snapshot }
+
+                { html_tooltip }
+                { html_cluster_card }
+
+                { html_annotation_input }
+
                 //Overlay SVG
                 <div style="position: absolute; left:0; top:0; display: flex; pointer-events: none; ">  
                     <svg style={format!("width: {}px; height: {}px; pointer-events: none;", canvas_w, canvas_h)}> // note: WxH must cover canvas!!  
                         { html_select }
+                        { html_lasso }
+                        { html_peer_cursors }
+                        { html_cluster_labels }
+                        { html_cell_ids }
+                        { html_annotations }
+                        { html_landmarks }
+                        { html_biplot }
                     </svg>
                 </div>
                 
-                // Button: Select
-                <div style={get_tool_style(canvas_w-40, self.current_tool==CurrentTool::Select)} onclick={cb_click_select}>
-                    <svg data-icon="polygon-filter" height="16" role="img" viewBox="0 0 16 16" width="16"><path d="M14 5c-.24 0-.47.05-.68.13L9.97 2.34c.01-.11.03-.22.03-.34 0-1.1-.9-2-2-2S6 .9 6 2c0 .04.01.08.01.12L2.88 4.21C2.61 4.08 2.32 4 2 4 .9 4 0 4.9 0 6c0 .74.4 1.38 1 1.72v4.55c-.6.35-1 .99-1 1.73 0 1.1.9 2 2 2 .74 0 1.38-.4 1.72-1h4.55c.35.6.98 1 1.72 1 1.1 0 2-.9 2-2 0-.37-.11-.7-.28-1L14 9c1.11-.01 2-.9 2-2s-.9-2-2-2zm-4.01 7c-.73 0-1.37.41-1.71 1H3.73c-.18-.3-.43-.55-.73-.72V7.72c.6-.34 1-.98 1-1.72 0-.04-.01-.08-.01-.12l3.13-2.09c.27.13.56.21.88.21.24 0 .47-.05.68-.13l3.35 2.79c-.01.11-.03.22-.03.34 0 .37.11.7.28 1l-2.29 4z" fill-rule="evenodd"></path></svg>
-                </div>
-
-                // Button: Zoom
-                <div style={get_tool_style(canvas_w-40-30, self.current_tool==CurrentTool::Zoom)} onclick={cb_click_zoom}>
-                    <svg data-icon="zoom-in" height="16" role="img" viewBox="0 0 16 16" width="16"><path d="M7.99 5.99v-2c0-.55-.45-1-1-1s-1 .45-1 1v2h-2c-.55 0-1 .45-1 1s.45 1 1 1h2v2c0 .55.45 1 1 1s1-.45 1-1v-2h2c.55 0 1-.45 1-1s-.45-1-1-1h-2zm7.56 7.44l-2.67-2.68a6.94 6.94 0 001.11-3.76c0-3.87-3.13-7-7-7s-7 3.13-7 7 3.13 7 7 7c1.39 0 2.68-.42 3.76-1.11l2.68 2.67a1.498 1.498 0 102.12-2.12zm-8.56-1.44c-2.76 0-5-2.24-5-5s2.24-5 5-5 5 2.24 5 5-2.24 5-5 5z" fill-rule="evenodd"></path></svg>
-                </div>
+                // Tool buttons
+                { html_toolbar }
 
-                // Button: Zoom all
-                <div style={get_tool_style(canvas_w-40-30-30, self.current_tool==CurrentTool::ZoomAll)} onclick={cb_click_zoomall}>
-                    <svg data-icon="zoom-in" height="16" width="16" xmlns="http://www.w3.org/2000/svg"><path style="fill:none;stroke:#000;stroke-width:2.01074px;stroke-linecap:butt;stroke-linejoin:miter;stroke-opacity:1" d="M14.733 8.764v5.973H9.586m-8.29-5.973v5.973h5.146m8.29-7.5V1.264H9.587m-8.29 5.973V1.264h5.146"/></svg>
-                </div>
-                 <div id = "continuous_var_legend" style="position: absolute; left: 8px; top: 55px; z-index: 1; pointer-events: none; height: 200px; width: 80px;">
+                // Legend, with controls for the color scale
+                <div style={format!("position: absolute; left: {}px; top: {}px; width: {}px; height: {}px; z-index: 1; pointer-events: none;", legend_x, legend_y, LEGEND_WIDTH, LEGEND_HEIGHT)}>
+                 <div id = "continuous_var_legend" style={format!("position: absolute; {}: 0px; top: 0px; pointer-events: none; height: 200px; width: 80px;", legend_side)}>
                  <canvas ref={self.node_refs[1].clone()} height = "180" width = "20" style="position: absolute; left: 0px; top: 17px;" id = "legend_canvas">
                  </canvas>
                   <svg height="200px" width="80px" style="position: absolute; left: 0px; top: 0px;">
                    <path d="M 20 10 H 19 V 200 Z" stroke="black" />
                  <text id="continuous_var_label" transform="rotate(-90)" y="2" x="-100" dy="1em" data-testid="continuous_legend_color_by_label" aria-label="nCount_RNA" style="text-anchor: middle; fill: white; padding: 2px;">{legend_name}</text>
+                 { list_legend_ticks }
                  </svg>
                  </div>
 
+                    // Color scale transform for numeric data
+                    <div style={format!("position: absolute; {}: 0px; top: 205px; pointer-events: auto;", legend_side)}>
+                        <select onchange={cb_color_transform}>
+                            { list_transform_html }
+                        </select>
+                    </div>
+
+                    // Threshold for showing numeric data in two colors
+                    <div style={format!("position: absolute; {}: 0px; top: 235px; pointer-events: auto;", legend_side)}>
+                        <input type="number" placeholder="Threshold" style="width: 80px;" value={threshold_value} onchange={cb_threshold}/>
+                    </div>
+
+                    // Minimum value of cells shown
+                    <div style={format!("position: absolute; {}: 0px; top: 265px; pointer-events: auto;", legend_side)}>
+                        { html_expression_filter }
+                    </div>
+
+                    // Number of cells in each category
+                    <canvas ref={self.node_refs[2].clone()} width="160" height="0" style={format!("position: absolute; {}: 0px; {} pointer-events: none;", legend_side, category_counts_y)}>
+                    </canvas>
+                </div>
+
+                // Performance overlay. Text is set when rendering
+                <div ref={self.node_refs[3].clone()} class="biscvi-perf-overlay" style={format!("left: {}px; top: 45px; display: {};", canvas_w.saturating_sub(330), if ctx.props().performance_overlay {"block"} else {"none"})}>
+                </div>
+
+                // Correlation between the coloring and another column
+                <div style="position: absolute; left: 8px; top: 8px; z-index: 1;">
+                    <select onchange={cb_correlate_with}>
+                        <option value="" selected={self.correlate_with.is_none()}>{"Correlate with..."}</option>
+                        { list_correlation_html }
+                    </select>
+                    { correlation_badge }
+                </div>
+
+                // Number of cells drawn
+                { html_subsample }
+
+                { html_confidence_legend }
+
+                // Category of the hovered cell
+                { html_cluster_status }
+
             </div>
         }
     }
 
 
 
+    ////////////////////////////////////////////////////////////
+    /// Called when this component is removed
+    fn destroy(&mut self, _ctx: &Context<Self>) {
+        self.stop_explore();
+    }
+
+
+
     ////////////////////////////////////////////////////////////
     /// Called after DOM has been created
     fn rendered(&mut self, ctx: &Context<Self>, _first_render: bool) {
-        let reduction_data = &ctx.props().reduction_data;
+        let reduction_data = &self.get_reduction_data(ctx);
 
         if let AsyncData::Loaded(datapoints) = reduction_data {
+            let t_start = js_sys::Date::now();
+
+            // Once rendered, store references for the canvas and GL context. These can be used for
+            // resizing the rendering area when the window or canvas element are resized, as well as
+            // for making GL calls.
+            let canvas = self.node_refs[0].cast::<HtmlCanvasElement>().unwrap();
+            self.pixel_ratio = get_pixel_ratio(ctx.props());
+
+            //Keep the camera in sync with the canvas shape
+            if canvas.width() > 0 {
+                self.camera.set_aspect_ratio(canvas.height() as f32 / canvas.width() as f32);
+            }
 
             //Fit camera whenever we get a new umap to show
             if self.last_reduction_data != *reduction_data {
+                //Cells appended to the reduction fly in from the edge of the view
+                if let AsyncData::Loaded(old_datapoints) = &self.last_reduction_data {
+                    if datapoints.num_point > old_datapoints.num_point {
+                        self.add_arrivals(old_datapoints.num_point..datapoints.num_point);
+                        self.start_animation(ctx);
+                    }
+                }
+
                 self.camera.fit_reduction(datapoints);
+
+                //Points within 1% of the reduction size count as hovered
+                let span = (datapoints.max_x - datapoints.min_x).max(datapoints.max_y - datapoints.min_y);
+                let max_dist = if span > 0.0 { span*0.01 } else { 1.0 };
+                self.closest_point_index.build_point_index(datapoints, max_dist);
+
+                self.density_grid = Some(compute_density_grid(datapoints, DENSITY_GRID_SIZE));
+                self.point_density.clear();
             }
             self.last_reduction_data = reduction_data.clone();
 
+            //Group cells again if the grouping or the cells changed
+            let group_input = (reduction_data.clone(), ctx.props().group_by.clone(), ctx.props().group_by_data.clone());
+            if self.last_group_input != group_input {
+                self.cell_groups = match (&ctx.props().group_by, &ctx.props().group_by_data) {
+                    (GroupByMode::Cluster(_), AsyncData::Loaded(column)) => group_by_category(datapoints, column),
+                    _ => None
+                };
+
+                //Groups are drawn large, so they can be hovered from further away
+                if let Some(groups) = &self.cell_groups {
+                    let span = (datapoints.max_x - datapoints.min_x).max(datapoints.max_y - datapoints.min_y);
+                    self.group_index.build_point_index(&groups.centroids, if span > 0.0 { span*0.05 } else { 1.0 });
+                } else {
+                    self.group_index.clear();
+                }
+                if self.last_group.take().is_some() {
+                    ctx.props().on_cells_hovered.emit(Vec::new());
+                }
+                if self.cluster_card.is_some() {
+                    ctx.link().send_message(MsgReduction::CloseClusterCard);
+                }
+                self.last_group_input = group_input;
+            }
+
+            //Place the labels of the categories again if the coloring or the cells changed
+            let label_input = (reduction_data.clone(), ctx.props().color_reduction_by.clone());
+            if self.last_label_input != label_input {
+                self.label_groups = match &ctx.props().color_reduction_by {
+                    ReductionColoringWithData::ByMeta(_, AsyncData::Loaded(column)) => group_by_category(datapoints, column),
+                    _ => None
+                };
+                self.last_label_input = label_input;
+            }
+
+            //Other components may load details needed at the new zoom level
+            let zoom_level = self.camera.zoom_level();
+            if zoom_level != self.zoom_level {
+                self.zoom_level = zoom_level;
+                ctx.props().on_zoom_level_changed.emit(zoom_level);
+            }
+
 
             // Only start the render loop if it's the first render
             // There's no loop cancellation taking place, so if multiple renders happen,
@@ -526,11 +2416,6 @@ impl Component for ReductionView {
             */
             
 
-            // Once rendered, store references for the canvas and GL context. These can be used for
-            // resizing the rendering area when the window or canvas element are resized, as well as
-            // for making GL calls.
-            let canvas = self.node_refs[0].cast::<HtmlCanvasElement>().unwrap();
-
             let gl: GL = canvas
                 .get_context("webgl")
                 .unwrap()
@@ -539,15 +2424,30 @@ impl Component for ReductionView {
                 .unwrap();
 
             let vert_code = String::from(include_str!("./umap.vert"));
-            let frag_code = include_str!("./umap.frag");
+            let frag_code = self.get_frag_shader(ctx, &gl);
 
             //Get position data
             let num_points = datapoints.num_point;
+
+            //Enable 32-bit indices for gl.draw_elements, if available. Once enabled, it stays enabled for this context
+            if !self.has_uint_index {
+                self.has_uint_index = matches!(gl.get_extension("OES_element_index_uint"), Ok(Some(_)));
+                if !self.has_uint_index && num_points > MAX_UINT16_INDEXED_POINTS {
+                    log::debug!("OES_element_index_uint not available; indexed draw calls limited to {} points", MAX_UINT16_INDEXED_POINTS);
+                }
+            }
             let vertices = &datapoints.data;    
             let mut vec_vertex:Vec<f32> = Vec::new();
 
-            let vec_vertex_size = 6;
-            vec_vertex.reserve(num_points*6);  //Size of vec3+vec3
+            //Crowded points are faded out: alpha = 1/(1 + density*weight). Cells of low confidence are faded out too
+            let density_weight = ctx.props().density_weight;
+            let confidence = ctx.props().confidence.as_ref();
+            if density_weight > 0.0 && self.point_density.len() != num_points {
+                self.point_density = compute_point_density(datapoints);
+            }
+
+            let vec_vertex_size = 8;
+            vec_vertex.reserve(num_points*vec_vertex_size);  //Size of vec3+vec3, the cell index, and how much it is faded out
             for i in 0..num_points {
                 let input_base = i*2;
                 vec_vertex.push(*vertices.get(input_base+0).unwrap());
@@ -557,13 +2457,29 @@ impl Component for ReductionView {
                 vec_vertex.push(0.0); ///////////////////////////////////////////////// color index. remove, put in separate buffer
                 vec_vertex.push(0.0); ///////////////////////////////////////////////// color index. remove, put in separate buffer    filler for now
                 vec_vertex.push(0.0); ///////////////////////////////////////////////// color index. remove, put in separate buffer
+
+                vec_vertex.push(i as f32); // exact up to 2^24 cells
+
+                let alpha = if density_weight > 0.0 { 1.0/(1.0 + self.point_density[i]*density_weight) } else { 1.0 };
+                let alpha = alpha*confidence.and_then(|c| c.get(i)).map(|c| c.clamp(0.0, 1.0)).unwrap_or(1.0);
+                vec_vertex.push(1.0 - alpha);
             }
 
             //Get color data
             let color_reduction_by = &ctx.props().color_reduction_by;
             log::debug!("Rendering {:?}",color_reduction_by);
-            if let ReductionColoringWithData::ByMeta(_name, color_data) = color_reduction_by {
-                if let AsyncData::Loaded(color_data) = color_data {
+            //Palette and number of cells of each category, if coloring by categories
+            let mut category_legend: Option<(Vec<Color3f>, Vec<usize>)> = None;
+
+            //Binary coloring by a threshold is done instead of a color scale
+            let threshold_colored = if let (Some(threshold), ReductionColoringWithData::ByMeta(_name, AsyncData::Loaded(color_data))) = (ctx.props().expression_threshold, color_reduction_by) {
+                threshold_point_colors(&mut vec_vertex, vec_vertex_size, color_data, threshold, ctx.props().color_above, ctx.props().color_below)
+            } else {
+                false
+            };
+
+            if let (false, ReductionColoringWithData::ByMeta(_name, color_data_async)) = (threshold_colored, color_reduction_by) {
+                if let AsyncData::Loaded(color_data) = color_data_async {
                     match color_data.as_ref() {
 
                         ///////// Color by categorical data
@@ -571,8 +2487,11 @@ impl Component for ReductionView {
                             //log::debug!("Making colors for category");
                             
                             //let palette = self.color_dict.get("default").unwrap();
+                            //For a gradient, categories next to each other in order get colors next to each other in hue
                             let palette = get_palette_for_categories(vec_cats.len());
+                            let palette = if ctx.props().category_gradient { sort_palette_by_hue(&palette) } else { palette };
 
+                            let mut category_counts = vec![0; vec_cats.len()];
                             for (i,p) in vec_data.iter().enumerate() {
                                 let col = palette.get((*p as usize) % palette.len()).unwrap();
                                 let base = vec_vertex_size*i;
@@ -580,6 +2499,25 @@ impl Component for ReductionView {
                                 vec_vertex[base + 4] = col.1;
                                 vec_vertex[base + 5] = col.2;
 
+                                if let Some(c) = category_counts.get_mut(*p as usize) {
+                                    *c += 1;
+                                }
+                            }
+                            if ctx.props().category_gradient {
+                                gradient_category_colors(&mut vec_vertex, vec_vertex_size, datapoints, vec_data, &palette);
+                            }
+                            category_legend = Some((palette, category_counts));
+
+                            //Distribution of another numeric column in each category. Slow, so only computed when either column changes
+                            let violin_input = (color_data_async.clone(), ctx.props().violin_data.clone());
+                            if self.violin_cache.as_ref().map(|(a, b, _)| (a, b)) != Some((&violin_input.0, &violin_input.1)) {
+                                let violins = match &violin_input.1 {
+                                    AsyncData::Loaded(violin_data) => dense_numeric_values(violin_data, vec_data.len())
+                                        .map(|values| category_violins(vec_data, vec_cats.len(), &values))
+                                        .unwrap_or_default(),
+                                    _ => Vec::new()
+                                };
+                                self.violin_cache = Some((violin_input.0, violin_input.1, violins));
                             }
 
                         },
@@ -588,12 +2526,17 @@ impl Component for ReductionView {
                         CountFileMetaColumnData::Numeric(vec_data) => {
 
                             //Normalize color range. TODO should only need to do this once during loading
-                            let (_min_val, max_val) = make_safe_minmax(&vec_data);
-                            for (i,p) in vec_data.into_iter().enumerate() {
-                                let base = vec_vertex_size*i;
-                                vec_vertex[base + 3] = p/max_val;
-                                vec_vertex[base + 4] = 0.0;
-                                vec_vertex[base + 5] = 0.0;
+                            let (min_val, max_val) = ctx.props().global_color_range.unwrap_or_else(|| make_safe_minmax(&vec_data));
+                            if let Some(center) = ctx.props().diverging_center {
+                                diverging_point_colors(&mut vec_vertex, vec_vertex_size, vec_data.iter().cloned().enumerate(), (min_val, max_val), center, self.color_transform);
+                            } else {
+                                let (_min_val, max_tval) = self.color_transform.apply_range(min_val, max_val);
+                                for (i,p) in vec_data.into_iter().enumerate() {
+                                    let base = vec_vertex_size*i;
+                                    vec_vertex[base + 3] = self.color_transform.apply(*p)/max_tval;
+                                    vec_vertex[base + 4] = 0.0;
+                                    vec_vertex[base + 5] = 0.0;
+                                }
                             }
 
                             let max_cont_val: f32 = max_val;
@@ -627,8 +2570,9 @@ impl Component for ReductionView {
     #[wasm_bindgen(module = "/src/color_legend_gradient.js")]
     extern "C" {
          fn color_gradient(context: CanvasRenderingContext2d) -> CanvasRenderingContext2d;
+         fn diverging_gradient(context: CanvasRenderingContext2d) -> CanvasRenderingContext2d;
     }
-    let context = color_gradient(context);
+    let context = if ctx.props().diverging_center.is_some() { diverging_gradient(context) } else { color_gradient(context) };
     context.fill();
     
 
@@ -669,137 +2613,1619 @@ impl Component for ReductionView {
                     let positionBuffer = gl_legend.create_buffer().unwrap();
                     gl_legend.bind_buffer(GL::ARRAY_BUFFER, Some(&positionBuffer));
 
-                    let positions: Vec<f32> = vec![
-                    -1.0, -1.0, 
-                    1.0, -1.0, 
-                    -1.0, 1.0, 
-                    -1.0, 1.0, 
-                    1.0, -1.0, 
-                    1.0, 1.0,
-                ];
-       
-                let verts = js_sys::Float32Array::from(positions.as_slice());
-                gl_legend.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &verts, GL::STATIC_DRAW);
+                    let positions: Vec<f32> = vec![
+                    -1.0, -1.0, 
+                    1.0, -1.0, 
+                    -1.0, 1.0, 
+                    -1.0, 1.0, 
+                    1.0, -1.0, 
+                    1.0, 1.0,
+                ];
+       
+                let verts = js_sys::Float32Array::from(positions.as_slice());
+                gl_legend.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &verts, GL::STATIC_DRAW);
+
+                gl_legend.vertex_attrib_pointer_with_i32(a_position, 2, GL::FLOAT, false, 0, 0);
+                gl_legend.enable_vertex_attrib_array(a_position);
+
+                gl_legend.draw_arrays(GL::TRIANGLES, 0, 6); */
+
+                        },
+
+                        ///////// Color by numerical data - sparse array
+                        CountFileMetaColumnData::SparseNumeric(vec_index, vec_data) => {
+
+                            //Normalize color range. TODO should only need to do this once during loading. note, for sparse, min_val should be 0 by definition, more or less
+                            let (min_val, max_val) = ctx.props().global_color_range.unwrap_or_else(|| make_safe_minmax(&vec_data));
+                            log::debug!("Render value range {} {}",min_val, max_val);
+                            let (_min_val, max_tval) = self.color_transform.apply_range(min_val, max_val);
+
+                            if let Some(center) = ctx.props().diverging_center {
+                                //Cells not listed are 0, which is not the lowest color on this scale
+                                let values = (0..num_points).map(|i| (i, 0.0))
+                                    .chain(vec_index.iter().zip(vec_data.iter()).map(|(i, p)| (*i as usize, *p)));
+                                diverging_point_colors(&mut vec_vertex, vec_vertex_size, values, (min_val, max_val), center, self.color_transform);
+                            } else {
+                                for (i,p) in vec_index.iter().zip(vec_data.iter()) {
+                                    let i = *i as usize;
+                                    let base = vec_vertex_size*i;
+                                    vec_vertex[base + 3] = self.color_transform.apply(*p)/max_tval;
+                                    vec_vertex[base + 4] = 0.0;
+                                    vec_vertex[base + 5] = 0.0;
+                                }
+                            }
+                        },
+                    }
+                }
+            } else if let ReductionColoringWithData::Custom(colors) = color_reduction_by {
+                for (i, col) in colors.iter().take(num_points).enumerate() {
+                    let base = vec_vertex_size*i;
+                    vec_vertex[base + 3] = col.0;
+                    vec_vertex[base + 4] = col.1;
+                    vec_vertex[base + 5] = col.2;
+                }
+            } else {
+                // Put in an empty color (default is black now)
+            }
+
+            //Show how many cells there are in each category
+            if let Some(legend_canvas) = self.node_refs[2].cast::<HtmlCanvasElement>() {
+                match &category_legend {
+                    Some((palette, category_counts)) => {
+                        let palette: Vec<Color3f> = palette.iter().map(|c| simulate_color(ctx.props().color_simulation, *c)).collect();
+                        let violins = self.violin_cache.as_ref().map(|(_, _, violins)| violins).filter(|violins| !violins.is_empty());
+                        draw_category_counts(&legend_canvas, &palette, category_counts, violins)
+                    },
+                    None => legend_canvas.set_height(0),
+                }
+            }
+
+            //Smooth the colors spatially to reduce noise
+            let blur_radius = ctx.props().blur_radius;
+            let smooth_factor = ctx.props().smooth_factor;
+            if blur_radius > 0.0 && smooth_factor > 0.0 {
+                smooth_point_colors(&mut vec_vertex, vec_vertex_size, datapoints, blur_radius, smooth_factor);
+            }
+
+            //Highlight cells of the selected samples by fading out the others
+            if let AsyncData::Loaded(sample_annotation) = &ctx.props().sample_annotation {
+                if !ctx.props().selected_samples.is_empty() {
+                    fade_unselected_samples(&mut vec_vertex, vec_vertex_size, &sample_annotation.sample_id, &ctx.props().selected_samples);
+                }
+            }
+
+            //Hide cells below the minimum value, outside the mask, or not in the display subset. Cell indices no longer match vertices after this
+            let keep = match (ctx.props().min_expression_filter, color_reduction_by, &self.cell_groups) {
+                (Some(min_value), ReductionColoringWithData::ByMeta(_name, AsyncData::Loaded(color_data)), None) => {
+                    Some(cells_above_min_value(color_data, num_points, min_value))
+                },
+                _ => None
+            };
+            let has_groups = self.cell_groups.is_some();
+            let keep = match self.get_cells_inside_mask(ctx, reduction_data) {
+                Some(inside) if !has_groups => Some(keep.unwrap_or_else(|| (0..num_points).collect()).into_iter().filter(|i| inside.get(*i).copied().unwrap_or(false)).collect::<Vec<usize>>()),
+                _ => keep
+            };
+            let keep = match &ctx.props().display_subset {
+                Some(subset) if !has_groups => Some(keep.unwrap_or_else(|| (0..num_points).collect()).into_iter().filter(|i| subset.contains(i)).collect::<Vec<usize>>()),
+                _ => keep
+            };
+            let (vec_vertex, num_points) = match keep {
+                Some(keep) => (subsample_vertices(&vec_vertex, vec_vertex_size, &keep), keep.len()),
+                None => (vec_vertex, num_points)
+            };
+
+            //Save all points in view as vector graphics. Done before merging or subsampling points
+            if self.svg_export_requested {
+                self.svg_export_requested = false;
+                let color_range = if ctx.props().expression_threshold.is_none() {
+                    ctx.props().global_color_range.or_else(|| get_numeric_coloring_range(&ctx.props().color_reduction_by))
+                } else {
+                    None
+                };
+                let (css_w, css_h) = self.get_css_canvas_size(ctx);
+                let svg = build_svg(&vec_vertex, vec_vertex_size, &self.camera, css_w, css_h, color_range);
+                if let Err(e) = download_text("reduction.svg", "image/svg+xml", &svg) {
+                    log::warn!("Could not export SVG: {:?}", e);
+                }
+            }
+
+            //When grouping, draw one point per group in the color of its category instead
+            let (vec_vertex, num_points) = match &self.cell_groups {
+                Some(groups) => {
+                    let palette = get_palette_for_categories(groups.num_categories);
+                    let mut vec_group: Vec<f32> = Vec::with_capacity(groups.centroids.num_point*vec_vertex_size);
+                    for (i, category) in groups.categories.iter().enumerate() {
+                        let col = palette[category % palette.len()];
+                        vec_group.extend([groups.centroids.data[i*2+0], groups.centroids.data[i*2+1], 0.0]);
+                        vec_group.extend([col.0, col.1, col.2, -1.0, 0.0]);
+                    }
+                    (vec_group, groups.centroids.num_point)
+                },
+                None => (vec_vertex, num_points)
+            };
+
+            //Show colors as seen with simulated color blindness
+            let mut vec_vertex = vec_vertex;
+            simulate_vertex_colors(&mut vec_vertex, vec_vertex_size, ctx.props().color_simulation);
+
+            //At low zoom, merge points that overlap on screen into clusters
+            let lod_cell_size = if self.cell_groups.is_some() { None } else { choose_lod_cell_size(&self.camera, self.get_css_canvas_size(ctx).0, datapoints) };
+            let (vec_vertex, num_points, vert_code) = match lod_cell_size {
+                Some(cell_size) => {
+                    let (vec_cluster, num_cluster) = cluster_points(&vec_vertex, vec_vertex_size, datapoints, cell_size);
+                    (vec_cluster, num_cluster, String::from(include_str!("./lod.vert")))
+                },
+                None => (vec_vertex, num_points, vert_code)
+            };
+
+            //With many points, only draw a random subset. Clusters are few enough already
+            let (vec_vertex, num_points) = match (ctx.props().max_display_points, lod_cell_size) {
+                (Some(max_points), None) if self.show_subsample && num_points > max_points => {
+                    let indices = self.get_display_subsample(num_points, max_points);
+                    (subsample_vertices(&vec_vertex, vec_vertex_size, indices), indices.len())
+                },
+                _ => (vec_vertex, num_points)
+            };
+
+            //For focus + context, put the context points first so that each group can be drawn separately
+            let (vec_vertex, num_context) = if ctx.props().focus_context==FocusContextMode::Enabled && lod_cell_size.is_none() && self.cell_groups.is_none() {
+                split_focus_context(&vec_vertex, vec_vertex_size, &self.camera)
+            } else {
+                (vec_vertex, 0)
+            };
+            let t_built = js_sys::Date::now();
+            let mut draw_calls = 0;
+
+            // clear canvas, then shade dense regions behind the points. The buffer may have been resized
+            gl.viewport(0, 0, canvas.width() as i32, canvas.height() as i32);
+            gl.clear_color(1.0, 1.0, 1.0, 1.0);
+            gl.clear(GL::COLOR_BUFFER_BIT);
+            if let (Some(density_grid), ZoomLevel::Overview) = (&self.density_grid, self.zoom_level) {
+                draw_density_background(&canvas, density_grid, &self.camera);
+                draw_calls += 1;
+            }
+
+            //Compact positions are only used by the opaque pass; transparent passes and clusters need the full vertices
+            let point_opacity = ctx.props().point_opacity;
+            let is_transparent = point_opacity < 1.0 && lod_cell_size.is_none();
+            let compact = if ctx.props().compact_positions && lod_cell_size.is_none() && !is_transparent {
+                Some(quantize_positions(&vec_vertex, vec_vertex_size))
+            } else {
+                None
+            };
+
+            //Programs for transparency and for the layers on top of the cells are only compiled once for each GL context
+            if self.overlay_gl.as_ref().map(|o| o.gl != gl).unwrap_or(true) {
+                self.overlay_gl = Some(OverlayGl::new(&gl));
+            }
+
+            //Connect vertex array to GL
+            let t_upload = js_sys::Date::now();
+            let vertex_buffer = gl.create_buffer().unwrap();
+            let position_buffer = compact.as_ref().map(|_| gl.create_buffer().unwrap());
+            let upload_bytes = if let (Some(compact), Some(position_buffer)) = (&compact, &position_buffer) {
+                let js_position = js_sys::Int16Array::from(compact.positions.as_slice());
+                gl.bind_buffer(GL::ARRAY_BUFFER, Some(position_buffer));
+                gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &js_position, GL::STATIC_DRAW);
+                let js_color = js_sys::Float32Array::from(compact.colors.as_slice());
+                gl.bind_buffer(GL::ARRAY_BUFFER, Some(&vertex_buffer));
+                gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &js_color, GL::STATIC_DRAW);
+                compact.positions.len()*2 + compact.colors.len()*4
+            } else {
+                let js_vertex = js_sys::Float32Array::from(vec_vertex.as_slice());
+                //let verts = js_sys::Int32Array::from(vertices_int.as_slice());
+                gl.bind_buffer(GL::ARRAY_BUFFER, Some(&vertex_buffer));
+                gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &js_vertex, GL::STATIC_DRAW);
+                vec_vertex.len()*4
+            };
+            self.render_stats.record_upload(t_upload, js_sys::Date::now(), upload_bytes);
+
+            //Compile vertex shader
+            let vert_shader = gl.create_shader(GL::VERTEX_SHADER).unwrap();
+            gl.shader_source(&vert_shader, vert_code.as_str());
+            gl.compile_shader(&vert_shader);
+
+            
+            /*let msg= gl.get_shader_info_log(&vert_shader);
+            if let Some(msg)=msg {
+                log::debug!("error {}", msg);
+            }*/
+
+            //Compile fragment shader
+            let frag_shader = gl.create_shader(GL::FRAGMENT_SHADER).unwrap();
+            gl.shader_source(&frag_shader, &frag_code);
+            gl.compile_shader(&frag_shader);
+
+            //Attach shaders
+            let shader_program = gl.create_program().unwrap();
+            gl.attach_shader(&shader_program, &vert_shader);
+            gl.attach_shader(&shader_program, &frag_shader);
+            gl.link_program(&shader_program);
+            gl.use_program(Some(&shader_program));
+
+            //Size of a float in bytes
+            let sizeof_float = 4;
+
+            //Attach the position vector as an attribute for the GL context.
+            let a_position = gl.get_attrib_location(&shader_program, "a_position") as u32;
+            //log::debug!("a_position {}",a_position);
+            gl.enable_vertex_attrib_array(a_position);
+
+            //Attach color vector as an attribute
+            let a_color = gl.get_attrib_location(&shader_program, "a_color") as u32;
+            //log::debug!("a_color {}",a_color);
+            gl.enable_vertex_attrib_array(a_color);
+
+            //Index of each cell, to find the hovered one. Not in all shaders
+            let a_idx = gl.get_attrib_location(&shader_program, "a_idx");
+            let a_idx = if a_idx >= 0 { Some(a_idx as u32) } else { None };
+            if let Some(a_idx) = a_idx {
+                gl.enable_vertex_attrib_array(a_idx);
+            }
+
+            //How much each cell is faded out. Not in all shaders; if disabled, it is 0
+            let a_fade = gl.get_attrib_location(&shader_program, "a_fade");
+            let a_fade = if a_fade >= 0 && (density_weight > 0.0 || confidence.is_some()) { Some(a_fade as u32) } else { None };
+            if let Some(a_fade) = a_fade {
+                gl.enable_vertex_attrib_array(a_fade);
+            }
+
+            let u_pos_scale = gl.get_uniform_location(&shader_program, "u_pos_scale");
+            let u_pos_offset = gl.get_uniform_location(&shader_program, "u_pos_offset");
+            if let (Some(compact), Some(position_buffer)) = (&compact, &position_buffer) {
+                //Shorts are normalized to [-1,1] by GL, then mapped back to world coordinates by the shader
+                gl.bind_buffer(GL::ARRAY_BUFFER, Some(position_buffer));
+                gl.vertex_attrib_pointer_with_i32(a_position, 2, GL::SHORT, true, 0, 0);
+                gl.bind_buffer(GL::ARRAY_BUFFER, Some(&vertex_buffer));
+                gl.vertex_attrib_pointer_with_i32(a_color, 3, GL::FLOAT, false, sizeof_float*5, 0);
+                if let Some(a_idx) = a_idx {
+                    gl.vertex_attrib_pointer_with_i32(a_idx, 1, GL::FLOAT, false, sizeof_float*5, sizeof_float*3);
+                }
+                if let Some(a_fade) = a_fade {
+                    gl.vertex_attrib_pointer_with_i32(a_fade, 1, GL::FLOAT, false, sizeof_float*5, sizeof_float*4);
+                }
+                gl.uniform2f(u_pos_scale.as_ref(), compact.scale.0, compact.scale.1);
+                gl.uniform2f(u_pos_offset.as_ref(), compact.offset.0, compact.offset.1);
+            } else {
+                let stride = sizeof_float*vec_vertex_size as i32;
+                gl.vertex_attrib_pointer_with_i32(a_position, 3, GL::FLOAT, false, stride, 0);  
+                gl.vertex_attrib_pointer_with_i32(a_color, 3, GL::FLOAT, false, stride, sizeof_float*3);   //index of out range   ... not big enough for the draw call
+                if let Some(a_idx) = a_idx {
+                    gl.vertex_attrib_pointer_with_i32(a_idx, 1, GL::FLOAT, false, stride, sizeof_float*6);
+                }
+                if let Some(a_fade) = a_fade {
+                    gl.vertex_attrib_pointer_with_i32(a_fade, 1, GL::FLOAT, false, stride, sizeof_float*7);
+                }
+                gl.uniform2f(u_pos_scale.as_ref(), 1.0, 1.0);
+                gl.uniform2f(u_pos_offset.as_ref(), 0.0, 0.0);
+            }
+
+            //Cells that just arrived fly in. Not for clusters, or the passes for transparency
+            let arrival_attributes = if lod_cell_size.is_none() && !is_transparent {
+                self.bind_arrivals(&gl, &shader_program, &vec_vertex, vec_vertex_size)
+            } else {
+                None
+            };
+
+            //Attach camera attributes
+            let u_camera_x = gl.get_uniform_location(&shader_program, "u_camera_x");
+            let u_camera_y = gl.get_uniform_location(&shader_program, "u_camera_y");
+            let u_camera_zoom_x = gl.get_uniform_location(&shader_program, "u_camera_zoom_x");
+            let u_camera_zoom_y = gl.get_uniform_location(&shader_program, "u_camera_zoom_y");
+            gl.uniform1f(u_camera_x.as_ref(), self.camera.x as f32);
+            gl.uniform1f(u_camera_y.as_ref(), self.camera.y as f32);
+            gl.uniform1f(u_camera_zoom_x.as_ref(), self.camera.zoom_x as f32);
+            gl.uniform1f(u_camera_zoom_y.as_ref(), self.camera.zoom_y as f32);
+            //Groups keep their size when zooming
+            let zoom_scale = if self.cell_groups.is_some() { 1.0 } else { self.camera.zoom_scale() };
+            gl.uniform1f(gl.get_uniform_location(&shader_program, "u_zoom_scale").as_ref(), zoom_scale);
+
+            //log::debug!("canvas {} {}   {:?}", canvas.width(), canvas.height(), self.camera);
+
+            let u_display_w = gl.get_uniform_location(&shader_program, "u_display_w");
+            let u_display_h = gl.get_uniform_location(&shader_program, "u_display_h");
+            gl.uniform1f(u_display_w.as_ref(), canvas.width() as f32);
+            gl.uniform1f(u_display_h.as_ref(), canvas.height() as f32);
+
+            //Groups are hovered as a whole, so no single cell stands out
+            let hover_idx = if self.cell_groups.is_some() { None } else { self.last_cell };
+            set_hover_uniform(&gl, &shader_program, hover_idx);
+            
+            let u_point_size = gl.get_uniform_location(&shader_program, "u_point_size");
+            let u_alpha = gl.get_uniform_location(&shader_program, "u_alpha");
+
+            //Context points: small and faint
+            if num_context > 0 {
+                gl.enable(GL::BLEND);
+                gl.blend_func(GL::SRC_ALPHA, GL::ONE_MINUS_SRC_ALPHA);
+                gl.uniform1f(u_point_size.as_ref(), 1.0*self.pixel_ratio);
+                gl.uniform1f(u_alpha.as_ref(), 0.3);
+                gl.draw_arrays(GL::POINTS, 0, num_context as i32);
+                gl.disable(GL::BLEND);
+                draw_calls += 1;
+            }
+
+            // to make round points, need to draw square https://stackoverflow.com/questions/7237086/opengl-es-2-0-equivalent-for-es-1-0-circles-using-gl-point-smooth
+            let point_size = self.pixel_ratio*if self.cell_groups.is_some() { GROUP_POINT_SIZE } else { ctx.props().point_size*self.zoom_level.point_size_factor() };
+            gl.uniform1f(u_point_size.as_ref(), point_size);
+            if is_transparent {
+                //Clusters are always opaque; their z is the number of points merged, not a depth
+                gl.disable_vertex_attrib_array(a_position);
+                gl.disable_vertex_attrib_array(a_color);
+                if let Some(a_idx) = a_idx {
+                    gl.disable_vertex_attrib_array(a_idx);
+                }
+                if let Some(a_fade) = a_fade {
+                    gl.disable_vertex_attrib_array(a_fade);
+                }
+                draw_calls += self.draw_points_transparent(
+                    &gl, &shader_program, &vertex_buffer, 
+                    &vec_vertex, vec_vertex_size, num_context, num_points - num_context, 
+                    point_opacity, point_size, hover_idx, canvas.width() as i32, canvas.height() as i32
+                );
+            } else {
+                gl.uniform1f(u_alpha.as_ref(), 1.0);
+                if a_fade.is_some() {
+                    gl.enable(GL::BLEND);
+                    gl.blend_func(GL::SRC_ALPHA, GL::ONE_MINUS_SRC_ALPHA);
+                }
+                gl.draw_arrays(GL::POINTS, num_context as i32, (num_points - num_context) as i32);
+                draw_calls += 1;
+                gl.disable(GL::BLEND);
+                gl.disable_vertex_attrib_array(a_position);
+                gl.disable_vertex_attrib_array(a_color);
+                if let Some(a_idx) = a_idx {
+                    gl.disable_vertex_attrib_array(a_idx);
+                }
+                if let Some(a_fade) = a_fade {
+                    gl.disable_vertex_attrib_array(a_fade);
+                }
+            }
+            if let Some((arrival_buffer, a_born_at, a_start_pos)) = arrival_attributes {
+                gl.disable_vertex_attrib_array(a_born_at);
+                gl.disable_vertex_attrib_array(a_start_pos);
+                gl.delete_buffer(Some(&arrival_buffer));
+            }
+            if let Some(position_buffer) = &position_buffer {
+                gl.delete_buffer(Some(position_buffer));
+            }
+
+            //Outline cells selected or marked by other components
+            let mut highlighted_cells = self.highlighted_cells.clone();
+            highlighted_cells.extend(ctx.props().highlighted_cell);
+            if !highlighted_cells.is_empty() {
+                draw_calls += self.draw_highlight(&gl, datapoints, &highlighted_cells);
+            }
+
+            //Show the direction of the trajectory
+            if let Some(trajectory) = &ctx.props().trajectory {
+                draw_calls += self.draw_trajectory(&gl, datapoints, trajectory);
+            }
+
+            //Draw where cells are heading, on top of the cells. Velocities only make sense in the original layout
+            if self.show_velocity && self.layout_data==AsyncData::NotLoaded {
+                draw_calls += self.draw_velocity(&gl, datapoints, &ctx.props().velocity_data, ctx.props().velocity_scale);
+            }
+
+            //Tell others where the camera is, unless it was placed by them
+            if self.camera != self.last_emitted_camera {
+                self.last_emitted_camera = self.camera.clone();
+                ctx.props().on_camera_changed.emit(self.camera.clone());
+            }
+
+            //Show timings. Set directly, as changing the state here would cause another render
+            if ctx.props().performance_overlay {
+                self.render_stats.record_frame(t_start, t_built, js_sys::Date::now(), draw_calls);
+                if let Some(overlay) = self.node_refs[3].cast::<HtmlElement>() {
+                    overlay.set_inner_text(&self.render_stats.overlay_text());
+                }
+            }
+
+            //Copy the drawing for printing, while the buffer still holds it
+            if let Some(snapshot) = self.node_refs[4].cast::<HtmlImageElement>() {
+                match canvas.to_data_url() {
+                    Ok(url) => snapshot.set_src(&url),
+                    Err(e) => log::warn!("Could not copy the canvas for printing: {:?}", e),
+                }
+            }
+        }
+
+    }
+}
+
+
+
+impl ReductionView {
+
+    ////////////////////////////////////////////////////////////
+    /// Make the given cells fly in from random places on the edge of the view
+    fn add_arrivals(&mut self, cells: std::ops::Range<usize>) {
+        let now = js_sys::Date::now();
+        if self.arrivals.is_empty() {
+            self.arrival_epoch = now;
+        }
+        for i in cells {
+            self.arrivals.insert(i, PointArrival {
+                born_at: now,
+                initial_screen_pos: random_edge_position(),
+            });
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Give the shader the arrival time and start position of each vertex. Vertices of cells that
+    /// are not arriving get a time long ago. Returns the buffer and attributes to release after drawing
+    fn bind_arrivals(&self, gl: &GL, shader_program: &WebGlProgram, vec_vertex: &Vec<f32>, vec_vertex_size: usize) -> Option<(WebGlBuffer, u32, u32)> {
+        let u_arrival_ms = gl.get_uniform_location(shader_program, "u_arrival_ms");
+        gl.uniform1f(u_arrival_ms.as_ref(), 0.0);
+        if self.arrivals.is_empty() {
+            return None;
+        }
+        let a_born_at = gl.get_attrib_location(shader_program, "a_born_at");
+        let a_start_pos = gl.get_attrib_location(shader_program, "a_start_pos");
+        if a_born_at < 0 || a_start_pos < 0 {
+            return None;
+        }
+        let (a_born_at, a_start_pos) = (a_born_at as u32, a_start_pos as u32);
+
+        let long_ago = -(ARRIVAL_MS as f32)*10.0;
+        let vec_arrival: Vec<f32> = vec_vertex.chunks_exact(vec_vertex_size).flat_map(|v| {
+            let arrival = if v[6] >= 0.0 { self.arrivals.get(&(v[6] as usize)) } else { None };
+            match arrival {
+                Some(arrival) => [(arrival.born_at - self.arrival_epoch) as f32, arrival.initial_screen_pos.0, arrival.initial_screen_pos.1],
+                None => [long_ago, 0.0, 0.0]
+            }
+        }).collect();
+
+        let arrival_buffer = gl.create_buffer().unwrap();
+        let js_arrival = js_sys::Float32Array::from(vec_arrival.as_slice());
+        gl.bind_buffer(GL::ARRAY_BUFFER, Some(&arrival_buffer));
+        gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &js_arrival, GL::STATIC_DRAW);
+        gl.enable_vertex_attrib_array(a_born_at);
+        gl.vertex_attrib_pointer_with_i32(a_born_at, 1, GL::FLOAT, false, 3*4, 0);
+        gl.enable_vertex_attrib_array(a_start_pos);
+        gl.vertex_attrib_pointer_with_i32(a_start_pos, 2, GL::FLOAT, false, 3*4, 4);
+
+        gl.uniform1f(gl.get_uniform_location(shader_program, "u_time").as_ref(), (js_sys::Date::now() - self.arrival_epoch) as f32);
+        gl.uniform1f(u_arrival_ms.as_ref(), ARRIVAL_MS as f32);
+        Some((arrival_buffer, a_born_at, a_start_pos))
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Size of the canvas on the page, in CSS pixels. Its buffer is larger or smaller by the pixel ratio
+    fn get_css_canvas_size(&self, ctx: &Context<Self>) -> (f32, f32) {
+        let pixel_ratio = get_pixel_ratio(ctx.props());
+        self.node_refs[0].cast::<HtmlCanvasElement>()
+            .map(|canvas| (canvas.width() as f32/pixel_ratio, canvas.height() as f32/pixel_ratio))
+            .unwrap_or((0.0, 0.0))
+    }
+
+    ////////////////////////////////////////////////////////////
+    /// Landmark with its pin closest to a point in camera coordinates, if within grabbing distance.
+    /// Both the point marked and the head of the pin can be grabbed
+    fn get_landmark_at(&self, ctx: &Context<Self>, cx: f32, cy: f32) -> Option<usize> {
+        let (w, h) = self.get_css_canvas_size(ctx);
+        let (px, py) = (cx*w/2.0 + w/2.0, cy*h/2.0 + h/2.0);
+        self.landmarks.iter().enumerate().filter_map(|(i, landmark)| {
+            let (lx, ly) = self.camera.world2cam(landmark.world_x, landmark.world_y);
+            let (lx, ly) = (lx*w/2.0 + w/2.0, ly*h/2.0 + h/2.0);
+            let dist = ((px - lx).powi(2) + (py - ly).powi(2)).sqrt()
+                .min(((px - lx).powi(2) + (py - ly + LANDMARK_PIN_HEIGHT).powi(2)).sqrt());
+            (dist <= LANDMARK_GRAB_RADIUS).then_some((i, dist))
+        }).min_by(|a, b| a.1.total_cmp(&b.1)).map(|(i, _)| i)
+    }
+
+    ////////////////////////////////////////////////////////////
+    /// For each cell, if it is inside the mask. Only computed again if the mask or reduction changed
+    fn get_cells_inside_mask(&mut self, ctx: &Context<Self>, reduction_data: &AsyncData<ReductionViewData>) -> Option<&Vec<bool>> {
+        let (Some(mask), AsyncData::Loaded(datapoints)) = (&ctx.props().mask_polygon, reduction_data) else {
+            self.mask_cache = None;
+            return None;
+        };
+        let is_cached = matches!(&self.mask_cache, Some((cached_mask, cached_data, _)) if cached_mask == mask && cached_data == reduction_data);
+        if !is_cached {
+            self.mask_cache = Some((mask.clone(), reduction_data.clone(), cells_inside_mask(datapoints, mask)));
+        }
+        self.mask_cache.as_ref().map(|(_, _, inside)| inside)
+    }
+
+    ////////////////////////////////////////////////////////////
+    /// Get the reduction to show: either the one given, or its radial layout
+    fn get_reduction_data(&self, ctx: &Context<Self>) -> AsyncData<ReductionViewData> {
+        if let AsyncData::Loaded(_) = &self.layout_data {
+            self.layout_data.clone()
+        } else {
+            ctx.props().reduction_data.clone()
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Draw semi-transparent points. Order-independent transparency is used if float textures
+    /// can be rendered to; otherwise points are sorted back to front. Returns the number of draw calls
+    fn draw_points_transparent(
+        &self, 
+        gl: &GL, 
+        shader_program: &WebGlProgram, 
+        vertex_buffer: &WebGlBuffer, 
+        vec_vertex: &Vec<f32>, 
+        vec_vertex_size: usize, 
+        first: usize, 
+        count: usize, 
+        alpha: f32, 
+        point_size: f32,
+        hover_idx: Option<usize>,
+        canvas_w: i32, 
+        canvas_h: i32
+    ) -> usize {
+        if let Ok(Some(_)) = gl.get_extension("OES_texture_float") {
+            if let Some(draw_calls) = self.draw_points_oit(gl, vertex_buffer, vec_vertex_size, first, count, alpha, point_size, hover_idx, canvas_w, canvas_h) {
+                return draw_calls;
+            }
+        }
+        self.draw_points_depth_sorted(gl, shader_program, vec_vertex, vec_vertex_size, first, count, alpha)
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Order-independent transparency in two passes. The first sums premultiplied colors and alphas
+    /// into a float texture; the second divides them to get the average color of each pixel.
+    /// Returns None if float textures cannot be rendered to
+    fn draw_points_oit(
+        &self, 
+        gl: &GL, 
+        vertex_buffer: &WebGlBuffer, 
+        vec_vertex_size: usize,
+        first: usize, 
+        count: usize, 
+        alpha: f32, 
+        point_size: f32,
+        hover_idx: Option<usize>,
+        canvas_w: i32, 
+        canvas_h: i32
+    ) -> Option<usize> {
+        let overlay = self.overlay_gl.as_ref()?;
+
+        //Accumulation target, the size of the canvas
+        let texture = gl.create_texture()?;
+        gl.bind_texture(GL::TEXTURE_2D, Some(&texture));
+        gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, GL::NEAREST as i32);
+        gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MAG_FILTER, GL::NEAREST as i32);
+        gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_S, GL::CLAMP_TO_EDGE as i32);
+        gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_T, GL::CLAMP_TO_EDGE as i32);
+        let created = gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+            GL::TEXTURE_2D, 0, GL::RGBA as i32, canvas_w, canvas_h, 0, GL::RGBA, GL::FLOAT, None
+        );
+        let framebuffer = gl.create_framebuffer()?;
+        gl.bind_framebuffer(GL::FRAMEBUFFER, Some(&framebuffer));
+        gl.framebuffer_texture_2d(GL::FRAMEBUFFER, GL::COLOR_ATTACHMENT0, GL::TEXTURE_2D, Some(&texture), 0);
+
+        //The extension allows float textures, but not all devices can render to them
+        if created.is_err() || gl.check_framebuffer_status(GL::FRAMEBUFFER) != GL::FRAMEBUFFER_COMPLETE {
+            gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+            gl.delete_framebuffer(Some(&framebuffer));
+            gl.delete_texture(Some(&texture));
+            return None;
+        }
+
+        //Pass 1: accumulate with additive blending. Transparency is not used for clusters, so points use umap.vert
+        let accum_program = &overlay.accum_program;
+        gl.use_program(Some(accum_program));
+        set_camera_uniforms(gl, accum_program, &self.camera, canvas_w, canvas_h);
+        set_hover_uniform(gl, accum_program, hover_idx);
+        gl.uniform1f(gl.get_uniform_location(accum_program, "u_point_size").as_ref(), point_size);
+        gl.uniform1f(gl.get_uniform_location(accum_program, "u_alpha").as_ref(), alpha);
+
+        let sizeof_float = 4;
+        let stride = sizeof_float*vec_vertex_size as i32;
+        gl.bind_buffer(GL::ARRAY_BUFFER, Some(vertex_buffer));
+        let a_position = gl.get_attrib_location(accum_program, "a_position") as u32;
+        gl.enable_vertex_attrib_array(a_position);
+        gl.vertex_attrib_pointer_with_i32(a_position, 3, GL::FLOAT, false, stride, 0);
+        let a_color = gl.get_attrib_location(accum_program, "a_color") as u32;
+        gl.enable_vertex_attrib_array(a_color);
+        gl.vertex_attrib_pointer_with_i32(a_color, 3, GL::FLOAT, false, stride, sizeof_float*3);
+        let a_idx = gl.get_attrib_location(accum_program, "a_idx") as u32;
+        gl.enable_vertex_attrib_array(a_idx);
+        gl.vertex_attrib_pointer_with_i32(a_idx, 1, GL::FLOAT, false, stride, sizeof_float*6);
+
+        gl.viewport(0, 0, canvas_w, canvas_h);
+        gl.clear_color(0.0, 0.0, 0.0, 0.0);
+        gl.clear(GL::COLOR_BUFFER_BIT);
+        gl.enable(GL::BLEND);
+        gl.blend_func(GL::ONE, GL::ONE);
+        gl.draw_arrays(GL::POINTS, first as i32, count as i32);
+        gl.disable_vertex_attrib_array(a_position);
+        gl.disable_vertex_attrib_array(a_color);
+        gl.disable_vertex_attrib_array(a_idx);
+        gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+
+        //Pass 2: composite over what is already drawn
+        let composite_program = &overlay.composite_program;
+        gl.use_program(Some(composite_program));
+        gl.bind_buffer(GL::ARRAY_BUFFER, Some(&overlay.quad_buffer));
+        let a_quad_position = gl.get_attrib_location(composite_program, "a_position") as u32;
+        gl.enable_vertex_attrib_array(a_quad_position);
+        gl.vertex_attrib_pointer_with_i32(a_quad_position, 2, GL::FLOAT, false, 0, 0);
+
+        gl.active_texture(GL::TEXTURE0);
+        gl.bind_texture(GL::TEXTURE_2D, Some(&texture));
+        gl.uniform1i(gl.get_uniform_location(composite_program, "u_accum").as_ref(), 0);
+        gl.blend_func(GL::SRC_ALPHA, GL::ONE_MINUS_SRC_ALPHA);
+        gl.draw_arrays(GL::TRIANGLES, 0, 6);
+
+        gl.disable_vertex_attrib_array(a_quad_position);
+        gl.disable(GL::BLEND);
+        gl.delete_framebuffer(Some(&framebuffer));
+        gl.delete_texture(Some(&texture));
+        Some(2)
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Draw semi-transparent points back to front, using the program for points. As in GL,
+    /// a larger z is further away; points of 2D reductions have the same z and keep their order
+    fn draw_points_depth_sorted(
+        &self, 
+        gl: &GL, 
+        shader_program: &WebGlProgram, 
+        vec_vertex: &Vec<f32>, 
+        vec_vertex_size: usize, 
+        first: usize, 
+        count: usize, 
+        alpha: f32
+    ) -> usize {
+        let mut list_points: Vec<&[f32]> = vec_vertex.chunks_exact(vec_vertex_size).skip(first).take(count).collect();
+        list_points.sort_by(|a, b| b[2].partial_cmp(&a[2]).unwrap_or(std::cmp::Ordering::Equal));
+        let vec_sorted: Vec<f32> = list_points.concat();
+
+        gl.use_program(Some(shader_program));
+        let sorted_buffer = gl.create_buffer().unwrap();
+        let js_sorted = js_sys::Float32Array::from(vec_sorted.as_slice());
+        gl.bind_buffer(GL::ARRAY_BUFFER, Some(&sorted_buffer));
+        gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &js_sorted, GL::STATIC_DRAW);
+
+        let sizeof_float = 4;
+        let stride = sizeof_float*vec_vertex_size as i32;
+        let a_position = gl.get_attrib_location(shader_program, "a_position") as u32;
+        gl.enable_vertex_attrib_array(a_position);
+        gl.vertex_attrib_pointer_with_i32(a_position, 3, GL::FLOAT, false, stride, 0);
+        let a_color = gl.get_attrib_location(shader_program, "a_color") as u32;
+        gl.enable_vertex_attrib_array(a_color);
+        gl.vertex_attrib_pointer_with_i32(a_color, 3, GL::FLOAT, false, stride, sizeof_float*3);
+        let a_idx = gl.get_attrib_location(shader_program, "a_idx") as u32;
+        gl.enable_vertex_attrib_array(a_idx);
+        gl.vertex_attrib_pointer_with_i32(a_idx, 1, GL::FLOAT, false, stride, sizeof_float*6);
+
+        gl.uniform1f(gl.get_uniform_location(shader_program, "u_alpha").as_ref(), alpha);
+        gl.enable(GL::BLEND);
+        gl.blend_func(GL::SRC_ALPHA, GL::ONE_MINUS_SRC_ALPHA);
+        gl.draw_arrays(GL::POINTS, 0, count as i32);
+
+        gl.disable(GL::BLEND);
+        gl.disable_vertex_attrib_array(a_position);
+        gl.disable_vertex_attrib_array(a_color);
+        gl.disable_vertex_attrib_array(a_idx);
+        gl.delete_buffer(Some(&sorted_buffer));
+        1
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Get the fragment shader for points: the custom one if given and it compiles, otherwise the built-in.
+    /// Each custom shader is only compiled once to check it
+    fn get_frag_shader(&mut self, ctx: &Context<Self>, gl: &GL) -> String {
+        if let Some(custom_code) = &ctx.props().custom_frag_shader {
+            let is_valid = match &self.custom_frag_checked {
+                Some((checked_code, is_valid)) if checked_code == custom_code => *is_valid,
+                _ => {
+                    let is_valid = match check_frag_shader(gl, custom_code) {
+                        Ok(()) => true,
+                        Err(msg) => {
+                            log::warn!("Custom fragment shader does not compile; using the built-in. {}", msg);
+                            false
+                        }
+                    };
+                    self.custom_frag_checked = Some((custom_code.clone(), is_valid));
+                    is_valid
+                }
+            };
+            if is_valid {
+                return custom_code.clone();
+            }
+        }
+        DEFAULT_FRAG_SHADER.to_string()
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Move each annotation label a step toward where the gravity well pushes it: radially away
+    /// from the well if close to it, otherwise back to its cell. Returns true if any label moved
+    fn update_label_offsets(&mut self, ctx: &Context<Self>) -> bool {
+        let (w, h) = self.get_css_canvas_size(ctx);
+        if w <= 0.0 {
+            return false;
+        }
+        let AsyncData::Loaded(reduction_data) = self.get_reduction_data(ctx) else {
+            return false;
+        };
+        let to_pixel = |wx: f32, wy: f32| {
+            let (cx, cy) = self.camera.world2cam(wx, wy);
+            (cx*w/2.0 + w/2.0, cy*h/2.0 + h/2.0)
+        };
+        let well = self.gravity_well.map(|(wx, wy)| to_pixel(wx, wy));
+
+        let mut moved = false;
+        let mut label_offsets = HashMap::new();
+        for cell in ctx.props().cell_annotations.keys() {
+            if *cell >= reduction_data.num_point {
+                continue;
+            }
+            let (x, y) = to_pixel(reduction_data.data[cell*2+0], reduction_data.data[cell*2+1]);
+            let target = match well {
+                Some((well_x, well_y)) => {
+                    let (dx, dy) = (x - well_x, y - well_y);
+                    let dist = (dx*dx + dy*dy).sqrt();
+                    if dist < GRAVITY_LABEL_RADIUS {
+                        let (dir_x, dir_y) = if dist > 1e-3 { (dx/dist, dy/dist) } else { (1.0, 0.0) };
+                        let push = GRAVITY_LABEL_RADIUS - dist;
+                        (dir_x*push, dir_y*push)
+                    } else {
+                        (0.0, 0.0)
+                    }
+                },
+                None => (0.0, 0.0)
+            };
+
+            let (ox, oy) = self.label_offsets.get(cell).cloned().unwrap_or((0.0, 0.0));
+            let step = ((target.0 - ox)*GRAVITY_SPRING, (target.1 - oy)*GRAVITY_SPRING);
+            if step.0.abs() > 0.05 || step.1.abs() > 0.05 {
+                moved = true;
+                label_offsets.insert(*cell, (ox + step.0, oy + step.1));
+            } else if target != (0.0, 0.0) {
+                label_offsets.insert(*cell, target);
+            }
+        }
+        self.label_offsets = label_offsets;
+        moved
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Cells to draw when subsampling. Only recomputed if the number of points changes
+    fn get_display_subsample(&mut self, num_point: usize, max_points: usize) -> &Vec<usize> {
+        if self.display_subsample.0 != (num_point, max_points) {
+            self.display_subsample = ((num_point, max_points), subsample_indices(num_point, max_points));
+        }
+        &self.display_subsample.1
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Pearson correlation between the coloring and the chosen column, over the cells in view.
+    /// If cells have been selected, only these are used. Returns the correlation and number of cells
+    fn get_visible_correlation(&self, ctx: &Context<Self>) -> Option<(f32, usize)> {
+        let correlate_with = self.correlate_with.as_ref()?;
+        let AsyncData::Loaded(reduction) = self.get_reduction_data(ctx) else {
+            return None;
+        };
+        let ReductionColoringWithData::ByMeta(_, AsyncData::Loaded(color_data)) = &ctx.props().color_reduction_by else {
+            return None;
+        };
+        let other_data = ctx.props().correlation_columns.iter().find_map(|(name, data)| {
+            match data {
+                AsyncData::Loaded(data) if name==correlate_with => Some(data.clone()),
+                _ => None
+            }
+        })?;
+
+        let values_a = dense_numeric_values(color_data, reduction.num_point)?;
+        let values_b = dense_numeric_values(&other_data, reduction.num_point)?;
+
+        //Cells in view; camera coordinates are in range [-1,1]
+        let candidates: Vec<usize> = if self.last_selection.is_empty() {
+            (0..reduction.num_point).collect()
+        } else {
+            self.last_selection.clone()
+        };
+        let visible: Vec<usize> = candidates.into_iter().filter(|i| {
+            let (cx, cy) = self.camera.world2cam(reduction.data[i*2+0], reduction.data[i*2+1]);
+            cx.abs() <= 1.0 && cy.abs() <= 1.0
+        }).collect();
+
+        pearson_correlation(&values_a, &values_b, &visible).map(|r| (r, visible.len()))
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Status text for the category of the hovered cell, with the number of cells in it.
+    /// None unless coloring by a loaded categorical column
+    fn get_hovered_cluster_name(&self, ctx: &Context<Self>) -> Option<String> {
+        let cell = self.last_cell?;
+        let ReductionColoringWithData::ByMeta(_, AsyncData::Loaded(color_data)) = &ctx.props().color_reduction_by else {
+            return None;
+        };
+        let CountFileMetaColumnData::Categorical(vec_data, vec_cats) = color_data.as_ref() else {
+            return None;
+        };
+        let category = *vec_data.get(cell)?;
+        let name = vec_cats.get(category as usize)?;
+        let num_cells = vec_data.iter().filter(|c| **c == category).count();
+        Some(format!("Cluster: {} (idx {}) | {} cells", name, category, format_count(num_cells)))
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Recompute the radial layout if the reduction or the coloring changed.
+    /// The layout is only possible when coloring by a categorical column
+    fn update_layout(&mut self, ctx: &Context<Self>) {
+        if self.layout_mode==LayoutMode::Original {
+            self.layout_data = AsyncData::NotLoaded;
+            self.layout_input = (AsyncData::NotLoaded, ReductionColoringWithData::None);
+            return;
+        }
+
+        //The relaxed layout does not depend on the coloring
+        let coloring = if self.layout_mode==LayoutMode::Relaxed { ReductionColoringWithData::None } else { ctx.props().color_reduction_by.clone() };
+        let input = (ctx.props().reduction_data.clone(), coloring);
+        if self.layout_input == input && self.layout_data != AsyncData::NotLoaded {
+            return;
+        }
+
+        self.layout_data = AsyncData::NotLoaded;
+        if self.layout_mode==LayoutMode::Relaxed {
+            if let AsyncData::Loaded(reduction_data) = &input.0 {
+                match relax_layout_gpu(reduction_data, RELAX_ITERATIONS) {
+                    Ok(relaxed) => {
+                        self.layout_data = AsyncData::new(relaxed);
+                    },
+                    Err(e) => {
+                        log::warn!("{}", e);
+                        self.layout_mode = LayoutMode::Original;
+                        self.layout_input = (AsyncData::NotLoaded, ReductionColoringWithData::None);
+                        return;
+                    }
+                }
+            }
+        } else if let (AsyncData::Loaded(reduction_data), ReductionColoringWithData::ByMeta(_name, AsyncData::Loaded(color_data))) = &input {
+            if let CountFileMetaColumnData::Categorical(vec_data, _vec_cats) = color_data.as_ref() {
+                self.layout_data = AsyncData::new(layout_radial_cluster(reduction_data, vec_data));
+            }
+        }
+        self.layout_input = input;
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Animate the camera to center on a cell, zoomed so that its nearest FOCUS_NUM_NEIGHBOURS cells fill the view
+    fn focus_cell(&mut self, ctx: &Context<Self>, cell: usize) {
+        let AsyncData::Loaded(reduction_data) = self.get_reduction_data(ctx) else {
+            return;
+        };
+        if cell >= reduction_data.num_point {
+            return;
+        }
+        let (x, y) = (reduction_data.data[cell*2+0], reduction_data.data[cell*2+1]);
+
+        //Radius of the neighbourhood is the distance to the furthest of the nearest cells
+        let radius = self.closest_point_index.get_k_nearest(x, y, FOCUS_NUM_NEIGHBOURS)
+            .iter()
+            .map(|j| ((reduction_data.data[j*2+0] - x).powi(2) + (reduction_data.data[j*2+1] - y).powi(2)).sqrt())
+            .fold(0.0, f32::max);
+
+        //Same margin as when fitting the whole reduction
+        let zoom_x = 0.9*(1.0/radius).min(self.camera.aspect_ratio/radius);
+        self.zoom_target = if zoom_x > 0.0 && zoom_x.is_finite() { Some(zoom_x) } else { None };
+        self.camera_target = Some((x, y));
+        self.follow_selection = false;
+        self.start_animation(ctx);
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Make sure AnimationTick messages are coming, unless already the case
+    fn start_animation(&mut self, ctx: &Context<Self>) {
+        if !self.animation_running {
+            self.animation_running = true;
+            request_animation_tick(ctx);
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Start sending ExploreStep messages every 3 seconds
+    fn start_explore(&mut self, ctx: &Context<Self>) {
+        if self.explore_interval.is_some() {
+            return;
+        }
+        let link = ctx.link().clone();
+        let on_step = Closure::wrap(Box::new(move || {
+            link.send_message(MsgReduction::ExploreStep);
+        }) as Box<dyn Fn()>);
+        let handle = window().expect("no window")
+            .set_interval_with_callback_and_timeout_and_arguments_0(on_step.as_ref().unchecked_ref(), 3000)
+            .expect("Could not set interval");
+        self.explore_interval = Some((handle, on_step));
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Stop sending ExploreStep messages
+    fn stop_explore(&mut self) {
+        if let Some((handle, _on_step)) = self.explore_interval.take() {
+            window().expect("no window").clear_interval_with_handle(handle);
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Pick a position of interest for the explore mode. This is a random
+    /// cell that is novel compared to what is currently being shown: of another
+    /// category, or among the highest values of the current numeric coloring
+    fn pick_explore_target(&self, ctx: &Context<Self>) -> Option<(f32,f32)> {
+        let reduction_data = self.get_reduction_data(ctx);
+        let reduction_data = if let AsyncData::Loaded(reduction_data) = &reduction_data {
+            reduction_data
+        } else {
+            return None;
+        };
+        let num_point = reduction_data.num_point;
+        if num_point == 0 {
+            return None;
+        }
+        let get_pos = |i: usize| (reduction_data.data[i*2+0], reduction_data.data[i*2+1]);
+
+        //Check if a cell is currently on screen
+        let is_visible = |i: usize| {
+            let (wx, wy) = get_pos(i);
+            let (cx, cy) = self.camera.world2cam(wx, wy);
+            cx.abs() < 1.0 && cy.abs() < 1.0
+        };
+
+        //Keep only the highest values, for cells not already on screen
+        let num_top = 50;
+        let top_values = |list_values: Vec<(usize, f32)>| -> Vec<usize> {
+            let mut list_values: Vec<(usize, f32)> = list_values.into_iter().filter(|(i,_v)| *i < num_point && !is_visible(*i)).collect();
+            list_values.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+            list_values.iter().take(num_top).map(|(i,_v)| *i).collect()
+        };
+
+        let mut list_cand: Vec<usize> = Vec::new();
+        if let ReductionColoringWithData::ByMeta(_name, AsyncData::Loaded(color_data)) = &ctx.props().color_reduction_by {
+            match color_data.as_ref() {
+                CountFileMetaColumnData::Categorical(vec_data, _vec_cats) => {
+                    //The category in view is the one of the cell closest to the center of the screen
+                    let dist_to_center = |i: usize| {
+                        let (wx, wy) = get_pos(i);
+                        let (cx, cy) = self.camera.world2cam(wx, wy);
+                        cx*cx + cy*cy
+                    };
+                    let center_cell = (0..num_point.min(vec_data.len()))
+                        .min_by(|a, b| dist_to_center(*a).partial_cmp(&dist_to_center(*b)).unwrap_or(std::cmp::Ordering::Equal));
+                    if let Some(center_cell) = center_cell {
+                        let center_cat = vec_data[center_cell];
+                        list_cand = (0..num_point.min(vec_data.len())).filter(|i| vec_data[*i] != center_cat).collect();
+                    }
+                },
+                CountFileMetaColumnData::Numeric(vec_data) => {
+                    list_cand = top_values(vec_data.iter().cloned().enumerate().collect());
+                },
+                CountFileMetaColumnData::SparseNumeric(vec_index, vec_data) => {
+                    list_cand = top_values(vec_index.iter().map(|i| *i as usize).zip(vec_data.iter().cloned()).collect());
+                },
+            }
+        } else {
+            list_cand = (0..num_point).filter(|i| !is_visible(*i)).collect();
+        }
+
+        //Fall back to any cell
+        let mut rng = rand::rng();
+        let cell = if list_cand.is_empty() {
+            rng.random_range(0..num_point)
+        } else {
+            list_cand[rng.random_range(0..list_cand.len())]
+        };
+        Some(get_pos(cell))
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Draw the velocity ghost layer: a faint line from each cell to where it
+    /// is projected to be in one step, and a hollow circle at the projected position.
+    /// Returns the number of draw calls
+    fn draw_velocity(&self, gl: &GL, datapoints: &ReductionViewData, velocity_data: &Vec<(f32,f32)>, velocity_scale: f32) -> usize {
+        let Some(overlay) = &self.overlay_gl else {
+            return 0;
+        };
+
+        //Velocity must be given for each cell
+        if velocity_data.len() != datapoints.num_point {
+            if !velocity_data.is_empty() {
+                log::debug!("Velocity data size {} does not match reduction {}", velocity_data.len(), datapoints.num_point);
+            }
+            return 0;
+        }
+
+        //Pairs of (current position, projected position)
+        let mut vec_lines: Vec<f32> = Vec::with_capacity(datapoints.num_point*4);
+        let mut vec_projected: Vec<f32> = Vec::with_capacity(datapoints.num_point*2);
+        for (i, (vx, vy)) in velocity_data.iter().enumerate() {
+            let x = datapoints.data[i*2+0];
+            let y = datapoints.data[i*2+1];
+            let px = x + velocity_scale*vx;
+            let py = y + velocity_scale*vy;
+            vec_lines.extend_from_slice(&[x, y, px, py]);
+            vec_projected.extend_from_slice(&[px, py]);
+        }
+
+        let shader_program = &overlay.point_program;
+        gl.use_program(Some(shader_program));
+
+        //Attach camera attributes
+        let u_camera_x = gl.get_uniform_location(shader_program, "u_camera_x");
+        let u_camera_y = gl.get_uniform_location(shader_program, "u_camera_y");
+        let u_camera_zoom_x = gl.get_uniform_location(shader_program, "u_camera_zoom_x");
+        let u_camera_zoom_y = gl.get_uniform_location(shader_program, "u_camera_zoom_y");
+        gl.uniform1f(u_camera_x.as_ref(), self.camera.x as f32);
+        gl.uniform1f(u_camera_y.as_ref(), self.camera.y as f32);
+        gl.uniform1f(u_camera_zoom_x.as_ref(), self.camera.zoom_x as f32);
+        gl.uniform1f(u_camera_zoom_y.as_ref(), self.camera.zoom_y as f32);
+
+        let u_point_size = gl.get_uniform_location(shader_program, "u_point_size");
+        let u_alpha = gl.get_uniform_location(shader_program, "u_alpha");
+        let u_hollow = gl.get_uniform_location(shader_program, "u_hollow");
+
+        gl.enable(GL::BLEND);
+        gl.blend_func(GL::SRC_ALPHA, GL::ONE_MINUS_SRC_ALPHA);
+
+        let a_position = gl.get_attrib_location(shader_program, "a_position") as u32;
+        gl.enable_vertex_attrib_array(a_position);
+
+        //First pass: faint lines
+        let js_lines = js_sys::Float32Array::from(vec_lines.as_slice());
+        gl.bind_buffer(GL::ARRAY_BUFFER, Some(&overlay.buffer));
+        gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &js_lines, GL::STATIC_DRAW);
+        gl.vertex_attrib_pointer_with_i32(a_position, 2, GL::FLOAT, false, 0, 0);
+        gl.uniform1f(u_alpha.as_ref(), 0.1);
+        gl.uniform1f(u_hollow.as_ref(), 0.0);
+        gl.draw_arrays(GL::LINES, 0, (datapoints.num_point*2) as i32);
+
+        //Second pass: projected positions as small hollow circles
+        let js_projected = js_sys::Float32Array::from(vec_projected.as_slice());
+        gl.bind_buffer(GL::ARRAY_BUFFER, Some(&overlay.buffer));
+        gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &js_projected, GL::STATIC_DRAW);
+        gl.vertex_attrib_pointer_with_i32(a_position, 2, GL::FLOAT, false, 0, 0);
+        gl.uniform1f(u_alpha.as_ref(), 0.3);
+        gl.uniform1f(u_hollow.as_ref(), 1.0);
+        gl.uniform1f(u_point_size.as_ref(), 4.0*self.pixel_ratio);
+        gl.draw_arrays(GL::POINTS, 0, datapoints.num_point as i32);
+
+        gl.disable(GL::BLEND);
+        2
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Draw the trajectory as a faint smooth curve, and its particles as small dots.
+    /// Returns the number of draw calls
+    fn draw_trajectory(&self, gl: &GL, datapoints: &ReductionViewData, trajectory: &Vec<usize>) -> usize {
+        let Some(overlay) = &self.overlay_gl else {
+            return 0;
+        };
+        let path = TrajectoryPath::smoothed(datapoints, trajectory);
+        if path.points.len() < 2 {
+            return 0;
+        }
+        let control_dist = control_point_distances(path.points.len(), SPLINE_SUBDIVISIONS);
+        let vec_line: Vec<f32> = path.points.iter().zip(control_dist.iter()).flat_map(|((x, y), d)| [*x, *y, *d]).collect();
+        let vec_particles: Vec<f32> = self.particles.iter()
+            .filter_map(|t| path.point_at(*t))
+            .flat_map(|(x, y)| [x, y])
+            .collect();
+
+        gl.enable(GL::BLEND);
+        gl.blend_func(GL::SRC_ALPHA, GL::ONE_MINUS_SRC_ALPHA);
+
+        //First pass: the curve, fading a little towards the cells it passes through
+        let line_program = &overlay.spline_program;
+        gl.use_program(Some(line_program));
+
+        let u_camera_x = gl.get_uniform_location(line_program, "u_camera_x");
+        let u_camera_y = gl.get_uniform_location(line_program, "u_camera_y");
+        let u_camera_zoom_x = gl.get_uniform_location(line_program, "u_camera_zoom_x");
+        let u_camera_zoom_y = gl.get_uniform_location(line_program, "u_camera_zoom_y");
+        gl.uniform1f(u_camera_x.as_ref(), self.camera.x as f32);
+        gl.uniform1f(u_camera_y.as_ref(), self.camera.y as f32);
+        gl.uniform1f(u_camera_zoom_x.as_ref(), self.camera.zoom_x as f32);
+        gl.uniform1f(u_camera_zoom_y.as_ref(), self.camera.zoom_y as f32);
+
+        let u_alpha = gl.get_uniform_location(line_program, "u_alpha");
+        let u_edge_alpha = gl.get_uniform_location(line_program, "u_edge_alpha");
+        let u_edge_ramp = gl.get_uniform_location(line_program, "u_edge_ramp");
+        let u_color = gl.get_uniform_location(line_program, "u_color");
+        gl.uniform1f(u_alpha.as_ref(), 0.4);
+        gl.uniform1f(u_edge_alpha.as_ref(), 0.5);
+        gl.uniform1f(u_edge_ramp.as_ref(), 0.15);
+        gl.uniform3f(u_color.as_ref(), 0.0, 0.0, 0.0);
+
+        let a_position = gl.get_attrib_location(line_program, "a_position") as u32;
+        let a_control_dist = gl.get_attrib_location(line_program, "a_control_dist") as u32;
+        gl.enable_vertex_attrib_array(a_position);
+        gl.enable_vertex_attrib_array(a_control_dist);
+
+        let js_line = js_sys::Float32Array::from(vec_line.as_slice());
+        gl.bind_buffer(GL::ARRAY_BUFFER, Some(&overlay.buffer));
+        gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &js_line, GL::STATIC_DRAW);
+        gl.vertex_attrib_pointer_with_i32(a_position, 2, GL::FLOAT, false, 3*4, 0);
+        gl.vertex_attrib_pointer_with_i32(a_control_dist, 1, GL::FLOAT, false, 3*4, 2*4);
+        gl.draw_arrays(GL::LINE_STRIP, 0, path.points.len() as i32);
+
+        gl.disable_vertex_attrib_array(a_control_dist);
+        gl.disable_vertex_attrib_array(a_position);
+
+        //Second pass: the particles
+        let shader_program = &overlay.point_program;
+        gl.use_program(Some(shader_program));
+
+        let u_camera_x = gl.get_uniform_location(shader_program, "u_camera_x");
+        let u_camera_y = gl.get_uniform_location(shader_program, "u_camera_y");
+        let u_camera_zoom_x = gl.get_uniform_location(shader_program, "u_camera_zoom_x");
+        let u_camera_zoom_y = gl.get_uniform_location(shader_program, "u_camera_zoom_y");
+        gl.uniform1f(u_camera_x.as_ref(), self.camera.x as f32);
+        gl.uniform1f(u_camera_y.as_ref(), self.camera.y as f32);
+        gl.uniform1f(u_camera_zoom_x.as_ref(), self.camera.zoom_x as f32);
+        gl.uniform1f(u_camera_zoom_y.as_ref(), self.camera.zoom_y as f32);
+
+        let u_point_size = gl.get_uniform_location(shader_program, "u_point_size");
+        let u_alpha = gl.get_uniform_location(shader_program, "u_alpha");
+        let u_hollow = gl.get_uniform_location(shader_program, "u_hollow");
+        let u_color = gl.get_uniform_location(shader_program, "u_color");
+
+        let a_position = gl.get_attrib_location(shader_program, "a_position") as u32;
+        gl.enable_vertex_attrib_array(a_position);
+
+        let js_particles = js_sys::Float32Array::from(vec_particles.as_slice());
+        gl.bind_buffer(GL::ARRAY_BUFFER, Some(&overlay.buffer));
+        gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &js_particles, GL::STATIC_DRAW);
+        gl.vertex_attrib_pointer_with_i32(a_position, 2, GL::FLOAT, false, 0, 0);
+        gl.uniform1f(u_alpha.as_ref(), 1.0);
+        gl.uniform1f(u_hollow.as_ref(), 0.0);
+        gl.uniform3f(u_color.as_ref(), PARTICLE_COLOR.0, PARTICLE_COLOR.1, PARTICLE_COLOR.2);
+        gl.uniform1f(u_point_size.as_ref(), 4.0*self.pixel_ratio);
+        gl.draw_arrays(GL::POINTS, 0, (vec_particles.len()/2) as i32);
+
+        gl.disable_vertex_attrib_array(a_position);
+        gl.disable(GL::BLEND);
+        2
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Draw a ring around each given cell. Returns the number of draw calls
+    fn draw_highlight(&self, gl: &GL, datapoints: &ReductionViewData, cells: &Vec<usize>) -> usize {
+        let Some(overlay) = &self.overlay_gl else {
+            return 0;
+        };
+        let vec_points: Vec<f32> = cells.iter()
+            .filter(|i| **i < datapoints.num_point)
+            .flat_map(|i| [datapoints.data[i*2+0], datapoints.data[i*2+1]])
+            .collect();
+        let num_points = vec_points.len()/2;
+
+        let shader_program = &overlay.point_program;
+        gl.use_program(Some(shader_program));
+
+        //Attach camera attributes
+        let u_camera_x = gl.get_uniform_location(shader_program, "u_camera_x");
+        let u_camera_y = gl.get_uniform_location(shader_program, "u_camera_y");
+        let u_camera_zoom_x = gl.get_uniform_location(shader_program, "u_camera_zoom_x");
+        let u_camera_zoom_y = gl.get_uniform_location(shader_program, "u_camera_zoom_y");
+        gl.uniform1f(u_camera_x.as_ref(), self.camera.x as f32);
+        gl.uniform1f(u_camera_y.as_ref(), self.camera.y as f32);
+        gl.uniform1f(u_camera_zoom_x.as_ref(), self.camera.zoom_x as f32);
+        gl.uniform1f(u_camera_zoom_y.as_ref(), self.camera.zoom_y as f32);
+
+        let u_point_size = gl.get_uniform_location(shader_program, "u_point_size");
+        let u_alpha = gl.get_uniform_location(shader_program, "u_alpha");
+        let u_hollow = gl.get_uniform_location(shader_program, "u_hollow");
+
+        gl.enable(GL::BLEND);
+        gl.blend_func(GL::SRC_ALPHA, GL::ONE_MINUS_SRC_ALPHA);
+
+        let a_position = gl.get_attrib_location(shader_program, "a_position") as u32;
+        gl.enable_vertex_attrib_array(a_position);
+
+        let js_points = js_sys::Float32Array::from(vec_points.as_slice());
+        gl.bind_buffer(GL::ARRAY_BUFFER, Some(&overlay.buffer));
+        gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &js_points, GL::STATIC_DRAW);
+        gl.vertex_attrib_pointer_with_i32(a_position, 2, GL::FLOAT, false, 0, 0);
+        gl.uniform1f(u_alpha.as_ref(), 0.8);
+        gl.uniform1f(u_hollow.as_ref(), 1.0);
+        gl.uniform1f(u_point_size.as_ref(), 9.0*self.pixel_ratio);
+        gl.draw_arrays(GL::POINTS, 0, num_points as i32);
+
+        gl.disable_vertex_attrib_array(a_position);
+        gl.disable(GL::BLEND);
+        1
+    }
+
+}
+
+
+
+////////////////////////////////////////////////////////////
+/// Set the camera and display size uniforms of a program for points
+pub fn set_camera_uniforms(gl: &GL, shader_program: &WebGlProgram, camera: &Camera2D, canvas_w: i32, canvas_h: i32) {
+    gl.uniform1f(gl.get_uniform_location(shader_program, "u_camera_x").as_ref(), camera.x as f32);
+    gl.uniform1f(gl.get_uniform_location(shader_program, "u_camera_y").as_ref(), camera.y as f32);
+    gl.uniform1f(gl.get_uniform_location(shader_program, "u_camera_zoom_x").as_ref(), camera.zoom_x as f32);
+    gl.uniform1f(gl.get_uniform_location(shader_program, "u_camera_zoom_y").as_ref(), camera.zoom_y as f32);
+    gl.uniform1f(gl.get_uniform_location(shader_program, "u_display_w").as_ref(), canvas_w as f32);
+    gl.uniform1f(gl.get_uniform_location(shader_program, "u_display_h").as_ref(), canvas_h as f32);
+    gl.uniform1f(gl.get_uniform_location(shader_program, "u_zoom_scale").as_ref(), camera.zoom_scale());
+    gl.uniform2f(gl.get_uniform_location(shader_program, "u_pos_scale").as_ref(), 1.0, 1.0);
+    gl.uniform2f(gl.get_uniform_location(shader_program, "u_pos_offset").as_ref(), 0.0, 0.0);
+    set_hover_uniform(gl, shader_program, None);
+}
+
+
+////////////////////////////////////////////////////////////
+/// Set the cell that the shader for points makes stand out, if any
+pub fn set_hover_uniform(gl: &GL, shader_program: &WebGlProgram, cell: Option<usize>) {
+    let idx = cell.map(|i| i as i32).unwrap_or(-1);
+    gl.uniform1i(gl.get_uniform_location(shader_program, "u_hover_idx").as_ref(), idx);
+}
+
+
+////////////////////////////////////////////////////////////
+/// Check that a fragment shader compiles. Returns the compiler log if not
+fn check_frag_shader(gl: &GL, frag_code: &str) -> Result<(), String> {
+    let frag_shader = gl.create_shader(GL::FRAGMENT_SHADER).ok_or("Could not create shader")?;
+    gl.shader_source(&frag_shader, frag_code);
+    gl.compile_shader(&frag_shader);
+    let compiled = gl.get_shader_parameter(&frag_shader, GL::COMPILE_STATUS).as_bool().unwrap_or(false);
+    let msg = gl.get_shader_info_log(&frag_shader).unwrap_or_default();
+    gl.delete_shader(Some(&frag_shader));
+    if compiled {
+        Ok(())
+    } else {
+        Err(msg)
+    }
+}
+
+
+////////////////////////////////////////////////////////////
+/// Color points on a diverging scale, white at the center. The range is widened to be symmetric
+/// around the center, after the color scale transform
+fn diverging_point_colors(vec_vertex: &mut Vec<f32>, vec_vertex_size: usize, values: impl Iterator<Item=(usize, f32)>, range: (f32,f32), center: f32, transform: ColorScaleTransform) {
+    let tcenter = transform.apply(center);
+    let half = (transform.apply(range.0) - tcenter).abs().max((transform.apply(range.1) - tcenter).abs()).max(1e-6);
+    for (i, p) in values {
+        let base = vec_vertex_size*i;
+        if base + 5 < vec_vertex.len() {
+            let col = diverging_color((transform.apply(p) - tcenter)/half);
+            vec_vertex[base + 3] = col.0;
+            vec_vertex[base + 4] = col.1;
+            vec_vertex[base + 5] = col.2;
+        }
+    }
+}
+
+
+////////////////////////////////////////////////////////////
+/// Vertical scroll of a wheel event in pixels, and if it seems to come from a trackpad. Trackpads
+/// give many small steps in pixels; mouse wheels give lines (Firefox) or large steps in pixels
+fn wheel_delta_pixels(e: &WheelEvent) -> (f32, bool) {
+    let dy = e.delta_y();
+    match e.delta_mode() {
+        WheelEvent::DOM_DELTA_LINE => ((dy*WHEEL_LINE_PIXELS) as f32, false),
+        WheelEvent::DOM_DELTA_PAGE => ((dy*WHEEL_PAGE_PIXELS) as f32, false),
+        _ => (dy as f32, dy.abs() < TRACKPAD_MAX_DELTA),
+    }
+}
+
+
+////////////////////////////////////////////////////////////
+/// Tell the parent, and the selection context if there is one, that the hovered cell changed
+fn emit_hovered(ctx: &Context<ReductionView>, selection_context: &Option<(SelectionContext, ContextHandle<SelectionContext>)>, cell: Option<usize>) {
+    ctx.props().on_cell_hovered.emit(cell);
+    if let Some((selection_context, _)) = selection_context {
+        selection_context.set_hovered.emit(cell);
+    }
+}
+
+
+////////////////////////////////////////////////////////////
+/// Tell the parent, and the selection context if there is one, that cells were selected
+fn emit_selection(ctx: &Context<ReductionView>, selection_context: &Option<(SelectionContext, ContextHandle<SelectionContext>)>, cells: Vec<usize>) {
+    ctx.props().on_cell_clicked.emit(cells.clone());
+    if let Some((selection_context, _)) = selection_context {
+        selection_context.set_selection.emit(cells);
+    }
+}
+
+
+////////////////////////////////////////////////////////////
+/// Compile and link a pair of shaders
+pub fn compile_shader_program(gl: &GL, vert_code: &str, frag_code: &str) -> WebGlProgram {
+    let vert_shader = gl.create_shader(GL::VERTEX_SHADER).unwrap();
+    gl.shader_source(&vert_shader, vert_code);
+    gl.compile_shader(&vert_shader);
+
+    let frag_shader = gl.create_shader(GL::FRAGMENT_SHADER).unwrap();
+    gl.shader_source(&frag_shader, frag_code);
+    gl.compile_shader(&frag_shader);
+
+    let shader_program = gl.create_program().unwrap();
+    gl.attach_shader(&shader_program, &vert_shader);
+    gl.attach_shader(&shader_program, &frag_shader);
+    gl.link_program(&shader_program);
+    shader_program
+}
+
+
+
+
+
+////////////////////////////////////////////////////////////
+/// Convert from vector to HTML color code
+pub fn rgbvec2string(c: Vec3) -> String {
+    let red=(c.0*255.0) as u8;
+    let green=(c.1*255.0) as u8;
+    let blue=(c.2*255.0) as u8;
+    format!("#{:02X}{:02X}{:02X}", red, green, blue)
+}
+
 
-                gl_legend.vertex_attrib_pointer_with_i32(a_position, 2, GL::FLOAT, false, 0, 0);
-                gl_legend.enable_vertex_attrib_array(a_position);
 
-                gl_legend.draw_arrays(GL::TRIANGLES, 0, 6); */
+////////////////////////////////////////////////////////////
+/// Blend the color of each point toward the mean color of its neighbors.
+/// Colors are stored at offset 3..6 of each vertex
+fn smooth_point_colors(vec_vertex: &mut Vec<f32>, vec_vertex_size: usize, datapoints: &ReductionViewData, blur_radius: f32, smooth_factor: f32) {
+    let mut index = ClosestPointIndex2D::new();
+    index.build_point_index(datapoints, blur_radius);
+
+    //Read from a copy, so that smoothing does not depend on the order of points
+    let vec_orig = vec_vertex.clone();
+    for i in 0..datapoints.num_point {
+        let x = datapoints.data[i*2+0];
+        let y = datapoints.data[i*2+1];
+
+        //The point itself is always within the radius, so there is at least one neighbor
+        let list_neighbors = index.get_points_within(x, y);
+        let mut mean = (0.0, 0.0, 0.0);
+        for j in list_neighbors.iter() {
+            let base = vec_vertex_size*j;
+            mean.0 += vec_orig[base + 3];
+            mean.1 += vec_orig[base + 4];
+            mean.2 += vec_orig[base + 5];
+        }
+        let n = list_neighbors.len().max(1) as f32;
 
-                        },
+        let base = vec_vertex_size*i;
+        vec_vertex[base + 3] += (mean.0/n - vec_orig[base + 3])*smooth_factor;
+        vec_vertex[base + 4] += (mean.1/n - vec_orig[base + 4])*smooth_factor;
+        vec_vertex[base + 5] += (mean.2/n - vec_orig[base + 5])*smooth_factor;
+    }
+}
 
-                        ///////// Color by numerical data - sparse array
-                        CountFileMetaColumnData::SparseNumeric(vec_index, vec_data) => {
 
-                            //Normalize color range. TODO should only need to do this once during loading. note, for sparse, min_val should be 0 by definition, more or less
-                            let (min_val, max_val) = make_safe_minmax(&vec_data);
-                            log::debug!("Render value range {} {}",min_val, max_val);
 
-                            for (i,p) in vec_index.iter().zip(vec_data.iter()) {
-                                let i = *i as usize;
-                                let base = vec_vertex_size*i;
-                                vec_vertex[base + 3] = p/max_val;
-                                vec_vertex[base + 4] = 0.0;
-                                vec_vertex[base + 5] = 0.0;
-                            }
-                        },
-                    }
-                }
-            } else {
-                // Put in an empty color (default is black now)
+////////////////////////////////////////////////////////////
+/// Blend the color of cells at the border of a category toward the color of the category
+/// next to it in hue, if any of its cells are nearby. The more neighbors in that category,
+/// the further along the gradient, up to halfway where the two categories meet.
+/// Cells not at a border keep the color of their category
+fn gradient_category_colors(vec_vertex: &mut Vec<f32>, vec_vertex_size: usize, datapoints: &ReductionViewData, vec_data: &Vec<u32>, palette: &Vec<Color3f>) {
+    let num_cats = palette.len();
+    if num_cats < 2 {
+        return;
+    }
+    let gradient = HueGradient::new(palette);
+
+    let span = (datapoints.max_x - datapoints.min_x).max(datapoints.max_y - datapoints.min_y);
+    let mut index = ClosestPointIndex2D::new();
+    index.build_point_index(datapoints, span*CATEGORY_GRADIENT_RADIUS);
+
+    for i in 0..datapoints.num_point.min(vec_data.len()) {
+        let cat = (vec_data[i] as usize) % num_cats;
+        let next = (cat + 1) % num_cats;
+        let prev = (cat + num_cats - 1) % num_cats;
+
+        //Count neighbors in the categories on either side in hue
+        let list_neighbors = index.get_points_within(datapoints.data[i*2+0], datapoints.data[i*2+1]);
+        let (mut num_next, mut num_prev) = (0, 0);
+        for j in list_neighbors.iter() {
+            match vec_data.get(*j).map(|c| (*c as usize) % num_cats) {
+                Some(c) if c == next => num_next += 1,
+                Some(c) if c == prev => num_prev += 1,
+                _ => {}
             }
+        }
+        if num_next == 0 && num_prev == 0 {
+            continue;
+        }
 
-            //Connect vertex array to GL
-            let vertex_buffer = gl.create_buffer().unwrap();
-            let js_vertex = js_sys::Float32Array::from(vec_vertex.as_slice());
-            //let verts = js_sys::Int32Array::from(vertices_int.as_slice());
-            gl.bind_buffer(GL::ARRAY_BUFFER, Some(&vertex_buffer));
-            gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &js_vertex, GL::STATIC_DRAW);
+        let n = list_neighbors.len().max(1) as f32;
+        let col = if num_next >= num_prev {
+            gradient.color(cat, true, (num_next as f32/n).min(0.5))
+        } else {
+            gradient.color(cat, false, (num_prev as f32/n).min(0.5))
+        };
+        let base = vec_vertex_size*i;
+        vec_vertex[base + 3] = col.0;
+        vec_vertex[base + 4] = col.1;
+        vec_vertex[base + 5] = col.2;
+    }
+}
 
-            //Compile vertex shader
-            let vert_shader = gl.create_shader(GL::VERTEX_SHADER).unwrap();
-            gl.shader_source(&vert_shader, vert_code.as_str());
-            gl.compile_shader(&vert_shader);
 
-            
-            /*let msg= gl.get_shader_info_log(&vert_shader);
-            if let Some(msg)=msg {
-                log::debug!("error {}", msg);
-            }*/
 
-            //Compile fragment shader
-            let frag_shader = gl.create_shader(GL::FRAGMENT_SHADER).unwrap();
-            gl.shader_source(&frag_shader, frag_code);
-            gl.compile_shader(&frag_shader);
+////////////////////////////////////////////////////////////
+/// Ask the browser for an AnimationTick message before the next repaint
+fn request_animation_tick(ctx: &Context<ReductionView>) {
+    let link = ctx.link().clone();
+    let cb = Closure::once_into_js(move || {
+        link.send_message(MsgReduction::AnimationTick);
+    });
+    window().expect("no window").request_animation_frame(cb.unchecked_ref()).expect("Could not request animation frame");
+}
 
-            //Attach shaders
-            let shader_program = gl.create_program().unwrap();
-            gl.attach_shader(&shader_program, &vert_shader);
-            gl.attach_shader(&shader_program, &frag_shader);
-            gl.link_program(&shader_program);
-            gl.use_program(Some(&shader_program));
 
-            //Size of a float in bytes
-            let sizeof_float = 4;
 
-            //Attach the position vector as an attribute for the GL context.
-            let a_position = gl.get_attrib_location(&shader_program, "a_position") as u32;
-            //log::debug!("a_position {}",a_position);
-            gl.enable_vertex_attrib_array(a_position);
-            gl.vertex_attrib_pointer_with_i32(a_position, 3, GL::FLOAT, false, sizeof_float*6, 0);  
+////////////////////////////////////////////////////////////
+/// Compute the centroid of a set of points, if any
+fn get_centroid(reduction_data: &ReductionViewData, list_points: &Vec<usize>) -> Option<(f32,f32)> {
+    let mut sum_x = 0.0;
+    let mut sum_y = 0.0;
+    let mut num_points = 0;
+    for i in list_points {
+        if *i < reduction_data.num_point {
+            sum_x += reduction_data.data[i*2+0];
+            sum_y += reduction_data.data[i*2+1];
+            num_points += 1;
+        }
+    }
+    if num_points > 0 {
+        Some((sum_x/(num_points as f32), sum_y/(num_points as f32)))
+    } else {
+        None
+    }
+}
 
-            //Attach color vector as an attribute
-            let a_color = gl.get_attrib_location(&shader_program, "a_color") as u32;
-            //log::debug!("a_color {}",a_color);
-            gl.enable_vertex_attrib_array(a_color);
-            gl.vertex_attrib_pointer_with_i32(a_color, 3, GL::FLOAT, false, sizeof_float*6, sizeof_float*3);   //index of out range   ... not big enough for the draw call
 
-            //Attach camera attributes
-            let u_camera_x = gl.get_uniform_location(&shader_program, "u_camera_x");
-            let u_camera_y = gl.get_uniform_location(&shader_program, "u_camera_y");
-            let u_camera_zoom_x = gl.get_uniform_location(&shader_program, "u_camera_zoom_x");
-            let u_camera_zoom_y = gl.get_uniform_location(&shader_program, "u_camera_zoom_y");
-            gl.uniform1f(u_camera_x.as_ref(), self.camera.x as f32);
-            gl.uniform1f(u_camera_y.as_ref(), self.camera.y as f32);
-            gl.uniform1f(u_camera_zoom_x.as_ref(), self.camera.zoom_x as f32);
-            gl.uniform1f(u_camera_zoom_y.as_ref(), self.camera.zoom_y as f32);
 
-            //log::debug!("canvas {} {}   {:?}", canvas.width(), canvas.height(), self.camera);
+////////////////////////////////////////////////////////////
+/// Render a horizontal bar chart of feature values, with bar length relative to the highest value
+fn make_gene_barchart(genes: &Vec<(String, f32)>) -> Html {
+    let row_height = 14.0;
+    let label_width = 60.0;
+    let bar_width = 100.0;
 
-            let u_display_w = gl.get_uniform_location(&shader_program, "u_display_w");
-            let u_display_h = gl.get_uniform_location(&shader_program, "u_display_h");
-            gl.uniform1f(u_display_w.as_ref(), canvas.width() as f32);
-            gl.uniform1f(u_display_h.as_ref(), canvas.height() as f32);
+    let max_val = genes.iter().map(|(_name, v)| *v).fold(0.0, f32::max);
 
-            // clear canvas
-            gl.clear_color(1.0, 1.0, 1.0, 1.0);
-            gl.clear(GL::COLOR_BUFFER_BIT);
-            
-            // to make round points, need to draw square https://stackoverflow.com/questions/7237086/opengl-es-2-0-equivalent-for-es-1-0-circles-using-gl-point-smooth
-            gl.draw_arrays(GL::POINTS, 0, num_points as i32);
+    let list_bars = genes.iter().enumerate().map(|(i, (name, v))| {
+        let y = (i as f32)*row_height;
+        let w = if max_val > 0.0 { bar_width*v/max_val } else { 0.0 };
+        html! {
+            <g>
+                <text x="0" y={(y + row_height - 3.0).to_string()} style="font-size: 10px;">{name}</text>
+                <rect x={label_width.to_string()} y={(y + 2.0).to_string()} width={w.to_string()} height={(row_height - 4.0).to_string()} fill="#0099FF"></rect>
+            </g>
         }
+    }).collect::<Html>();
 
+    html! {
+        <svg width={(label_width + bar_width).to_string()} height={(row_height*(genes.len() as f32)).to_string()}>
+            { list_bars }
+        </svg>
     }
 }
 
 
 
+////////////////////////////////////////////////////////////
+/// Get the range of values, if coloring by numeric data
+pub fn get_numeric_coloring_range(color_reduction_by: &ReductionColoringWithData) -> Option<(f32,f32)> {
+    if let ReductionColoringWithData::ByMeta(_name, AsyncData::Loaded(color_data)) = color_reduction_by {
+        match color_data.as_ref() {
+            CountFileMetaColumnData::Numeric(vec_data) => Some(make_safe_minmax(vec_data)),
+            CountFileMetaColumnData::SparseNumeric(_vec_index, vec_data) => Some(make_safe_minmax(vec_data)),
+            CountFileMetaColumnData::Categorical(_, _) => None,
+        }
+    } else {
+        None
+    }
+}
+
 
 
 ////////////////////////////////////////////////////////////
-/// Convert from vector to HTML color code
-pub fn rgbvec2string(c: Vec3) -> String {
-    let red=(c.0*255.0) as u8;
-    let green=(c.1*255.0) as u8;
-    let blue=(c.2*255.0) as u8;
-    format!("#{:02X}{:02X}{:02X}", red, green, blue)
+/// A cell recently added to the reduction, flying in to its place
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PointArrival {
+    born_at: f64,                    // time of arrival, from js_sys::Date::now()
+    initial_screen_pos: (f32,f32),   // camera position it flies in from, in [-1,1]
+}
+
+
+////////////////////////////////////////////////////////////
+/// Programs and vertex buffers for order-independent transparency and for the layers drawn on top of the cells.
+/// The overlay buffer is filled again for each pass
+struct OverlayGl {
+    gl: GL,                           // context these belong to
+    accum_program: WebGlProgram,      // sums the colors of transparent points
+    composite_program: WebGlProgram,  // averages the sums onto the canvas
+    quad_buffer: WebGlBuffer,         // covers the canvas, for compositing
+    spline_program: WebGlProgram,     // smooth curves
+    point_program: WebGlProgram,      // lines, dots and rings, with a single color
+    buffer: WebGlBuffer,
+}
+impl OverlayGl {
+
+    ////////////////////////////////////////////////////////////
+    /// Compile the programs for a GL context
+    fn new(gl: &GL) -> OverlayGl {
+        let quad: Vec<f32> = vec![
+            -1.0, -1.0,
+             1.0, -1.0,
+            -1.0,  1.0,
+            -1.0,  1.0,
+             1.0, -1.0,
+             1.0,  1.0,
+        ];
+        let quad_buffer = gl.create_buffer().unwrap();
+        let js_quad = js_sys::Float32Array::from(quad.as_slice());
+        gl.bind_buffer(GL::ARRAY_BUFFER, Some(&quad_buffer));
+        gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &js_quad, GL::STATIC_DRAW);
+
+        OverlayGl {
+            gl: gl.clone(),
+            accum_program: compile_shader_program(gl, include_str!("./umap.vert"), include_str!("./points_accum.frag")),
+            composite_program: compile_shader_program(gl, include_str!("./oit_composite.vert"), include_str!("./oit_composite.frag")),
+            quad_buffer: quad_buffer,
+            spline_program: compile_shader_program(gl, include_str!("./spline.vert"), include_str!("./spline.frag")),
+            point_program: compile_shader_program(gl, include_str!("./velocity.vert"), include_str!("./velocity.frag")),
+            buffer: gl.create_buffer().unwrap(),
+        }
+    }
+}
+
+
+////////////////////////////////////////////////////////////
+/// Random position on the edge of the view, in camera coordinates
+fn random_edge_position() -> (f32,f32) {
+    let p = js_sys::Math::random()*4.0;
+    let f = (p.fract()*2.0 - 1.0) as f32;
+    match p as usize {
+        0 => (f, -1.0),
+        1 => (1.0, f),
+        2 => (f, 1.0),
+        _ => (-1.0, f),
+    }
+}
+
+
+////////////////////////////////////////////////////////////
+/// Canvas pixels per CSS pixel to render at, given the properties
+fn get_pixel_ratio(props: &Props) -> f32 {
+    if props.print_layout { PRINT_DPI/CSS_DPI } else { props.pixel_ratio }
 }
 
 
+////////////////////////////////////////////////////////////
+/// Canvas pixels per CSS pixel of the display, e.g. 2 on most high resolution screens
+pub fn device_pixel_ratio() -> f32 {
+    window().map(|w| w.device_pixel_ratio() as f32).filter(|r| *r > 0.0).unwrap_or(1.0)
+}
+
 
 ////////////////////////////////////////////////////////////
 /// Get current camera position from a mouse event
-fn mouseevent_get_cx(e: &MouseEvent) -> (f32,f32) {
+pub fn mouseevent_get_cx(e: &MouseEvent) -> (f32,f32) {
     let target: Option<EventTarget> = e.target();
     let canvas: HtmlCanvasElement = target.and_then(|t| t.dyn_into::<HtmlCanvasElement>().ok()).expect("wrong type");
 
@@ -866,17 +4292,470 @@ pub fn parse_palette(csv_colors:&str) -> Vec<(f32,f32,f32)> {
 
 
 ////////////////////////////////////////////////////////////
-/// Get palette suitable for the given categories
-pub fn get_palette_for_categories(_num_cats: usize) -> Vec<Color3f> {
+/// Get palette suitable for the given categories. If there are more categories
+/// than colors in the static palette, generate one instead to avoid repeated colors
+pub fn get_palette_for_categories(num_cats: usize) -> Vec<Color3f> {
 //    let palette = self.color_dict.get("default").unwrap();
     let pal = parse_palette(include_str!("./palette.csv"));
-    pal
+    if num_cats > pal.len() {
+        generate_palette(num_cats)
+    } else {
+        pal
+    }
+}
+
+
+////////////////////////////////////////////////////////////
+/// Draw a color swatch for each category, next to a bar proportional to the number of cells.
+/// If given, the violin of each category is drawn between the swatch and the bar
+fn draw_category_counts(canvas: &HtmlCanvasElement, palette: &Vec<Color3f>, category_counts: &Vec<usize>, violins: Option<&Vec<Vec<f32>>>) {
+    let row_h = 14.0;
+    let swatch_w = 12.0;
+    let max_bar_w = 100.0;
+    let violin_w = if violins.is_some() { VIOLIN_WIDTH } else { 0.0 };
+    canvas.set_width((160.0 + violin_w) as u32);
+    canvas.set_height((category_counts.len() as f64 * row_h) as u32);
+
+    let context = canvas
+        .get_context("2d")
+        .unwrap()
+        .unwrap()
+        .dyn_into::<CanvasRenderingContext2d>()
+        .unwrap();
+    context.clear_rect(0.0, 0.0, canvas.width() as f64, canvas.height() as f64);
+    context.set_font("10px sans-serif");
+    context.set_text_baseline("middle");
+
+    let max_count = category_counts.iter().copied().max().unwrap_or(0).max(1) as f64;
+    for (i, count) in category_counts.iter().enumerate() {
+        let y = i as f64 * row_h;
+        let col = palette[i % palette.len()];
+        let col = format!("rgb({}, {}, {})", col.0*255.0, col.1*255.0, col.2*255.0);
+
+        context.set_fill_style_str(&col);
+        context.fill_rect(0.0, y + 1.0, swatch_w, row_h - 2.0);
+
+        //Violin: density along x, mirrored around the middle of the row. Scaled to fill the row
+        if let Some(violin) = violins.and_then(|violins| violins.get(i)) {
+            let max_density = violin.iter().cloned().fold(0.0, f32::max);
+            if max_density > 0.0 {
+                let x0 = swatch_w + 2.0;
+                let mid = y + row_h/2.0;
+                let step = (violin_w - 4.0)/(violin.len().max(2) - 1) as f64;
+                let half_h = |d: f32| (d/max_density) as f64*(row_h/2.0 - 1.0);
+                context.set_stroke_style_str(&col);
+                context.set_line_width(1.0);
+                context.begin_path();
+                for (k, d) in violin.iter().enumerate() {
+                    context.line_to(x0 + k as f64*step, mid - half_h(*d));
+                }
+                for (k, d) in violin.iter().enumerate().rev() {
+                    context.line_to(x0 + k as f64*step, mid + half_h(*d));
+                }
+                context.close_path();
+                context.stroke();
+            }
+        }
+
+        let bar_w = max_bar_w * (*count as f64) / max_count;
+        context.set_fill_style_str("#BBBBBB");
+        context.fill_rect(swatch_w + 2.0 + violin_w, y + 3.0, bar_w, row_h - 6.0);
+
+        context.set_fill_style_str("black");
+        let _ = context.fill_text(&count.to_string(), swatch_w + 4.0 + violin_w + bar_w, y + row_h/2.0);
+    }
+}
+
+
+////////////////////////////////////////////////////////////
+/// Fade the color of cells that are not in any of the selected samples
+fn fade_unselected_samples(vec_vertex: &mut Vec<f32>, vec_vertex_size: usize, sample_id: &Vec<u32>, selected_samples: &HashSet<u32>) {
+    let fade = 0.8;
+    for (v, id) in vec_vertex.chunks_exact_mut(vec_vertex_size).zip(sample_id.iter()) {
+        if !selected_samples.contains(id) {
+            for c in v[3..6].iter_mut() {
+                *c = *c*(1.0 - fade) + 0.9*fade;
+            }
+        }
+    }
+}
+
+
+////////////////////////////////////////////////////////////
+/// Reorder vertices such that those outside the focus region come first.
+/// Returns the vertices and the number of points outside the focus region
+fn split_focus_context(vec_vertex: &Vec<f32>, vec_vertex_size: usize, camera: &Camera2D) -> (Vec<f32>, usize) {
+    let mut vec_context = Vec::with_capacity(vec_vertex.len());
+    let mut vec_focus = Vec::with_capacity(vec_vertex.len());
+    for v in vec_vertex.chunks_exact(vec_vertex_size) {
+        let (cx, cy) = camera.world2cam(v[0], v[1]);
+        if cx.abs() < FOCUS_REGION_SIZE && cy.abs() < FOCUS_REGION_SIZE {
+            vec_focus.extend_from_slice(v);
+        } else {
+            vec_context.extend_from_slice(v);
+        }
+    }
+    let num_context = vec_context.len() / vec_vertex_size;
+    vec_context.extend(vec_focus);
+    (vec_context, num_context)
+}
+
+
+////////////////////////////////////////////////////////////
+/// Color cells by whether their value is above a threshold. Cells missing in sparse data
+/// count as 0. Returns false if the data is not numeric
+fn threshold_point_colors(vec_vertex: &mut Vec<f32>, vec_vertex_size: usize, color_data: &CountFileMetaColumnData, threshold: f32, color_above: Color3f, color_below: Color3f) -> bool {
+    let num_points = vec_vertex.len() / vec_vertex_size;
+    let mut set_color = |i: usize, v: f32| {
+        if i < num_points {
+            let col = if v > threshold { color_above } else { color_below };
+            let base = vec_vertex_size*i;
+            vec_vertex[base + 3] = col.0;
+            vec_vertex[base + 4] = col.1;
+            vec_vertex[base + 5] = col.2;
+        }
+    };
+    match color_data {
+        CountFileMetaColumnData::Categorical(_, _) => false,
+        CountFileMetaColumnData::Numeric(vec_data) => {
+            for (i, v) in vec_data.iter().enumerate() {
+                set_color(i, *v);
+            }
+            true
+        },
+        CountFileMetaColumnData::SparseNumeric(vec_index, vec_data) => {
+            for i in 0..num_points {
+                set_color(i, 0.0);
+            }
+            for (i, v) in vec_index.iter().zip(vec_data.iter()) {
+                set_color(*i as usize, *v);
+            }
+            true
+        },
+    }
+}
+
+
+////////////////////////////////////////////////////////////
+/// Cells with a numeric value at least the given minimum. Cells without a value in a sparse column count
+/// as 0. All cells are kept for categorical data
+fn cells_above_min_value(color_data: &CountFileMetaColumnData, num_points: usize, min_value: f32) -> Vec<usize> {
+    match color_data {
+        CountFileMetaColumnData::Categorical(_, _) => (0..num_points).collect(),
+        CountFileMetaColumnData::Numeric(vec_data) => {
+            (0..num_points).filter(|i| vec_data.get(*i).map(|v| *v >= min_value).unwrap_or(false)).collect()
+        },
+        CountFileMetaColumnData::SparseNumeric(vec_index, vec_data) => {
+            let mut values = vec![0.0; num_points];
+            for (i, v) in vec_index.iter().zip(vec_data.iter()) {
+                if let Some(value) = values.get_mut(*i as usize) {
+                    *value = *v;
+                }
+            }
+            (0..num_points).filter(|i| values[*i] >= min_value).collect()
+        },
+    }
+}
+
+
+////////////////////////////////////////////////////////////
+/// Vertices with positions quantized to 16 bits. Colors are kept as floats, in a separate array
+pub struct CompactVertices {
+    pub positions: Vec<i16>,   // x,y of each point, in [-32767,32767]
+    pub colors: Vec<f32>,      // r,g,b, cell index and fading of each point
+    pub scale: (f32, f32),     // world ≈ position/32767 * scale + offset
+    pub offset: (f32, f32),
+}
+
+
+////////////////////////////////////////////////////////////
+/// Quantize x,y of each vertex over the range of all vertices. This takes 4 bytes per point instead of 12 for
+/// x,y,z as floats; the z only matters for transparency and clusters. The precision is 1/65535 of the range,
+/// better than half floats would give
+pub fn quantize_positions(vec_vertex: &Vec<f32>, vec_vertex_size: usize) -> CompactVertices {
+    let num_points = vec_vertex.len() / vec_vertex_size;
+    let (mut min_x, mut max_x, mut min_y, mut max_y) = (f32::MAX, f32::MIN, f32::MAX, f32::MIN);
+    for v in vec_vertex.chunks_exact(vec_vertex_size) {
+        min_x = min_x.min(v[0]);
+        max_x = max_x.max(v[0]);
+        min_y = min_y.min(v[1]);
+        max_y = max_y.max(v[1]);
+    }
+    if num_points == 0 {
+        (min_x, max_x, min_y, max_y) = (0.0, 0.0, 0.0, 0.0);
+    }
+
+    let offset = ((min_x + max_x)/2.0, (min_y + max_y)/2.0);
+    let scale = ((max_x - min_x)/2.0, (max_y - min_y)/2.0);
+    let quantize = |v: f32, offset: f32, scale: f32| {
+        if scale > 0.0 { ((v - offset)/scale*32767.0).round().clamp(-32767.0, 32767.0) as i16 } else { 0 }
+    };
+
+    let mut positions = Vec::with_capacity(num_points*2);
+    let mut colors = Vec::with_capacity(num_points*5);
+    for v in vec_vertex.chunks_exact(vec_vertex_size) {
+        positions.push(quantize(v[0], offset.0, scale.0));
+        positions.push(quantize(v[1], offset.1, scale.1));
+        colors.extend_from_slice(&v[3..8]);
+    }
+    CompactVertices {
+        positions: positions,
+        colors: colors,
+        scale: scale,
+        offset: offset,
+    }
+}
+
+
+////////////////////////////////////////////////////////////
+/// Smallest rectangle containing all cells of a category. None if no cell has it
+fn category_bounding_box(reduction: &ReductionViewData, vec_data: &Vec<u32>, category_index: usize) -> Option<Rectangle2D> {
+    let mut rect: Option<Rectangle2D> = None;
+    for i in 0..reduction.num_point.min(vec_data.len()) {
+        if vec_data[i] as usize != category_index {
+            continue;
+        }
+        let (px, py) = (reduction.data[i*2+0], reduction.data[i*2+1]);
+        rect = Some(match rect {
+            Some(r) => Rectangle2D { x1: r.x1.min(px), x2: r.x2.max(px), y1: r.y1.min(py), y2: r.y2.max(py) },
+            None => Rectangle2D { x1: px, x2: px, y1: py, y2: py },
+        });
+    }
+    rect
+}
+
+
+////////////////////////////////////////////////////////////
+/// Nearest cell within BROWSE_MAX_ANGLE of a direction from a cell, skipping cells already visited
+fn find_cell_in_direction(reduction: &ReductionViewData, index: &ClosestPointIndex2D, cell: usize, dir: (f32, f32), visited: &HashSet<usize>) -> Option<usize> {
+    if cell >= reduction.num_point {
+        return None;
+    }
+    let (x, y) = (reduction.data[cell*2+0], reduction.data[cell*2+1]);
+    let min_cos = BROWSE_MAX_ANGLE.cos();
+    let in_direction = |j: &usize| {
+        let vx = reduction.data[*j*2+0] - x;
+        let vy = reduction.data[*j*2+1] - y;
+        let len = (vx*vx + vy*vy).sqrt();
+        *j != cell && !visited.contains(j) && len > 0.0 && (vx*dir.0 + vy*dir.1)/len >= min_cos
+    };
+
+    //Candidates come closest first
+    for k in [BROWSE_CANDIDATES, BROWSE_CANDIDATES*BROWSE_CANDIDATES_WIDE] {
+        if let Some(j) = index.get_k_nearest(x, y, k).into_iter().find(in_direction) {
+            return Some(j);
+        }
+    }
+    None
+}
+
+
+////////////////////////////////////////////////////////////
+/// Name of a column, as shown to the user
+pub fn column_label(name: &PerCellDataSource) -> String {
+    match name {
+        PerCellDataSource::Metadata(column_name) => column_name.clone(),
+        PerCellDataSource::Counts(_counts_name, feature_name) => feature_name.clone(),
+        PerCellDataSource::ClusterProportionScore { base_column, category_index, k } => format!("{} #{} among {} neighbors", base_column, category_index, k),
+        PerCellDataSource::ExpressionRatio { gene_a, gene_b } => format!("log(({}+1)/({}+1))", gene_a, gene_b),
+    }
 }
 
 
 ////////////////////////////////////////////////////////////
 /// Get the style of a tool button
-fn get_tool_style(pos: usize, selected: bool) -> String {
+fn get_tool_style(pos_x: usize, pos_y: usize, selected: bool) -> String {
     let c=if selected {"#0099FF"} else {"lightgray"};
-    format!("position: absolute; left:{}px; top:10px; display: flex; border-radius: 3px; border: 2px solid gray; padding: 5px; background-color: {};", pos, c)
-}
\ No newline at end of file
+    format!("position: absolute; left:{}px; top:{}px; display: flex; border-radius: 3px; border: 2px solid gray; padding: 5px; background-color: {};", pos_x, pos_y, c)
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_response(x: Vec<f32>, y: Vec<f32>) -> ReductionResponse {
+        ReductionResponse {
+            x,
+            y,
+            version: 0,
+        }
+    }
+
+    #[test]
+    fn interleaves_coordinates() {
+        let red = convert_from_response_to_reduction_data(make_response(
+            vec![1.0, 2.0, 3.0],
+            vec![4.0, 5.0, 6.0],
+        )).unwrap();
+        assert_eq!(red.num_point, 3);
+        assert_eq!(red.data, vec![1.0, 4.0, 2.0, 5.0, 3.0, 6.0]);
+    }
+
+    #[test]
+    fn range_of_negative_values() {
+        let red = convert_from_response_to_reduction_data(make_response(
+            vec![-1.0, -5.0, -3.0],
+            vec![-10.0, -2.0, -7.0],
+        )).unwrap();
+        assert_eq!(red.min_x, -5.0);
+        assert_eq!(red.max_x, -1.0);
+        assert_eq!(red.min_y, -10.0);
+        assert_eq!(red.max_y, -2.0);
+    }
+
+    #[test]
+    fn mismatched_lengths_rejected() {
+        let res = convert_from_response_to_reduction_data(make_response(
+            vec![1.0, 2.0, 3.0],
+            vec![4.0, 5.0],
+        ));
+        assert_eq!(res.unwrap_err(), ReductionError::DimensionMismatch { num_x: 3, num_y: 2 });
+    }
+
+    #[test]
+    fn empty_reduction() {
+        let red = convert_from_response_to_reduction_data(make_response(
+            vec![],
+            vec![],
+        )).unwrap();
+        assert_eq!(red.num_point, 0);
+        assert!(red.data.is_empty());
+    }
+
+    #[test]
+    fn msgpack_same_as_cbor() {
+        let resp = make_response(vec![1.0, -2.5, 3.0], vec![4.0, 5.0, -6.25]);
+        let bytes = rmp_serde::to_vec(&resp).unwrap();
+        let red_msgpack = convert_from_msgpack_to_reduction_data(&bytes).unwrap();
+        let red_cbor = convert_from_response_to_reduction_data(resp).unwrap();
+        assert_eq!(red_msgpack.data, red_cbor.data);
+        assert_eq!(red_msgpack.min_x, red_cbor.min_x);
+        assert_eq!(red_msgpack.max_y, red_cbor.max_y);
+    }
+
+    #[test]
+    fn invalid_msgpack_rejected() {
+        let res = convert_from_msgpack_to_reduction_data(&[0xc1, 0x00]);
+        assert!(matches!(res, Err(ReductionError::Decode(_))));
+    }
+
+    #[test]
+    fn ratio_of_feature_columns() {
+        //Cells 0..3 are rows; cell 1 has no counts
+        let cnt = my_web_app::countfile_struct::CountFileMat {
+            list_feature_names: vec!["A".into(), "B".into()],
+            list_indptr: vec![0, 2, 2, 3],
+        };
+        let indices = [0, 1, 1];
+        let data = [4.0, 1.0, 3.0];
+
+        //Read one entry at a time, as the server reads the matrix in chunks
+        let read_column = |col: u32| {
+            let (mut rows, mut values) = (Vec::new(), Vec::new());
+            for i in 0..indices.len() {
+                cnt.collect_column(i, &indices[i..i+1], &data[i..i+1], col, &mut rows, &mut values);
+            }
+            CountFileMetaColumnData::SparseNumeric(rows, values)
+        };
+        let (data_a, data_b) = (read_column(0), read_column(1));
+        assert!(matches!(&data_a, CountFileMetaColumnData::SparseNumeric(rows, _) if *rows == vec![0]));
+        assert!(matches!(&data_b, CountFileMetaColumnData::SparseNumeric(rows, _) if *rows == vec![0, 2]));
+
+        let ratio = crate::correlation::expression_log_ratio(&data_a, &data_b, 3).unwrap();
+        assert!(ratio[0] > 0.0);
+        assert_eq!(ratio[1], 0.0);
+        assert!(ratio[2] < 0.0);
+    }
+
+    #[test]
+    fn zoom_bounds_follow_feature() {
+        //Feature A is only counted in cell 1, and feature B in cells 0 and 2
+        let cnt = my_web_app::countfile_struct::CountFileMat {
+            list_feature_names: vec!["A".into(), "B".into()],
+            list_indptr: vec![0, 1, 2, 3],
+        };
+        let read_column = |col: u32| {
+            let (mut rows, mut values) = (Vec::new(), Vec::new());
+            cnt.collect_column(0, &[1, 0, 1], &[2.0, 5.0, 3.0], col, &mut rows, &mut values);
+            dense_numeric_values(&CountFileMetaColumnData::SparseNumeric(rows, values), 3).unwrap()
+        };
+        let reduction = convert_from_response_to_reduction_data(make_response(vec![0.0, 10.0, 20.0], vec![0.0, 10.0, 20.0])).unwrap();
+
+        let (rect_a, num_a) = expressor_bounds(&reduction, &read_column(0), 1.0).unwrap();
+        assert_eq!(num_a, 1);
+        assert_eq!((rect_a.x1, rect_a.x2), (10.0, 10.0));
+
+        let (rect_b, num_b) = expressor_bounds(&reduction, &read_column(1), 1.0).unwrap();
+        assert_eq!(num_b, 2);
+        assert_eq!((rect_b.x1, rect_b.x2), (-2.0, 22.0));
+
+        assert!(expressor_bounds(&reduction, &read_column(0), 10.0).is_none());
+    }
+
+    ////////////////////////////////////////////////////////////
+    /// Current time in milliseconds
+    fn now_ms() -> f64 {
+        #[cfg(target_arch = "wasm32")]
+        {
+            js_sys::Date::now()
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs_f64() * 1000.0
+        }
+    }
+
+    ////////////////////////////////////////////////////////////
+    /// Compare the time of interleaving 10M points using iterators, as done, and by filling a preallocated
+    /// vector by index, as done before. Both should take about the same time.
+    /// Run using: cargo test --release -- --ignored --nocapture bench_interleave_coordinates
+    #[test]
+    #[ignore]
+    fn bench_interleave_coordinates() {
+        let n = 10_000_000;
+        let x: Vec<f32> = (0..n).map(|i| (i as f32) * 0.001).collect();
+        let y: Vec<f32> = (0..n).map(|i| -(i as f32) * 0.002).collect();
+
+        let t0 = now_ms();
+        let data_iter = interleave_coordinates(&x, &y);
+        let t1 = now_ms();
+        let mut data_index = vec![0.0f32; n*2];
+        for i in 0..n {
+            data_index[i*2] = x[i];
+            data_index[i*2+1] = y[i];
+        }
+        let t2 = now_ms();
+
+        println!("iterators: {:.1} ms", t1 - t0);
+        println!("indexed:   {:.1} ms", t2 - t1);
+        assert_eq!(data_iter, data_index);
+    }
+
+    ////////////////////////////////////////////////////////////
+    /// Compare size and parse time of MessagePack vs CBOR for 1M points.
+    /// Run in the browser using: wasm-pack test --headless --firefox app
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    #[ignore]
+    fn bench_msgpack_vs_cbor() {
+        let n = 1_000_000;
+        let resp = make_response(
+            (0..n).map(|i| (i as f32) * 0.001).collect(),
+            (0..n).map(|i| -(i as f32) * 0.002).collect(),
+        );
+        let bytes_msgpack = rmp_serde::to_vec(&resp).unwrap();
+        let bytes_cbor = serde_cbor::to_vec(&resp).unwrap();
+
+        let t0 = now_ms();
+        let red = convert_from_msgpack_to_reduction_data(&bytes_msgpack).unwrap();
+        let t1 = now_ms();
+        let resp_cbor: ReductionResponse = serde_cbor::from_slice(&bytes_cbor).unwrap();
+        let red_cbor = convert_from_response_to_reduction_data(resp_cbor).unwrap();
+        let t2 = now_ms();
+
+        println!("msgpack: {} bytes, {:.1} ms", bytes_msgpack.len(), t1 - t0);
+        println!("cbor:    {} bytes, {:.1} ms", bytes_cbor.len(), t2 - t1);
+        assert_eq!(red.num_point, red_cbor.num_point);
+    }
+}
@@ -1,9 +1,45 @@
-use crate::{appstate::{AsyncData, PerCellDataSource}, component_reduction_main::{ReductionColoring, ReductionColoringWithData, ReductionView}, core_model::*};
+use crate::{appstate::{AsyncData, PerCellDataSource}, component_reduction_main::{column_label, get_numeric_coloring_range, CurrentTool, GroupByMode, ReductionColoring, ReductionColoringWithData, ReductionView}, core_model::*};
 
+use std::collections::{HashMap, HashSet};
+use my_web_app::CountFileMetaColumnData;
 use yew::{prelude::*};
 
+use crate::component_cell_compare::{CellCompareView, CellComparisonRow};
+use crate::component_cell_metadata::{CellMetadataRow, CellMetadataView};
+use crate::component_cluster_purity::ClusterPurityView;
+use crate::purity::compute_cluster_purity;
+use crate::component_sankey::SankeyView;
+use crate::component_pseudotime_chart::PseudotimeChartView;
+use crate::component_parallel_coords::ParallelCoordsView;
+use crate::component_cellstate_heatmap::CellStateHeatmapView;
+use crate::component_cell_table::{CellTableRow, CellTableView};
+use crate::component_correlation_matrix::CorrelationMatrixView;
+use crate::selection_context::SelectionContext;
+use crate::parallel_coords::{build_parallel_axis, cell_line_colors, MAX_PARALLEL_COORDS_CELLS};
+use crate::sankey::compute_transitions;
+use crate::component_collab::CollabPanel;
+use crate::component_markers::MarkerView;
+use crate::component_reduction_correlation::ReductionCorrelationView;
+use crate::component_cross_projection::CrossProjectionView;
+use crate::component_batch_correction::BatchCorrectionView;
+use crate::component_reduction_params::ReductionParamsView;
+use crate::camera::Camera2D;
+use crate::camera::ZoomLevel;
 use crate::component_reduction_left::MetadataView;
 use crate::component_reduction_right::FeatureView;
+use crate::component_grn::GrnView;
+use crate::legend::LegendPosition;
+use crate::local_umap::DEFAULT_LOCAL_UMAP_NEIGHBORS;
+use my_web_app::local_umap::MAX_LOCAL_UMAP_CELLS;
+
+////////////////////////////////////////////////////////////
+/// Beyond this many cells, only a random subset is drawn unless the user asks for all
+const MAX_DISPLAY_POINTS: usize = 200_000;
+
+////////////////////////////////////////////////////////////
+/// Rows of the table of selected cells; values of more cells are not looked up
+const MAX_CELL_TABLE_ROWS: usize = 200;
+
 
 impl Model {
 
@@ -16,7 +52,142 @@ impl Model {
                 let dat=self.current_data.lock().unwrap().get_metadata(&name);
                 ReductionColoringWithData::ByMeta(name.clone(), dat)
             },
+            ReductionColoring::Custom => ReductionColoringWithData::Custom(self.custom_colors.clone()),
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Get the color range covering the numeric coloring of all reduction panels,
+    /// if the color scale should be the same in all of them
+    pub fn get_global_color_range(&self, list_coloring: &Vec<ReductionColoringWithData>) -> Option<(f32,f32)> {
+        if !self.sync_color_scale {
+            return None;
+        }
+        list_coloring.iter()
+            .filter_map(|c| get_numeric_coloring_range(c))
+            .reduce(|(min1, max1), (min2, max2)| (min1.min(min2), max1.max(max2)))
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Get the value of each loaded column for one cell. Columns still loading are left out
+    pub fn get_cell_metadata(&self, cell: usize) -> Vec<CellMetadataRow> {
+        let current_data = self.current_data.lock().unwrap();
+        let mut list_rows: Vec<CellMetadataRow> = current_data.metadatas.iter().filter_map(|(name, data)| {
+            let data = if let AsyncData::Loaded(data) = data {
+                data
+            } else {
+                return None;
+            };
+            let (value, is_sparse_zero) = match data.as_ref() {
+                CountFileMetaColumnData::Categorical(list_data, list_cats) => {
+                    let cat = list_data.get(cell).and_then(|c| list_cats.get(*c as usize))?;
+                    (cat.clone(), false)
+                },
+                CountFileMetaColumnData::Numeric(list_data) => {
+                    (format!("{}", list_data.get(cell)?), false)
+                },
+                CountFileMetaColumnData::SparseNumeric(list_indices, list_data) => {
+                    match list_indices.iter().position(|i| *i as usize == cell) {
+                        Some(p) => (format!("{}", list_data[p]), false),
+                        None => ("0".to_string(), true),
+                    }
+                },
+            };
+            Some(CellMetadataRow {
+                name: column_label(name),
+                value: value,
+                is_sparse_zero: is_sparse_zero,
+            })
+        }).collect();
+        list_rows.sort_by(|a, b| a.name.cmp(&b.name));
+        list_rows
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Names of the loaded columns, and their values for the first selected cells
+    pub fn get_cell_table_rows(&self, cells: &Vec<usize>) -> (Vec<String>, Vec<CellTableRow>) {
+        let mut columns: Vec<String> = Vec::new();
+        let rows = cells.iter().take(MAX_CELL_TABLE_ROWS).map(|cell| {
+            let metadata = self.get_cell_metadata(*cell);
+            for row in &metadata {
+                if !columns.contains(&row.name) {
+                    columns.push(row.name.clone());
+                }
+            }
+            let name = match &self.cell_ids {
+                AsyncData::Loaded(cell_ids) if *cell < cell_ids.len() => cell_ids[*cell].clone(),
+                _ => format!("Cell {}", cell),
+            };
+            (*cell, name, metadata)
+        }).collect::<Vec<_>>();
+        columns.sort();
+
+        let rows = rows.into_iter().map(|(cell, name, metadata)| {
+            CellTableRow {
+                cell: cell,
+                name: name,
+                values: columns.iter().map(|column| {
+                    metadata.iter().find(|row| &row.name == column).map(|row| row.value.clone()).unwrap_or_default()
+                }).collect(),
+            }
+        }).collect();
+        (columns, rows)
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Get the values of all loaded numeric metadata columns for two cells.
+    /// Also returns the number of columns still loading
+    pub fn get_cell_comparison(&self, cell_a: usize, cell_b: usize) -> (Vec<CellComparisonRow>, usize) {
+        let current_data = self.current_data.lock().unwrap();
+        let mut list_rows = Vec::new();
+        let mut num_loading = 0;
+        for (name, data) in current_data.metadatas.iter() {
+            let column_name = if let PerCellDataSource::Metadata(column_name) = name {
+                column_name
+            } else {
+                continue;
+            };
+            let data = match data {
+                AsyncData::Loaded(data) => data,
+                AsyncData::Loading => {
+                    num_loading += 1;
+                    continue;
+                },
+                AsyncData::NotLoaded | AsyncData::Error(_) => continue,
+            };
+
+            //Sparse columns are zero where not listed
+            let (value_a, value_b, list_values) = match data.as_ref() {
+                CountFileMetaColumnData::Categorical(_, _) => continue,
+                CountFileMetaColumnData::Numeric(list_data) => (
+                    list_data.get(cell_a).copied().unwrap_or(f32::NAN),
+                    list_data.get(cell_b).copied().unwrap_or(f32::NAN),
+                    list_data,
+                ),
+                CountFileMetaColumnData::SparseNumeric(list_indices, list_data) => {
+                    let get_value = |cell: usize| list_indices.iter().position(|i| *i as usize == cell).map(|p| list_data[p]).unwrap_or(0.0);
+                    (get_value(cell_a), get_value(cell_b), list_data)
+                },
+            };
+            if value_a.is_nan() || value_b.is_nan() {
+                continue;
+            }
+
+            let max_abs = list_values.iter().filter(|v| !v.is_nan()).fold(0.0f32, |m, v| m.max(v.abs()));
+            let norm = |v: f32| if max_abs > 0.0 { v / max_abs } else { 0.0 };
+            list_rows.push(CellComparisonRow {
+                name: column_name.clone(),
+                value_a,
+                value_b,
+                norm_a: norm(value_a),
+                norm_b: norm(value_b),
+            });
         }
+        (list_rows, num_loading)
     }
 
 
@@ -24,12 +195,35 @@ impl Model {
     /// x
     pub fn view_dimred_page(&self, ctx: &Context<Self>) -> Html {
 
-        //Callback: Hovering a certain cell
-        let on_cell_hovered = Callback::from(move |_name: Option<usize>| {
+        //Hovered and selected cells, shared with all views of the page. Views change them through the callbacks
+        let selection_context = SelectionContext {
+            hovered: self.hovered_cell,
+            selection: self.last_clicked_cells.clone(),
+            set_hovered: ctx.link().callback(Msg::HoverCell),
+            set_selection: ctx.link().callback(Msg::SelectCells),
+        };
+
+        //Callback: Hovering a group of cells
+        let on_cells_hovered = ctx.link().callback(move |cells: Vec<usize>| {
+            Msg::HoverCells(cells)
         });
 
-        //Callback: Clicked on a cell
-        let on_cell_clicked = Callback::from(move |_name: Vec<usize>| {
+        //Callback: Hovering a feature in the list of features
+        let on_hover_feature = ctx.link().callback(move |feature_name: Option<String>| {
+            Msg::HoverFeature(feature_name)
+        });
+
+        //Regulatory network of the hovered feature, next to the list of features
+        let grn_panel = match &self.hovered_feature {
+            Some(gene) => html! {
+                <GrnView gene={gene.clone()} network={self.grn_cache.get(gene).cloned()}/>
+            },
+            None => html! {""}
+        };
+
+        //Callback: Summary card of a group opened
+        let on_group_clicked = ctx.link().callback(move |cells: Vec<usize>| {
+            Msg::RequestClusterTopGenes(cells)
         });
 
         //Callback: coloring by something
@@ -37,6 +231,29 @@ impl Model {
             Msg::RequestSetColorByMeta(name)  // UmapColoring instead?
         });
 
+        //Callback: load the data of a metadata column
+        let on_loadmeta = ctx.link().callback(move |name: PerCellDataSource| {
+            Msg::RequestMetadata(name)
+        });
+
+        //Callback: select or deselect a sample
+        let on_toggle_sample = ctx.link().callback(move |sample: u32| {
+            Msg::ToggleSample(sample)
+        });
+
+        //Callback: search for cells by metadata value
+        let on_search_cells = ctx.link().callback(move |query: String| {
+            Msg::SearchCells(query)
+        });
+
+        //Callback: color cells using an imported table
+        let on_import_color_table = ctx.link().callback(move |csv: Vec<u8>| {
+            Msg::ImportColorTable(csv)
+        });
+
+        //Load state of all columns
+        let metadatas = self.current_data.lock().unwrap().metadatas.to_hashmap();
+
         //Get reduction
         let mut current_umap_data = AsyncData::NotLoaded;
         if let Some(current_reduction) = &self.current_reduction {
@@ -46,30 +263,723 @@ impl Model {
         //Get current coloring data
         let coloring_data = self.get_umap_coloring();
 
-        html! {
-            <div>
-                <div class="biscvi-dimred-maindiv"> ////////// if behind everything, could take full screen!! but buttons need space adjustment
-                    <ReductionView 
-                        on_cell_hovered={on_cell_hovered} 
-                        on_cell_clicked={on_cell_clicked} 
-                        reduction_data={current_umap_data} 
-                        color_reduction_by={coloring_data.clone()} 
-                        last_component_size={self.last_component_size.clone()}
-                        current_colorby={self.current_colorby.clone()}
+        //Color range shared by all panels. Only one panel for now
+        let global_color_range = self.get_global_color_range(&vec![coloring_data.clone()]);
+        let on_toggle_sync_color_scale = ctx.link().callback(move |_: ()| {
+            Msg::ToggleSyncColorScale
+        });
+
+        //Button to compare two cells, once two have been picked
+        let compare_button = if let [cell_a, cell_b] = self.last_clicked_cells[..] {
+            let cb_compare = ctx.link().callback(move |_e: MouseEvent| {
+                Msg::CompareSelectedCells(cell_a, cell_b)
+            });
+            html! {
+                <button onclick={cb_compare}>{ format!("Compare cells {} and {}", cell_a, cell_b) }</button>
+            }
+        } else if self.last_clicked_cells.len() > 2 {
+            //Button to compute a new UMAP of a selected region
+            let selection = self.last_clicked_cells.clone();
+            let num_cells = selection.len();
+            let cb_local_umap = ctx.link().callback(move |_e: MouseEvent| {
+                Msg::RunLocalUMAP(selection.clone(), DEFAULT_LOCAL_UMAP_NEIGHBORS)
+            });
+            let label = if self.local_umap_running {
+                "Re-embedding...".to_string()
+            } else if num_cells > MAX_LOCAL_UMAP_CELLS {
+                format!("Too many cells to re-embed (max {})", MAX_LOCAL_UMAP_CELLS)
+            } else {
+                format!("Re-embed {} cells", num_cells)
+            };
+            let disabled = self.local_umap_running || num_cells > MAX_LOCAL_UMAP_CELLS;
+            html! {
+                <button onclick={cb_local_umap} disabled={disabled}>{ label }</button>
+            }
+        } else {
+            html! {""}
+        };
+
+        //Button to show a trajectory through the selected cells, ordered by the numeric coloring
+        let trajectory_button = if self.trajectory.is_some() {
+            let cb_clear = ctx.link().callback(move |_e: MouseEvent| {
+                Msg::SetTrajectory(None)
+            });
+            html! {
+                <button onclick={cb_clear}>{"Hide trajectory"}</button>
+            }
+        } else if self.last_clicked_cells.len() > 2 {
+            let cb_show = ctx.link().callback(move |_e: MouseEvent| {
+                Msg::ShowTrajectoryOfSelection
+            });
+            html! {
+                <button onclick={cb_show}>{"Show trajectory"}</button>
+            }
+        } else {
+            html! {""}
+        };
+
+        //Button to find what sets the selected cells apart from the rest
+        let markers_button = if !self.last_clicked_cells.is_empty() {
+            let cb_find_markers = ctx.link().callback(move |_e: MouseEvent| {
+                Msg::FindMarkers
+            });
+            let disabled = matches!(self.markers, AsyncData::Loading);
+            html! {
+                <button onclick={cb_find_markers} disabled={disabled}>{"Find markers"}</button>
+            }
+        } else {
+            html! {""}
+        };
+
+        //Panel with the markers found
+        let markers_panel = if matches!(self.markers, AsyncData::NotLoaded) {
+            html! {""}
+        } else {
+            let on_close = ctx.link().callback(move |_: ()| {
+                Msg::CloseMarkers
+            });
+            html! {
+                <MarkerView
+                    markers={self.markers.clone()}
+                    num_a={self.markers_num_cells.0}
+                    num_b={self.markers_num_cells.1}
+                    on_close={on_close}
+                />
+            }
+        };
+
+        //Button to save cells and their annotations
+        let cb_export_csv = ctx.link().callback(move |_e: MouseEvent| {
+            Msg::ExportCsv
+        });
+        let export_button = html! {
+            <button onclick={cb_export_csv}>{"Export CSV"}</button>
+        };
+
+        //Callback: annotate a cell
+        let on_set_annotation = ctx.link().callback(move |(cell, text): (usize, String)| {
+            Msg::SetAnnotation(cell, text)
+        });
+
+        //Panel comparing two cells
+        let compare_panel = if let Some((cell_a, cell_b)) = self.compare_cells {
+            let (list_rows, num_loading) = self.get_cell_comparison(cell_a, cell_b);
+            let on_close = ctx.link().callback(move |_: ()| {
+                Msg::CloseCellComparison
+            });
+            html! {
+                <CellCompareView
+                    cell_a={cell_a}
+                    cell_b={cell_b}
+                    list_rows={list_rows}
+                    num_loading={num_loading}
+                    on_close={on_close}
+                />
+            }
+        } else {
+            html! {""}
+        };
+
+        //Table of the selected cells
+        let cell_table_panel = if self.show_cell_table {
+            let (columns, rows) = self.get_cell_table_rows(&self.last_clicked_cells);
+            let on_close = ctx.link().callback(move |_: ()| {
+                Msg::ToggleCellTable
+            });
+            let distribution_distance = self.distribution_distance.as_ref().map(|(column, num_a, num_b, distance)| {
+                format!("Wasserstein distance of {}, {} vs {} cells: {:.4}", column_label(column), num_a, num_b, distance)
+            });
+            html! {
+                <CellTableView
+                    columns={columns}
+                    rows={rows}
+                    distribution_distance={distribution_distance}
+                    on_close={on_close}
+                />
+            }
+        } else {
+            html! {""}
+        };
+
+        //Values of all loaded columns for the hovered cell. Otherwise the last clicked, so that the panel can be reached using the mouse
+        let table_cell = self.hovered_cell.or_else(|| if self.last_clicked_cells.len() <= 2 { self.last_clicked_cells.last().copied() } else { None });
+        let cell_metadata_panel = if let Some(cell) = table_cell {
+            let cell_name = match &self.cell_ids {
+                AsyncData::Loaded(cell_ids) if cell < cell_ids.len() => cell_ids[cell].clone(),
+                _ => format!("Cell {}", cell),
+            };
+            html! {
+                <CellMetadataView
+                    cell_name={cell_name}
+                    list_rows={self.get_cell_metadata(cell)}
+                />
+            }
+        } else {
+            html! {""}
+        };
+
+        //Panel comparing predicted clusters with a ground truth
+        let purity_panel = if let Some((predicted_col, gt_col)) = &self.purity_columns {
+            let purity = {
+                let current_data = self.current_data.lock().unwrap();
+                let predicted = current_data.get_metadata(&PerCellDataSource::Metadata(predicted_col.clone()));
+                let ground_truth = current_data.get_metadata(&PerCellDataSource::Metadata(gt_col.clone()));
+                if let (AsyncData::Loaded(predicted), AsyncData::Loaded(ground_truth)) = (predicted, ground_truth) {
+                    compute_cluster_purity(&predicted, &ground_truth)
+                } else {
+                    None
+                }
+            };
+            let on_close = ctx.link().callback(move |_: ()| {
+                Msg::ClosePurity
+            });
+            html! {
+                <ClusterPurityView
+                    predicted_col={predicted_col.clone()}
+                    gt_col={gt_col.clone()}
+                    purity={purity}
+                    on_close={on_close}
+                />
+            }
+        } else {
+            html! {""}
+        };
+
+        //Panel with the flow of cells between two time points
+        let transition_panel = if let Some((from_col, to_col)) = &self.transition_columns {
+            let transitions = {
+                let current_data = self.current_data.lock().unwrap();
+                let from = current_data.get_metadata(&PerCellDataSource::Metadata(from_col.clone()));
+                let to = current_data.get_metadata(&PerCellDataSource::Metadata(to_col.clone()));
+                if let (AsyncData::Loaded(from), AsyncData::Loaded(to)) = (from, to) {
+                    compute_transitions(&from, &to)
+                } else {
+                    None
+                }
+            };
+            let on_close = ctx.link().callback(move |_: ()| {
+                Msg::CloseTransitions
+            });
+            html! {
+                <SankeyView
+                    from_col={from_col.clone()}
+                    to_col={to_col.clone()}
+                    transitions={transitions}
+                    on_close={on_close}
+                />
+            }
+        } else {
+            html! {""}
+        };
+
+        //Mean of the numeric coloring along the trajectory
+        let pseudotime_panel = match (&self.trajectory, metadatas.get(&self.current_colorby)) {
+            (Some(trajectory), Some(column)) if self.show_pseudotime_chart => {
+                let is_numeric = match column {
+                    AsyncData::Loaded(data) => !matches!(data.as_ref(), CountFileMetaColumnData::Categorical(_, _)),
+                    _ => true
+                };
+                let on_close = ctx.link().callback(move |_: ()| {
+                    Msg::TogglePseudotimeChart
+                });
+                if is_numeric {
+                    html! {
+                        <PseudotimeChartView
+                            reduction={current_umap_data.clone()}
+                            trajectory={trajectory.clone()}
+                            column_name={column_label(&self.current_colorby)}
+                            column={column.clone()}
+                            on_close={on_close}
+                        />
+                    }
+                } else {
+                    html! {""}
+                }
+            },
+            _ => html! {""}
+        };
+
+        //Panel comparing the values of the selected cells in all loaded numeric columns
+        let parallel_coords_panel = if self.show_parallel_coords {
+            let num_point = match &current_umap_data {
+                AsyncData::Loaded(reduction) => reduction.num_point,
+                _ => 0
+            };
+            let subset_indices: Vec<usize> = self.last_clicked_cells.iter().cloned().take(MAX_PARALLEL_COORDS_CELLS).collect();
+            let mut axes: Vec<_> = metadatas.iter().filter_map(|(name, data)| {
+                match (name, data) {
+                    (PerCellDataSource::Metadata(column_name), AsyncData::Loaded(data)) => build_parallel_axis(column_name, data, &subset_indices, num_point),
+                    _ => None
+                }
+            }).collect();
+            axes.sort_by(|a, b| a.name.cmp(&b.name));
+            let colors = cell_line_colors(&coloring_data, &subset_indices);
+            let on_cell_clicked = ctx.link().callback(move |cell: usize| {
+                Msg::SetParallelCoordsCell(cell)
+            });
+            let on_close = ctx.link().callback(move |_: ()| {
+                Msg::ToggleParallelCoords
+            });
+            html! {
+                <ParallelCoordsView
+                    subset_indices={subset_indices}
+                    axes={axes}
+                    colors={colors}
+                    highlighted_cell={self.parallel_coords_cell}
+                    on_cell_clicked={on_cell_clicked}
+                    on_close={on_close}
+                />
+            }
+        } else {
+            html! {""}
+        };
+
+        //Heatmap of the genes colored by so far, in the selected cells versus all others
+        let state_heatmap_panel = if self.show_state_heatmap {
+            let num_point = match &current_umap_data {
+                AsyncData::Loaded(reduction) => reduction.num_point,
+                _ => 0
+            };
+            let selected: HashSet<usize> = self.last_clicked_cells.iter().cloned().filter(|c| *c < num_point).collect();
+            let mut group_a: Vec<usize> = selected.iter().cloned().collect();
+            group_a.sort();
+            let group_b: Vec<usize> = (0..num_point).filter(|c| !selected.contains(c)).collect();
+
+            let gene_data: HashMap<String, AsyncData<CountFileMetaColumnData>> = metadatas.iter().filter_map(|(name, data)| {
+                match name {
+                    PerCellDataSource::Counts(_counts_name, feature_name) => Some((feature_name.clone(), data.clone())),
+                    _ => None
+                }
+            }).collect();
+            let mut gene_list: Vec<String> = gene_data.keys().cloned().collect();
+            gene_list.sort();
+
+            let on_close = ctx.link().callback(move |_: ()| {
+                Msg::ToggleStateHeatmap
+            });
+            html! {
+                <CellStateHeatmapView
+                    group_a={group_a}
+                    group_b={group_b}
+                    group_names={("Selected".to_string(), "Others".to_string())}
+                    gene_list={gene_list}
+                    gene_data={gene_data}
+                    num_point={num_point}
+                    on_close={on_close}
+                />
+            }
+        } else {
+            html! {""}
+        };
+
+        //Correlation of each pair of numeric metadata columns
+        let correlation_matrix_panel = if self.show_correlation_matrix {
+            let num_point = match &current_umap_data {
+                AsyncData::Loaded(reduction) => reduction.num_point,
+                _ => 0
+            };
+            let columns: Vec<(String, AsyncData<CountFileMetaColumnData>)> = match &self.metadata_schema {
+                AsyncData::Loaded(schema) => schema.columns.iter().filter(|column| !column.is_categorical).map(|column| {
+                    let data = metadatas.get(&PerCellDataSource::Metadata(column.name.clone())).cloned().unwrap_or(AsyncData::NotLoaded);
+                    (column.name.clone(), data)
+                }).collect(),
+                _ => Vec::new()
+            };
+
+            let on_close = ctx.link().callback(move |_: ()| {
+                Msg::ToggleCorrelationMatrix
+            });
+            html! {
+                <CorrelationMatrixView
+                    columns={columns}
+                    num_point={num_point}
+                    on_close={on_close}
+                />
+            }
+        } else {
+            html! {""}
+        };
+
+        //Panel with sliders to recompute the reduction
+        let params_panel = match (&self.current_reduction, self.show_algorithm_params) {
+            (Some(reduction_name), true) => {
+                let on_set_param = ctx.link().callback(move |(name, value): (String, f32)| {
+                    Msg::SetAlgorithmParam(name, value)
+                });
+                let on_close = ctx.link().callback(move |_: ()| {
+                    Msg::ToggleAlgorithmParams
+                });
+                html! {
+                    <ReductionParamsView
+                        reduction_name={base_reduction_name(reduction_name)}
+                        algorithm_params={self.algorithm_params.clone()}
+                        running={self.recompute_running}
+                        on_set_param={on_set_param}
+                        on_close={on_close}
                     />
-                </div>
-                <MetadataView 
-                    current_datadesc={self.current_datadesc.clone()} 
-                    on_colorbymeta={on_colorbymeta.clone()}
-                    current_colorby={self.current_colorby.clone()}
+                }
+            },
+            _ => html! {""}
+        };
+
+        //Message about the last action, e.g. how clustering went
+        let notification = if let Some(text) = &self.notification {
+            let cb_close = ctx.link().callback(move |_e: MouseEvent| {
+                Msg::ClearNotification
+            });
+            html! {
+                <div class="biscvi-notification" onclick={cb_close}>{ text.clone() }</div>
+            }
+        } else {
+            html! {""}
+        };
+
+        //Shown over the reduction while the server recomputes it
+        let recompute_overlay = if self.recompute_running {
+            html! { <div class="biscvi-recompute-overlay">{"Recomputing reduction..."}<span class="biscvi-spinner"></span></div> }
+        } else {
+            html! {""}
+        };
+
+        //Panel plotting the first UMAP coordinate against the first principal component
+        let projection_comparison_panel = if let Some((name_umap, name_pca)) = &self.projection_comparison {
+            let (reduction_umap, reduction_pca) = {
+                let current_data = self.current_data.lock().unwrap();
+                (current_data.get_reduction(name_umap), current_data.get_reduction(name_pca))
+            };
+            let clusters = metadatas.get(&self.current_colorby).cloned().unwrap_or(AsyncData::NotLoaded);
+            let on_select = ctx.link().callback(move |cells: Vec<usize>| {
+                Msg::SelectFromProjections(cells)
+            });
+            let on_close = ctx.link().callback(move |_: ()| {
+                Msg::CloseProjectionComparison
+            });
+            html! {
+                <CrossProjectionView
+                    name_umap={name_umap.clone()}
+                    name_pca={name_pca.clone()}
+                    reduction_umap={reduction_umap}
+                    reduction_pca={reduction_pca}
+                    clusters={clusters}
+                    on_select={on_select}
+                    on_close={on_close}
                 />
-                <FeatureView
-                    current_datadesc={self.current_datadesc.clone()}
-                    on_colorbyfeature={on_colorbymeta}  //expand, not just meta?
-                    current_colorby={self.current_colorby.clone()}
-                    //current_data={self.current_data.clone()}
+            }
+        } else {
+            html! {""}
+        };
+
+        //Panel with a reduction before and after batch correction
+        let batch_comparison_panel = if let Some((name_original, name_corrected)) = &self.batch_comparison {
+            let (original_reduction, corrected_reduction) = {
+                let current_data = self.current_data.lock().unwrap();
+                (current_data.get_reduction(name_original), current_data.get_reduction(name_corrected))
+            };
+            let batches = metadatas.get(&self.current_colorby).cloned().unwrap_or(AsyncData::NotLoaded);
+            let on_close = ctx.link().callback(move |_: ()| {
+                Msg::CloseBatchComparison
+            });
+            html! {
+                <BatchCorrectionView
+                    name_original={name_original.clone()}
+                    name_corrected={name_corrected.clone()}
+                    original_reduction={original_reduction}
+                    corrected_reduction={corrected_reduction}
+                    batches={batches}
+                    on_close={on_close}
+                />
+            }
+        } else {
+            html! {""}
+        };
+
+        //Panel plotting two reductions against each other
+        let reduction_comparison_panel = if let Some((name_a, name_b)) = &self.reduction_comparison {
+            let (reduction_a, reduction_b) = {
+                let current_data = self.current_data.lock().unwrap();
+                (current_data.get_reduction(name_a), current_data.get_reduction(name_b))
+            };
+            let on_close = ctx.link().callback(move |_: ()| {
+                Msg::CloseReductionComparison
+            });
+            html! {
+                <ReductionCorrelationView
+                    name_a={name_a.clone()}
+                    name_b={name_b.clone()}
+                    reduction_a={reduction_a}
+                    reduction_b={reduction_b}
+                    on_close={on_close}
                 />
-            </div>
+            }
+        } else {
+            html! {""}
+        };
+
+        let on_toggle_focus_context = ctx.link().callback(move |_: ()| {
+            Msg::ToggleFocusContext
+        });
+
+        //Mask imported for the reduction currently shown
+        let mask_polygon = self.geo_mask.as_ref()
+            .filter(|(reduction_name, _mask)| Some(reduction_name) == self.current_reduction.as_ref())
+            .map(|(_reduction_name, mask)| mask.clone());
+
+        //Threshold and filter, if set for the column currently shown
+        let expression_threshold = self.expression_threshold.as_ref()
+            .filter(|(column, _threshold)| *column == self.current_colorby)
+            .map(|(_column, threshold)| *threshold);
+        let min_expression_filter = self.expression_filter.as_ref()
+            .filter(|(column, _min_value)| *column == self.current_colorby)
+            .map(|(_column, min_value)| *min_value);
+        let on_set_expression_filter = ctx.link().callback(move |min_value: f32| {
+            Msg::SetExpressionFilter(min_value)
+        });
+        let current_colorby = self.current_colorby.clone();
+        let on_set_expression_threshold = ctx.link().callback(move |threshold: Option<f32>| {
+            match threshold {
+                Some(threshold) => Msg::SetExpressionThreshold(current_colorby.clone(), threshold),
+                None => Msg::ClearExpressionThreshold,
+            }
+        });
+
+        //Loaded numeric columns, other than the coloring, that it can be correlated with
+        let mut correlation_columns: Vec<(PerCellDataSource, AsyncData<CountFileMetaColumnData>)> = metadatas.iter()
+            .filter(|(name, data)| {
+                **name != self.current_colorby && match data {
+                    AsyncData::Loaded(data) => !matches!(data.as_ref(), CountFileMetaColumnData::Categorical(_, _)),
+                    _ => false
+                }
+            })
+            .map(|(name, data)| (name.clone(), data.clone()))
+            .collect();
+        correlation_columns.sort_by_key(|(name, _)| name.to_string());
+
+        //A snapshot of the state, or a GeoJSON mask, can be dropped on the reduction
+        let cb_dragover = Callback::from(move |e: DragEvent| {
+            e.prevent_default();
+        });
+        let link = ctx.link().clone();
+        let cb_drop = Callback::from(move |e: DragEvent| {
+            e.prevent_default();
+            let file = e.data_transfer().and_then(|dt| dt.files()).and_then(|files| files.get(0));
+            if let Some(file) = file {
+                let filename = file.name().to_lowercase();
+                if filename.ends_with(".geojson") {
+                    read_text_file(link.clone(), file, Msg::ImportGeoJsonMask);
+                } else if filename.ends_with(".json") {
+                    read_text_file(link.clone(), file, Msg::ImportState);
+                }
+            }
+        });
+
+        //Callbacks for the collaborative session
+        let on_camera_changed = ctx.link().callback(move |camera: Camera2D| {
+            Msg::BroadcastCamera(camera)
+        });
+        let on_cursor_moved = ctx.link().callback(move |(x, y): (f32, f32)| {
+            Msg::BroadcastCursor(x, y)
+        });
+        let on_tool_changed = ctx.link().callback(move |tool: CurrentTool| {
+            Msg::SetActiveTool(tool)
+        });
+        let on_zoom_level_changed = ctx.link().callback(move |zoom_level: ZoomLevel| {
+            Msg::ZoomLevelChanged(zoom_level)
+        });
+        let on_start_collab = ctx.link().callback(move |_: ()| {
+            Msg::StartCollabSession
+        });
+        let on_join_collab = ctx.link().callback(move |offer_sdp: String| {
+            Msg::JoinCollabSession(offer_sdp)
+        });
+        let on_accept_collab = ctx.link().callback(move |answer_sdp: String| {
+            Msg::AcceptCollabAnswer(answer_sdp)
+        });
+        let peer_cursors: Vec<(f32,f32)> = self.peer_cursors.values().cloned().collect();
+        let num_collab_connected = self.collab_sessions.iter().filter(|s| s.is_connected()).count();
+
+        //Column to group cells by, and the size and category of the hovered group
+        let group_by_data = match &self.group_by {
+            GroupByMode::Cluster(column) => self.current_data.lock().unwrap().get_metadata(column),
+            GroupByMode::None => AsyncData::NotLoaded,
+        };
+        let violin_data = match &self.violin_column {
+            Some(column) => self.current_data.lock().unwrap().get_metadata(column),
+            None => AsyncData::NotLoaded,
+        };
+        let group_hover_label = match (&group_by_data, self.hovered_group.first()) {
+            (AsyncData::Loaded(column), Some(cell)) => {
+                let category = match column.as_ref() {
+                    CountFileMetaColumnData::Categorical(codes, names) => codes.get(*cell).and_then(|c| names.get(*c as usize)).cloned().unwrap_or_default(),
+                    _ => String::new(),
+                };
+                html! {
+                    <div class="biscvi-group-hover">{ format!("{}: {} cells", category, self.hovered_group.len()) }</div>
+                }
+            },
+            _ => html! {""}
+        };
+
+        //Timeline of the last colorings. Each pip goes back to a coloring; the current one is larger
+        let coloring_timeline = if self.coloring_history.len() > 1 {
+            let list_pips = self.coloring_history.iter().enumerate().map(|(i, (_, description))| {
+                let class = if i == self.coloring_cursor { "biscvi-timeline-pip biscvi-timeline-pip-current" } else { "biscvi-timeline-pip" };
+                let cb_click = ctx.link().callback(move |_e: MouseEvent| {
+                    Msg::JumpToColoring(i)
+                });
+                html! {
+                    <span class={class} title={description.clone()} onclick={cb_click}></span>
+                }
+            }).collect::<Html>();
+            html! {
+                <div class="biscvi-coloring-timeline">{ list_pips }</div>
+            }
+        } else {
+            html! {""}
+        };
+
+        //Buttons and panels over the reduction. The print layout only has a way back
+        let controls = if self.print_layout {
+            let cb_exit_print = ctx.link().callback(move |_e: MouseEvent| {
+                Msg::TogglePrintLayout
+            });
+            html! {
+                <button class="biscvi-print-exit biscvi-print-hidden" onclick={cb_exit_print}>{"Exit print layout"}</button>
+            }
+        } else {
+            html! {
+                <>
+                    <div class="biscvi-compare-button">
+                        { compare_button }
+                        { trajectory_button }
+                        { markers_button }
+                        { export_button }
+                    </div>
+                    { coloring_timeline }
+                    { transition_panel }
+                    { pseudotime_panel }
+                </>
+            }
+        };
+
+        //Panels and views next to the reduction; left out of the print layout
+        let side_panels = if self.print_layout {
+            html! {""}
+        } else {
+            html! {
+                <>
+                    { compare_panel }
+                    { reduction_comparison_panel }
+                    { projection_comparison_panel }
+                    { batch_comparison_panel }
+                    { purity_panel }
+                    { params_panel }
+                    { parallel_coords_panel }
+                    { state_heatmap_panel }
+                    { cell_table_panel }
+                    { correlation_matrix_panel }
+                    { markers_panel }
+                    { cell_metadata_panel }
+                    <CollabPanel
+                        local_sdp={self.collab_local_sdp.clone()}
+                        num_peers={self.collab_sessions.len()}
+                        num_connected={num_collab_connected}
+                        on_start={on_start_collab}
+                        on_join={on_join_collab}
+                        on_accept={on_accept_collab}
+                    />
+                    <MetadataView 
+                        metadata_schema={self.metadata_schema.clone()} 
+                        metadatas={metadatas}
+                        on_colorbymeta={on_colorbymeta.clone()}
+                        on_loadmeta={on_loadmeta}
+                        current_colorby={self.current_colorby.clone()}
+                        column_summary={self.column_summary.clone()}
+                        sample_annotation={self.sample_annotation.clone()}
+                        sample_cell_counts={self.sample_cell_counts.clone()}
+                        selected_samples={self.selected_samples.clone()}
+                        on_toggle_sample={on_toggle_sample}
+                        on_import_color_table={on_import_color_table}
+                        cell_search_query={self.cell_search_query.clone()}
+                        cell_search_pending={self.cell_search_pending}
+                        cell_search_num_found={self.cell_search_num_found}
+                        on_search_cells={on_search_cells}
+                    />
+                    <FeatureView
+                        current_datadesc={self.current_datadesc.clone()}
+                        on_colorbyfeature={on_colorbymeta}  //expand, not just meta?
+                        current_colorby={self.current_colorby.clone()}
+                        on_hover_feature={on_hover_feature}
+                        //current_data={self.current_data.clone()}
+                    />
+                    { grn_panel }
+                </>
+            }
+        };
+
+        //Top features of the hovered cell, if known
+        let tooltip_genes = self.hovered_cell.and_then(|cell| self.cell_top_genes.get(&cell).cloned());
+
+        html! {
+            <ContextProvider<SelectionContext> context={selection_context}>
+                <div>
+                    <div class={classes!("biscvi-dimred-maindiv", self.print_layout.then_some("biscvi-print-layout"))} ondragover={cb_dragover} ondrop={cb_drop}> ////////// if behind everything, could take full screen!! but buttons need space adjustment
+                        <ReductionView 
+                            on_cells_hovered={on_cells_hovered}
+                            on_group_clicked={on_group_clicked}
+                            cluster_card_genes={self.cluster_card.as_ref().and_then(|(_, genes)| genes.clone())}
+                            group_by={self.group_by.clone()}
+                            group_by_data={group_by_data}
+                            violin_data={violin_data}
+                            reduction_data={current_umap_data} 
+                            color_reduction_by={coloring_data.clone()} 
+                            last_component_size={self.last_component_size.clone()}
+                            current_colorby={self.current_colorby.clone()}
+                            tooltip_genes={tooltip_genes}
+                            global_color_range={global_color_range}
+                            diverging_center={matches!(self.current_colorby, PerCellDataSource::ExpressionRatio { .. }).then_some(0.0)}
+                            sync_color_scale={self.sync_color_scale}
+                            on_toggle_sync_color_scale={on_toggle_sync_color_scale}
+                            expression_threshold={expression_threshold}
+                            on_set_expression_threshold={on_set_expression_threshold}
+                            min_expression_filter={min_expression_filter}
+                            mask_polygon={mask_polygon}
+                            on_set_expression_filter={on_set_expression_filter}
+                            sample_annotation={self.sample_annotation.clone()}
+                            selected_samples={self.selected_samples.clone()}
+                            display_subset={self.display_subset.clone()}
+                            focus_context={self.focus_context}
+                            color_simulation={self.color_simulation}
+                            category_gradient={self.category_gradient}
+                            on_toggle_focus_context={on_toggle_focus_context}
+                            correlation_columns={correlation_columns}
+                            performance_overlay={cfg!(debug_assertions) && !self.print_layout}  //Only for development
+                            max_display_points={if self.print_layout { None } else { Some(MAX_DISPLAY_POINTS) }}
+                            print_layout={self.print_layout}
+                            legend_position={LegendPosition::Automatic}
+                            forced_selection={self.forced_selection.clone()}
+                            focus_cell_index={self.focus_cell}
+                            highlighted_cell={self.parallel_coords_cell}
+                            forced_camera={self.forced_camera.clone()}
+                            forced_command={self.reduction_command.clone()}
+                            on_camera_changed={on_camera_changed}
+                            on_tool_changed={on_tool_changed}
+                            on_cursor_moved={on_cursor_moved}
+                            peer_cursors={peer_cursors}
+                            trajectory={self.trajectory.clone()}
+                            cell_annotations={self.cell_annotations.clone()}
+                            on_set_annotation={on_set_annotation}
+                            cell_ids={self.cell_ids.clone()}
+                            on_zoom_level_changed={on_zoom_level_changed}
+                            biplot_arrows={self.biplot_arrows.clone()}
+                        />
+                        { group_hover_label }
+                        { recompute_overlay }
+                        { notification }
+                        { controls }
+                    </div>
+                    { side_panels }
+                </div>
+            </ContextProvider<SelectionContext>>
         }
     }
 
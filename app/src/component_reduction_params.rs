@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+
+use my_web_app::local_umap::{DEFAULT_MIN_DIST, DEFAULT_N_NEIGHBORS, DEFAULT_PERPLEXITY};
+use web_sys::HtmlInputElement;
+use yew::{html, Callback, Component, Context, Html, InputEvent, MouseEvent, TargetCast};
+use yew::Properties;
+
+
+////////////////////////////////////////////////////////////
+/// Slider for a hyperparameter: name sent to the server, label, min, max, step, default
+type ParamSlider = (&'static str, &'static str, f32, f32, f32, f32);
+
+////////////////////////////////////////////////////////////
+/// Hyperparameters of UMAP
+const UMAP_PARAMS: [ParamSlider; 2] = [
+    ("n_neighbors", "Neighbors", 2.0, 100.0, 1.0, DEFAULT_N_NEIGHBORS),
+    ("min_dist", "Min distance", 0.0, 1.0, 0.01, DEFAULT_MIN_DIST),
+];
+
+////////////////////////////////////////////////////////////
+/// Hyperparameters of t-SNE
+const TSNE_PARAMS: [ParamSlider; 1] = [
+    ("perplexity", "Perplexity", 5.0, 50.0, 1.0, DEFAULT_PERPLEXITY),
+];
+
+
+////////////////////////////////////////////////////////////
+/// Properties for ReductionParamsView
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub reduction_name: String,
+    pub algorithm_params: HashMap<String, f32>,
+    pub running: bool,
+    pub on_set_param: Callback<(String, f32)>,
+    pub on_close: Callback<()>,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Panel with sliders for the hyperparameters of the current reduction. The server computes the
+/// reduction again once a slider stops moving
+pub struct ReductionParamsView {
+}
+
+impl Component for ReductionParamsView {
+    type Message = ();
+    type Properties = Props;
+
+    ////////////////////////////////////////////////////////////
+    /// Create this component
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Render the panel
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+
+        let on_close = props.on_close.clone();
+        let cb_close = Callback::from(move |_e: MouseEvent| {
+            on_close.emit(());
+        });
+
+        //Sliders of the algorithm, guessed from the name of the reduction
+        let list_params: &[ParamSlider] = if props.reduction_name.to_lowercase().contains("tsne") { &TSNE_PARAMS } else { &UMAP_PARAMS };
+        let list_sliders = list_params.iter().map(|(name, label, min, max, step, default)| {
+            let value = props.algorithm_params.get(*name).cloned().unwrap_or(*default);
+            let on_set_param = props.on_set_param.clone();
+            let name = name.to_string();
+            let cb_input = Callback::from(move |e: InputEvent| {
+                let input: HtmlInputElement = e.target_unchecked_into();
+                if let Ok(v) = input.value().parse::<f32>() {
+                    on_set_param.emit((name.clone(), v));
+                }
+            });
+            html! {
+                <div>
+                    <div class="bisci-label-left">{ format!("{}: {}", label, value) }</div>
+                    <input type="range" min={min.to_string()} max={max.to_string()} step={step.to_string()} value={value.to_string()} oninput={cb_input}/>
+                </div>
+            }
+        }).collect::<Html>();
+
+        let status = if props.running {
+            html! { <div>{"Recomputing..."}<span class="biscvi-spinner"></span></div> }
+        } else {
+            html! {""}
+        };
+
+        html! {
+            <div class="biscvi-compare-panel">
+                <button onclick={cb_close}>{"Close"}</button>
+                <div class="bisci-label-left">
+                    { format!("Parameters of {}", props.reduction_name) }
+                </div>
+                { list_sliders }
+                { status }
+            </div>
+        }
+    }
+
+}
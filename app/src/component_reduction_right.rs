@@ -22,6 +22,7 @@ pub enum MsgFeature {
 //    ToggleExpand(String)
     FeatureSearchChange(String, bool),
     SetLastCountName(String),
+    HoverFeature(Option<String>),
 }
 
 
@@ -33,6 +34,8 @@ pub struct Props {
     pub on_colorbyfeature: Callback<PerCellDataSource>,
 
     pub current_colorby: PerCellDataSource,
+    #[prop_or_default]
+    pub on_hover_feature: Callback<Option<String>>,   // feature under the mouse, or None when it leaves
     //pub current_data: Arc<Mutex<BiscviData>>,
 }
 
@@ -101,6 +104,12 @@ impl Component for FeatureView {
             MsgFeature::SetLastCountName(countname) => {
                 self.last_counttype_select = countname;
                 false
+            },
+
+            //////// Mouse entered or left a feature
+            MsgFeature::HoverFeature(feature_name) => {
+                ctx.props().on_hover_feature.emit(feature_name);
+                false
             }
 
 /*
@@ -266,6 +275,14 @@ impl FeatureView {
             MsgFeature::SetColorBy(combo_feature_copy.clone())
         });
 
+        let feature_name_copy = feature_name.clone();
+        let cb_enter = ctx.link().callback(move |_e: MouseEvent | {
+            MsgFeature::HoverFeature(Some(feature_name_copy.clone()))
+        });
+        let cb_leave = ctx.link().callback(move |_e: MouseEvent | {
+            MsgFeature::HoverFeature(None)
+        });
+
         let style_colorby_button = if ctx.props().current_colorby == combo_feature {
             "background-color: #FF0000;"
         } else {
@@ -275,7 +292,7 @@ impl FeatureView {
 
 
         html! {
-            <div>
+            <div onmouseenter={cb_enter} onmouseleave={cb_leave}>
                 <div style="margin-left: 5px; margin-right: 0px; margin-top: 2px; display: flex; justify-content: space-between; align-items: center;">
                     <div style="display: flex; justify-content: space-between; width: 100%;">  // role="menuitem" tabindex="0" data-testid="XBP1:gene-expand"    cursor: pointer; 
                         <div>
@@ -0,0 +1,107 @@
+use yew::{html, Callback, Component, Context, Html, MouseEvent};
+use yew::Properties;
+
+use crate::component_reduction_main::{get_palette_for_categories, rgbvec2string};
+use crate::sankey::{layout_sankey, TransitionCounts};
+
+
+////////////////////////////////////////////////////////////
+/// Size of the diagram, in pixels. Labels are drawn outside of it
+const SANKEY_W: f32 = 300.0;
+const SANKEY_H: f32 = 200.0;
+const SANKEY_NODE_W: f32 = 10.0;
+const SANKEY_GAP: f32 = 4.0;
+const SANKEY_LABEL_W: f32 = 100.0;
+
+
+////////////////////////////////////////////////////////////
+/// Properties for SankeyView
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub from_col: String,
+    pub to_col: String,
+    pub transitions: Option<TransitionCounts>,   // None while the columns are loading, or if not categorical
+    pub on_close: Callback<()>,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Panel with a Sankey diagram of how cells move between the categories of two columns,
+/// e.g. the annotations at two time points. The width of each flow is the number of cells
+pub struct SankeyView {
+}
+
+impl Component for SankeyView {
+    type Message = ();
+    type Properties = Props;
+
+    ////////////////////////////////////////////////////////////
+    /// Create this component
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Render the panel
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+
+        let on_close = props.on_close.clone();
+        let cb_close = Callback::from(move |_e: MouseEvent| {
+            on_close.emit(());
+        });
+
+        let chart = if let Some(transitions) = &props.transitions {
+            let layout = layout_sankey(transitions, SANKEY_W, SANKEY_H, SANKEY_NODE_W, SANKEY_GAP);
+
+            //Flows take the color of where they come from
+            let palette = get_palette_for_categories(transitions.from_names.len());
+            let color_of = |i: usize| rgbvec2string(palette.get(i).cloned().unwrap_or((0.5, 0.5, 0.5)));
+
+            let list_links = layout.links.iter().map(|link| html! {
+                <path d={link.path.clone()} fill={color_of(link.from)} fill-opacity="0.4">
+                    <title>{ format!("{} → {}: {} cells", transitions.from_names[link.from], transitions.to_names[link.to], link.count) }</title>
+                </path>
+            }).collect::<Html>();
+
+            let list_from = layout.from_nodes.iter().enumerate().filter(|(_, node)| node.count > 0).map(|(i, node)| html! {
+                <>
+                    <rect x={format!("{}", node.x)} y={format!("{}", node.y)} width={format!("{}", SANKEY_NODE_W)} height={format!("{}", node.height)} fill={color_of(i)}/>
+                    <text x={format!("{}", node.x - 3.0)} y={format!("{}", node.y + node.height/2.0 + 4.0)} text-anchor="end" font-size="12">{ node.name.clone() }</text>
+                </>
+            }).collect::<Html>();
+
+            let list_to = layout.to_nodes.iter().filter(|node| node.count > 0).map(|node| html! {
+                <>
+                    <rect x={format!("{}", node.x)} y={format!("{}", node.y)} width={format!("{}", SANKEY_NODE_W)} height={format!("{}", node.height)} fill="#555555"/>
+                    <text x={format!("{}", node.x + SANKEY_NODE_W + 3.0)} y={format!("{}", node.y + node.height/2.0 + 4.0)} font-size="12">{ node.name.clone() }</text>
+                </>
+            }).collect::<Html>();
+
+            html! {
+                <svg width={format!("{}", SANKEY_W + 2.0*SANKEY_LABEL_W)} height={format!("{}", SANKEY_H + 10.0)}>
+                    <g transform={format!("translate({},5)", SANKEY_LABEL_W)}>
+                        { list_links }
+                        { list_from }
+                        { list_to }
+                    </g>
+                </svg>
+            }
+        } else {
+            html! { <div>{"Loading columns..."}</div> }
+        };
+
+        html! {
+            <div class="biscvi-sankey-panel">
+                <button onclick={cb_close}>{"Close"}</button>
+                <div class="bisci-label-left">
+                    { format!("Transitions from {} to {}", props.from_col, props.to_col) }
+                </div>
+                { chart }
+            </div>
+        }
+    }
+
+}
@@ -1,15 +1,46 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
 use std::sync::Arc;
 use std::sync::Mutex;
 
-use my_web_app::FeatureCountsRequest;
+use my_web_app::CellIdsRequest;
+use my_web_app::CellIdsResponse;
+use my_web_app::CellTopGenesRequest;
+use my_web_app::CellTopGenesResponse;
+use my_web_app::ClusterTopGenesRequest;
+use my_web_app::{GrnRequest, GrnResponse};
+use my_web_app::CountFileMetaColumnData;
+use my_web_app::FeatureColumnRequest;
+use my_web_app::FindMarkersRequest;
+use my_web_app::FindMarkersResponse;
 use my_web_app::DatasetDescRequest;
 use my_web_app::DatasetDescResponse;
 use my_web_app::MetadataColumnRequest;
 use my_web_app::MetadataColumnResponse;
+use my_web_app::MetadataSchemaRequest;
+use my_web_app::MetadataSchemaResponse;
+use my_web_app::RecomputeReductionRequest;
+use my_web_app::ReductionDelta;
+use my_web_app::ReductionDeltaRequest;
 use my_web_app::ReductionRequest;
 use my_web_app::ReductionResponse;
-
+use my_web_app::SampleAnnotation;
+use my_web_app::SampleAnnotationRequest;
+use my_web_app::SampleAnnotationResponse;
+use my_web_app::SearchCellsRequest;
+use my_web_app::SearchCellsResponse;
+
+use gloo_timers::callback::Timeout;
+use wasm_bindgen::prelude::Closure;
+use wasm_bindgen::JsCast;
+use wasm_bindgen::JsValue;
 use web_sys::window;
+use web_sys::File;
+use web_sys::HtmlInputElement;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::KeyboardEvent;
 use yew::prelude::*;
 
 use bytes::Buf;
@@ -17,9 +48,50 @@ use bytes::Buf;
 use crate::appstate::AsyncData;
 use crate::appstate::BiscviData;
 use crate::appstate::PerCellDataSource;
+use crate::camera::Camera2D;
+use crate::camera::ZoomLevel;
+use crate::collab::accept_answer;
+use crate::collab::create_answer;
+use crate::collab::create_offer;
+use crate::collab::CollabEvent;
+use crate::collab::CollabSession;
+use crate::color_table::parse_color_table;
+use crate::column_summary::ColumnSummary;
+use crate::cluster_proportion::compute_cluster_proportion;
+use crate::csv_export::build_cells_csv;
+use crate::geojson_mask::{parse_geojson_polygon, PolygonMask};
+use crate::biplot::{parse_loadings_csv, top_loadings};
+use crate::state_snapshot::{parse_snapshot, snapshot_to_json, AppStateSnapshot, SNAPSHOT_VERSION};
+use crate::url_state::{parse_query, snapshot_to_query, MAX_URL_SELECTION};
+use crate::download::download_text;
+use crate::fetch_retry::{post_for_bytes, FetchRetry, FetchTarget};
+use crate::correlation::{dense_numeric_values, expression_log_ratio};
+use crate::ot::wasserstein_1d;
+use crate::trajectory::trajectory_from_selection;
+use crate::local_umap::build_local_umap_input;
+use crate::local_umap::place_local_umap;
+use crate::local_umap::run_local_umap;
+use my_web_app::local_umap::MAX_LOCAL_UMAP_CELLS;
+use crate::louvain::{knn_graph_from_reduction, louvain, DEFAULT_LOUVAIN_NEIGHBORS, LOUVAIN_COLUMN};
+use crate::smoothing::{smooth_over_graph, TransitionMatrix, DEFAULT_SMOOTH_ALPHA, DEFAULT_SMOOTH_ITERATIONS, DEFAULT_SMOOTH_NEIGHBORS};
+use crate::component_reduction_main::apply_delta;
+use crate::component_reduction_main::expressor_bounds;
 use crate::component_reduction_main::convert_from_response_to_reduction_data;
+use crate::component_reduction_main::convert_from_msgpack_to_reduction_data;
+use crate::component_reduction_main::ReductionViewData;
+use crate::component_reduction_main::ReductionError;
+use crate::component_reduction_main::Color3f;
 use crate::component_reduction_main::ReductionColoring;
+use crate::component_reduction_main::FocusContextMode;
+use crate::colorblind_sim::ColorSimulationMode;
+use crate::component_reduction_main::GroupByMode;
+use crate::component_reduction_main::CurrentTool;
+use crate::component_reduction_main::ReductionCommand;
+use crate::component_reduction_main::column_label;
+use crate::component_command_palette::Command;
+use crate::component_command_palette::CommandPalette;
 use crate::resize::ComponentSize;
+use crate::subsample::{format_count, sample_normalized_indices};
 use crate::resize::ComponentSizeObserver;
 
 
@@ -45,18 +117,196 @@ pub enum Msg {
     SetDatasetDesc(DatasetDescResponse),
 
     GetReduction(String),
-    SetReduction(String, ReductionResponse),
+    FetchReduction(String),
+    SetReduction(String, Result<ReductionViewData, ReductionError>),
+    GetReductionDelta(String, u32, u32),
+    SetReductionDelta(String, u32, ReductionDelta),
+    FetchFailed(FetchTarget, FetchRetry, String),
+    RetryFetch(FetchTarget, FetchRetry),
+
+    GetMetadataSchema(),
+    SetMetadataSchema(MetadataSchemaResponse),
+    RequestMetadata(PerCellDataSource),
+    SetMetadata(PerCellDataSource, MetadataColumnResponse),
+    GetSampleAnnotation(),
+    SetSampleAnnotation(SampleAnnotationResponse),
+    ToggleSample(u32),
+    SetSampleNormalization(bool),   // draw as many cells of each sample
+    PromptCellsPerSample,
+    GetCellIds(),
+    SetCellIds(CellIdsResponse),
+    ZoomLevelChanged(ZoomLevel),
+    SearchCells(String),   // on every keystroke
+    RunCellSearch(String),
+    SetCellSearchResult(String, SearchCellsResponse),
 
     RequestSetColorByMeta(PerCellDataSource),
     SetColorByMeta(PerCellDataSource, Option<MetadataColumnResponse>),
+    ComputeClusterProportion(PerCellDataSource),   // once the base column and reduction are loaded
+    ComputeExpressionRatio(PerCellDataSource),     // once both features are loaded
+    PromptExpressionRatio,
+    ZoomToExpressors { gene: PerCellDataSource, threshold: f32 },   // fit the camera to cells above the threshold, once loaded
+    PromptZoomToExpressors,
+    ImportColorTable(Vec<u8>),  // CSV file content
+
+    SetAnnotation(usize, String),   // cell, text. Empty text removes the annotation
+    ExportCsv,
+    ExportState,
+    PickStateFile,
+    ImportState(String),   // JSON of an AppStateSnapshot
+    PickGeoJsonMask,
+    ImportGeoJsonMask(String),   // GeoJSON with a polygon in the coordinates of the current reduction
+    ClearGeoJsonMask,
+    PickBiplotLoadings,
+    ImportBiplotLoadings(String),   // CSV with rows gene,pc1,pc2
+    LoadBiplotArrows(Vec<(String,f32,f32)>),   // gene, loading on PC1, loading on PC2. Empty to hide the arrows
+    ApplyPendingCamera,
 
     DataChanged, //Just update using "true"
 
     WindowResize(ComponentSize),
 
+    HoverCell(Option<usize>),
+    ToggleSyncColorScale,
+    SetCellTopGenes(usize, CellTopGenesResponse),
+    HoverFeature(Option<String>),   // in the list of features; shows its regulatory network
+    SetGrn(String, GrnResponse),
+    RequestClusterTopGenes(Vec<usize>),   // cells of a group whose summary card was opened
+    SetClusterTopGenes(Vec<usize>, CellTopGenesResponse),
+
+    SelectCells(Vec<usize>),
+    CompareSelectedCells(usize, usize),
+    CloseCellComparison,
+
+    UndoColoring,
+    RedoColoring,
+    JumpToColoring(usize),   // position in the history
+
+    SetExpressionThreshold(PerCellDataSource, f32),
+    ClearExpressionThreshold,
+    SetExpressionFilter(f32),   // minimum value of cells shown, for the current coloring
+
+    ToggleFocusContext,
+    SetColorSimulation(ColorSimulationMode),
+    ToggleCategoryGradient,
+
+    RunLocalUMAP(Vec<usize>, usize),  // selection, n_neighbors
+    RunLouvain { n_neighbors: usize },
+    SmoothColumn { column: PerCellDataSource, iterations: usize, alpha: f32 },
+    ShowNotification(String),
+    ClearNotification,
+    ToggleAlgorithmParams,
+    ToggleParallelCoords,
+    ToggleStateHeatmap,
+    ToggleCellTable,
+    ToggleCorrelationMatrix,
+    TogglePrintLayout,
+    PrintPage,
+    SetParallelCoordsCell(usize),
+    SetAlgorithmParam(String, f32),   // on every slider move
+    RecomputeReduction,
+    SetRecomputedReduction(String, HashMap<String, f32>, Result<ReductionViewData, ReductionError>),   // reduction it was computed from, parameters
+    SetLocalUMAP(String, Vec<usize>, Result<Vec<f32>, String>),  // reduction it was computed from, selection, positions
+
+    ShowTrajectoryOfSelection,
+    DistanceOfSelection,
+    ToggleLegendViolins,
+    ComputeDistributionDistance(Vec<usize>, Vec<usize>, PerCellDataSource),   // group A, group B, numeric column
+    SetTrajectory(Option<Vec<usize>>),
+    TogglePseudotimeChart,
+
+    StartCollabSession,
+    JoinCollabSession(String),    // offer SDP
+    AcceptCollabAnswer(String),   // answer SDP
+    SetCollabLocalSdp(String),
+    RecvCollabEvent(CollabEvent),
+    BroadcastCamera(Camera2D),
+    SetActiveTool(CurrentTool),
+    BroadcastCursor(f32, f32),
+
+    ComputePurity(String, String),   // predicted clusters, ground truth
+    ClosePurity,
+    ShowTransitions(String, String),   // annotation at the first time point, at the second
+    CloseTransitions,
+    CompareReductions(String, String),
+    CloseReductionComparison,
+    CompareProjections(String, String),   // UMAP, PCA
+    CloseProjectionComparison,
+    SelectFromProjections(Vec<usize>),
+    CompareBatchCorrection(String, String),   // original, corrected
+    CloseBatchComparison,
+    FindMarkers,   // of the selected cells against the rest
+    SetMarkers(Result<FindMarkersResponse, String>),
+    CloseMarkers,
+    SetGroupBy(GroupByMode),
+    HoverCells(Vec<usize>),   // cells of the hovered group
+
+    ToggleCommandPalette,
+    CloseCommandPalette,
+    RunReductionCommand(ReductionCommand),
+
+}
+
+
+
+
+////////////////////////////////////////////////////////////
+/// Largest number of versions a reduction can be behind, and still be updated
+/// using a delta. Beyond this, the whole reduction is fetched again, as half
+/// precision errors accumulate
+const MAX_REDUCTION_DELTA_GAP: u32 = 5;
+
+////////////////////////////////////////////////////////////
+/// Number of features to show in the tooltip of a hovered cell
+const NUM_TOOLTIP_GENES: usize = 5;
+
+////////////////////////////////////////////////////////////
+/// Number of top features shown in the summary card of a group
+const NUM_CLUSTER_CARD_GENES: usize = 3;
+
+////////////////////////////////////////////////////////////
+/// Number of regulators, and of targets, shown in the regulatory network of a feature
+const NUM_GRN_EDGES: usize = 8;
+
+////////////////////////////////////////////////////////////
+/// Number of points in a trajectory built from a selection
+const NUM_TRAJECTORY_POINTS: usize = 20;
+
+////////////////////////////////////////////////////////////
+/// Time without typing before searching for cells, in milliseconds
+const CELL_SEARCH_DEBOUNCE_MS: u32 = 300;
+
+////////////////////////////////////////////////////////////
+/// Time without moving a hyperparameter slider before recomputing the reduction, in milliseconds
+const RECOMPUTE_DEBOUNCE_MS: u32 = 500;
+
+////////////////////////////////////////////////////////////
+/// Wait after switching to the print layout before printing, so that the reduction has been drawn at full size
+const PRINT_DELAY_MS: u32 = 100;
+
+////////////////////////////////////////////////////////////
+/// Id of the style element added for the print layout
+const PRINT_STYLE_ID: &str = "biscvi-print-style";
+
+////////////////////////////////////////////////////////////
+/// Rules for printing the print layout: an A4 page with only the reduction, printed from its snapshot
+const PRINT_STYLE: &str = "
+@page { size: A4 portrait; margin: 0; }
+@media print {
+    .biscvi-topdiv, .biscvi-print-hidden, .biscvi-notification { display: none !important; }
+    .biscvi-dimred-maindiv.biscvi-print-layout { position: static; width: auto; height: auto; }
+    .biscvi-print-layout canvas { visibility: hidden; }
+    .biscvi-print-layout .biscvi-print-snapshot { display: block; }
 }
+";
 
+////////////////////////////////////////////////////////////
+/// Time a notification is shown, in milliseconds
+const NOTIFICATION_MS: u32 = 5000;
 
+////////////////////////////////////////////////////////////
+/// Number of colorings remembered for undo/redo
+const MAX_COLORING_HISTORY: usize = 20;
 
 
 ////////////////////////////////////////////////////////////
@@ -65,10 +315,84 @@ pub struct Model {
     pub current_page: CurrentPage,
     pub current_reduction: Option<String>,              //should be state of a page; move later
     pub current_datadesc: AsyncData<DatasetDescResponse>,  //For now, makes sense to keep this here, as it is static. but risks becoming really large
+    pub metadata_schema: AsyncData<MetadataSchemaResponse>,  //Names and types of metadata columns; the data is loaded when needed
+    pub sample_annotation: AsyncData<SampleAnnotation>,  //NotLoaded if the dataset has no samples
+    pub cells_per_sample: Option<usize>,    //Cells drawn of each sample when normalizing by sample; None for the size of the smallest sample
+    pub display_subset: Option<Arc<HashSet<usize>>>,   //Only these cells are drawn, if set. The data is not affected
+    pub sample_cell_counts: Vec<usize>,
+    pub selected_samples: HashSet<u32>,
+    pub cell_ids: AsyncData<Vec<String>>,   //Loaded when first needed
+    pub pending_color_table: Option<Vec<u8>>,   //Imported while the cell IDs were loading
+    pub last_camera: Option<Camera2D>,   //As last moved by the user
+    pub pending_camera: Option<Camera2D>,   //Imported with a reduction that is still loading
+    pub custom_colors: Vec<Color3f>,   //Colors of the last imported color table
+    pub active_tool: Option<CurrentTool>,   //As last chosen by the user
+    pub last_url_query: String,   //State last written to the URL, so that it can be shared
+    pub url_selection_too_large: bool,   //The selection was left out of the URL; the user has been warned
+    pub cell_annotations: HashMap<usize, String>,   //Text labels given to cells by the user
+    pub cell_search_query: String,
+    pub cell_search_pending: bool,   //Waiting for the user to stop typing, or for the server
+    pub cell_search_num_found: Option<usize>,
+    pub debounce_timer: Option<Timeout>,   //Dropping it cancels the search
     pub current_data: Arc<Mutex<BiscviData>>,           //Has interior mutability. Yew will not be able to sense updates! Need to signal in other ways
     pub color_umap_by: ReductionColoring, //// currently assumed   change this
     pub current_colorby: PerCellDataSource,
-    pub last_component_size: ComponentSize
+    pub last_component_size: ComponentSize,
+    pub column_summary: HashMap<String, ColumnSummary>,  //Computed when a metadata column is first loaded and the number of cells is known
+    pub hovered_cell: Option<usize>,
+    pub cell_top_genes: HashMap<usize, Vec<(String, f32)>>,  //Cache of top features for hovered cells
+    pub sync_color_scale: bool,
+    pub last_clicked_cells: Vec<usize>,  //The last two cells clicked, or the cells in the last region selected
+    pub compare_cells: Option<(usize, usize)>,
+    pub coloring_history: VecDeque<(ReductionColoring, String)>,   //With a description to show in the timeline
+    pub coloring_cursor: usize,    //Position of the current coloring in the history
+    pub expression_threshold: Option<(PerCellDataSource, f32)>,  //Show this column in two colors, split at the threshold
+    pub expression_filter: Option<(PerCellDataSource, f32)>,     //Hide cells below this value of the column
+    pub focus_context: FocusContextMode,
+    pub color_simulation: ColorSimulationMode,
+    pub category_gradient: bool,   //Blend the colors of categories where they meet
+    pub geo_mask: Option<(String, PolygonMask)>,   // reduction it applies to, and the polygon
+    pub biplot_arrows: Vec<(String,f32,f32)>,   // top PCA loadings, drawn as arrows from the origin
+    pub local_umap_running: bool,
+    pub notification: Option<String>,
+    pub notification_timer: Option<Timeout>,   //Dropping it keeps the notification
+    pub algorithm_params: HashMap<String, f32>,   // hyperparameters to recompute the reduction with, by name
+    pub show_algorithm_params: bool,
+    pub show_parallel_coords: bool,
+    pub show_state_heatmap: bool,
+    pub show_cell_table: bool,
+    pub show_correlation_matrix: bool,
+    pub show_pseudotime_chart: bool,   // mean of the numeric coloring along the trajectory, when there is one
+    pub violin_column: Option<PerCellDataSource>,   // numeric column whose distribution in each category is shown in the legend
+    pub distribution_distance: Option<(PerCellDataSource, usize, usize, f32)>,   // column, size of both groups, Wasserstein distance
+    pub print_layout: bool,   // only the reduction, at print resolution
+    pub parallel_coords_cell: Option<usize>,   //Line clicked in the parallel coordinates plot; marked in the reduction
+    pub recompute_timer: Option<Timeout>,   //Dropping it cancels the recomputation
+    pub recompute_running: bool,
+    pub trajectory: Option<Vec<usize>>,   // ordered cells
+    pub collab_sessions: Vec<CollabSession>,   // one per connected peer
+    pub collab_peer_id: u32,
+    pub collab_local_sdp: Option<String>,      // offer or answer, to be given to the other peer
+    pub peer_cursors: BTreeMap<u32, (f32,f32)>,
+    pub forced_camera: Option<Camera2D>,
+    pub forced_selection: Option<Vec<usize>>,
+    pub pending_zoom_to_expressors: Option<(PerCellDataSource, f32)>,   // waiting for the data of the feature
+    pub hovered_feature: Option<String>,   // in the list of features
+    pub grn_cache: HashMap<String, GrnResponse>,   // regulatory network around each feature, once loaded
+    pub cluster_card: Option<(Vec<usize>, Option<Vec<(String, f32)>>)>,   // cells of the group with a summary card, and their top features once loaded
+    pub focus_cell: Option<usize>,   // the reduction centers on this cell, e.g. the single result of a search
+    pub purity_columns: Option<(String, String)>,   // predicted clusters, ground truth
+    pub transition_columns: Option<(String, String)>,   // categories cells come from, go to
+    pub reduction_comparison: Option<(String, String)>,   // reductions whose coordinates are plotted against each other
+    pub projection_comparison: Option<(String, String)>,   // UMAP whose x is plotted against PC1 of the PCA
+    pub batch_comparison: Option<(String, String)>,   // reductions before and after batch correction
+    pub group_by: GroupByMode,
+    pub markers: AsyncData<FindMarkersResponse>,   // not loaded unless the panel is open
+    pub markers_num_cells: (usize, usize),        // selected, others
+    pub hovered_group: Vec<usize>,
+    pub reduction_command: Option<(u32, ReductionCommand)>,   // sequence number, so the same command can be run again
+    pub command_palette_open: bool,
+    pub on_keydown: Closure<dyn Fn(KeyboardEvent)>,
 }
 impl Component for Model {
 
@@ -81,18 +405,132 @@ impl Component for Model {
 
         //Get initial data to show
         ctx.link().send_message(Msg::GetDatasetDesc());
-        ctx.link().send_message(Msg::GetReduction("kraken_umap".into()));
+        ctx.link().send_message(Msg::GetMetadataSchema());
+        ctx.link().send_message(Msg::GetSampleAnnotation());
+
+        //A shared URL tells what to show; otherwise the default reduction
+        let url_snapshot = window().and_then(|w| w.location().search().ok()).and_then(|query| parse_query(&query));
+        match url_snapshot {
+            Some(snapshot) => {
+                if snapshot.current_reduction.is_none() {
+                    ctx.link().send_message(Msg::GetReduction("kraken_umap".into()));
+                }
+                ctx.link().send_message(Msg::ImportState(snapshot_to_json(&snapshot)));
+            },
+            None => {
+                ctx.link().send_message(Msg::GetReduction("kraken_umap".into()));
+            }
+        }
 
         let current_data = Arc::new(Mutex::new(BiscviData::new()));
 
+        //Keyboard shortcuts: Ctrl+[ and Ctrl+] to undo/redo coloring, Ctrl+K for the command palette
+        let link = ctx.link().clone();
+        let on_keydown = Closure::wrap(Box::new(move |e: KeyboardEvent| {
+            if e.ctrl_key() {
+                match e.code().as_str() {
+                    "BracketLeft" => {
+                        e.prevent_default();
+                        link.send_message(Msg::UndoColoring);
+                    },
+                    "BracketRight" => {
+                        e.prevent_default();
+                        link.send_message(Msg::RedoColoring);
+                    },
+                    "KeyK" => {
+                        e.prevent_default();
+                        link.send_message(Msg::ToggleCommandPalette);
+                    },
+                    _ => {}
+                }
+            }
+        }) as Box<dyn Fn(KeyboardEvent)>);
+        window().expect("no window")
+            .add_event_listener_with_callback("keydown", on_keydown.as_ref().unchecked_ref())
+            .expect("Could not add keyboard listener");
+
         Self {
             current_page: CurrentPage::Home,
             current_reduction: None,
             current_datadesc: AsyncData::NotLoaded,
+            metadata_schema: AsyncData::NotLoaded,
+            sample_annotation: AsyncData::NotLoaded,
+            cells_per_sample: None,
+            display_subset: None,
+            sample_cell_counts: Vec::new(),
+            selected_samples: HashSet::new(),
+            cell_ids: AsyncData::NotLoaded,
+            pending_color_table: None,
+            last_camera: None,
+            pending_camera: None,
+            custom_colors: Vec::new(),
+            active_tool: None,
+            last_url_query: String::new(),
+            url_selection_too_large: false,
+            cell_annotations: HashMap::new(),
+            cell_search_query: String::new(),
+            cell_search_pending: false,
+            cell_search_num_found: None,
+            debounce_timer: None,
             current_data: current_data,
             color_umap_by: ReductionColoring::None,
             last_component_size: ComponentSize { width: 100.0, height: 100.0 },
             current_colorby: PerCellDataSource::Metadata("".into()),
+            column_summary: HashMap::new(),
+            hovered_cell: None,
+            cell_top_genes: HashMap::new(),
+            sync_color_scale: false,
+            last_clicked_cells: Vec::new(),
+            compare_cells: None,
+            coloring_history: VecDeque::from([(ReductionColoring::None, describe_coloring(&ReductionColoring::None))]),
+            coloring_cursor: 0,
+            expression_threshold: None,
+            expression_filter: None,
+            focus_context: FocusContextMode::Disabled,
+            color_simulation: ColorSimulationMode::Normal,
+            category_gradient: false,
+            geo_mask: None,
+            biplot_arrows: Vec::new(),
+            local_umap_running: false,
+            notification: None,
+            notification_timer: None,
+            algorithm_params: HashMap::new(),
+            show_algorithm_params: false,
+            show_parallel_coords: false,
+            show_state_heatmap: false,
+            show_cell_table: false,
+            show_correlation_matrix: false,
+            show_pseudotime_chart: false,
+            violin_column: None,
+            distribution_distance: None,
+            print_layout: false,
+            parallel_coords_cell: None,
+            recompute_timer: None,
+            recompute_running: false,
+            trajectory: None,
+            collab_sessions: Vec::new(),
+            collab_peer_id: rand::random(),
+            collab_local_sdp: None,
+            peer_cursors: BTreeMap::new(),
+            forced_camera: None,
+            pending_zoom_to_expressors: None,
+            hovered_feature: None,
+            grn_cache: HashMap::new(),
+            cluster_card: None,
+            forced_selection: None,
+            focus_cell: None,
+            purity_columns: None,
+            transition_columns: None,
+            reduction_comparison: None,
+            projection_comparison: None,
+            batch_comparison: None,
+            group_by: GroupByMode::None,
+            markers: AsyncData::NotLoaded,
+            markers_num_cells: (0, 0),
+            hovered_group: Vec::new(),
+            reduction_command: None,
+            command_palette_open: false,
+            on_keydown: on_keydown,
         }
     }
 
@@ -149,33 +587,153 @@ impl Component for Model {
             // Message: Set reduction data, sent from server
             Msg::SetDatasetDesc(res) => {
                 //log::debug!("got desc {:?}",res);
+
+                //If the current reduction has a newer version, get it
+                if let Some(current_reduction) = &self.current_reduction {
+                    if let AsyncData::Loaded(red) = self.current_data.lock().unwrap().get_reduction(current_reduction) {
+                        if let Some(desc_red) = res.reductions.get(current_reduction) {
+                            if desc_red.version != red.version {
+                                ctx.link().send_message(Msg::GetReduction(current_reduction.clone()));
+                            }
+                        }
+                    }
+                }
+
                 self.current_datadesc = AsyncData::new(res);
+
+                //Columns loaded before the number of cells was known have not been summarized yet
+                let loaded_columns: Vec<(String, Arc<CountFileMetaColumnData>)> = self.current_data.lock().unwrap().metadatas.iter()
+                    .filter_map(|(name, data)| match (name, data) {
+                        (PerCellDataSource::Metadata(column_name), AsyncData::Loaded(data)) => Some((column_name.clone(), data.clone())),
+                        _ => None
+                    })
+                    .collect();
+                for (column_name, data) in loaded_columns {
+                    self.summarize_column(&column_name, &data);
+                }
                 true
             },
 
 
-            ////////////////////////////////////////////////////////////      ////////////////// call only when data needed?
-            // Message: Get a given reduction
-            Msg::GetReduction(reduction_name) => {
+            ////////////////////////////////////////////////////////////
+            // Message: Get names and types of metadata columns
+            Msg::GetMetadataSchema() => {
+                let query = MetadataSchemaRequest {
+                };
+                let query_json = serde_json::to_vec(&query).expect("Could not convert to json");
+                
+                let get_data = async move {
+                    let client = reqwest::Client::new();
+                    let res = client.post(format!("{}/get_metadata_schema",get_host_url()))
+                        .header("Content-Type", "application/json")
+                        .body(query_json) 
+                        .send()
+                        .await
+                        .expect("Failed to send request")
+                        .bytes()
+                        .await
+                        .expect("Could not get binary data");
+                    let res = serde_cbor::from_reader(res.reader()).expect("Failed to deserialize");
+                    Msg::SetMetadataSchema(res)
+                };
+                ctx.link().send_future(get_data);
+                false
+            },
 
-                //Show new reduction
-                log::debug!("ask for reduction {:?}",reduction_name);
-                self.current_reduction = Some(reduction_name.clone());
+            ////////////////////////////////////////////////////////////
+            // Message: Set names and types of metadata columns, sent from server
+            Msg::SetMetadataSchema(res) => {
+                self.metadata_schema = AsyncData::new(res);
+                true
+            },
 
-                //Insert a loading place holder until data received
-                let mut current_data = self.current_data.lock().unwrap();
-                current_data.reductions.insert(reduction_name.clone(), AsyncData::Loading);
-                log::debug!("for now added Loading reduction {:?}",reduction_name);
 
-                //Request data
-                let query = ReductionRequest {
-                    reduction_name: reduction_name.clone()
+            ////////////////////////////////////////////////////////////
+            // Message: Get the sample of each cell
+            Msg::GetSampleAnnotation() => {
+                let query = SampleAnnotationRequest {
+                };
+                let query_json = serde_json::to_vec(&query).expect("Could not convert to json");
+                
+                let get_data = async move {
+                    let client = reqwest::Client::new();
+                    let res = client.post(format!("{}/get_sample_annotation",get_host_url()))
+                        .header("Content-Type", "application/json")
+                        .body(query_json) 
+                        .send()
+                        .await
+                        .expect("Failed to send request")
+                        .bytes()
+                        .await
+                        .expect("Could not get binary data");
+                    let res = serde_cbor::from_reader(res.reader()).expect("Failed to deserialize");
+                    Msg::SetSampleAnnotation(res)
+                };
+                ctx.link().send_future(get_data);
+                false
+            },
+
+            ////////////////////////////////////////////////////////////
+            // Message: Set the sample of each cell, sent from server
+            Msg::SetSampleAnnotation(res) => {
+                if let Some(annotation) = res.annotation {
+                    self.sample_cell_counts = annotation.count_cells();
+                    self.sample_annotation = AsyncData::new(annotation);
+                }
+                self.selected_samples.clear();
+                self.display_subset = None;
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Draw a random subset with the same number of cells from each sample, or all cells
+            Msg::SetSampleNormalization(on) => {
+                if !on {
+                    return self.display_subset.take().is_some();
+                }
+                let AsyncData::Loaded(annotation) = &self.sample_annotation else {
+                    ctx.link().send_message(Msg::ShowNotification("The dataset has no samples to normalize by".to_string()));
+                    return false;
+                };
+                let subset = sample_normalized_indices(&annotation.sample_id, annotation.sample_names.len(), self.cells_per_sample);
+                ctx.link().send_message(Msg::ShowNotification(format!("Showing {} cells, sampled evenly across {} samples", format_count(subset.len()), annotation.sample_names.len())));
+                self.display_subset = Some(Arc::new(subset));
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Ask for the number of cells to draw of each sample, then normalize by sample
+            Msg::PromptCellsPerSample => {
+                let answer = window()
+                    .and_then(|w| w.prompt_with_message("Cells to show per sample. Leave empty for the size of the smallest sample").ok().flatten());
+                let Some(answer) = answer else {
+                    return false;
+                };
+                let answer = answer.trim();
+                if answer.is_empty() {
+                    self.cells_per_sample = None;
+                } else if let Ok(n) = answer.parse::<usize>() {
+                    self.cells_per_sample = Some(n);
+                } else {
+                    ctx.link().send_message(Msg::ShowNotification(format!("Not a number of cells: {}", answer)));
+                    return false;
+                }
+                ctx.link().send_message(Msg::SetSampleNormalization(true));
+                false
+            },
+
+            ////////////////////////////////////////////////////////////
+            // Message: Get the ID of each cell
+            Msg::GetCellIds() => {
+                let query = CellIdsRequest {
                 };
                 let query_json = serde_json::to_vec(&query).expect("Could not convert to json");
 
                 let get_data = async move {
                     let client = reqwest::Client::new();
-                    let res = client.post(format!("{}/get_reduction",get_host_url()))
+                    let res = client.post(format!("{}/get_cell_ids",get_host_url()))
                         .header("Content-Type", "application/json")
                         .body(query_json) 
                         .send()
@@ -184,109 +742,295 @@ impl Component for Model {
                         .bytes()
                         .await
                         .expect("Could not get binary data");
-                    //log::debug!("sent reduction request {:?}",res);
                     let res = serde_cbor::from_reader(res.reader()).expect("Failed to deserialize");
-                    Msg::SetReduction(reduction_name, res)
+                    Msg::SetCellIds(res)
                 };
+                self.cell_ids = AsyncData::Loading;
                 ctx.link().send_future(get_data);
+                false
+            },
 
-                true //can already show loading status, so true
+            ////////////////////////////////////////////////////////////
+            // Message: The reduction was zoomed to show other details. IDs of cells are shown when zoomed in close
+            Msg::ZoomLevelChanged(zoom_level) => {
+                if zoom_level == ZoomLevel::Cell && self.cell_ids == AsyncData::NotLoaded {
+                    ctx.link().send_message(Msg::GetCellIds());
+                }
+                false
             },
 
+            ////////////////////////////////////////////////////////////
+            // Message: Set the ID of each cell, sent from server
+            Msg::SetCellIds(res) => {
+                self.cell_ids = AsyncData::new(res.cell_ids);
+                if let Some(csv) = self.pending_color_table.take() {
+                    ctx.link().send_message(Msg::ImportColorTable(csv));
+                }
+                false
+            },
 
+            ////////////////////////////////////////////////////////////
+            // Message: Search text edited. The search is only run once the user stops typing
+            Msg::SearchCells(query) => {
+                self.cell_search_query = query.clone();
+                self.cell_search_num_found = None;
+                if query.trim().is_empty() {
+                    self.debounce_timer = None;
+                    self.cell_search_pending = false;
+                } else {
+                    let link = ctx.link().clone();
+                    self.debounce_timer = Some(Timeout::new(CELL_SEARCH_DEBOUNCE_MS, move || {
+                        link.send_message(Msg::RunCellSearch(query));
+                    }));
+                    self.cell_search_pending = true;
+                }
+                true
+            },
 
             ////////////////////////////////////////////////////////////
-            // Message: Set reduction data, sent from server
-            Msg::SetReduction(reduction_name, res) => {
-                //log::debug!("set reduction from server {} :: {:?}; this should trigger a refresh??",reduction_name, res);
-                log::debug!("set reduction from server {} ",reduction_name);
+            // Message: Search for cells on the server
+            Msg::RunCellSearch(query) => {
+                self.debounce_timer = None;
+                let search = SearchCellsRequest {
+                    query: query.trim().to_string(),
+                };
+                let query_json = serde_json::to_vec(&search).expect("Could not convert to json");
 
-                let mut current_data = self.current_data.lock().unwrap();
-                let umap_data = convert_from_response_to_reduction_data(res);
-                
-                current_data.reductions.insert(reduction_name, AsyncData::new(umap_data));
+                let get_data = async move {
+                    let client = reqwest::Client::new();
+                    let res = client.post(format!("{}/search_cells",get_host_url()))
+                        .header("Content-Type", "application/json")
+                        .body(query_json) 
+                        .send()
+                        .await
+                        .expect("Failed to send request")
+                        .bytes()
+                        .await
+                        .expect("Could not get binary data");
+                    let res = serde_cbor::from_reader(res.reader()).expect("Failed to deserialize");
+                    Msg::SetCellSearchResult(query, res)
+                };
+                ctx.link().send_future(get_data);
+                false
+            },
 
+            ////////////////////////////////////////////////////////////
+            // Message: Cells found, sent from server. Results of outdated searches are ignored
+            Msg::SetCellSearchResult(query, res) => {
+                if query != self.cell_search_query || self.debounce_timer.is_some() {
+                    return false;
+                }
+                self.cell_search_pending = false;
+                self.cell_search_num_found = Some(res.cells.len());
+                self.focus_cell = if res.cells.len() == 1 { res.cells.first().cloned() } else { None };
+                self.forced_selection = Some(res.cells);
                 true
             },
 
+            ////////////////////////////////////////////////////////////
+            // Message: Color cells using a table of cell ID and color. Cells not in the table are gray
+            Msg::ImportColorTable(csv) => {
+                let AsyncData::Loaded(cell_ids) = &self.cell_ids else {
+                    //Import once the cell IDs are known
+                    if !matches!(self.cell_ids, AsyncData::Loading) {
+                        ctx.link().send_message(Msg::GetCellIds());
+                    }
+                    self.pending_color_table = Some(csv);
+                    return false;
+                };
+
+                let table = parse_color_table(&csv, cell_ids);
+                log::debug!("Imported colors for {} cells, skipped {} rows", table.num_matched, table.num_skipped);
+                if table.num_matched == 0 {
+                    alert("No cell in the color table matches the cells of this dataset");
+                    return false;
+                }
+
+                self.custom_colors = table.colors;
+                self.push_coloring_history(ReductionColoring::Custom);
+                self.color_umap_by = ReductionColoring::Custom;
+                self.current_colorby = PerCellDataSource::Metadata("".into());
+                true
+            },
 
             ////////////////////////////////////////////////////////////
-            // Message: Set reduction data, sent from server
-            Msg::RequestSetColorByMeta(name) => {   //name??
+            // Message: Select or deselect all cells of a sample
+            Msg::ToggleSample(sample) => {
+                if !self.selected_samples.remove(&sample) {
+                    self.selected_samples.insert(sample);
+                }
+                true
+            },
 
-                log::debug!("RequestSetColorByMeta {} ",name);
 
-                let has_data = self.current_data.lock().unwrap().metadatas.contains_key(&name);
+            ////////////////////////////////////////////////////////////      ////////////////// call only when data needed?
+            // Message: Get a given reduction
+            Msg::GetReduction(reduction_name) => {
 
-                //For now, point to show new data. But we might not yet have it
-                self.current_colorby = name.clone();
-                ctx.link().send_message(Msg::SetColorByMeta(name.clone(), None));
+                //Show new reduction
+                log::debug!("ask for reduction {:?}",reduction_name);
+                self.current_reduction = Some(reduction_name.clone());
+                ctx.link().send_message(Msg::FetchReduction(reduction_name));
+                self.refresh_cluster_proportion(ctx);
+                true
+            },
 
-                //If needed, request data
-                if !has_data {
 
-                    match &name {
-                        PerCellDataSource::Metadata(column_name) => {
+            ////////////////////////////////////////////////////////////
+            // Message: Load a given reduction, without showing it
+            Msg::FetchReduction(reduction_name) => {
 
-                            let query: MetadataColumnRequest = MetadataColumnRequest {
-                                column_name: column_name.clone(),
-                            };
-                            let query_json = serde_json::to_vec(&query).expect("Could not convert to json");
+                //If an older version is loaded, and not too old, only get the points that moved
+                let loaded_version = match self.current_data.lock().unwrap().get_reduction(&reduction_name) {
+                    AsyncData::Loaded(red) => Some(red.version),
+                    _ => None
+                };
+                let latest_version = match &self.current_datadesc {
+                    AsyncData::Loaded(desc) => desc.reductions.get(&reduction_name).map(|r| r.version),
+                    _ => None
+                };
+                if let (Some(from_version), Some(to_version)) = (loaded_version, latest_version) {
+                    if to_version > from_version && to_version - from_version <= MAX_REDUCTION_DELTA_GAP {
+                        ctx.link().send_message(Msg::GetReductionDelta(reduction_name, from_version, to_version));
+                        return true;
+                    }
+                }
 
-                            let name=name.clone();
-                            let get_data = async move {
-                                let client = reqwest::Client::new();
-                                let res = client.post(format!("{}/get_metacolumn",get_host_url())) 
-                                    .header("Content-Type", "application/json")
-                                    .body(query_json) 
-                                    .send()
-                                    .await
-                                    .expect("Failed to send request")
-                                    .bytes()
-                                    .await
-                                    .expect("Could not get binary data");
-                                let res: MetadataColumnResponse  = serde_cbor::from_reader(res.reader()).expect("Failed to deserialize");
-
-                                log::debug!("got MetadataColumnRequest response {:?}",res);
-
-                                Msg::SetColorByMeta(name, Some(res))
-                            };
-                            ctx.link().send_future(get_data);                            
+                //Insert a loading place holder until data received
+                let mut current_data = self.current_data.lock().unwrap();
+                current_data.reductions.insert(reduction_name.clone(), AsyncData::Loading);
+                log::debug!("for now added Loading reduction {:?}",reduction_name);
 
-                        },
-                        PerCellDataSource::Counts(counts_name, feature_name) => {
+                drop(current_data);
+                self.fetch_reduction(ctx, reduction_name, FetchRetry::default());
 
-                            let query = FeatureCountsRequest {
-                                counts_name: counts_name.clone(),
-                                row: 0, // column_name.clone(),   feature_name
-                            };
-                            let query_json = serde_json::to_vec(&query).expect("Could not convert to json");
+                true //can already show loading status, so true
+            },
 
-                            let name=name.clone();
-                            let get_data = async move {
-                                let client = reqwest::Client::new();
-                                let res = client.post(format!("{}/get_featurecounts",get_host_url()))  /////////////////////////////////
-                                    .header("Content-Type", "application/json")
-                                    .body(query_json) 
-                                    .send()
-                                    .await
-                                    .expect("Failed to send request")
-                                    .bytes()
-                                    .await
-                                    .expect("Could not get binary data");
-                                let res: MetadataColumnResponse  = serde_cbor::from_reader(res.reader()).expect("Failed to deserialize");
-
-                                log::debug!("got FeatureCountsRequest response {:?}",res);
-
-                                Msg::SetColorByMeta(name, Some(res))
-                            };
-                            ctx.link().send_future(get_data);
 
+
+            ////////////////////////////////////////////////////////////
+            // Message: Set reduction data, sent from server
+            Msg::SetReduction(reduction_name, umap_data) => {
+                //log::debug!("set reduction from server {} :: {:?}; this should trigger a refresh??",reduction_name, res);
+                log::debug!("set reduction from server {} ",reduction_name);
+
+                let mut current_data = self.current_data.lock().unwrap();
+                match umap_data {
+                    Ok(umap_data) => {
+                        let is_current = self.current_reduction.as_ref() == Some(&reduction_name);
+                        current_data.reductions.insert(reduction_name, AsyncData::new(umap_data));
+                        drop(current_data);
+                        self.refresh_cluster_proportion(ctx);
+
+                        //The view fits the camera to a new reduction as it is drawn, so an imported camera is set after that
+                        if is_current && self.pending_camera.is_some() {
+                            let link = ctx.link().clone();
+                            Timeout::new(0, move || link.send_message(Msg::ApplyPendingCamera)).forget();
+                        }
+                    },
+                    Err(e) => {
+                        log::error!("Invalid reduction {}: {}", reduction_name, e);
+                        current_data.reductions.remove(&reduction_name);
+                        alert(&format!("Could not load reduction {}: {}", reduction_name, e));
+                    }
+                }
+
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: A request for data failed. Try again after a while, waiting longer each time, or give up
+            Msg::FetchFailed(target, retry, e) => {
+                if let Some(next_retry) = retry.next() {
+                    log::warn!("Could not load {}, retry {} in {}ms: {}", target, next_retry.retry_count, next_retry.retry_delay_ms, e);
+                    let link = ctx.link().clone();
+                    Timeout::new(next_retry.retry_delay_ms, move || {
+                        link.send_message(Msg::RetryFetch(target, next_retry));
+                    }).forget();
+                    false
+                } else {
+                    log::error!("Could not load {}: {}", target, e);
+                    let mut current_data = self.current_data.lock().unwrap();
+                    match &target {
+                        FetchTarget::Reduction(name) => {
+                            current_data.reductions.insert(name.clone(), AsyncData::Error(e.clone()));
                         },
+                        FetchTarget::PerCellData(name) => {
+                            current_data.metadatas.insert(name.clone(), AsyncData::Error(e.clone()));
+                        },
+                    }
+                    drop(current_data);
+                    ctx.link().send_message(Msg::ShowNotification(format!("Could not load {}: {}", target, e)));
+                    true
+                }
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Request data again, after an earlier request failed
+            Msg::RetryFetch(target, retry) => {
+                match target {
+                    FetchTarget::Reduction(name) => self.fetch_reduction(ctx, name, retry),
+                    FetchTarget::PerCellData(name) => self.fetch_per_cell_data(ctx, name, retry),
+                }
+                false
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Get the change of a reduction between two versions. If the server no longer
+            // has the loaded version, the whole reduction is fetched again instead
+            Msg::GetReductionDelta(reduction_name, from_version, to_version) => {
+                log::debug!("ask for reduction delta {:?} {} -> {}",reduction_name, from_version, to_version);
+
+                let query = ReductionDeltaRequest {
+                    reduction_name: reduction_name.clone(),
+                    from_version: from_version,
+                    to_version: to_version,
+                };
+                let query_json = serde_json::to_vec(&query).expect("Could not convert to json");
+
+                let get_data = async move {
+                    let res = match post_for_bytes(format!("{}/get_reduction_delta",get_host_url()), query_json, None).await {
+                        Ok((res, _content_type)) => res,
+                        Err(e) => return Msg::FetchFailed(FetchTarget::Reduction(reduction_name), FetchRetry::default(), e)
+                    };
+                    match serde_cbor::from_reader(res.reader()) {
+                        Ok(res) => Msg::SetReductionDelta(reduction_name, to_version, res),
+                        Err(e) => Msg::FetchFailed(FetchTarget::Reduction(reduction_name), FetchRetry::default(), format!("Failed to deserialize: {}", e))
                     }
+                };
+                ctx.link().send_future(get_data);
+                false
+            },
+
 
+            ////////////////////////////////////////////////////////////
+            // Message: Apply change of reduction, sent from server
+            Msg::SetReductionDelta(reduction_name, to_version, delta) => {
+                log::debug!("set reduction delta from server {}, {} points moved",reduction_name, delta.moved_indices.len());
 
+                let mut current_data = self.current_data.lock().unwrap();
+                if let AsyncData::Loaded(red) = current_data.get_reduction(&reduction_name) {
+                    //Copy, so that yew sees a new reduction
+                    let mut red = red.as_ref().clone();
+                    apply_delta(&mut red, delta);
+                    red.version = to_version;
+                    current_data.reductions.insert(reduction_name, AsyncData::new(red));
                 }
+                drop(current_data);
+                self.refresh_cluster_proportion(ctx);
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Set reduction data, sent from server
+            Msg::RequestSetColorByMeta(name) => {   //name??
+                self.push_coloring_history(ReductionColoring::ByMeta(name.clone()));
+                self.set_color_by_meta(ctx, name);
                 false
             },
 
@@ -299,8 +1043,23 @@ impl Component for Model {
 
                 //Update data if needed
                 if let Some(res) = res {
+                    //Summarize metadata columns the first time they are loaded
+                    if let PerCellDataSource::Metadata(column_name) = &name {
+                        self.summarize_column(column_name, &res.data);
+                    }
+
                     let mut current_data = self.current_data.lock().unwrap();
                     current_data.metadatas.insert(name.clone(), AsyncData::new(res.data));
+                    drop(current_data);
+                    self.resume_zoom_to_expressors(ctx, &name);
+
+                    //A ratio in the current coloring may be waiting for this feature. The coloring stays the ratio
+                    if let (PerCellDataSource::ExpressionRatio { gene_a, gene_b }, PerCellDataSource::Counts(_, feature_name)) = (&self.current_colorby, &name) {
+                        if feature_name == gene_a || feature_name == gene_b {
+                            ctx.link().send_message(Msg::ComputeExpressionRatio(self.current_colorby.clone()));
+                            return false;
+                        }
+                    }
                 }
                 self.color_umap_by = ReductionColoring::ByMeta(name);  //TODO: could compare by pointer to force updates
                 true
@@ -308,16 +1067,1392 @@ impl Component for Model {
 
 
             ////////////////////////////////////////////////////////////
-            // Message: Window is resized
-            Msg::WindowResize(size) => {  
-                self.last_component_size = size;
-                true
-            }
-
+            // Message: Compute the fraction of neighbors in a category for each cell, if the data is there yet
+            Msg::ComputeClusterProportion(name) => {
+                let PerCellDataSource::ClusterProportionScore { base_column, category_index, k } = &name else {
+                    return false;
+                };
+                let Some(reduction_name) = self.current_reduction.clone() else {
+                    return false;
+                };
 
+                let mut current_data = self.current_data.lock().unwrap();
+                let (AsyncData::Loaded(reduction), AsyncData::Loaded(base_data)) = (
+                    current_data.get_reduction(&reduction_name),
+                    current_data.get_metadata(&PerCellDataSource::Metadata(base_column.clone()))
+                ) else {
+                    return false;
+                };
 
-        }
-    }
+                if let Some(values) = compute_cluster_proportion(&reduction, &base_data, *category_index, *k) {
+                    current_data.metadatas.insert(name, AsyncData::new(CountFileMetaColumnData::Numeric(values)));
+                    true
+                } else {
+                    log::error!("Cannot compute proportion of neighbors; {} is not categorical", base_column);
+                    false
+                }
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Compute the log ratio of two features for each cell, if the data is there yet
+            Msg::ComputeExpressionRatio(name) => {
+                let PerCellDataSource::ExpressionRatio { gene_a, gene_b } = &name else {
+                    return false;
+                };
+                let (Some(counts_name), Some(reduction_name)) = (self.get_default_counts_name(), self.current_reduction.clone()) else {
+                    return false;
+                };
+
+                let mut current_data = self.current_data.lock().unwrap();
+                let (AsyncData::Loaded(reduction), AsyncData::Loaded(data_a), AsyncData::Loaded(data_b)) = (
+                    current_data.get_reduction(&reduction_name),
+                    current_data.get_metadata(&PerCellDataSource::Counts(counts_name.clone(), gene_a.clone())),
+                    current_data.get_metadata(&PerCellDataSource::Counts(counts_name.clone(), gene_b.clone()))
+                ) else {
+                    return false;
+                };
+
+                if let Some(values) = expression_log_ratio(&data_a, &data_b, reduction.num_point) {
+                    current_data.metadatas.insert(name.clone(), AsyncData::new(CountFileMetaColumnData::Numeric(values)));
+                    drop(current_data);
+                    self.resume_zoom_to_expressors(ctx, &name);
+                    true
+                } else {
+                    log::error!("Cannot compute ratio of {} and {}; they are not numeric", gene_a, gene_b);
+                    false
+                }
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Ask for two features, and color by the log ratio of their expression
+            Msg::PromptExpressionRatio => {
+                let answer = window()
+                    .and_then(|w| w.prompt_with_message("Color by the ratio of two features, e.g. CD4/CD8A").ok().flatten())
+                    .unwrap_or_default();
+                let Some((gene_a, gene_b)) = answer.split_once('/') else {
+                    return false;
+                };
+                let (gene_a, gene_b) = (gene_a.trim(), gene_b.trim());
+                if gene_a.is_empty() || gene_b.is_empty() {
+                    return false;
+                }
+                ctx.link().send_message(Msg::RequestSetColorByMeta(PerCellDataSource::ExpressionRatio {
+                    gene_a: gene_a.to_string(),
+                    gene_b: gene_b.to_string(),
+                }));
+                false
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Zoom to the cells where a feature is above a threshold. The reduction is
+            // colored by the feature, which also requests its data if needed
+            Msg::ZoomToExpressors { gene, threshold } => {
+                let Some(reduction_name) = self.current_reduction.clone() else {
+                    return false;
+                };
+                let current_data = self.current_data.lock().unwrap();
+                let (AsyncData::Loaded(reduction), Some(AsyncData::Loaded(data))) = (current_data.get_reduction(&reduction_name), current_data.metadatas.get(&gene).cloned()) else {
+                    drop(current_data);
+                    self.pending_zoom_to_expressors = Some((gene.clone(), threshold));
+                    ctx.link().send_message(Msg::RequestSetColorByMeta(gene));
+                    return false;
+                };
+                drop(current_data);
+                self.pending_zoom_to_expressors = None;
+
+                let Some(values) = dense_numeric_values(&data, reduction.num_point) else {
+                    ctx.link().send_message(Msg::ShowNotification(format!("{} is not numeric", gene)));
+                    return false;
+                };
+
+                let Some((rect, num_cells)) = expressor_bounds(&reduction, &values, threshold) else {
+                    ctx.link().send_message(Msg::ShowNotification(format!("No cells with {} above {}", gene, threshold)));
+                    return false;
+                };
+
+                let mut camera = self.last_camera.clone().unwrap_or_else(Camera2D::new);
+                camera.fit_rect(&rect);
+                self.forced_camera = Some(camera);
+                ctx.link().send_message(Msg::ShowNotification(format!("{} cells with {} above {}", num_cells, gene, threshold)));
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Ask for a feature and threshold, and zoom to the cells expressing it
+            Msg::PromptZoomToExpressors => {
+                let answer = window()
+                    .and_then(|w| w.prompt_with_message("Zoom to cells expressing a feature, e.g. CD4 or CD4 > 1").ok().flatten())
+                    .unwrap_or_default();
+                let (gene, threshold) = match answer.split_once('>') {
+                    Some((gene, threshold)) => (gene.trim(), threshold.trim().parse::<f32>().ok()),
+                    None => (answer.trim(), Some(0.0)),
+                };
+                let (Some(counts_name), Some(threshold)) = (self.get_default_counts_name(), threshold) else {
+                    return false;
+                };
+                if gene.is_empty() {
+                    return false;
+                }
+                ctx.link().send_message(Msg::ZoomToExpressors {
+                    gene: PerCellDataSource::Counts(counts_name, gene.to_string()),
+                    threshold: threshold,
+                });
+                false
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Window is resized
+            Msg::WindowResize(size) => {  
+                self.last_component_size = size;
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Find the features that differ the most between the selected cells and all others
+            Msg::FindMarkers => {
+                let num_cells = match self.current_reduction.as_ref().map(|name| self.current_data.lock().unwrap().get_reduction(name)) {
+                    Some(AsyncData::Loaded(reduction)) => reduction.num_point,
+                    _ => return false,
+                };
+                let Some(counts_name) = self.get_default_counts_name() else {
+                    return false;
+                };
+
+                let selected: HashSet<usize> = self.last_clicked_cells.iter().cloned().filter(|i| *i < num_cells).collect();
+                let mut group_a: Vec<usize> = selected.iter().cloned().collect();
+                group_a.sort();
+                let group_b: Vec<usize> = (0..num_cells).filter(|i| !selected.contains(i)).collect();
+                if group_a.is_empty() || group_b.is_empty() {
+                    return false;
+                }
+                self.markers_num_cells = (group_a.len(), group_b.len());
+                self.markers = AsyncData::Loading;
+
+                let query = FindMarkersRequest {
+                    counts_name: counts_name,
+                    group_a: group_a,
+                    group_b: group_b,
+                };
+                let query_json = serde_json::to_vec(&query).expect("Could not convert to json");
+
+                let get_data = async move {
+                    let res = match post_for_bytes(format!("{}/find_markers",get_host_url()), query_json, None).await {
+                        Ok((res, _content_type)) => serde_cbor::from_reader::<FindMarkersResponse, _>(res.reader())
+                            .map_err(|e| format!("Failed to deserialize: {}", e)),
+                        Err(e) => Err(e)
+                    };
+                    Msg::SetMarkers(res)
+                };
+                ctx.link().send_future(get_data);
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Markers found, sent from server. Ignored if the panel was closed meanwhile
+            Msg::SetMarkers(res) => {
+                if matches!(self.markers, AsyncData::NotLoaded) {
+                    return false;
+                }
+                self.markers = match res {
+                    Ok(res) => AsyncData::new(res),
+                    Err(e) => {
+                        ctx.link().send_message(Msg::ShowNotification(format!("Could not find markers: {}", e)));
+                        AsyncData::Error(e)
+                    }
+                };
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Marker panel closed
+            Msg::CloseMarkers => {
+                self.markers = AsyncData::NotLoaded;
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Show one point per category instead of the cells, loading the column if needed
+            Msg::SetGroupBy(group_by) => {
+                if let GroupByMode::Cluster(column) = &group_by {
+                    ctx.link().send_message(Msg::RequestMetadata(column.clone()));
+                }
+                self.group_by = group_by;
+                self.hovered_group.clear();
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Mouse is over a group of cells, or none if empty
+            Msg::HoverCells(cells) => {
+                self.hovered_group = cells;
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Mouse is over a given cell, or none
+            Msg::HoverCell(cell) => {
+                self.hovered_cell = cell;
+
+                //Get the top features for the tooltip, unless already known
+                if let Some(cell) = cell {
+                    if !self.cell_top_genes.contains_key(&cell) {
+                        if let Some(counts_name) = self.get_default_counts_name() {
+                            let query = CellTopGenesRequest {
+                                counts_name: counts_name,
+                                row: cell as u32,
+                                num_genes: NUM_TOOLTIP_GENES,
+                            };
+                            let query_json = serde_json::to_vec(&query).expect("Could not convert to json");
+
+                            //Failures are remembered as no features, so that hovering does not keep asking
+                            let get_data = async move {
+                                let empty = CellTopGenesResponse { genes: Vec::new() };
+                                let res = match post_for_bytes(format!("{}/get_cell_top_genes",get_host_url()), query_json, None).await {
+                                    Ok((res, _content_type)) => res,
+                                    Err(e) => {
+                                        log::error!("Could not get the top features of cell {}: {}", cell, e);
+                                        return Msg::SetCellTopGenes(cell, empty);
+                                    }
+                                };
+                                match serde_cbor::from_reader(res.reader()) {
+                                    Ok(res) => Msg::SetCellTopGenes(cell, res),
+                                    Err(e) => {
+                                        log::error!("Could not get the top features of cell {}: {}", cell, e);
+                                        Msg::SetCellTopGenes(cell, empty)
+                                    }
+                                }
+                            };
+                            ctx.link().send_future(get_data);
+                        }
+                    }
+                }
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Toggle using the same color scale for all reduction panels
+            Msg::ToggleSyncColorScale => {
+                self.sync_color_scale = !self.sync_color_scale;
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Top features of a cell, sent from server
+            Msg::SetCellTopGenes(cell, res) => {
+                self.cell_top_genes.insert(cell, res.genes);
+                self.hovered_cell == Some(cell)
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Mouse is over a feature in the list, or none. Get its regulatory network, unless already known
+            Msg::HoverFeature(feature_name) => {
+                if feature_name == self.hovered_feature {
+                    return false;
+                }
+                self.hovered_feature = feature_name.clone();
+                if let Some(gene) = feature_name {
+                    if !self.grn_cache.contains_key(&gene) {
+                        let query = GrnRequest {
+                            gene: gene.clone(),
+                            max_edges: NUM_GRN_EDGES,
+                        };
+                        let query_json = serde_json::to_vec(&query).expect("Could not convert to json");
+
+                        //Failures are remembered as an empty network, so that hovering does not keep asking
+                        let get_data = async move {
+                            let empty = GrnResponse { regulators: Vec::new(), targets: Vec::new() };
+                            let res = match post_for_bytes(format!("{}/grn",get_host_url()), query_json, None).await {
+                                Ok((res, _content_type)) => res,
+                                Err(e) => {
+                                    log::error!("Could not get the regulatory network of {}: {}", gene, e);
+                                    return Msg::SetGrn(gene, empty);
+                                }
+                            };
+                            match serde_cbor::from_reader(res.reader()) {
+                                Ok(res) => Msg::SetGrn(gene, res),
+                                Err(e) => {
+                                    log::error!("Could not get the regulatory network of {}: {}", gene, e);
+                                    Msg::SetGrn(gene, empty)
+                                }
+                            }
+                        };
+                        ctx.link().send_future(get_data);
+                    }
+                }
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Regulatory network around a feature, sent from server
+            Msg::SetGrn(gene, res) => {
+                let is_hovered = self.hovered_feature.as_ref() == Some(&gene);
+                self.grn_cache.insert(gene, res);
+                is_hovered
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Summary card of a group opened; get the top features of its cells
+            Msg::RequestClusterTopGenes(cells) => {
+                let Some(counts_name) = self.get_default_counts_name() else {
+                    self.cluster_card = Some((cells, Some(Vec::new())));
+                    return true;
+                };
+                let query = ClusterTopGenesRequest {
+                    counts_name: counts_name,
+                    cells: cells.clone(),
+                    num_genes: NUM_CLUSTER_CARD_GENES,
+                };
+                let query_json = serde_json::to_vec(&query).expect("Could not convert to json");
+                self.cluster_card = Some((cells.clone(), None));
+
+                let get_data = async move {
+                    let res = match post_for_bytes(format!("{}/cluster_top_genes",get_host_url()), query_json, None).await {
+                        Ok((res, _content_type)) => res,
+                        Err(e) => return Msg::ShowNotification(format!("Could not get top features of the group: {}", e))
+                    };
+                    match serde_cbor::from_reader(res.reader()) {
+                        Ok(res) => Msg::SetClusterTopGenes(cells, res),
+                        Err(e) => Msg::ShowNotification(format!("Could not get top features of the group: {}", e))
+                    }
+                };
+                ctx.link().send_future(get_data);
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Top features of a group, sent from server. Dropped if another card was opened since
+            Msg::SetClusterTopGenes(cells, res) => {
+                match &mut self.cluster_card {
+                    Some((card_cells, genes)) if *card_cells == cells => {
+                        *genes = Some(res.genes);
+                        true
+                    },
+                    _ => false
+                }
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Cells have been clicked or selected. Clicked cells are remembered pairwise for comparison
+            Msg::SelectCells(cells) => {
+                if cells.len() == 1 {
+                    self.last_clicked_cells.push(cells[0]);
+                    if self.last_clicked_cells.len() > 2 {
+                        self.last_clicked_cells.remove(0);
+                    }
+                } else {
+                    self.last_clicked_cells = cells.clone();
+                }
+
+                //Share with peers, unless the selection came from them
+                if self.forced_selection.as_ref() != Some(&cells) {
+                    self.broadcast_collab_event(&CollabEvent::SelectionUpdate { cells: cells });
+                }
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Set the annotation of a cell
+            Msg::SetAnnotation(cell, text) => {
+                if text.is_empty() {
+                    self.cell_annotations.remove(&cell);
+                } else {
+                    self.cell_annotations.insert(cell, text);
+                }
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Save all cells of the current reduction, with annotations, as CSV
+            Msg::ExportCsv => {
+                let Some(reduction_name) = &self.current_reduction else {
+                    return false;
+                };
+                let AsyncData::Loaded(reduction) = self.current_data.lock().unwrap().get_reduction(reduction_name) else {
+                    return false;
+                };
+                let cell_ids = match &self.cell_ids {
+                    AsyncData::Loaded(cell_ids) => Some(cell_ids.as_ref()),
+                    _ => None
+                };
+                let csv = build_cells_csv(&reduction, cell_ids, &self.cell_annotations);
+                if let Err(e) = download_text(&format!("{}.csv", reduction_name), "text/csv", &csv) {
+                    log::warn!("Could not export CSV: {:?}", e);
+                }
+                false
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Save what is shown as JSON, so that the view can be restored later
+            Msg::ExportState => {
+                let snapshot = self.get_state_snapshot();
+                if let Err(e) = download_text("biscvi_state.json", "application/json", &snapshot_to_json(&snapshot)) {
+                    log::warn!("Could not export state: {:?}", e);
+                }
+                false
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Let the user choose a snapshot file to import
+            Msg::PickStateFile => {
+                pick_text_file(ctx, ".json,application/json", Msg::ImportState);
+                false
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Let the user choose a GeoJSON file with a mask to import
+            Msg::PickGeoJsonMask => {
+                pick_text_file(ctx, ".geojson,.json,application/geo+json,application/json", Msg::ImportGeoJsonMask);
+                false
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Hide cells of the current reduction outside a polygon, e.g. the outline of a tissue
+            Msg::ImportGeoJsonMask(json) => {
+                let Some(reduction_name) = self.current_reduction.clone() else {
+                    alert("Show a reduction before importing a mask");
+                    return false;
+                };
+                match parse_geojson_polygon(&json) {
+                    Ok(mask) => {
+                        ctx.link().send_message(Msg::ShowNotification(format!("Hiding cells of {} outside the mask", reduction_name)));
+                        self.geo_mask = Some((reduction_name, mask));
+                        true
+                    },
+                    Err(e) => {
+                        alert(&format!("Could not import mask: {}", e));
+                        false
+                    }
+                }
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Show all cells again, after importing a mask
+            Msg::ClearGeoJsonMask => {
+                self.geo_mask = None;
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Let the user choose a CSV file with PCA loadings to draw as a biplot
+            Msg::PickBiplotLoadings => {
+                pick_text_file(ctx, ".csv,text/csv", Msg::ImportBiplotLoadings);
+                false
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Read PCA loadings from a CSV file
+            Msg::ImportBiplotLoadings(csv) => {
+                let loadings = parse_loadings_csv(&csv);
+                if loadings.is_empty() {
+                    alert("No loadings found; expected rows of gene,pc1,pc2");
+                    return false;
+                }
+                ctx.link().send_message(Msg::LoadBiplotArrows(loadings));
+                false
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Show the loadings of the largest magnitude as arrows over the reduction
+            Msg::LoadBiplotArrows(loadings) => {
+                self.biplot_arrows = top_loadings(loadings);
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Restore the view from a snapshot. Data not loaded yet is requested
+            Msg::ImportState(json) => {
+                let snapshot = match parse_snapshot(&json) {
+                    Ok(snapshot) => snapshot,
+                    Err(e) => {
+                        alert(&format!("Could not import state: {}", e));
+                        return false;
+                    }
+                };
+
+                //Show the reduction; the camera is set once it is loaded
+                self.pending_camera = None;
+                if let Some(reduction_name) = &snapshot.current_reduction {
+                    let is_loaded = self.current_reduction.as_ref() == Some(reduction_name)
+                        && matches!(self.current_data.lock().unwrap().get_reduction(reduction_name), AsyncData::Loaded(_));
+                    if is_loaded {
+                        self.forced_camera = snapshot.camera.clone();
+                    } else {
+                        self.pending_camera = snapshot.camera.clone();
+                        ctx.link().send_message(Msg::GetReduction(reduction_name.clone()));
+                    }
+                }
+
+                if snapshot.color_umap_by == ReductionColoring::Custom {
+                    self.custom_colors = snapshot.custom_colors;
+                }
+                self.push_coloring_history(snapshot.color_umap_by);
+                self.apply_coloring_history(ctx, self.coloring_cursor);
+
+                if let Some(tool) = snapshot.tool {
+                    self.active_tool = Some(tool.clone());
+                    ctx.link().send_message(Msg::RunReductionCommand(ReductionCommand::SelectTool(tool)));
+                }
+
+                self.last_clicked_cells = snapshot.selection.clone();
+                self.forced_selection = Some(snapshot.selection);
+                self.cell_annotations = snapshot.cell_annotations;
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Reduction of an imported snapshot has been drawn; move the camera as it was
+            Msg::ApplyPendingCamera => {
+                if let Some(camera) = self.pending_camera.take() {
+                    self.forced_camera = Some(camera);
+                    true
+                } else {
+                    false
+                }
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Compare the numeric metadata of two cells. Get any column not yet loaded
+            Msg::CompareSelectedCells(idx_a, idx_b) => {
+                self.compare_cells = Some((idx_a, idx_b));
+
+                if let AsyncData::Loaded(schema) = &self.metadata_schema {
+                    for column in schema.columns.iter() {
+                        if !column.is_categorical {
+                            ctx.link().send_message(Msg::RequestMetadata(PerCellDataSource::Metadata(column.name.clone())));
+                        }
+                    }
+                }
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Get the data of a metadata column, unless already loaded or loading
+            Msg::RequestMetadata(name) => {
+                if !matches!(name, PerCellDataSource::Metadata(_)) {
+                    return false;
+                }
+
+                let mut current_data = self.current_data.lock().unwrap();
+                if matches!(current_data.metadatas.lookup(&name), Some(AsyncData::Loaded(_)) | Some(AsyncData::Loading)) {
+                    return false;
+                }
+                current_data.metadatas.insert(name.clone(), AsyncData::Loading);
+
+                drop(current_data);
+                self.fetch_per_cell_data(ctx, name, FetchRetry::default());
+                true //show as loading
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Data of a metadata column, sent from server
+            Msg::SetMetadata(name, res) => {
+                if let PerCellDataSource::Metadata(column_name) = &name {
+                    self.summarize_column(column_name, &res.data);
+                }
+                //Scores in the current coloring may be waiting for this column
+                if let PerCellDataSource::ClusterProportionScore { base_column, .. } = &self.current_colorby {
+                    if name == PerCellDataSource::Metadata(base_column.clone()) {
+                        ctx.link().send_message(Msg::ComputeClusterProportion(self.current_colorby.clone()));
+                    }
+                }
+                let mut current_data = self.current_data.lock().unwrap();
+                current_data.metadatas.insert(name.clone(), AsyncData::new(res.data));
+                drop(current_data);
+                self.resume_zoom_to_expressors(ctx, &name);
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Close the cell comparison panel
+            Msg::CloseCellComparison => {
+                self.compare_cells = None;
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Show a column using one color above the threshold, another below
+            Msg::SetExpressionThreshold(column, threshold) => {
+                self.expression_threshold = Some((column, threshold));
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Show the color scale again instead of a threshold
+            Msg::ClearExpressionThreshold => {
+                self.expression_threshold = None;
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Hide cells of the current coloring below a value
+            Msg::SetExpressionFilter(min_value) => {
+                self.expression_filter = Some((self.current_colorby.clone(), min_value));
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Toggle drawing only the middle of the reduction in detail
+            Msg::ToggleFocusContext => {
+                self.focus_context = if self.focus_context==FocusContextMode::Disabled { FocusContextMode::Enabled } else { FocusContextMode::Disabled };
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Show colors as seen with a type of color blindness, or normally
+            Msg::SetColorSimulation(mode) => {
+                self.color_simulation = mode;
+                true
+            },
+
+            ////////////////////////////////////////////////////////////
+            // Message: Switch between discrete colors for categories and a gradient between them
+            Msg::ToggleCategoryGradient => {
+                self.category_gradient = !self.category_gradient;
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Compute a new UMAP of the selected cells, using the numeric columns loaded so far
+            Msg::RunLocalUMAP(selection, n_neighbors) => {
+                let Some(reduction_name) = self.current_reduction.clone() else {
+                    return false;
+                };
+                if self.local_umap_running || selection.len() < 3 || selection.len() > MAX_LOCAL_UMAP_CELLS {
+                    return false;
+                }
+
+                let current_data = self.current_data.lock().unwrap();
+                let AsyncData::Loaded(reduction) = current_data.get_reduction(&reduction_name) else {
+                    return false;
+                };
+                let list_columns: Vec<&CountFileMetaColumnData> = current_data.metadatas.values()
+                    .filter_map(|m| if let AsyncData::Loaded(m) = m { Some(m.as_ref()) } else { None })
+                    .collect();
+                let (positions, graph) = build_local_umap_input(&reduction, &selection, &list_columns, n_neighbors);
+
+                let get_data = async move {
+                    let positions = run_local_umap(positions, graph).await;
+                    Msg::SetLocalUMAP(reduction_name, selection, positions)
+                };
+                ctx.link().send_future(get_data);
+                self.local_umap_running = true;
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: New UMAP of selected cells has been computed. Show it in place of the old positions
+            Msg::SetLocalUMAP(reduction_name, selection, positions) => {
+                self.local_umap_running = false;
+                let positions = match positions {
+                    Ok(positions) => positions,
+                    Err(e) => {
+                        ctx.link().send_message(Msg::ShowNotification(format!("Could not compute UMAP of selection: {}", e)));
+                        return true;
+                    }
+                };
+
+                let mut current_data = self.current_data.lock().unwrap();
+                if let AsyncData::Loaded(reduction) = current_data.get_reduction(&reduction_name) {
+                    let local_name = format!("{}_local", reduction_name.trim_end_matches("_local"));
+                    let local_reduction = place_local_umap(&reduction, &selection, &positions);
+                    current_data.reductions.insert(local_name.clone(), AsyncData::new(local_reduction));
+                    self.current_reduction = Some(local_name);
+                }
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Find communities in the neighbor graph of the current reduction, and color by them
+            Msg::RunLouvain { n_neighbors } => {
+                let Some(reduction_name) = self.current_reduction.clone() else {
+                    return false;
+                };
+                let AsyncData::Loaded(reduction) = self.current_data.lock().unwrap().get_reduction(&reduction_name) else {
+                    return false;
+                };
+
+                let graph = knn_graph_from_reduction(&reduction, n_neighbors);
+                let result = louvain(&graph);
+                let names: Vec<String> = (0..result.num_communities).map(|c| c.to_string()).collect();
+
+                //Stored as any other column, so that it can be colored by. It replaces the communities of any earlier run
+                let name = PerCellDataSource::Metadata(LOUVAIN_COLUMN.to_string());
+                self.current_data.lock().unwrap().metadatas.insert(name.clone(), AsyncData::new(CountFileMetaColumnData::Categorical(result.communities, names)));
+                ctx.link().send_message(Msg::RequestSetColorByMeta(name));
+                ctx.link().send_message(Msg::ShowNotification(format!(
+                    "Louvain: {} communities, modularity {:.3}, converged after {} levels",
+                    result.num_communities, result.modularity, result.num_levels
+                )));
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Reduce noise in a numeric column by spreading values to neighboring cells, and color by the result
+            Msg::SmoothColumn { column, iterations, alpha } => {
+                let Some(reduction_name) = self.current_reduction.clone() else {
+                    return false;
+                };
+                let (reduction, data) = {
+                    let current_data = self.current_data.lock().unwrap();
+                    (current_data.get_reduction(&reduction_name), current_data.get_metadata(&column))
+                };
+                let (AsyncData::Loaded(reduction), AsyncData::Loaded(data)) = (reduction, data) else {
+                    ctx.link().send_message(Msg::ShowNotification(format!("{} is not loaded yet", column_label(&column))));
+                    return false;
+                };
+                let values = match dense_numeric_values(&data, reduction.num_point) {
+                    Some(values) if values.len() == reduction.num_point => values,
+                    _ => {
+                        ctx.link().send_message(Msg::ShowNotification(format!("Only numeric columns can be smoothed; {} is not", column_label(&column))));
+                        return false;
+                    }
+                };
+
+                let matrix = TransitionMatrix::from_graph(&knn_graph_from_reduction(&reduction, DEFAULT_SMOOTH_NEIGHBORS));
+                let smoothed = smooth_over_graph(&matrix, &values, iterations, alpha);
+
+                let name = PerCellDataSource::Metadata(format!("{}_smoothed", column_label(&column)));
+                self.current_data.lock().unwrap().metadatas.insert(name.clone(), AsyncData::new(CountFileMetaColumnData::Numeric(smoothed)));
+                ctx.link().send_message(Msg::RequestSetColorByMeta(name));
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Show a message for a while
+            Msg::ShowNotification(text) => {
+                self.notification = Some(text);
+                let link = ctx.link().clone();
+                self.notification_timer = Some(Timeout::new(NOTIFICATION_MS, move || {
+                    link.send_message(Msg::ClearNotification);
+                }));
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Notification timed out or closed
+            Msg::ClearNotification => {
+                self.notification = None;
+                self.notification_timer = None;
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Show or hide the parallel coordinates plot of the selected cells
+            Msg::ToggleParallelCoords => {
+                self.show_parallel_coords = !self.show_parallel_coords;
+                self.parallel_coords_cell = None;
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Switch to a layout with only the reduction, at print resolution, and print it. Or switch back
+            Msg::TogglePrintLayout => {
+                self.print_layout = !self.print_layout;
+                set_print_style(self.print_layout);
+                if self.print_layout {
+                    let link = ctx.link().clone();
+                    Timeout::new(PRINT_DELAY_MS, move || link.send_message(Msg::PrintPage)).forget();
+                }
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Open the print dialog of the browser
+            Msg::PrintPage => {
+                if let Err(e) = window().expect("no window").print() {
+                    log::warn!("Could not print: {:?}", e);
+                }
+                false
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Show or hide the heatmap of gene expression in the selected cells and the others
+            Msg::ToggleStateHeatmap => {
+                self.show_state_heatmap = !self.show_state_heatmap;
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Show or hide the table of the selected cells
+            Msg::ToggleCellTable => {
+                self.show_cell_table = !self.show_cell_table;
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Show or hide the correlation matrix of the numeric metadata columns. These are loaded when it is shown
+            Msg::ToggleCorrelationMatrix => {
+                self.show_correlation_matrix = !self.show_correlation_matrix;
+                if self.show_correlation_matrix {
+                    if let AsyncData::Loaded(schema) = &self.metadata_schema {
+                        for column in schema.columns.iter() {
+                            if !column.is_categorical {
+                                ctx.link().send_message(Msg::RequestMetadata(PerCellDataSource::Metadata(column.name.clone())));
+                            }
+                        }
+                    }
+                }
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Line of a cell clicked in the parallel coordinates plot
+            Msg::SetParallelCoordsCell(cell) => {
+                self.parallel_coords_cell = Some(cell);
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Show or hide the sliders for the hyperparameters of the reduction
+            Msg::ToggleAlgorithmParams => {
+                self.show_algorithm_params = !self.show_algorithm_params;
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Hyperparameter slider moved. The reduction is only recomputed once it stops moving
+            Msg::SetAlgorithmParam(name, value) => {
+                self.algorithm_params.insert(name, value);
+                let link = ctx.link().clone();
+                self.recompute_timer = Some(Timeout::new(RECOMPUTE_DEBOUNCE_MS, move || {
+                    link.send_message(Msg::RecomputeReduction);
+                }));
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Ask the server to compute the current reduction again, using the chosen hyperparameters
+            Msg::RecomputeReduction => {
+                self.recompute_timer = None;
+                let Some(reduction_name) = self.current_reduction.as_ref().map(|name| base_reduction_name(name)) else {
+                    return false;
+                };
+                let params = self.algorithm_params.clone();
+                let query = RecomputeReductionRequest {
+                    reduction_name: reduction_name.clone(),
+                    params: params.clone(),
+                };
+                let query_json = serde_json::to_vec(&query).expect("Could not convert to json");
+
+                let get_data = async move {
+                    //The server refuses reductions that are too large
+                    let umap_data = match post_for_bytes(format!("{}/recompute_reduction",get_host_url()), query_json, None).await {
+                        Ok((res, _content_type)) => match serde_cbor::from_reader::<ReductionResponse, _>(res.reader()) {
+                            Ok(res) => convert_from_response_to_reduction_data(res),
+                            Err(e) => Err(ReductionError::Decode(e.to_string()))
+                        },
+                        Err(e) => Err(ReductionError::ServerError { message: e })
+                    };
+                    Msg::SetRecomputedReduction(reduction_name, params, umap_data)
+                };
+                ctx.link().send_future(get_data);
+                self.recompute_running = true;
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Reduction recomputed by the server. Shown in place of the current one, unless the
+            // parameters changed meanwhile
+            Msg::SetRecomputedReduction(reduction_name, params, umap_data) => {
+                if params != self.algorithm_params || self.recompute_timer.is_some() {
+                    return false;
+                }
+                self.recompute_running = false;
+                match umap_data {
+                    Ok(umap_data) => {
+                        let recomputed_name = format!("{}_recomputed", reduction_name);
+                        self.current_data.lock().unwrap().reductions.insert(recomputed_name.clone(), AsyncData::new(umap_data));
+                        self.current_reduction = Some(recomputed_name);
+                        self.refresh_cluster_proportion(ctx);
+                    },
+                    Err(e) => {
+                        alert(&format!("Could not recompute reduction {}: {}", reduction_name, e));
+                    }
+                }
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Show a trajectory through the selected cells, following the numeric column used for coloring
+            Msg::ShowTrajectoryOfSelection => {
+                let Some(reduction_name) = &self.current_reduction else {
+                    return false;
+                };
+                let current_data = self.current_data.lock().unwrap();
+                let (AsyncData::Loaded(reduction), AsyncData::Loaded(color_data)) = (current_data.get_reduction(reduction_name), current_data.get_metadata(&self.current_colorby)) else {
+                    return false;
+                };
+                let Some(values) = dense_numeric_values(&color_data, reduction.num_point) else {
+                    log::debug!("Trajectories need numeric coloring");
+                    return false;
+                };
+                let trajectory = trajectory_from_selection(&reduction, &self.last_clicked_cells, &values, NUM_TRAJECTORY_POINTS);
+                ctx.link().send_message(Msg::SetTrajectory(Some(trajectory)));
+                false
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Show or hide violins in the legend of categories. They show the distribution of the
+            // numeric column colored by most recently before the current coloring, e.g. a gene before the clusters
+            Msg::ToggleLegendViolins => {
+                if self.violin_column.take().is_some() {
+                    return true;
+                }
+                let current_data = self.current_data.lock().unwrap();
+                let previous = self.coloring_history.iter().take(self.coloring_cursor).rev().find_map(|(coloring, _)| {
+                    let ReductionColoring::ByMeta(name) = coloring else {
+                        return None;
+                    };
+                    match current_data.get_metadata(name) {
+                        AsyncData::Loaded(data) if !matches!(data.as_ref(), CountFileMetaColumnData::Categorical(_, _)) => Some(name.clone()),
+                        _ => None
+                    }
+                });
+                if previous.is_none() {
+                    ctx.link().send_message(Msg::ShowNotification("Color by a numeric column, then by categories, to show violins".to_string()));
+                }
+                self.violin_column = previous;
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Compare the distribution of the coloring in the selected cells to that in all others
+            Msg::DistanceOfSelection => {
+                let num_cells = match self.current_reduction.as_ref().map(|name| self.current_data.lock().unwrap().get_reduction(name)) {
+                    Some(AsyncData::Loaded(reduction)) => reduction.num_point,
+                    _ => return false,
+                };
+                let selected: HashSet<usize> = self.last_clicked_cells.iter().cloned().filter(|i| *i < num_cells).collect();
+                let mut group_a: Vec<usize> = selected.iter().cloned().collect();
+                group_a.sort();
+                let group_b: Vec<usize> = (0..num_cells).filter(|i| !selected.contains(i)).collect();
+                ctx.link().send_message(Msg::ComputeDistributionDistance(group_a, group_b, self.current_colorby.clone()));
+                false
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Wasserstein distance between the values of a numeric column in two groups of cells. Shown with the table of the selection
+            Msg::ComputeDistributionDistance(group_a, group_b, column) => {
+                let Some(reduction_name) = &self.current_reduction else {
+                    return false;
+                };
+                let current_data = self.current_data.lock().unwrap();
+                let (AsyncData::Loaded(reduction), AsyncData::Loaded(data)) = (current_data.get_reduction(reduction_name), current_data.get_metadata(&column)) else {
+                    ctx.link().send_message(Msg::ShowNotification(format!("{} is not loaded", column_label(&column))));
+                    return false;
+                };
+                let Some(values) = dense_numeric_values(&data, reduction.num_point) else {
+                    ctx.link().send_message(Msg::ShowNotification("The distance of distributions needs a numeric column".to_string()));
+                    return false;
+                };
+                let values_of = |cells: &Vec<usize>| cells.iter().filter_map(|i| values.get(*i).cloned()).collect::<Vec<f32>>();
+                let Some(distance) = wasserstein_1d(&values_of(&group_a), &values_of(&group_b)) else {
+                    ctx.link().send_message(Msg::ShowNotification("Both groups need cells with values".to_string()));
+                    return false;
+                };
+                self.distribution_distance = Some((column, group_a.len(), group_b.len(), distance));
+                self.show_cell_table = true;
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Set or remove the trajectory shown
+            Msg::SetTrajectory(trajectory) => {
+                self.show_pseudotime_chart = trajectory.is_some();
+                self.trajectory = trajectory;
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Show or hide the chart of the coloring along the trajectory
+            Msg::TogglePseudotimeChart => {
+                self.show_pseudotime_chart = !self.show_pseudotime_chart;
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Start a collaborative session, as the peer making the offer
+            Msg::StartCollabSession => {
+                match CollabSession::start(ctx.link().callback(Msg::RecvCollabEvent)) {
+                    Ok(session) => {
+                        let peer = session.peer.clone();
+                        self.collab_sessions.push(session);
+                        ctx.link().send_future(async move {
+                            match create_offer(peer).await {
+                                Ok(sdp) => Msg::SetCollabLocalSdp(sdp),
+                                Err(e) => {
+                                    log::warn!("Could not create collab offer: {:?}", e);
+                                    Msg::DataChanged
+                                }
+                            }
+                        });
+                    },
+                    Err(e) => log::warn!("Could not start collab session: {:?}", e),
+                }
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Join a collaborative session using the offer of another peer
+            Msg::JoinCollabSession(offer_sdp) => {
+                match CollabSession::join(ctx.link().callback(Msg::RecvCollabEvent)) {
+                    Ok(session) => {
+                        let peer = session.peer.clone();
+                        self.collab_sessions.push(session);
+                        ctx.link().send_future(async move {
+                            match create_answer(peer, offer_sdp).await {
+                                Ok(sdp) => Msg::SetCollabLocalSdp(sdp),
+                                Err(e) => {
+                                    log::warn!("Could not create collab answer: {:?}", e);
+                                    Msg::DataChanged
+                                }
+                            }
+                        });
+                    },
+                    Err(e) => log::warn!("Could not join collab session: {:?}", e),
+                }
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Connect to the peer that answered the latest offer
+            Msg::AcceptCollabAnswer(answer_sdp) => {
+                if let Some(session) = self.collab_sessions.last() {
+                    let peer = session.peer.clone();
+                    ctx.link().send_future(async move {
+                        if let Err(e) = accept_answer(peer, answer_sdp).await {
+                            log::warn!("Could not accept collab answer: {:?}", e);
+                        }
+                        Msg::DataChanged
+                    });
+                }
+                false
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Offer or answer is ready to be given to the other peer
+            Msg::SetCollabLocalSdp(sdp) => {
+                self.collab_local_sdp = Some(sdp);
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Event from a peer
+            Msg::RecvCollabEvent(event) => {
+                match event {
+                    CollabEvent::CameraUpdate { x, y, zoom_x, zoom_y } => {
+                        let mut camera = Camera2D::new();
+                        camera.x = x;
+                        camera.y = y;
+                        camera.zoom_x = zoom_x;
+                        camera.zoom_y = zoom_y;
+                        self.forced_camera = Some(camera);
+                    },
+                    CollabEvent::SelectionUpdate { cells } => {
+                        self.forced_selection = Some(cells);
+                    },
+                    CollabEvent::CursorUpdate { peer_id, x, y } => {
+                        if peer_id != self.collab_peer_id {
+                            self.peer_cursors.insert(peer_id, (x, y));
+                        }
+                    },
+                }
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Camera moved; tell peers
+            Msg::BroadcastCamera(camera) => {
+                self.last_camera = Some(camera.clone());
+                self.update_url(ctx);
+                self.broadcast_collab_event(&CollabEvent::CameraUpdate {
+                    x: camera.x,
+                    y: camera.y,
+                    zoom_x: camera.zoom_x,
+                    zoom_y: camera.zoom_y,
+                });
+                false
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: The user chose another tool in the reduction
+            Msg::SetActiveTool(tool) => {
+                self.active_tool = Some(tool);
+                self.update_url(ctx);
+                false
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Cursor moved; tell peers
+            Msg::BroadcastCursor(x, y) => {
+                self.broadcast_collab_event(&CollabEvent::CursorUpdate {
+                    peer_id: self.collab_peer_id,
+                    x: x,
+                    y: y,
+                });
+                false
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Show how well predicted clusters match a ground-truth annotation. The columns are loaded if needed
+            Msg::ComputePurity(predicted_col, gt_col) => {
+                ctx.link().send_message(Msg::RequestMetadata(PerCellDataSource::Metadata(predicted_col.clone())));
+                ctx.link().send_message(Msg::RequestMetadata(PerCellDataSource::Metadata(gt_col.clone())));
+                self.purity_columns = Some((predicted_col, gt_col));
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Cluster purity panel closed
+            Msg::ClosePurity => {
+                self.purity_columns = None;
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Show how cells move between the categories of two time points. The columns are loaded if needed
+            Msg::ShowTransitions(from_col, to_col) => {
+                ctx.link().send_message(Msg::RequestMetadata(PerCellDataSource::Metadata(from_col.clone())));
+                ctx.link().send_message(Msg::RequestMetadata(PerCellDataSource::Metadata(to_col.clone())));
+                self.transition_columns = Some((from_col, to_col));
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Transition panel closed
+            Msg::CloseTransitions => {
+                self.transition_columns = None;
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Plot the coordinates of cells in one reduction against another, loading them if needed
+            Msg::CompareReductions(name_a, name_b) => {
+                for name in [&name_a, &name_b] {
+                    let is_requested = self.current_data.lock().unwrap().reductions.contains_key(name);
+                    if !is_requested {
+                        ctx.link().send_message(Msg::FetchReduction(name.clone()));
+                    }
+                }
+                self.reduction_comparison = Some((name_a, name_b));
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Reduction comparison closed
+            Msg::CloseReductionComparison => {
+                self.reduction_comparison = None;
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Plot the first UMAP coordinate of cells against their first principal component, loading them if needed
+            Msg::CompareProjections(name_umap, name_pca) => {
+                for name in [&name_umap, &name_pca] {
+                    let is_requested = self.current_data.lock().unwrap().reductions.contains_key(name);
+                    if !is_requested {
+                        ctx.link().send_message(Msg::FetchReduction(name.clone()));
+                    }
+                }
+                self.projection_comparison = Some((name_umap, name_pca));
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Projection comparison closed
+            Msg::CloseProjectionComparison => {
+                self.projection_comparison = None;
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Cells selected in the projection comparison; the reduction selects them too
+            Msg::SelectFromProjections(cells) => {
+                self.forced_selection = Some(cells);
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Show a reduction before and after batch correction side by side, loading them if needed
+            Msg::CompareBatchCorrection(name_original, name_corrected) => {
+                for name in [&name_original, &name_corrected] {
+                    let is_requested = self.current_data.lock().unwrap().reductions.contains_key(name);
+                    if !is_requested {
+                        ctx.link().send_message(Msg::FetchReduction(name.clone()));
+                    }
+                }
+                self.batch_comparison = Some((name_original, name_corrected));
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Batch correction comparison closed
+            Msg::CloseBatchComparison => {
+                self.batch_comparison = None;
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Ctrl+K pressed
+            Msg::ToggleCommandPalette => {
+                self.command_palette_open = !self.command_palette_open;
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Command palette closed, after running a command or not
+            Msg::CloseCommandPalette => {
+                self.command_palette_open = false;
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Run an action of the reduction view
+            Msg::RunReductionCommand(command) => {
+                let seq = self.reduction_command.as_ref().map(|(seq, _)| seq + 1).unwrap_or(0);
+                self.reduction_command = Some((seq, command));
+                true
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Go back to the previous coloring
+            Msg::UndoColoring => {
+                if self.coloring_cursor > 0 {
+                    self.apply_coloring_history(ctx, self.coloring_cursor - 1);
+                    true
+                } else {
+                    false
+                }
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Go forward to a coloring that was undone
+            Msg::RedoColoring => {
+                if self.coloring_cursor + 1 < self.coloring_history.len() {
+                    self.apply_coloring_history(ctx, self.coloring_cursor + 1);
+                    true
+                } else {
+                    false
+                }
+            },
+
+
+            ////////////////////////////////////////////////////////////
+            // Message: Pip clicked in the coloring timeline. The history is kept, so that one can jump forward again
+            Msg::JumpToColoring(cursor) => {
+                if cursor < self.coloring_history.len() && cursor != self.coloring_cursor {
+                    self.apply_coloring_history(ctx, cursor);
+                    true
+                } else {
+                    false
+                }
+            },
+
+
+
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Called after DOM has been generated
+    fn rendered(&mut self, ctx: &Context<Self>, _first_render: bool) {
+        self.update_url(ctx);
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Called when the component is removed
+    fn destroy(&mut self, _ctx: &Context<Self>) {
+        let _ = window().expect("no window")
+            .remove_event_listener_with_callback("keydown", self.on_keydown.as_ref().unchecked_ref());
+    }
 
 
     ////////////////////////////////////////////////////////////
@@ -348,10 +2483,22 @@ impl Component for Model {
             Msg::WindowResize(size)
         });
 
+        let command_palette = if self.command_palette_open {
+            html! {
+                <CommandPalette
+                    commands={self.get_palette_commands()}
+                    on_command={ctx.link().callback(|msg: Msg| msg)}
+                    on_close={ctx.link().callback(|_| Msg::CloseCommandPalette)}
+                />
+            }
+        } else {
+            html! {""}
+        };
+
         html! {
             <div style="position: relative;"> // added style
                 <ComponentSizeObserver onsize={onsize} />
-                <div class="biscvi-topdiv">
+                <div class="biscvi-topdiv" hidden={self.print_layout}>
                     <div style="float: left; padding: 10px; font-size: 30px; font-family: 'Roboto', sans-serif; font-weight: 900;">
                         {"Biscvi"}
                     </div>
@@ -363,7 +2510,7 @@ impl Component for Model {
 
                 </div>
                 { current_page }
-
+                { command_palette }
 
             </div>
         }
@@ -374,6 +2521,559 @@ impl Component for Model {
 
 
 
+impl Model {
+
+    ////////////////////////////////////////////////////////////
+    /// Get the name of the count table to use when none has been chosen.
+    /// Picks the first by name, to be the same every time
+    pub fn get_default_counts_name(&self) -> Option<String> {
+        if let AsyncData::Loaded(desc) = &self.current_datadesc {
+            desc.matrices.keys().min().cloned()
+        } else {
+            None
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Get the cells of all selected samples, e.g. to compare against the other samples
+    pub fn get_selected_sample_cells(&self) -> Vec<usize> {
+        if let AsyncData::Loaded(annotation) = &self.sample_annotation {
+            annotation.sample_id.iter().enumerate()
+                .filter(|(_i, id)| self.selected_samples.contains(id))
+                .map(|(i, _id)| i)
+                .collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// All actions that can be run from the command palette
+    fn get_palette_commands(&self) -> Vec<Command> {
+        let mut commands = vec![
+            Command::new("Select tool", "Click or drag to select cells", || Msg::RunReductionCommand(ReductionCommand::SelectTool(CurrentTool::Select))),
+            Command::new("Lasso tool", "Draw around cells to select them", || Msg::RunReductionCommand(ReductionCommand::SelectTool(CurrentTool::Lasso))),
+            Command::new("Replay lasso", "Animate the drawing of the last lasso", || Msg::RunReductionCommand(ReductionCommand::ReplayLasso)),
+            Command::new("Zoom tool", "Drag to zoom into a region", || Msg::RunReductionCommand(ReductionCommand::SelectTool(CurrentTool::Zoom))),
+            Command::new("Zoom to fit", "Show all cells", || Msg::RunReductionCommand(ReductionCommand::SelectTool(CurrentTool::ZoomAll))),
+            Command::new("Explore", "Move the camera around automatically", || Msg::RunReductionCommand(ReductionCommand::SelectTool(CurrentTool::Explore))),
+            Command::new("Zoom in", "Zoom into the middle of the view", || Msg::RunReductionCommand(ReductionCommand::ZoomIn)),
+            Command::new("Zoom out", "Zoom out from the middle of the view", || Msg::RunReductionCommand(ReductionCommand::ZoomOut)),
+            Command::new("Toggle zoom lock", "Prevent the mouse wheel from zooming", || Msg::RunReductionCommand(ReductionCommand::ToggleZoomLock)),
+            Command::new("Toggle velocity", "Show or hide RNA velocity arrows", || Msg::RunReductionCommand(ReductionCommand::ToggleShowVelocity)),
+            Command::new("Toggle radial layout", "Arrange clusters radially", || Msg::RunReductionCommand(ReductionCommand::ToggleRadialLayout)),
+            Command::new("Relax layout", "Spread out overlapping cells, computed on the GPU", || Msg::RunReductionCommand(ReductionCommand::ToggleRelaxLayout)),
+            Command::new("Toggle subsample", "Draw all cells or a random subset", || Msg::RunReductionCommand(ReductionCommand::ToggleSubsample)),
+            Command::new("Toggle focus+context", "Draw cells outside the middle of the view faintly", || Msg::ToggleFocusContext),
+            Command::new("Simulate deuteranopia", "Show colors as seen without green cones", || Msg::SetColorSimulation(ColorSimulationMode::DeuteranopiaSim)),
+            Command::new("Simulate protanopia", "Show colors as seen without red cones", || Msg::SetColorSimulation(ColorSimulationMode::ProtanopiaSim)),
+            Command::new("Normal color vision", "Stop simulating color blindness", || Msg::SetColorSimulation(ColorSimulationMode::Normal)),
+            Command::new("Color gradient between categories", "Order category colors by hue and blend them where categories meet", || Msg::ToggleCategoryGradient),
+            Command::new("Toggle synchronized color scale", "Use the same color scale in all views", || Msg::ToggleSyncColorScale),
+            Command::new("Undo coloring", "Go back to the previous coloring", || Msg::UndoColoring),
+            Command::new("Redo coloring", "Go forward to the next coloring", || Msg::RedoColoring),
+            Command::new("Show trajectory", "Build a trajectory through the selected cells", || Msg::ShowTrajectoryOfSelection),
+            Command::new("Expression over pseudotime", "Mean of the numeric coloring along the trajectory", || Msg::TogglePseudotimeChart),
+            Command::new("Find Louvain communities", "Cluster cells using the neighbors in the reduction, and color by the clusters", || Msg::RunLouvain { n_neighbors: DEFAULT_LOUVAIN_NEIGHBORS }),
+            Command::new("Compare profiles of selected cells", "Parallel coordinates plot of the loaded numeric columns", || Msg::ToggleParallelCoords),
+            Command::new("Print layout", "Show only the reduction, on an A4 page at 300 DPI, and print it", || Msg::TogglePrintLayout),
+            Command::new("Expression heatmap of selection", "Mean expression of the genes colored by, in the selected cells and all others", || Msg::ToggleStateHeatmap),
+            Command::new("Zoom to expressing cells", "Fit the view to the cells where a feature is above a threshold", || Msg::PromptZoomToExpressors),
+            Command::new("Color by expression ratio", "Log ratio of two features; blue where the second is higher, red where the first is", || Msg::PromptExpressionRatio),
+            Command::new("Table of selection", "Loaded columns of the selected cells, highlighting the hovered cell", || Msg::ToggleCellTable),
+            Command::new("Distance of selection to others", "Wasserstein distance between the coloring of the selected cells and all others", || Msg::DistanceOfSelection),
+            Command::new("Toggle legend violins", "Distribution of the previous numeric coloring in each category", || Msg::ToggleLegendViolins),
+            Command::new("Correlation matrix", "Pearson correlation of each pair of numeric metadata columns", || Msg::ToggleCorrelationMatrix),
+            Command::new("Reduction parameters", "Compute the reduction again with other hyperparameters", || Msg::ToggleAlgorithmParams),
+            Command::new("Export SVG", "Save the cells in view as an SVG file", || Msg::RunReductionCommand(ReductionCommand::ExportSvg)),
+            Command::new("Add landmark", "Place a named pin in the middle of the view; drag it to move it", || Msg::RunReductionCommand(ReductionCommand::AddLandmark)),
+            Command::new("Clear landmarks", "Remove all landmark pins", || Msg::RunReductionCommand(ReductionCommand::ClearLandmarks)),
+            Command::new("Export CSV", "Save the coordinates and annotations of all cells", || Msg::ExportCsv),
+            Command::new("Export state", "Save the reduction, coloring, camera, selection and annotations as JSON", || Msg::ExportState),
+            Command::new("Import state", "Restore a view saved with Export state", || Msg::PickStateFile),
+            Command::new("Import GeoJSON mask", "Hide cells of the current reduction outside a polygon", || Msg::PickGeoJsonMask),
+            Command::new("Import PCA loadings", "Show the top genes of PC1 and PC2 as arrows over the reduction", || Msg::PickBiplotLoadings),
+            Command::new("Open dimensional reduction", "Go to the reduction page", || Msg::OpenPage(CurrentPage::Home)),
+            Command::new("Open files", "Go to the files page", || Msg::OpenPage(CurrentPage::Files)),
+            Command::new("Open genome browser", "Go to the genome browser", || Msg::OpenPage(CurrentPage::GenomeBrowser)),
+            Command::new("Open about", "Go to the about page", || Msg::OpenPage(CurrentPage::About)),
+        ];
+
+        //Spread the values of the current coloring over neighboring cells
+        let column = self.current_colorby.clone();
+        commands.push(Command::new(
+            &format!("Smooth {}", column_label(&column)),
+            "Reduce noise by averaging the values of neighboring cells",
+            move || Msg::SmoothColumn { column: column.clone(), iterations: DEFAULT_SMOOTH_ITERATIONS, alpha: DEFAULT_SMOOTH_ALPHA }
+        ));
+
+        //How well the cache of loaded columns works, for diagnostics
+        let cache_stats = self.current_data.lock().unwrap().metadatas.describe_stats();
+        commands.push(Command::new(
+            "Metadata cache statistics",
+            "Number of loaded columns, and how often requested columns were already loaded",
+            move || Msg::ShowNotification(cache_stats.clone())
+        ));
+
+        //Compare the current reduction with each of the others, e.g. to check that two UMAP runs agree
+        if let (Some(current_reduction), AsyncData::Loaded(desc)) = (&self.current_reduction, &self.current_datadesc) {
+            for name in desc.reductions.keys().filter(|name| *name != current_reduction) {
+                let (name_a, name_b) = (current_reduction.clone(), name.clone());
+                commands.push(Command::new(
+                    &format!("Compare with {}", name),
+                    &format!("Plot coordinates of each cell in {} against {}", current_reduction, name),
+                    move || Msg::CompareReductions(name_a.clone(), name_b.clone())
+                ));
+                let (name_original, name_corrected) = (current_reduction.clone(), name.clone());
+                commands.push(Command::new(
+                    &format!("Batch correction: {} to {}", current_reduction, name),
+                    "Show both reductions side by side, colored by the current coloring, and morph between them",
+                    move || Msg::CompareBatchCorrection(name_original.clone(), name_corrected.clone())
+                ));
+                if name.to_lowercase().contains("pca") && !current_reduction.to_lowercase().contains("pca") {
+                    let (name_umap, name_pca) = (current_reduction.clone(), name.clone());
+                    commands.push(Command::new(
+                        &format!("Plot against PC1 of {}", name),
+                        &format!("Scatter plot of the first coordinate of {} against the first principal component, colored by cluster", current_reduction),
+                        move || Msg::CompareProjections(name_umap.clone(), name_pca.clone())
+                    ));
+                }
+            }
+        }
+
+        //Purity of each other categorical column, taking the current coloring as ground truth
+        if let (PerCellDataSource::Metadata(gt_col), AsyncData::Loaded(schema)) = (&self.current_colorby, &self.metadata_schema) {
+            let gt_is_categorical = schema.columns.iter().any(|c| c.name == *gt_col && c.is_categorical);
+            if gt_is_categorical {
+                for column in schema.columns.iter().filter(|c| c.is_categorical && c.name != *gt_col) {
+                    let (predicted_col, gt_col) = (column.name.clone(), gt_col.clone());
+                    commands.push(Command::new(
+                        &format!("Purity of {}", column.name),
+                        &format!("How well the clusters of {} match {}", column.name, gt_col),
+                        move || Msg::ComputePurity(predicted_col.clone(), gt_col.clone())
+                    ));
+                }
+            }
+        }
+
+        //Transitions from the current coloring to each other categorical column, e.g. a later time point
+        if let (PerCellDataSource::Metadata(from_col), AsyncData::Loaded(schema)) = (&self.current_colorby, &self.metadata_schema) {
+            let from_is_categorical = schema.columns.iter().any(|c| c.name == *from_col && c.is_categorical);
+            if from_is_categorical {
+                for column in schema.columns.iter().filter(|c| c.is_categorical && c.name != *from_col) {
+                    let (from_col, to_col) = (from_col.clone(), column.name.clone());
+                    commands.push(Command::new(
+                        &format!("Transitions to {}", column.name),
+                        &format!("Sankey diagram of cells going from the categories of {} to {}", from_col, column.name),
+                        move || Msg::ShowTransitions(from_col.clone(), to_col.clone())
+                    ));
+                }
+            }
+        }
+
+        //Collapse the cells of each category into one point
+        if let AsyncData::Loaded(schema) = &self.metadata_schema {
+            for column in schema.columns.iter().filter(|c| c.is_categorical) {
+                let name = column.name.clone();
+                commands.push(Command::new(
+                    &format!("Group by {}", column.name),
+                    "Show one point per category, at the center of its cells",
+                    move || Msg::SetGroupBy(GroupByMode::Cluster(PerCellDataSource::Metadata(name.clone())))
+                ));
+            }
+        }
+        if matches!(self.sample_annotation, AsyncData::Loaded(_)) {
+            if self.display_subset.is_some() {
+                commands.push(Command::new("Show all cells of each sample", "Stop normalizing the number of cells drawn by sample", || Msg::SetSampleNormalization(false)));
+            } else {
+                commands.push(Command::new("Normalize by sample", "Draw as many cells of each sample as the smallest sample has", || Msg::SetSampleNormalization(true)));
+            }
+            commands.push(Command::new("Normalize by sample, choosing the number of cells", "Draw a given number of cells of each sample", || Msg::PromptCellsPerSample));
+        }
+        if self.group_by != GroupByMode::None {
+            commands.push(Command::new("Ungroup cells", "Show all cells again", || Msg::SetGroupBy(GroupByMode::None)));
+        }
+        if self.geo_mask.is_some() {
+            commands.push(Command::new("Remove GeoJSON mask", "Show cells outside the imported polygon again", || Msg::ClearGeoJsonMask));
+        }
+        if !self.biplot_arrows.is_empty() {
+            commands.push(Command::new("Hide projection axes", "Remove the arrows of the PCA loadings", || Msg::LoadBiplotArrows(Vec::new())));
+        }
+
+        //One command per metadata column to color by
+        if let AsyncData::Loaded(schema) = &self.metadata_schema {
+            for column in schema.columns.iter() {
+                let name = column.name.clone();
+                commands.push(Command::new(
+                    &format!("Color by {}", column.name),
+                    if column.is_categorical { "Categorical metadata" } else { "Numeric metadata" },
+                    move || Msg::RequestSetColorByMeta(PerCellDataSource::Metadata(name.clone()))
+                ));
+            }
+        }
+        commands
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Send an event to all peers of the collaborative session
+    fn broadcast_collab_event(&self, event: &CollabEvent) {
+        for session in self.collab_sessions.iter() {
+            session.send(event);
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Number of cells in the dataset, from the rows of a count matrix. 0 if not known yet
+    fn get_num_cells(&self) -> usize {
+        match &self.current_datadesc {
+            AsyncData::Loaded(desc) => desc.matrices.values().map(|m| m.list_indptr.len().saturating_sub(1)).max().unwrap_or(0),
+            _ => 0
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Index of a feature in a count matrix. None if the dataset description is not loaded yet, or the feature is unknown
+    fn get_feature_index(&self, counts_name: &String, feature_name: &String) -> Option<u32> {
+        let AsyncData::Loaded(desc) = &self.current_datadesc else {
+            return None;
+        };
+        let mat = desc.matrices.get(counts_name)?;
+        mat.list_feature_names.iter().position(|f| f == feature_name).map(|i| i as u32)
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Summarize a metadata column the first time it is loaded. Sparse columns have implicit zeros,
+    /// so this waits until the number of cells is known from the dataset description
+    fn summarize_column(&mut self, column_name: &String, data: &CountFileMetaColumnData) {
+        let num_cells = self.get_num_cells();
+        if num_cells > 0 && !self.column_summary.contains_key(column_name) {
+            self.column_summary.insert(column_name.clone(), ColumnSummary::build(data, num_cells));
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Request a reduction from the server. If this fails, it is tried again later
+    fn fetch_reduction(&self, ctx: &Context<Self>, reduction_name: String, retry: FetchRetry) {
+        let query = ReductionRequest {
+            reduction_name: reduction_name.clone()
+        };
+        let query_json = serde_json::to_vec(&query).expect("Could not convert to json");
+
+        let get_data = async move {
+            let res = post_for_bytes(
+                format!("{}/get_reduction",get_host_url()),
+                query_json,
+                Some("application/msgpack, application/octet-stream")
+            ).await;
+            let (res, content_type) = match res {
+                Ok(res) => res,
+                Err(e) => return Msg::FetchFailed(FetchTarget::Reduction(reduction_name), retry, e)
+            };
+
+            //Server may answer in MessagePack or CBOR
+            let is_msgpack = content_type
+                .map(|h| h.starts_with("application/msgpack"))
+                .unwrap_or(false);
+            let umap_data = if is_msgpack {
+                convert_from_msgpack_to_reduction_data(&res)
+            } else {
+                match serde_cbor::from_reader::<ReductionResponse, _>(res.reader()) {
+                    Ok(res) => convert_from_response_to_reduction_data(res),
+                    Err(e) => return Msg::FetchFailed(FetchTarget::Reduction(reduction_name), retry, format!("Failed to deserialize: {}", e))
+                }
+            };
+            Msg::SetReduction(reduction_name, umap_data)
+        };
+        ctx.link().send_future(get_data);
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Request a metadata column or feature counts from the server. If this fails, it is tried again later
+    fn fetch_per_cell_data(&self, ctx: &Context<Self>, name: PerCellDataSource, retry: FetchRetry) {
+        let (url, query_json) = match &name {
+            PerCellDataSource::Metadata(column_name) => {
+                let query = MetadataColumnRequest {
+                    column_name: column_name.clone(),
+                };
+                (format!("{}/get_metacolumn",get_host_url()), serde_json::to_vec(&query).expect("Could not convert to json"))
+            },
+            PerCellDataSource::Counts(counts_name, feature_name) => {
+                //The feature is looked up in the dataset description, which may not have arrived yet
+                let Some(col) = self.get_feature_index(counts_name, feature_name) else {
+                    let e = format!("Unknown feature {} in {}", feature_name, counts_name);
+                    ctx.link().send_message(Msg::FetchFailed(FetchTarget::PerCellData(name), retry, e));
+                    return;
+                };
+                let query = FeatureColumnRequest {
+                    counts_name: counts_name.clone(),
+                    col: col,
+                };
+                (format!("{}/get_featurecolumn",get_host_url()), serde_json::to_vec(&query).expect("Could not convert to json"))
+            },
+            PerCellDataSource::ClusterProportionScore { .. } | PerCellDataSource::ExpressionRatio { .. } => {
+                //Computed locally
+                return;
+            },
+        };
+
+        let get_data = async move {
+            let res = match post_for_bytes(url, query_json, None).await {
+                Ok((res, _content_type)) => res,
+                Err(e) => return Msg::FetchFailed(FetchTarget::PerCellData(name), retry, e)
+            };
+            let res: MetadataColumnResponse = match serde_cbor::from_reader(res.reader()) {
+                Ok(res) => res,
+                Err(e) => return Msg::FetchFailed(FetchTarget::PerCellData(name), retry, format!("Failed to deserialize: {}", e))
+            };
+            log::debug!("got per-cell data response {:?}",res);
+
+            match name {
+                PerCellDataSource::Counts(_, _) => Msg::SetColorByMeta(name, Some(res)),
+                _ => Msg::SetMetadata(name, res)
+            }
+        };
+        ctx.link().send_future(get_data);
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Color by a metadata column or feature, requesting the data if needed.
+    /// Does not affect the coloring history
+    fn set_color_by_meta(&mut self, ctx: &Context<Self>, name: PerCellDataSource) {
+
+        log::debug!("RequestSetColorByMeta {} ",name);
+
+        //Data that failed to load is requested again
+        let has_data = matches!(self.current_data.lock().unwrap().metadatas.lookup(&name), Some(AsyncData::Loaded(_)) | Some(AsyncData::Loading));
+
+        //For now, point to show new data. But we might not yet have it
+        self.current_colorby = name.clone();
+        ctx.link().send_message(Msg::SetColorByMeta(name.clone(), None));
+
+        //If needed, request data
+        if !has_data {
+
+            match &name {
+                PerCellDataSource::Metadata(_column_name) => {
+                    ctx.link().send_message(Msg::RequestMetadata(name.clone()));
+                },
+                PerCellDataSource::ClusterProportionScore { base_column, .. } => {
+                    //Computed locally once the base column is in
+                    ctx.link().send_message(Msg::RequestMetadata(PerCellDataSource::Metadata(base_column.clone())));
+                    ctx.link().send_message(Msg::ComputeClusterProportion(name.clone()));
+                },
+                PerCellDataSource::Counts(_counts_name, _feature_name) => {
+                    self.fetch_per_cell_data(ctx, name.clone(), FetchRetry::default());
+                },
+                PerCellDataSource::ExpressionRatio { gene_a, gene_b } => {
+                    //Computed locally once both features are in
+                    let Some(counts_name) = self.get_default_counts_name() else {
+                        return;
+                    };
+                    for gene in [gene_a, gene_b] {
+                        let gene_name = PerCellDataSource::Counts(counts_name.clone(), gene.clone());
+                        let has_gene = matches!(self.current_data.lock().unwrap().metadatas.lookup(&gene_name), Some(AsyncData::Loaded(_)) | Some(AsyncData::Loading));
+                        if !has_gene {
+                            self.fetch_per_cell_data(ctx, gene_name, FetchRetry::default());
+                        }
+                    }
+                    ctx.link().send_message(Msg::ComputeExpressionRatio(name.clone()));
+                },
+            }
+
+
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Zoom to the cells expressing a feature, if that was waiting for the data just loaded
+    fn resume_zoom_to_expressors(&mut self, ctx: &Context<Self>, name: &PerCellDataSource) {
+        if let Some((gene, threshold)) = &self.pending_zoom_to_expressors {
+            if gene == name {
+                ctx.link().send_message(Msg::ZoomToExpressors { gene: gene.clone(), threshold: *threshold });
+            }
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Scores of neighbors depend on the reduction; drop them when it changes, and compute
+    /// the score of the current coloring again
+    fn refresh_cluster_proportion(&mut self, ctx: &Context<Self>) {
+        self.current_data.lock().unwrap().metadatas.retain(|name, _| !matches!(name, PerCellDataSource::ClusterProportionScore { .. }));
+        if matches!(self.current_colorby, PerCellDataSource::ClusterProportionScore { .. }) {
+            ctx.link().send_message(Msg::ComputeClusterProportion(self.current_colorby.clone()));
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// What is shown, to save or share
+    fn get_state_snapshot(&self) -> AppStateSnapshot {
+        AppStateSnapshot {
+            version: SNAPSHOT_VERSION,
+            current_reduction: self.current_reduction.clone(),
+            color_umap_by: self.color_umap_by.clone(),
+            custom_colors: if self.color_umap_by == ReductionColoring::Custom { self.custom_colors.clone() } else { Vec::new() },
+            camera: self.last_camera.clone(),
+            selection: self.last_clicked_cells.clone(),
+            cell_annotations: self.cell_annotations.clone(),
+            tool: self.active_tool.clone(),
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Write the state to the URL, so that it can be shared. The history is not added to; going back
+    /// leaves the page. The user is warned once if the selection is too large to include
+    fn update_url(&mut self, ctx: &Context<Self>) {
+        let (query, selection_fits) = snapshot_to_query(&self.get_state_snapshot());
+        if !selection_fits && !self.url_selection_too_large {
+            ctx.link().send_message(Msg::ShowNotification(format!("More than {} cells selected; the selection is not included in the URL", MAX_URL_SELECTION)));
+        }
+        self.url_selection_too_large = !selection_fits;
+
+        if query == self.last_url_query {
+            return;
+        }
+        let Some(window) = window() else {
+            return;
+        };
+        let url = match window.location().pathname() {
+            Ok(path) if query.is_empty() => path,
+            Ok(path) => format!("{}?{}", path, query),
+            Err(_) => return,
+        };
+        if let Ok(history) = window.history() {
+            if let Err(e) = history.replace_state_with_url(&JsValue::NULL, "", Some(&url)) {
+                log::warn!("Could not update the URL: {:?}", e);
+            }
+        }
+        self.last_url_query = query;
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Add a coloring to the history. Any colorings that could be redone are dropped
+    fn push_coloring_history(&mut self, coloring: ReductionColoring) {
+        self.coloring_history.truncate(self.coloring_cursor + 1);
+        if self.coloring_history.back().map(|(c, _)| c) == Some(&coloring) {
+            return;
+        }
+        let description = describe_coloring(&coloring);
+        self.coloring_history.push_back((coloring, description));
+        while self.coloring_history.len() > MAX_COLORING_HISTORY {
+            self.coloring_history.pop_front();
+        }
+        self.coloring_cursor = self.coloring_history.len() - 1;
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Show the coloring at the given position in the history
+    fn apply_coloring_history(&mut self, ctx: &Context<Self>, cursor: usize) {
+        if let Some((coloring, _)) = self.coloring_history.get(cursor).cloned() {
+            self.coloring_cursor = cursor;
+            match coloring {
+                ReductionColoring::None => {
+                    self.color_umap_by = ReductionColoring::None;
+                    self.current_colorby = PerCellDataSource::Metadata("".into());
+                },
+                ReductionColoring::ByMeta(name) => {
+                    self.set_color_by_meta(ctx, name);
+                },
+                ReductionColoring::Custom => {
+                    self.color_umap_by = ReductionColoring::Custom;
+                    self.current_colorby = PerCellDataSource::Metadata("".into());
+                },
+            }
+        }
+    }
+
+}
+
+
+
+////////////////////////////////////////////////////////////
+/// Read a text file chosen or dropped by the user, e.g. a snapshot, then send its content as a message
+pub fn read_text_file(link: html::Scope<Model>, file: File, to_msg: fn(String) -> Msg) {
+    wasm_bindgen_futures::spawn_local(async move {
+        match JsFuture::from(file.text()).await {
+            Ok(text) => link.send_message(to_msg(text.as_string().unwrap_or_default())),
+            Err(e) => log::warn!("Could not read file {}: {:?}", file.name(), e),
+        }
+    });
+}
+
+
+////////////////////////////////////////////////////////////
+/// Add or remove the style rules for printing the print layout
+fn set_print_style(enabled: bool) {
+    let document = window().expect("no window").document().expect("no document");
+    let existing = document.get_element_by_id(PRINT_STYLE_ID);
+    match (enabled, existing) {
+        (true, None) => {
+            let style = document.create_element("style").expect("can create style");
+            style.set_id(PRINT_STYLE_ID);
+            style.set_text_content(Some(PRINT_STYLE));
+            if let Some(body) = document.body() {
+                let _ = body.append_child(&style);
+            }
+        },
+        (false, Some(style)) => style.remove(),
+        _ => {}
+    }
+}
+
+
+////////////////////////////////////////////////////////////
+/// Let the user choose a text file of the given types, then read it as with read_text_file
+fn pick_text_file(ctx: &Context<Model>, accept: &str, to_msg: fn(String) -> Msg) {
+    let document = window().expect("no window").document().expect("no document");
+    let input: HtmlInputElement = document.create_element("input").expect("can create input").dyn_into().expect("is input");
+    input.set_type("file");
+    input.set_accept(accept);
+
+    let link = ctx.link().clone();
+    let chosen = input.clone();
+    let onchange = Closure::<dyn Fn()>::new(move || {
+        if let Some(file) = chosen.files().and_then(|files| files.get(0)) {
+            read_text_file(link.clone(), file, to_msg);
+        }
+    });
+    input.set_onchange(Some(onchange.as_ref().unchecked_ref()));
+    onchange.forget();
+    input.click();
+}
+
+
+////////////////////////////////////////////////////////////
+/// Description of a coloring, as shown in the timeline
+pub fn describe_coloring(coloring: &ReductionColoring) -> String {
+    match coloring {
+        ReductionColoring::None => "No coloring".to_string(),
+        ReductionColoring::ByMeta(name) => column_label(name),
+        ReductionColoring::Custom => "Imported colors".to_string(),
+    }
+}
+
+
+////////////////////////////////////////////////////////////
+/// Name of the reduction in the file that a reduction computed in this app was derived from
+pub fn base_reduction_name(name: &str) -> String {
+    name.trim_end_matches("_recomputed").trim_end_matches("_local").to_string()
+}
+
+
 ////////////////////////////////////////////////////////////
 /// Show an alert message
 pub fn alert(s: &str) {
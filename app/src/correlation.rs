@@ -0,0 +1,66 @@
+use my_web_app::CountFileMetaColumnData;
+
+
+////////////////////////////////////////////////////////////
+/// Values of a numeric column for all cells. Cells missing in sparse data are 0.
+/// Returns None for categorical data
+pub fn dense_numeric_values(data: &CountFileMetaColumnData, num_point: usize) -> Option<Vec<f32>> {
+    match data {
+        CountFileMetaColumnData::Numeric(vec_data) => {
+            Some(vec_data.clone())
+        },
+        CountFileMetaColumnData::SparseNumeric(vec_index, vec_data) => {
+            let mut values = vec![0.0; num_point];
+            for (i, v) in vec_index.iter().zip(vec_data.iter()) {
+                if let Some(x) = values.get_mut(*i as usize) {
+                    *x = *v;
+                }
+            }
+            Some(values)
+        },
+        CountFileMetaColumnData::Categorical(_, _) => None,
+    }
+}
+
+
+////////////////////////////////////////////////////////////
+/// Pearson correlation of two columns over the given cells. None if either column
+/// is constant over the cells, or fewer than two cells are given
+pub fn pearson_correlation(a: &Vec<f32>, b: &Vec<f32>, cells: &Vec<usize>) -> Option<f32> {
+    let cells: Vec<usize> = cells.iter().cloned().filter(|i| *i < a.len() && *i < b.len()).collect();
+    if cells.len() < 2 {
+        return None;
+    }
+
+    //Accumulate in f64; sums over many cells lose precision in f32
+    let n = cells.len() as f64;
+    let mean_a = cells.iter().map(|i| a[*i] as f64).sum::<f64>() / n;
+    let mean_b = cells.iter().map(|i| b[*i] as f64).sum::<f64>() / n;
+
+    let mut cov = 0.0;
+    let mut var_a = 0.0;
+    let mut var_b = 0.0;
+    for i in cells.iter() {
+        let da = a[*i] as f64 - mean_a;
+        let db = b[*i] as f64 - mean_b;
+        cov += da*db;
+        var_a += da*da;
+        var_b += db*db;
+    }
+
+    if var_a > 0.0 && var_b > 0.0 {
+        Some((cov / (var_a*var_b).sqrt()) as f32)
+    } else {
+        None
+    }
+}
+
+
+////////////////////////////////////////////////////////////
+/// Log ratio of two features for each cell, log((A+1)/(B+1)). Positive where A is higher.
+/// None if either column is categorical
+pub fn expression_log_ratio(data_a: &CountFileMetaColumnData, data_b: &CountFileMetaColumnData, num_point: usize) -> Option<Vec<f32>> {
+    let values_a = dense_numeric_values(data_a, num_point)?;
+    let values_b = dense_numeric_values(data_b, num_point)?;
+    Some(values_a.iter().zip(values_b.iter()).map(|(a, b)| ((a + 1.0)/(b + 1.0)).ln()).collect())
+}
@@ -0,0 +1,33 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::component_reduction_main::ReductionViewData;
+
+
+////////////////////////////////////////////////////////////
+/// Table of all cells: ID if known, position in the reduction, and annotation
+pub fn build_cells_csv(reduction: &ReductionViewData, cell_ids: Option<&Vec<String>>, cell_annotations: &HashMap<usize, String>) -> String {
+    let mut csv = String::from("cell,x,y,annotation\n");
+    for i in 0..reduction.num_point {
+        let cell_id = cell_ids.and_then(|ids| ids.get(i)).cloned().unwrap_or_else(|| i.to_string());
+        let annotation = cell_annotations.get(&i).map(|s| s.as_str()).unwrap_or("");
+        let _ = writeln!(csv, "{},{},{},{}",
+            csv_escape(&cell_id), 
+            reduction.data[i*2+0], 
+            reduction.data[i*2+1], 
+            csv_escape(annotation)
+        );
+    }
+    csv
+}
+
+
+////////////////////////////////////////////////////////////
+/// Quote a CSV field if needed
+pub fn csv_escape(s: &str) -> String {
+    if s.contains(|c| c == ',' || c == '"' || c == '\n' || c == '\r') {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
@@ -0,0 +1,28 @@
+precision mediump float;
+
+// Background shaded by the density of points: white where there are none, light blue where dense
+
+varying vec2 v_uv;
+
+uniform sampler2D u_density;
+
+uniform float u_camera_x;
+uniform float u_camera_y;
+uniform float u_camera_zoom_x;
+uniform float u_camera_zoom_y;
+
+uniform vec2 u_grid_min;
+uniform vec2 u_grid_max;
+
+void main() {
+    // From [0,1] screen coordinates back to world coordinates; camera y is inverted as in umap.vert
+    vec2 cam_pos = v_uv * 2.0 - 1.0;
+    vec2 world = vec2(cam_pos.x / u_camera_zoom_x + u_camera_x, -cam_pos.y / u_camera_zoom_y + u_camera_y);
+    vec2 grid_uv = (world - u_grid_min) / (u_grid_max - u_grid_min);
+
+    float density = 0.0;
+    if (grid_uv.x >= 0.0 && grid_uv.x <= 1.0 && grid_uv.y >= 0.0 && grid_uv.y <= 1.0) {
+        density = texture2D(u_density, grid_uv).r;
+    }
+    gl_FragColor = vec4(mix(vec3(1.0, 1.0, 1.0), vec3(0.75, 0.87, 1.0), density), 1.0);
+}
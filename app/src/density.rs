@@ -0,0 +1,199 @@
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlCanvasElement, WebGlRenderingContext as GL};
+
+use crate::camera::Camera2D;
+use crate::component_reduction_main::{compile_shader_program, ReductionViewData};
+
+
+////////////////////////////////////////////////////////////
+/// Number of cells along each side of the density grid
+pub const DENSITY_GRID_SIZE: usize = 128;
+
+////////////////////////////////////////////////////////////
+/// Width of the Gaussian kernel, in grid cells. The kernel is cut off at 3 sigma
+const DENSITY_SIGMA: f32 = 1.5;
+const DENSITY_RADIUS: usize = 5;
+
+////////////////////////////////////////////////////////////
+/// Number of cells along each side of the grid that neighbors of points are counted in
+const POINT_DENSITY_GRID_SIZE: usize = 100;
+
+
+////////////////////////////////////////////////////////////
+/// Density of points over a grid covering the reduction, scaled to [0,1]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DensityGrid {
+    pub size: usize,
+    pub values: Vec<f32>,   // row by row; the first row is at min_y
+    pub min_x: f32,
+    pub max_x: f32,
+    pub min_y: f32,
+    pub max_y: f32,
+}
+
+
+////////////////////////////////////////////////////////////
+/// How crowded the region around each point is: the number of points in its grid cell and the
+/// 8 cells around it, divided by the mean of this over all points. 1 is an average crowding
+pub fn compute_point_density(reduction: &ReductionViewData) -> Vec<f32> {
+    let size = POINT_DENSITY_GRID_SIZE;
+    let span_x = (reduction.max_x - reduction.min_x).max(1e-6);
+    let span_y = (reduction.max_y - reduction.min_y).max(1e-6);
+    let grid_pos = |i: usize| {
+        let gx = ((reduction.data[i*2+0] - reduction.min_x)/span_x*(size - 1) as f32) as usize;
+        let gy = ((reduction.data[i*2+1] - reduction.min_y)/span_y*(size - 1) as f32) as usize;
+        (gx.min(size - 1), gy.min(size - 1))
+    };
+
+    let mut counts = vec![0u32; size*size];
+    for i in 0..reduction.num_point {
+        let (gx, gy) = grid_pos(i);
+        counts[gy*size + gx] += 1;
+    }
+
+    let mut density: Vec<f32> = (0..reduction.num_point).map(|i| {
+        let (gx, gy) = grid_pos(i);
+        let mut n = 0;
+        for y in gy.saturating_sub(1)..(gy + 2).min(size) {
+            for x in gx.saturating_sub(1)..(gx + 2).min(size) {
+                n += counts[y*size + x];
+            }
+        }
+        n as f32
+    }).collect();
+
+    let mean = density.iter().sum::<f32>() / (density.len().max(1) as f32);
+    if mean > 0.0 {
+        for d in density.iter_mut() {
+            *d /= mean;
+        }
+    }
+    density
+}
+
+
+////////////////////////////////////////////////////////////
+/// Sum of a Gaussian around each point, evaluated on a grid. Points are first counted per grid cell,
+/// then the counts are blurred along each axis, which gives the same sum for points at cell centers.
+/// The grid has a margin so that the Gaussians of points at the edge are not cut
+pub fn compute_density_grid(reduction: &ReductionViewData, size: usize) -> DensityGrid {
+    let margin = DENSITY_RADIUS as f32;
+    let inner = (size as f32 - 2.0*margin).max(1.0);
+    let span_x = (reduction.max_x - reduction.min_x).max(1e-6);
+    let span_y = (reduction.max_y - reduction.min_y).max(1e-6);
+    let (cell_w, cell_h) = (span_x/inner, span_y/inner);
+    let (min_x, min_y) = (reduction.min_x - margin*cell_w, reduction.min_y - margin*cell_h);
+
+    //Count points per cell
+    let mut counts = vec![0.0f32; size*size];
+    for i in 0..reduction.num_point {
+        let gx = ((reduction.data[i*2+0] - min_x)/cell_w) as usize;
+        let gy = ((reduction.data[i*2+1] - min_y)/cell_h) as usize;
+        if gx < size && gy < size {
+            counts[gy*size + gx] += 1.0;
+        }
+    }
+
+    //Separable Gaussian blur: first along x, then along y
+    let kernel: Vec<f32> = (0..=2*DENSITY_RADIUS)
+        .map(|k| (-((k as f32 - margin).powi(2))/(2.0*DENSITY_SIGMA*DENSITY_SIGMA)).exp())
+        .collect();
+    let blur = |input: &Vec<f32>, step: usize, stride: usize| -> Vec<f32> {
+        let mut output = vec![0.0f32; size*size];
+        for line in 0..size {
+            for pos in 0..size {
+                let mut sum = 0.0;
+                for (k, w) in kernel.iter().enumerate() {
+                    let p = pos as i64 + k as i64 - DENSITY_RADIUS as i64;
+                    if p >= 0 && (p as usize) < size {
+                        sum += w*input[line*stride + (p as usize)*step];
+                    }
+                }
+                output[line*stride + pos*step] = sum;
+            }
+        }
+        output
+    };
+    let values = blur(&blur(&counts, 1, size), size, 1);
+
+    //The square root lifts moderately dense regions, which would otherwise be hidden by the densest ones
+    let max_value = values.iter().cloned().fold(0.0f32, f32::max);
+    let values = if max_value > 0.0 {
+        values.iter().map(|v| (v/max_value).sqrt()).collect()
+    } else {
+        values
+    };
+
+    DensityGrid {
+        size: size,
+        values: values,
+        min_x: min_x,
+        max_x: min_x + cell_w*size as f32,
+        min_y: min_y,
+        max_y: min_y + cell_h*size as f32,
+    }
+}
+
+
+////////////////////////////////////////////////////////////
+/// Shade the whole canvas by the density of points, white where there are none.
+/// Meant to be drawn right after clearing, before the points
+pub fn draw_density_background(canvas: &HtmlCanvasElement, density: &DensityGrid, camera: &Camera2D) {
+    let gl: GL = canvas
+        .get_context("webgl")
+        .unwrap()
+        .unwrap()
+        .dyn_into()
+        .unwrap();
+
+    //Densities as bytes; this works without extensions for float textures
+    let texels: Vec<u8> = density.values.iter().map(|v| (v*255.0).round().clamp(0.0, 255.0) as u8).collect();
+    let texture = gl.create_texture().unwrap();
+    gl.active_texture(GL::TEXTURE0);
+    gl.bind_texture(GL::TEXTURE_2D, Some(&texture));
+    gl.pixel_storei(GL::UNPACK_ALIGNMENT, 1);
+    gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, GL::LINEAR as i32);
+    gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MAG_FILTER, GL::LINEAR as i32);
+    gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_S, GL::CLAMP_TO_EDGE as i32);
+    gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_T, GL::CLAMP_TO_EDGE as i32);
+    let uploaded = gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+        GL::TEXTURE_2D, 0, GL::LUMINANCE as i32, density.size as i32, density.size as i32, 0, GL::LUMINANCE, GL::UNSIGNED_BYTE, Some(&texels)
+    );
+    if uploaded.is_err() {
+        log::warn!("Could not upload density texture");
+        gl.delete_texture(Some(&texture));
+        return;
+    }
+
+    //Full-screen quad; the shader maps each pixel back to world coordinates
+    let program = compile_shader_program(&gl, include_str!("./oit_composite.vert"), include_str!("./density.frag"));
+    gl.use_program(Some(&program));
+    let quad: Vec<f32> = vec![
+        -1.0, -1.0,
+         1.0, -1.0,
+        -1.0,  1.0,
+        -1.0,  1.0,
+         1.0, -1.0,
+         1.0,  1.0,
+    ];
+    let quad_buffer = gl.create_buffer().unwrap();
+    let js_quad = js_sys::Float32Array::from(quad.as_slice());
+    gl.bind_buffer(GL::ARRAY_BUFFER, Some(&quad_buffer));
+    gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &js_quad, GL::STATIC_DRAW);
+    let a_position = gl.get_attrib_location(&program, "a_position") as u32;
+    gl.enable_vertex_attrib_array(a_position);
+    gl.vertex_attrib_pointer_with_i32(a_position, 2, GL::FLOAT, false, 0, 0);
+
+    gl.uniform1i(gl.get_uniform_location(&program, "u_density").as_ref(), 0);
+    gl.uniform1f(gl.get_uniform_location(&program, "u_camera_x").as_ref(), camera.x);
+    gl.uniform1f(gl.get_uniform_location(&program, "u_camera_y").as_ref(), camera.y);
+    gl.uniform1f(gl.get_uniform_location(&program, "u_camera_zoom_x").as_ref(), camera.zoom_x);
+    gl.uniform1f(gl.get_uniform_location(&program, "u_camera_zoom_y").as_ref(), camera.zoom_y);
+    gl.uniform2f(gl.get_uniform_location(&program, "u_grid_min").as_ref(), density.min_x, density.min_y);
+    gl.uniform2f(gl.get_uniform_location(&program, "u_grid_max").as_ref(), density.max_x, density.max_y);
+    gl.draw_arrays(GL::TRIANGLES, 0, 6);
+
+    gl.disable_vertex_attrib_array(a_position);
+    gl.delete_buffer(Some(&quad_buffer));
+    gl.delete_texture(Some(&texture));
+}
@@ -0,0 +1,22 @@
+use wasm_bindgen::JsCast;
+use wasm_bindgen::JsValue;
+use web_sys::{window, Blob, BlobPropertyBag, HtmlAnchorElement, Url};
+
+
+////////////////////////////////////////////////////////////
+/// Let the user save text as a file. A temporary link to the content is clicked
+pub fn download_text(filename: &str, mime_type: &str, content: &str) -> Result<(), JsValue> {
+    let parts = js_sys::Array::of1(&JsValue::from_str(content));
+    let options = BlobPropertyBag::new();
+    options.set_type(mime_type);
+    let blob = Blob::new_with_str_sequence_and_options(&parts, &options)?;
+    let url = Url::create_object_url_with_blob(&blob)?;
+
+    let document = window().expect("no window").document().expect("no document");
+    let anchor: HtmlAnchorElement = document.create_element("a")?.dyn_into()?;
+    anchor.set_href(&url);
+    anchor.set_download(filename);
+    anchor.click();
+
+    Url::revoke_object_url(&url)
+}
@@ -0,0 +1,81 @@
+use bytes::Bytes;
+
+use crate::appstate::PerCellDataSource;
+
+
+////////////////////////////////////////////////////////////
+/// Number of times a failed fetch is tried again before giving up
+pub const MAX_FETCH_RETRIES: u8 = 4;
+
+////////////////////////////////////////////////////////////
+/// Wait before the first retry; doubled for each retry after that (500ms, 1s, 2s, 4s)
+pub const FETCH_RETRY_BASE_MS: u32 = 500;
+
+
+////////////////////////////////////////////////////////////
+/// Data that is fetched again if the request fails
+#[derive(Debug, Clone, PartialEq)]
+pub enum FetchTarget {
+    Reduction(String),
+    PerCellData(PerCellDataSource),
+}
+impl std::fmt::Display for FetchTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FetchTarget::Reduction(name) => write!(f, "reduction {}", name),
+            FetchTarget::PerCellData(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+
+////////////////////////////////////////////////////////////
+/// How many times a fetch has been retried, and how long was waited before the last retry
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FetchRetry {
+    pub retry_count: u8,
+    pub retry_delay_ms: u32,
+}
+impl FetchRetry {
+
+    ////////////////////////////////////////////////////////////
+    /// State of the next retry, or None if all retries have been used
+    pub fn next(&self) -> Option<FetchRetry> {
+        if self.retry_count >= MAX_FETCH_RETRIES {
+            None
+        } else {
+            Some(FetchRetry {
+                retry_count: self.retry_count + 1,
+                retry_delay_ms: FETCH_RETRY_BASE_MS << self.retry_count,
+            })
+        }
+    }
+
+}
+
+
+////////////////////////////////////////////////////////////
+/// Post a JSON query and get the binary answer, along with its content type. Errors, including
+/// error status codes from the server, are returned as text rather than panicking
+pub async fn post_for_bytes(url: String, query_json: Vec<u8>, accept: Option<&str>) -> Result<(Bytes, Option<String>), String> {
+    let client = reqwest::Client::new();
+    let mut request = client.post(url)
+        .header("Content-Type", "application/json")
+        .body(query_json);
+    if let Some(accept) = accept {
+        request = request.header("Accept", accept);
+    }
+    let res = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request: {}", e))?
+        .error_for_status()
+        .map_err(|e| format!("Server error: {}", e))?;
+    let content_type = res.headers().get("Content-Type")
+        .and_then(|h| h.to_str().ok())
+        .map(|h| h.to_string());
+    let bytes = res.bytes()
+        .await
+        .map_err(|e| format!("Could not get binary data: {}", e))?;
+    Ok((bytes, content_type))
+}
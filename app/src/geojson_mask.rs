@@ -0,0 +1,123 @@
+use geojson::{Feature, GeoJson, Geometry, JsonObject, PolygonType, Value};
+
+use crate::component_reduction_main::ReductionViewData;
+
+
+////////////////////////////////////////////////////////////
+/// Outline of a region, e.g. a tissue section, in the coordinates of a reduction. Cells outside
+/// the outer ring, or inside one of the holes, are outside the mask
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolygonMask {
+    pub outer: Vec<(f32,f32)>,
+    pub holes: Vec<Vec<(f32,f32)>>,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Reason a GeoJSON mask could not be read
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeoJsonError {
+    InvalidGeoJson(String),
+    NoPolygon,
+    InvalidRing,
+}
+impl std::fmt::Display for GeoJsonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GeoJsonError::InvalidGeoJson(e) => write!(f, "not valid GeoJSON: {}", e),
+            GeoJsonError::NoPolygon => write!(f, "no Polygon geometry found"),
+            GeoJsonError::InvalidRing => write!(f, "a ring of the polygon does not have at least 3 points"),
+        }
+    }
+}
+
+
+////////////////////////////////////////////////////////////
+/// Read the first Polygon of a GeoJSON geometry, Feature or FeatureCollection. The first polygon of a
+/// MultiPolygon is also accepted. Coordinates are mapped to the reduction as x*scale + offset, where
+/// "scale" (a number) and "offset" ([x, y]) are optional properties of the feature
+pub fn parse_geojson_polygon(json: &str) -> Result<PolygonMask, GeoJsonError> {
+    let geojson: GeoJson = json.parse().map_err(|e: geojson::Error| GeoJsonError::InvalidGeoJson(e.to_string()))?;
+    let (rings, properties) = match &geojson {
+        GeoJson::Geometry(geometry) => find_polygon(geometry, None),
+        GeoJson::Feature(feature) => find_feature_polygon(feature),
+        GeoJson::FeatureCollection(collection) => collection.features.iter().find_map(find_feature_polygon),
+    }.ok_or(GeoJsonError::NoPolygon)?;
+
+    let scale = properties.and_then(|p| p.get("scale")).and_then(|s| s.as_f64()).unwrap_or(1.0);
+    let offset = properties.and_then(|p| p.get("offset")).and_then(|o| o.as_array())
+        .and_then(|o| Some((o.get(0)?.as_f64()?, o.get(1)?.as_f64()?)))
+        .unwrap_or((0.0, 0.0));
+
+    let mut rings = rings.iter().map(|ring| {
+        let points: Vec<(f32,f32)> = ring.iter()
+            .filter(|p| p.len() >= 2)
+            .map(|p| ((p[0]*scale + offset.0) as f32, (p[1]*scale + offset.1) as f32))
+            .collect();
+        if points.len() < 3 {
+            return Err(GeoJsonError::InvalidRing);
+        }
+        Ok(points)
+    }).collect::<Result<Vec<Vec<(f32,f32)>>, GeoJsonError>>()?;
+    if rings.is_empty() {
+        return Err(GeoJsonError::NoPolygon);
+    }
+
+    let outer = rings.remove(0);
+    Ok(PolygonMask {
+        outer: outer,
+        holes: rings,
+    })
+}
+
+
+////////////////////////////////////////////////////////////
+/// Rings of the polygon of a feature, along with its properties
+fn find_feature_polygon(feature: &Feature) -> Option<(&PolygonType, Option<&JsonObject>)> {
+    find_polygon(feature.geometry.as_ref()?, feature.properties.as_ref())
+}
+
+
+////////////////////////////////////////////////////////////
+/// Rings of the first polygon in a geometry
+fn find_polygon<'a>(geometry: &'a Geometry, properties: Option<&'a JsonObject>) -> Option<(&'a PolygonType, Option<&'a JsonObject>)> {
+    match &geometry.value {
+        Value::Polygon(rings) => Some((rings, properties)),
+        Value::MultiPolygon(polygons) => Some((polygons.first()?, properties)),
+        Value::GeometryCollection(geometries) => geometries.iter().find_map(|g| find_polygon(g, properties)),
+        _ => None
+    }
+}
+
+
+////////////////////////////////////////////////////////////
+/// Test if a point is inside a ring, by counting how many of its edges a ray to the right crosses.
+/// The ring may or may not repeat its first point at the end
+pub fn point_in_polygon(x: f32, y: f32, ring: &Vec<(f32,f32)>) -> bool {
+    let mut inside = false;
+    let n = ring.len();
+    for i in 0..n {
+        let (xi, yi) = ring[i];
+        let (xj, yj) = ring[(i + n - 1) % n];
+        if (yi > y) != (yj > y) && x < (xj - xi)*(y - yi)/(yj - yi) + xi {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+
+////////////////////////////////////////////////////////////
+/// For each cell of the reduction, if it is inside the mask
+pub fn cells_inside_mask(reduction: &ReductionViewData, mask: &PolygonMask) -> Vec<bool> {
+    //Bounding box of the outer ring, to skip most cells outside quickly
+    let (min_x, max_x) = mask.outer.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), p| (lo.min(p.0), hi.max(p.0)));
+    let (min_y, max_y) = mask.outer.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), p| (lo.min(p.1), hi.max(p.1)));
+
+    (0..reduction.num_point).map(|i| {
+        let (x, y) = (reduction.data[i*2+0], reduction.data[i*2+1]);
+        x >= min_x && x <= max_x && y >= min_y && y <= max_y
+            && point_in_polygon(x, y, &mask.outer)
+            && !mask.holes.iter().any(|hole| point_in_polygon(x, y, hole))
+    }).collect()
+}
@@ -0,0 +1,195 @@
+use js_sys::{Array, Float32Array};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{window, HtmlCanvasElement, WebGl2RenderingContext as GL2, WebGlBuffer, WebGlProgram};
+
+use crate::component_reduction_main::ReductionViewData;
+
+
+////////////////////////////////////////////////////////////
+/// Number of iterations run when the layout is relaxed
+pub const RELAX_ITERATIONS: usize = 100;
+
+////////////////////////////////////////////////////////////
+/// Other points each point is pushed away from, per iteration
+const RELAX_NUM_SAMPLES: i32 = 16;
+
+////////////////////////////////////////////////////////////
+/// Size the layout is scaled to while relaxing. The UMAP forces are tuned for embeddings of about this size
+const RELAX_SPAN: f32 = 20.0;
+
+////////////////////////////////////////////////////////////
+/// Strength of the pull back to the server layout
+const RELAX_SPRING: f32 = 0.05;
+
+////////////////////////////////////////////////////////////
+/// Largest move of a point in one iteration, as UMAP clips its gradient
+const RELAX_MAX_STEP: f32 = 4.0;
+
+
+////////////////////////////////////////////////////////////
+/// Spread out overlapping points with UMAP-style repulsion, run on the GPU.
+///
+/// Positions are kept in two buffers. In each iteration, one is read as vertex attributes and
+/// copied to a texture, so that each point can look up the positions of others; the vertex shader
+/// writes the new positions to the other buffer using transform feedback. Then the buffers swap.
+/// This needs WebGL2, which the main view does not use, so a separate offscreen canvas is created
+pub fn relax_layout_gpu(reduction: &ReductionViewData, iterations: usize) -> Result<ReductionViewData, String> {
+    let num_point = reduction.num_point;
+    if num_point < 2 {
+        return Ok(reduction.clone());
+    }
+
+    //Scale the layout to a standard size around the origin
+    let center = ((reduction.min_x + reduction.max_x)/2.0, (reduction.min_y + reduction.max_y)/2.0);
+    let scale = RELAX_SPAN / (reduction.max_x - reduction.min_x).max(reduction.max_y - reduction.min_y).max(1e-6);
+    let tex_width = (num_point as f64).sqrt().ceil() as usize;
+    let tex_height = num_point.div_ceil(tex_width);
+    let mut positions = vec![0.0f32; tex_width*tex_height*2];   // padded to fill the texture
+    for i in 0..num_point {
+        positions[i*2+0] = (reduction.data[i*2+0] - center.0)*scale;
+        positions[i*2+1] = (reduction.data[i*2+1] - center.1)*scale;
+    }
+
+    let document = window().expect("no window").document().expect("no document");
+    let canvas: HtmlCanvasElement = document.create_element("canvas").unwrap().dyn_into().unwrap();
+    let gl: GL2 = canvas
+        .get_context("webgl2")
+        .ok()
+        .flatten()
+        .ok_or("WebGL2 is not supported by this browser")?
+        .dyn_into()
+        .unwrap();
+
+    let program = compile_feedback_program(&gl, include_str!("./relax.vert"), include_str!("./relax.frag"), "v_pos")?;
+    gl.use_program(Some(&program));
+
+    //Anchors are the initial positions, and do not change
+    let make_buffer = |data: &[f32], usage: u32| -> WebGlBuffer {
+        let buffer = gl.create_buffer().unwrap();
+        gl.bind_buffer(GL2::ARRAY_BUFFER, Some(&buffer));
+        gl.buffer_data_with_array_buffer_view(GL2::ARRAY_BUFFER, &Float32Array::from(data), usage);
+        buffer
+    };
+    let anchor_buffer = make_buffer(&positions, GL2::STATIC_DRAW);
+    let position_buffers = [make_buffer(&positions, GL2::DYNAMIC_COPY), make_buffer(&positions, GL2::DYNAMIC_COPY)];
+
+    let texture = gl.create_texture().unwrap();
+    gl.active_texture(GL2::TEXTURE0);
+    gl.bind_texture(GL2::TEXTURE_2D, Some(&texture));
+    gl.tex_parameteri(GL2::TEXTURE_2D, GL2::TEXTURE_MIN_FILTER, GL2::NEAREST as i32);
+    gl.tex_parameteri(GL2::TEXTURE_2D, GL2::TEXTURE_MAG_FILTER, GL2::NEAREST as i32);
+    gl.tex_storage_2d(GL2::TEXTURE_2D, 1, GL2::RG32F, tex_width as i32, tex_height as i32);
+
+    let a_pos = gl.get_attrib_location(&program, "a_pos") as u32;
+    let a_anchor = gl.get_attrib_location(&program, "a_anchor") as u32;
+    gl.bind_buffer(GL2::ARRAY_BUFFER, Some(&anchor_buffer));
+    gl.enable_vertex_attrib_array(a_anchor);
+    gl.vertex_attrib_pointer_with_i32(a_anchor, 2, GL2::FLOAT, false, 0, 0);
+    gl.enable_vertex_attrib_array(a_pos);
+
+    gl.uniform1i(gl.get_uniform_location(&program, "u_positions").as_ref(), 0);
+    gl.uniform1i(gl.get_uniform_location(&program, "u_tex_width").as_ref(), tex_width as i32);
+    gl.uniform1i(gl.get_uniform_location(&program, "u_num_point").as_ref(), num_point as i32);
+    gl.uniform1i(gl.get_uniform_location(&program, "u_num_samples").as_ref(), RELAX_NUM_SAMPLES);
+    gl.uniform1f(gl.get_uniform_location(&program, "u_spring").as_ref(), RELAX_SPRING);
+    gl.uniform1f(gl.get_uniform_location(&program, "u_max_step").as_ref(), RELAX_MAX_STEP);
+    let u_offset = gl.get_uniform_location(&program, "u_offset");
+    let u_alpha = gl.get_uniform_location(&program, "u_alpha");
+
+    let feedback = gl.create_transform_feedback().unwrap();
+    gl.enable(GL2::RASTERIZER_DISCARD);
+    for it in 0..iterations {
+        let (src, dst) = (&position_buffers[it % 2], &position_buffers[1 - it % 2]);
+
+        //Current positions into the texture, copied on the GPU
+        gl.bind_buffer(GL2::PIXEL_UNPACK_BUFFER, Some(src));
+        gl.tex_sub_image_2d_with_i32_and_i32_and_u32_and_type_and_i32(
+            GL2::TEXTURE_2D, 0, 0, 0, tex_width as i32, tex_height as i32, GL2::RG, GL2::FLOAT, 0
+        ).map_err(|e| format!("Could not update position texture: {:?}", e))?;
+        gl.bind_buffer(GL2::PIXEL_UNPACK_BUFFER, None);
+
+        gl.bind_buffer(GL2::ARRAY_BUFFER, Some(src));
+        gl.vertex_attrib_pointer_with_i32(a_pos, 2, GL2::FLOAT, false, 0, 0);
+        gl.bind_buffer(GL2::ARRAY_BUFFER, None);
+
+        gl.uniform1i(u_offset.as_ref(), ((it as u64*7919) % num_point as u64) as i32);
+        gl.uniform1f(u_alpha.as_ref(), 1.0 - it as f32/iterations as f32);
+
+        gl.bind_transform_feedback(GL2::TRANSFORM_FEEDBACK, Some(&feedback));
+        gl.bind_buffer_base(GL2::TRANSFORM_FEEDBACK_BUFFER, 0, Some(dst));
+        gl.begin_transform_feedback(GL2::POINTS);
+        gl.draw_arrays(GL2::POINTS, 0, num_point as i32);
+        gl.end_transform_feedback();
+        gl.bind_buffer_base(GL2::TRANSFORM_FEEDBACK_BUFFER, 0, None);
+        gl.bind_transform_feedback(GL2::TRANSFORM_FEEDBACK, None);
+    }
+    gl.disable(GL2::RASTERIZER_DISCARD);
+
+    //Read back the final positions
+    let result = Float32Array::new_with_length((num_point*2) as u32);
+    gl.bind_buffer(GL2::COPY_READ_BUFFER, Some(&position_buffers[iterations % 2]));
+    gl.get_buffer_sub_data_with_i32_and_array_buffer_view(GL2::COPY_READ_BUFFER, 0, &result);
+    gl.bind_buffer(GL2::COPY_READ_BUFFER, None);
+
+    gl.delete_transform_feedback(Some(&feedback));
+    gl.delete_texture(Some(&texture));
+    gl.delete_buffer(Some(&anchor_buffer));
+    for buffer in &position_buffers {
+        gl.delete_buffer(Some(buffer));
+    }
+    gl.delete_program(Some(&program));
+
+    //Back to the coordinates of the reduction
+    let data: Vec<f32> = result.to_vec().chunks_exact(2)
+        .flat_map(|p| [p[0]/scale + center.0, p[1]/scale + center.1])
+        .collect();
+    if data.iter().any(|v| !v.is_finite()) {
+        return Err("Relaxing the layout gave invalid positions".to_string());
+    }
+
+    let mut relaxed = ReductionViewData {
+        num_point: num_point,
+        data: data,
+        max_x: f32::MIN,
+        max_y: f32::MIN,
+        min_x: f32::MAX,
+        min_y: f32::MAX,
+        version: reduction.version,
+    };
+    for p in relaxed.data.chunks_exact(2) {
+        relaxed.max_x = relaxed.max_x.max(p[0]);
+        relaxed.min_x = relaxed.min_x.min(p[0]);
+        relaxed.max_y = relaxed.max_y.max(p[1]);
+        relaxed.min_y = relaxed.min_y.min(p[1]);
+    }
+    Ok(relaxed)
+}
+
+
+////////////////////////////////////////////////////////////
+/// Compile and link a pair of shaders, capturing one output of the vertex shader with transform feedback
+fn compile_feedback_program(gl: &GL2, vert_code: &str, frag_code: &str, varying: &str) -> Result<WebGlProgram, String> {
+    let vert_shader = gl.create_shader(GL2::VERTEX_SHADER).unwrap();
+    gl.shader_source(&vert_shader, vert_code);
+    gl.compile_shader(&vert_shader);
+
+    let frag_shader = gl.create_shader(GL2::FRAGMENT_SHADER).unwrap();
+    gl.shader_source(&frag_shader, frag_code);
+    gl.compile_shader(&frag_shader);
+
+    let shader_program = gl.create_program().unwrap();
+    gl.attach_shader(&shader_program, &vert_shader);
+    gl.attach_shader(&shader_program, &frag_shader);
+    let varyings = Array::of1(&JsValue::from_str(varying));
+    gl.transform_feedback_varyings(&shader_program, &varyings, GL2::SEPARATE_ATTRIBS);
+    gl.link_program(&shader_program);
+
+    if gl.get_program_parameter(&shader_program, GL2::LINK_STATUS).as_bool().unwrap_or(false) {
+        Ok(shader_program)
+    } else {
+        let log = gl.get_program_info_log(&shader_program)
+            .or_else(|| gl.get_shader_info_log(&vert_shader))
+            .unwrap_or_default();
+        Err(format!("Could not link relaxation shader: {}", log))
+    }
+}
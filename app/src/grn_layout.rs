@@ -0,0 +1,121 @@
+use my_web_app::GrnResponse;
+
+
+////////////////////////////////////////////////////////////
+/// Role of a feature in the network around the hovered feature
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GrnNodeKind {
+    Center,
+    Regulator,
+    Target,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Feature placed in the network. The weight is that of its edge to the center
+#[derive(Debug, Clone, PartialEq)]
+pub struct GrnNode {
+    pub name: String,
+    pub kind: GrnNodeKind,
+    pub weight: f32,
+    pub x: f32,
+    pub y: f32,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Number of steps of the force-directed layout
+const GRN_LAYOUT_ITERATIONS: usize = 200;
+
+////////////////////////////////////////////////////////////
+/// Rest length and stiffness of the springs between the center and its neighbours
+const SPRING_LENGTH: f32 = 1.0;
+const SPRING_STIFFNESS: f32 = 0.2;
+
+////////////////////////////////////////////////////////////
+/// Strength of the repulsion between every pair of nodes
+const REPULSION: f32 = 0.1;
+
+////////////////////////////////////////////////////////////
+/// Strength of the pull of regulators above the center, and of targets below it
+const LAYER_PULL: f32 = 0.2;
+
+////////////////////////////////////////////////////////////
+/// Largest distance a node moves in the first step. Reduced linearly to zero, so that the layout settles
+const MAX_STEP: f32 = 0.2;
+
+
+////////////////////////////////////////////////////////////
+/// Lay out a feature and its regulators and targets using springs between the feature and
+/// each neighbour, and repulsion between all nodes. Regulators are pulled above the feature and
+/// targets below it. The feature stays at the origin. The layout is deterministic
+pub fn layout_grn(gene: &String, network: &GrnResponse) -> Vec<GrnNode> {
+    let mut nodes = vec![GrnNode {
+        name: gene.clone(),
+        kind: GrnNodeKind::Center,
+        weight: 0.0,
+        x: 0.0,
+        y: 0.0,
+    }];
+
+    //Start out spread along a row above or below the center
+    for (kind, edges) in [(GrnNodeKind::Regulator, &network.regulators), (GrnNodeKind::Target, &network.targets)] {
+        let row_y = if kind == GrnNodeKind::Regulator { -SPRING_LENGTH } else { SPRING_LENGTH };
+        let n = edges.len();
+        for (i, (name, weight)) in edges.iter().enumerate() {
+            let x = if n > 1 { (i as f32)/((n - 1) as f32)*2.0 - 1.0 } else { 0.0 };
+            nodes.push(GrnNode {
+                name: name.clone(),
+                kind: kind,
+                weight: *weight,
+                x: x*SPRING_LENGTH,
+                y: row_y,
+            });
+        }
+    }
+
+    let num_nodes = nodes.len();
+    for iter in 0..GRN_LAYOUT_ITERATIONS {
+        let mut forces = vec![(0.0f32, 0.0f32); num_nodes];
+
+        //Repulsion between all pairs. Coinciding nodes are pushed apart sideways
+        for i in 0..num_nodes {
+            for j in (i + 1)..num_nodes {
+                let (dx, dy) = (nodes[i].x - nodes[j].x, nodes[i].y - nodes[j].y);
+                let dist2 = (dx*dx + dy*dy).max(1e-4);
+                let dist = dist2.sqrt();
+                let (ux, uy) = if dist > 1e-2 { (dx/dist, dy/dist) } else { (1.0, 0.0) };
+                let f = REPULSION/dist2;
+                forces[i].0 += ux*f;
+                forces[i].1 += uy*f;
+                forces[j].0 -= ux*f;
+                forces[j].1 -= uy*f;
+            }
+        }
+
+        //Springs to the center, and the pull toward the row of each kind
+        for i in 1..num_nodes {
+            let (dx, dy) = (nodes[i].x, nodes[i].y);
+            let dist = (dx*dx + dy*dy).sqrt().max(1e-4);
+            let f = SPRING_STIFFNESS*(dist - SPRING_LENGTH);
+            forces[i].0 -= dx/dist*f;
+            forces[i].1 -= dy/dist*f;
+
+            let row_y = if nodes[i].kind == GrnNodeKind::Regulator { -SPRING_LENGTH } else { SPRING_LENGTH };
+            forces[i].1 += (row_y - nodes[i].y)*LAYER_PULL;
+        }
+
+        //Move each node along its force, at most by the current step
+        let max_step = MAX_STEP*(1.0 - (iter as f32)/(GRN_LAYOUT_ITERATIONS as f32));
+        for i in 1..num_nodes {
+            let (fx, fy) = forces[i];
+            let f = (fx*fx + fy*fy).sqrt();
+            if f > 0.0 {
+                let step = f.min(max_step);
+                nodes[i].x += fx/f*step;
+                nodes[i].y += fy/f*step;
+            }
+        }
+    }
+    nodes
+}
@@ -0,0 +1,86 @@
+use my_web_app::CountFileMetaColumnData;
+
+use crate::component_reduction_main::Color3f;
+use crate::correlation::dense_numeric_values;
+
+
+////////////////////////////////////////////////////////////
+/// Z-scores beyond this are drawn with the color of the end of the scale
+pub const HEATMAP_Z_LIMIT: f32 = 2.0;
+
+////////////////////////////////////////////////////////////
+/// Viridis color map, sampled evenly from 0 to 1
+const VIRIDIS: [Color3f; 9] = [
+    (0.267, 0.005, 0.329),
+    (0.283, 0.141, 0.458),
+    (0.254, 0.265, 0.530),
+    (0.207, 0.372, 0.553),
+    (0.164, 0.471, 0.558),
+    (0.128, 0.567, 0.551),
+    (0.135, 0.659, 0.518),
+    (0.478, 0.821, 0.318),
+    (0.993, 0.906, 0.144),
+];
+
+
+////////////////////////////////////////////////////////////
+/// Color of a value in [0,1] using the viridis color map
+pub fn viridis(t: f32) -> Color3f {
+    let t = if t.is_finite() { t.clamp(0.0, 1.0) } else { 0.0 };
+    let pos = t*(VIRIDIS.len() - 1) as f32;
+    let i = (pos.floor() as usize).min(VIRIDIS.len() - 2);
+    let f = pos - i as f32;
+    let (a, b) = (VIRIDIS[i], VIRIDIS[i+1]);
+    (a.0 + (b.0 - a.0)*f, a.1 + (b.1 - a.1)*f, a.2 + (b.2 - a.2)*f)
+}
+
+
+////////////////////////////////////////////////////////////
+/// Mean expression of a feature in each group of cells, as a z-score: how many standard deviations
+/// over all cells the mean of the group is from the mean of all cells. None for categorical data
+pub fn group_mean_zscores(data: &CountFileMetaColumnData, groups: &[&Vec<usize>], num_point: usize) -> Option<Vec<f32>> {
+    let values = dense_numeric_values(data, num_point)?;
+    let n = values.len().max(1) as f32;
+    let mean_all = values.iter().sum::<f32>()/n;
+    let sd_all = (values.iter().map(|v| (v - mean_all)*(v - mean_all)).sum::<f32>()/n).sqrt();
+
+    Some(groups.iter().map(|cells| {
+        let in_range: Vec<f32> = cells.iter().filter_map(|c| values.get(*c).copied()).collect();
+        if in_range.is_empty() || sd_all == 0.0 {
+            0.0
+        } else {
+            let mean = in_range.iter().sum::<f32>()/in_range.len() as f32;
+            (mean - mean_all)/sd_all
+        }
+    }).collect())
+}
+
+
+////////////////////////////////////////////////////////////
+/// Order of rows so that similar rows are next to each other, from hierarchical clustering with
+/// single linkage: the two clusters with the closest pair of rows are merged, until one is left
+pub fn single_linkage_order(rows: &Vec<Vec<f32>>) -> Vec<usize> {
+    let dist = |a: usize, b: usize| -> f32 {
+        rows[a].iter().zip(rows[b].iter()).map(|(x, y)| (x - y)*(x - y)).sum::<f32>().sqrt()
+    };
+
+    //Each cluster lists its rows in the order they are drawn
+    let mut clusters: Vec<Vec<usize>> = (0..rows.len()).map(|i| vec![i]).collect();
+    while clusters.len() > 1 {
+        let mut best = (0, 1, f32::INFINITY);
+        for i in 0..clusters.len() {
+            for j in (i+1)..clusters.len() {
+                let d = clusters[i].iter()
+                    .flat_map(|a| clusters[j].iter().map(move |b| (*a, *b)))
+                    .map(|(a, b)| dist(a, b))
+                    .fold(f32::INFINITY, f32::min);
+                if d < best.2 {
+                    best = (i, j, d);
+                }
+            }
+        }
+        let merged = clusters.remove(best.1);
+        clusters[best.0].extend(merged);
+    }
+    clusters.pop().unwrap_or_default()
+}
@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+use web_sys::window;
+
+use crate::component_reduction_main::Color3f;
+
+
+////////////////////////////////////////////////////////////
+/// Key in localStorage under which landmarks are kept between sessions
+const LANDMARK_STORAGE_KEY: &str = "biscvi-landmarks";
+
+////////////////////////////////////////////////////////////
+/// How close to a pin, in pixels, a mouse press must be to drag it
+pub const LANDMARK_GRAB_RADIUS: f32 = 10.0;
+
+////////////////////////////////////////////////////////////
+/// Height of the stem of a pin, in pixels. The head is drawn this far above the point it marks
+pub const LANDMARK_PIN_HEIGHT: f32 = 16.0;
+
+
+////////////////////////////////////////////////////////////
+/// Named marker placed by the user on the reduction, e.g. to point out a region while presenting
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Landmark {
+    pub world_x: f32,
+    pub world_y: f32,
+    pub label: String,
+    pub color: Color3f,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Landmarks saved in localStorage. Empty if there are none, or they cannot be read
+pub fn load_landmarks() -> Vec<Landmark> {
+    let storage = window().and_then(|w| w.local_storage().ok().flatten());
+    storage
+        .and_then(|s| s.get_item(LANDMARK_STORAGE_KEY).ok().flatten())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+
+////////////////////////////////////////////////////////////
+/// Save landmarks to localStorage. Failures, e.g. storage being disabled, are only logged
+pub fn save_landmarks(landmarks: &Vec<Landmark>) {
+    let Some(storage) = window().and_then(|w| w.local_storage().ok().flatten()) else {
+        log::warn!("localStorage not available; landmarks not saved");
+        return;
+    };
+    let json = serde_json::to_string(landmarks).expect("Failed to serialize landmarks");
+    if storage.set_item(LANDMARK_STORAGE_KEY, &json).is_err() {
+        log::warn!("Could not save landmarks");
+    }
+}
@@ -0,0 +1,77 @@
+use std::collections::BTreeMap;
+
+use crate::component_reduction_main::ReductionViewData;
+
+
+////////////////////////////////////////////////////////////
+/// How cells are laid out in the reduction view
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LayoutMode {
+    Original,
+    RadialCluster,
+    Relaxed,   // overlapping points spread out on the GPU
+}
+
+
+////////////////////////////////////////////////////////////
+/// Radius of the empty hole in the middle, relative to the outer radius.
+/// Without it, all sectors would meet in a single point
+const INNER_RADIUS: f32 = 0.15;
+
+////////////////////////////////////////////////////////////
+/// Fraction of each sector left empty, to separate neighbouring categories
+const SECTOR_GAP: f32 = 0.1;
+
+
+////////////////////////////////////////////////////////////
+/// Reposition cells so that each category occupies a pie-slice sector of a disc.
+///
+/// Sectors are given the same angle, in the order of the category codes. Within a sector,
+/// cells are spread uniformly by area in polar coordinates. The positions are deterministic,
+/// following a golden-ratio sequence, so the layout does not change between redraws
+pub fn layout_radial_cluster(reduction: &ReductionViewData, vec_cats: &Vec<u32>) -> ReductionViewData {
+
+    let num_point = reduction.num_point.min(vec_cats.len());
+
+    //Group cells by category, keeping their original order
+    let mut map_cat_cells: BTreeMap<u32, Vec<usize>> = BTreeMap::new();
+    for i in 0..num_point {
+        map_cat_cells.entry(vec_cats[i]).or_default().push(i);
+    }
+
+    //Cells without a category keep their place at the center
+    let mut data = vec![0.0; reduction.num_point*2];
+
+    let num_sectors = map_cat_cells.len().max(1) as f32;
+    let sector_angle = 2.0*std::f32::consts::PI / num_sectors;
+    let golden = 0.618034;
+
+    for (sector_i, list_cells) in map_cat_cells.values().enumerate() {
+        let angle_start = sector_angle * (sector_i as f32 + SECTOR_GAP*0.5);
+        let angle_width = sector_angle * (1.0 - SECTOR_GAP);
+        let n = list_cells.len() as f32;
+
+        for (j, i) in list_cells.iter().enumerate() {
+            //Radius such that the density per area is uniform
+            let u = (j as f32 + 0.5) / n;
+            let r = (INNER_RADIUS*INNER_RADIUS + u*(1.0 - INNER_RADIUS*INNER_RADIUS)).sqrt();
+
+            //Angle from a low-discrepancy sequence
+            let v = (j as f32 * golden).fract();
+            let theta = angle_start + v*angle_width;
+
+            data[i*2+0] = r*theta.cos();
+            data[i*2+1] = r*theta.sin();
+        }
+    }
+
+    ReductionViewData {
+        num_point: reduction.num_point,
+        data: data,
+        max_x: 1.0,
+        max_y: 1.0,
+        min_x: -1.0,
+        min_y: -1.0,
+        version: reduction.version,
+    }
+}
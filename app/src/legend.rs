@@ -0,0 +1,60 @@
+use crate::camera::Camera2D;
+use crate::component_reduction_main::ReductionViewData;
+
+
+////////////////////////////////////////////////////////////
+/// Where the legend is placed over the reduction
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LegendPosition {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    Automatic,   // the corner with the fewest points
+}
+impl LegendPosition {
+
+    ////////////////////////////////////////////////////////////
+    /// Is this one of the bottom corners?
+    pub fn is_bottom(&self) -> bool {
+        matches!(self, LegendPosition::BottomLeft | LegendPosition::BottomRight)
+    }
+
+    ////////////////////////////////////////////////////////////
+    /// Is this one of the right corners?
+    pub fn is_right(&self) -> bool {
+        matches!(self, LegendPosition::TopRight | LegendPosition::BottomRight)
+    }
+
+}
+
+
+////////////////////////////////////////////////////////////
+/// Size of the corner regions where points are counted, in pixels
+const CORNER_REGION_SIZE: f32 = 100.0;
+
+
+////////////////////////////////////////////////////////////
+/// Find the corner of the canvas with the fewest points in view. On ties, the earlier
+/// corner in the order top-left, top-right, bottom-left, bottom-right is taken
+pub fn choose_legend_corner(reduction: &ReductionViewData, camera: &Camera2D, canvas_w: f32, canvas_h: f32) -> LegendPosition {
+    let mut counts = [0usize; 4];
+    for i in 0..reduction.num_point {
+        let (cx, cy) = camera.world2cam(reduction.data[i*2+0], reduction.data[i*2+1]);
+        let x = cx*canvas_w/2.0 + canvas_w/2.0;
+        let y = cy*canvas_h/2.0 + canvas_h/2.0;
+
+        let left = x >= 0.0 && x < CORNER_REGION_SIZE;
+        let right = x >= canvas_w - CORNER_REGION_SIZE && x <= canvas_w;
+        let top = y >= 0.0 && y < CORNER_REGION_SIZE;
+        let bottom = y >= canvas_h - CORNER_REGION_SIZE && y <= canvas_h;
+        if top && left { counts[0] += 1; }
+        if top && right { counts[1] += 1; }
+        if bottom && left { counts[2] += 1; }
+        if bottom && right { counts[3] += 1; }
+    }
+
+    let corners = [LegendPosition::TopLeft, LegendPosition::TopRight, LegendPosition::BottomLeft, LegendPosition::BottomRight];
+    let (best, _) = counts.iter().enumerate().min_by_key(|(_, c)| **c).expect("there are four corners");
+    corners[best]
+}
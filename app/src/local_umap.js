@@ -0,0 +1,158 @@
+
+// One epoch of UMAP layout optimization. Each invocation moves one cell.
+// Must give the same result as optimize_layout() in src/local_umap.rs
+const UMAP_SHADER = `
+struct Params {
+    n: u32,
+    epoch: u32,
+    n_epochs: u32,
+    num_negative: u32,
+};
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> pos_in: array<vec2<f32>>;
+@group(0) @binding(2) var<storage, read_write> pos_out: array<vec2<f32>>;
+@group(0) @binding(3) var<storage, read> offsets: array<u32>;
+@group(0) @binding(4) var<storage, read> neighbors: array<u32>;
+
+const UMAP_A: f32 = 1.577;
+const UMAP_B: f32 = 0.895;
+const GRADIENT_CLIP: f32 = 4.0;
+
+fn hash_u32(x: u32) -> u32 {
+    let state = x * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+fn clip(v: vec2<f32>) -> vec2<f32> {
+    return clamp(v, vec2<f32>(-GRADIENT_CLIP), vec2<f32>(GRADIENT_CLIP));
+}
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
+    let i = gid.x;
+    if (i >= params.n) {
+        return;
+    }
+    let yi = pos_in[i];
+    var force = vec2<f32>(0.0, 0.0);
+
+    // Pull towards neighbours
+    for (var e = offsets[i]; e < offsets[i + 1u]; e = e + 1u) {
+        let d = yi - pos_in[neighbors[e]];
+        let d2 = dot(d, d);
+        if (d2 > 0.0) {
+            let coeff = -2.0 * UMAP_A * UMAP_B * pow(d2, UMAP_B - 1.0) / (1.0 + UMAP_A * pow(d2, UMAP_B));
+            force = force + clip(coeff * d);
+        }
+    }
+
+    // Push away from random cells
+    for (var s = 0u; s < params.num_negative; s = s + 1u) {
+        let k = hash_u32((i * 2654435769u) ^ hash_u32(params.epoch * params.num_negative + s)) % params.n;
+        if (k == i) {
+            continue;
+        }
+        let d = yi - pos_in[k];
+        let d2 = dot(d, d);
+        if (d2 > 0.0) {
+            let coeff = 2.0 * UMAP_B / ((0.001 + d2) * (1.0 + UMAP_A * pow(d2, UMAP_B)));
+            force = force + clip(coeff * d);
+        }
+    }
+
+    let alpha = 1.0 - f32(params.epoch) / f32(params.n_epochs);
+    pos_out[i] = yi + alpha * force;
+}
+`;
+
+
+export function has_webgpu() {
+    return typeof navigator !== "undefined" && !!navigator.gpu;
+}
+
+
+// Lay out a neighbour graph using UMAP on the GPU. Positions are x,y pairs.
+// Resolves to the new positions as a Float32Array
+export async function run_local_umap_webgpu(positions, offsets, neighbors, n_epochs, num_negative) {
+    // The arrays are views into wasm memory, which may move while waiting; copy them first
+    positions = positions.slice();
+    offsets = offsets.slice();
+    neighbors = neighbors.slice();
+
+    const adapter = await navigator.gpu.requestAdapter();
+    if (!adapter) {
+        throw new Error("No WebGPU adapter available");
+    }
+    const device = await adapter.requestDevice();
+    const n = positions.length / 2;
+
+    function makeBuffer(data, usage) {
+        const buffer = device.createBuffer({
+            size: Math.max(data.byteLength, 4),
+            usage: usage,
+            mappedAtCreation: true,
+        });
+        new data.constructor(buffer.getMappedRange()).set(data);
+        buffer.unmap();
+        return buffer;
+    }
+    const storage = GPUBufferUsage.STORAGE;
+    const buf_pos = [
+        makeBuffer(positions, storage | GPUBufferUsage.COPY_SRC),
+        makeBuffer(positions, storage | GPUBufferUsage.COPY_SRC),
+    ];
+    const buf_offsets = makeBuffer(offsets, storage);
+    const buf_neighbors = makeBuffer(neighbors, storage);
+    const buf_params = device.createBuffer({
+        size: 16,
+        usage: GPUBufferUsage.UNIFORM | GPUBufferUsage.COPY_DST,
+    });
+    const buf_read = device.createBuffer({
+        size: positions.byteLength,
+        usage: GPUBufferUsage.MAP_READ | GPUBufferUsage.COPY_DST,
+    });
+
+    const pipeline = device.createComputePipeline({
+        layout: "auto",
+        compute: {
+            module: device.createShaderModule({ code: UMAP_SHADER }),
+            entryPoint: "main",
+        },
+    });
+
+    // Ping-pong between the two position buffers
+    const bind_groups = [0, 1].map((from) => device.createBindGroup({
+        layout: pipeline.getBindGroupLayout(0),
+        entries: [
+            { binding: 0, resource: { buffer: buf_params } },
+            { binding: 1, resource: { buffer: buf_pos[from] } },
+            { binding: 2, resource: { buffer: buf_pos[1 - from] } },
+            { binding: 3, resource: { buffer: buf_offsets } },
+            { binding: 4, resource: { buffer: buf_neighbors } },
+        ],
+    }));
+
+    // One submit per epoch, so that the epoch number is updated in between
+    for (let epoch = 0; epoch < n_epochs; epoch++) {
+        device.queue.writeBuffer(buf_params, 0, new Uint32Array([n, epoch, n_epochs, num_negative]));
+        const encoder = device.createCommandEncoder();
+        const pass = encoder.beginComputePass();
+        pass.setPipeline(pipeline);
+        pass.setBindGroup(0, bind_groups[epoch % 2]);
+        pass.dispatchWorkgroups(Math.ceil(n / 64));
+        pass.end();
+        device.queue.submit([encoder.finish()]);
+    }
+
+    // Read back the buffer written by the last epoch
+    const encoder = device.createCommandEncoder();
+    encoder.copyBufferToBuffer(buf_pos[n_epochs % 2], 0, buf_read, 0, positions.byteLength);
+    device.queue.submit([encoder.finish()]);
+    await buf_read.mapAsync(GPUMapMode.READ);
+    const result = new Float32Array(buf_read.getMappedRange()).slice();
+    buf_read.unmap();
+    device.destroy();
+    return result;
+}
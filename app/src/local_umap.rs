@@ -0,0 +1,161 @@
+use bytes::Buf;
+use my_web_app::local_umap::knn_graph;
+use my_web_app::local_umap::NeighborGraph;
+use my_web_app::local_umap::NUM_NEGATIVE_SAMPLES;
+use my_web_app::CountFileMetaColumnData;
+use my_web_app::LocalUmapRequest;
+use my_web_app::LocalUmapResponse;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+
+use crate::component_reduction_main::ReductionViewData;
+use crate::core_model::get_host_url;
+use crate::fetch_retry::post_for_bytes;
+
+
+////////////////////////////////////////////////////////////
+/// Number of neighbours of each cell, if not specified
+pub const DEFAULT_LOCAL_UMAP_NEIGHBORS: usize = 15;
+
+////////////////////////////////////////////////////////////
+/// Number of optimization epochs
+const LOCAL_UMAP_EPOCHS: u32 = 200;
+
+////////////////////////////////////////////////////////////
+/// Initial positions are scaled to [-INITIAL_SPREAD, INITIAL_SPREAD], a typical range of UMAP layouts
+const INITIAL_SPREAD: f32 = 10.0;
+
+
+#[wasm_bindgen(module = "/src/local_umap.js")]
+extern "C" {
+    fn has_webgpu() -> bool;
+
+    #[wasm_bindgen(catch)]
+    fn run_local_umap_webgpu(positions: &[f32], offsets: &[u32], neighbors: &[u32], n_epochs: u32, num_negative: u32) -> Result<js_sys::Promise, JsValue>;
+}
+
+
+////////////////////////////////////////////////////////////
+/// Prepare the UMAP input for a selection of cells: initial positions, taken from the current reduction,
+/// and the neighbour graph. Neighbours are found using the given numeric columns, or using the current
+/// reduction if there are none
+pub fn build_local_umap_input(
+    reduction: &ReductionViewData,
+    selection: &Vec<usize>,
+    list_columns: &Vec<&CountFileMetaColumnData>,
+    n_neighbors: usize
+) -> (Vec<f32>, NeighborGraph) {
+
+    //Initial positions, scaled to the range of a UMAP
+    let mut positions: Vec<f32> = Vec::with_capacity(selection.len()*2);
+    let span = (reduction.max_x - reduction.min_x).max(reduction.max_y - reduction.min_y).max(f32::EPSILON);
+    for i in selection {
+        let x = reduction.data[i*2+0];
+        let y = reduction.data[i*2+1];
+        positions.push((2.0*(x - reduction.min_x)/span - 1.0)*INITIAL_SPREAD);
+        positions.push((2.0*(y - reduction.min_y)/span - 1.0)*INITIAL_SPREAD);
+    }
+
+    //Features of the selected cells, one vector per column
+    let mut features: Vec<Vec<f32>> = Vec::new();
+    for column in list_columns {
+        match column {
+            CountFileMetaColumnData::Numeric(data) => {
+                features.push(selection.iter().map(|i| data[*i]).collect());
+            },
+            CountFileMetaColumnData::SparseNumeric(indices, data) => {
+                let mut dense = vec![0.0; reduction.num_point];
+                for (i, v) in indices.iter().zip(data.iter()) {
+                    dense[*i as usize] = *v;
+                }
+                features.push(selection.iter().map(|i| dense[*i]).collect());
+            },
+            CountFileMetaColumnData::Categorical(_, _) => {},
+        }
+    }
+    if features.is_empty() {
+        features.push(positions.iter().step_by(2).cloned().collect());
+        features.push(positions.iter().skip(1).step_by(2).cloned().collect());
+    }
+
+    let graph = knn_graph(&features, selection.len(), n_neighbors);
+    (positions, graph)
+}
+
+
+////////////////////////////////////////////////////////////
+/// Lay out the graph using UMAP. Runs on the GPU using WebGPU if the browser supports it,
+/// otherwise the server does the work
+pub async fn run_local_umap(positions: Vec<f32>, graph: NeighborGraph) -> Result<Vec<f32>, String> {
+    if has_webgpu() {
+        match run_local_umap_gpu(&positions, &graph).await {
+            Ok(result) => return Ok(result),
+            Err(e) => log::warn!("WebGPU layout failed, using server instead: {:?}", e),
+        }
+    }
+    run_local_umap_server(positions, graph).await
+}
+
+
+////////////////////////////////////////////////////////////
+/// Lay out the graph using a WebGPU compute shader
+async fn run_local_umap_gpu(positions: &Vec<f32>, graph: &NeighborGraph) -> Result<Vec<f32>, JsValue> {
+    let promise = run_local_umap_webgpu(positions, &graph.offsets, &graph.neighbors, LOCAL_UMAP_EPOCHS, NUM_NEGATIVE_SAMPLES)?;
+    let result = JsFuture::from(promise).await?;
+    Ok(js_sys::Float32Array::new(&result).to_vec())
+}
+
+
+////////////////////////////////////////////////////////////
+/// Lay out the graph on the server
+async fn run_local_umap_server(positions: Vec<f32>, graph: NeighborGraph) -> Result<Vec<f32>, String> {
+    let query = LocalUmapRequest {
+        positions: positions,
+        graph: graph,
+        n_epochs: LOCAL_UMAP_EPOCHS,
+    };
+    let query_json = serde_json::to_vec(&query).expect("Could not convert to json");
+
+    let (res, _content_type) = post_for_bytes(format!("{}/run_local_umap",get_host_url()), query_json, None).await?;
+    let res: LocalUmapResponse = serde_cbor::from_reader(res.reader()).map_err(|e| format!("Failed to deserialize: {}", e))?;
+    Ok(res.positions)
+}
+
+
+////////////////////////////////////////////////////////////
+/// Put the new layout of the selected cells into a copy of the reduction. The selected cells are
+/// fitted into the bounding box they had before, so the rest of the reduction stays as context
+pub fn place_local_umap(reduction: &ReductionViewData, selection: &Vec<usize>, positions: &Vec<f32>) -> ReductionViewData {
+
+    let bbox = |data: &Vec<f32>, list_i: &mut dyn Iterator<Item=usize>| {
+        let (mut min_x, mut max_x, mut min_y, mut max_y) = (f32::MAX, f32::MIN, f32::MAX, f32::MIN);
+        for i in list_i {
+            min_x = min_x.min(data[i*2+0]);
+            max_x = max_x.max(data[i*2+0]);
+            min_y = min_y.min(data[i*2+1]);
+            max_y = max_y.max(data[i*2+1]);
+        }
+        (min_x, max_x, min_y, max_y)
+    };
+    let (old_min_x, old_max_x, old_min_y, old_max_y) = bbox(&reduction.data, &mut selection.iter().cloned());
+    let (new_min_x, new_max_x, new_min_y, new_max_y) = bbox(positions, &mut (0..selection.len()));
+
+    let scale_x = (old_max_x - old_min_x) / (new_max_x - new_min_x).max(f32::EPSILON);
+    let scale_y = (old_max_y - old_min_y) / (new_max_y - new_min_y).max(f32::EPSILON);
+
+    let mut data = reduction.data.clone();
+    for (j, i) in selection.iter().enumerate() {
+        data[i*2+0] = old_min_x + (positions[j*2+0] - new_min_x)*scale_x;
+        data[i*2+1] = old_min_y + (positions[j*2+1] - new_min_y)*scale_y;
+    }
+
+    ReductionViewData {
+        num_point: reduction.num_point,
+        data: data,
+        max_x: reduction.max_x,
+        max_y: reduction.max_y,
+        min_x: reduction.min_x,
+        min_y: reduction.min_y,
+        version: reduction.version,
+    }
+}
@@ -0,0 +1,32 @@
+precision mediump float;
+
+attribute vec3 a_position;   // x, y, number of points merged into this cluster
+attribute vec3 a_color;
+
+
+varying highp vec3 color;
+varying lowp float alpha;   // clusters are never faded out
+
+
+uniform float u_camera_x;
+uniform float u_camera_y;
+
+uniform float u_camera_zoom_x;
+uniform float u_camera_zoom_y;
+
+
+void main() {
+
+    //Transform from world coordinates to [-1,1] camera coordinates. Same as umap.vert
+    vec2 a_cam_pos = vec2(u_camera_x, u_camera_y);
+    vec2 u_camera_zoom = vec2(u_camera_zoom_x, u_camera_zoom_y);
+    vec2 scaled = (a_position.xy - a_cam_pos) * u_camera_zoom;
+
+    gl_Position = vec4(scaled.x, -scaled.y, 0.0, 1.0);
+
+    //Size grows with the number of merged points, so that area is proportional to count
+    gl_PointSize = min(5.0 * sqrt(max(a_position.z, 1.0)), 40.0);
+
+    color = a_color;
+    alpha = 1.0;
+}
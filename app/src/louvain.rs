@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+
+use crate::closestpoint::ClosestPointIndex2D;
+use crate::component_reduction_main::ReductionViewData;
+
+
+////////////////////////////////////////////////////////////
+/// Number of neighbors of each cell in the graph that communities are detected in
+pub const DEFAULT_LOUVAIN_NEIGHBORS: usize = 15;
+
+////////////////////////////////////////////////////////////
+/// Name of the metadata column that communities are stored in
+pub const LOUVAIN_COLUMN: &str = "louvain_clusters";
+
+////////////////////////////////////////////////////////////
+/// Largest number of times all nodes are visited in one level, in case moves never settle
+const MAX_LOUVAIN_SWEEPS: usize = 100;
+
+
+////////////////////////////////////////////////////////////
+/// Weighted undirected graph. Each edge is listed for both of its nodes; a self-loop is listed once,
+/// with a weight that counts both directions
+pub type WeightedGraph = Vec<Vec<(usize, f64)>>;
+
+
+////////////////////////////////////////////////////////////
+/// Communities found by the Louvain algorithm
+#[derive(Debug, Clone, PartialEq)]
+pub struct LouvainResult {
+    pub communities: Vec<u32>,   // for each cell; the largest community is 0
+    pub num_communities: usize,
+    pub num_levels: usize,       // number of times the graph was aggregated before no move improved modularity
+    pub modularity: f64,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Symmetric k-nearest neighbor graph of the cells in a reduction, with all edges of weight 1
+pub fn knn_graph_from_reduction(reduction: &ReductionViewData, n_neighbors: usize) -> WeightedGraph {
+
+    //Buckets of about 1% of the reduction size, as for finding hovered cells
+    let span = (reduction.max_x - reduction.min_x).max(reduction.max_y - reduction.min_y);
+    let mut index = ClosestPointIndex2D::new();
+    index.build_point_index(reduction, if span > 0.0 { span*0.01 } else { 1.0 });
+
+    let mut list_adj: Vec<Vec<usize>> = vec![Vec::new(); reduction.num_point];
+    for i in 0..reduction.num_point {
+        for j in index.get_k_nearest(reduction.data[i*2+0], reduction.data[i*2+1], n_neighbors + 1) {
+            if j != i {
+                list_adj[i].push(j);
+                list_adj[j].push(i);
+            }
+        }
+    }
+    list_adj.into_iter().map(|mut adj| {
+        adj.sort();
+        adj.dedup();
+        adj.into_iter().map(|j| (j, 1.0)).collect()
+    }).collect()
+}
+
+
+////////////////////////////////////////////////////////////
+/// Modularity of a partition of a graph: the fraction of edge weight within communities,
+/// minus what would be expected if edges were placed at random
+pub fn modularity(graph: &WeightedGraph, communities: &Vec<usize>, num_communities: usize) -> f64 {
+    let mut weight_in = vec![0.0; num_communities];
+    let mut weight_tot = vec![0.0; num_communities];
+    let mut m2 = 0.0;
+    for (i, adj) in graph.iter().enumerate() {
+        let ci = communities[i];
+        for (j, w) in adj.iter() {
+            if communities[*j] == ci {
+                weight_in[ci] += w;
+            }
+            weight_tot[ci] += w;
+            m2 += w;
+        }
+    }
+    if m2 == 0.0 {
+        return 0.0;
+    }
+    weight_in.iter().zip(weight_tot.iter()).map(|(w_in, w_tot)| w_in/m2 - (w_tot/m2)*(w_tot/m2)).sum()
+}
+
+
+////////////////////////////////////////////////////////////
+/// Move nodes one at a time to the neighboring community that increases modularity the most,
+/// until no move helps. Returns the community of each node, numbered from 0, and if any node moved
+fn louvain_local_moves(graph: &WeightedGraph) -> (Vec<usize>, bool) {
+    let n = graph.len();
+    let degree: Vec<f64> = graph.iter().map(|adj| adj.iter().map(|(_, w)| w).sum()).collect();
+    let m2: f64 = degree.iter().sum();
+    let mut community: Vec<usize> = (0..n).collect();
+    let mut tot = degree.clone();   // sum of degrees in each community
+    let mut improved = false;
+    if m2 == 0.0 {
+        return (community, false);
+    }
+
+    let mut weight_to: HashMap<usize, f64> = HashMap::new();
+    for _sweep in 0..MAX_LOUVAIN_SWEEPS {
+        let mut moved = false;
+        for i in 0..n {
+            let ci = community[i];
+
+            //Weight of edges to each neighboring community
+            weight_to.clear();
+            weight_to.insert(ci, 0.0);
+            for (j, w) in graph[i].iter() {
+                if *j != i {
+                    *weight_to.entry(community[*j]).or_insert(0.0) += w;
+                }
+            }
+
+            //Take the node out, then put it where the gain is largest. Staying wins ties, then the lowest community,
+            //so that the result does not depend on the order of the hash map
+            tot[ci] -= degree[i];
+            let gain = |c: usize, w_to: f64| w_to - tot[c]*degree[i]/m2;
+            let mut best = (ci, gain(ci, weight_to[&ci]));
+            let mut list_candidates: Vec<(usize, f64)> = weight_to.iter().map(|(c, w)| (*c, *w)).collect();
+            list_candidates.sort_unstable_by_key(|(c, _)| *c);
+            for (c, w_to) in list_candidates.iter() {
+                let g = gain(*c, *w_to);
+                if g > best.1 + 1e-12 {
+                    best = (*c, g);
+                }
+            }
+            tot[best.0] += degree[i];
+            if best.0 != ci {
+                community[i] = best.0;
+                moved = true;
+                improved = true;
+            }
+        }
+        if !moved {
+            break;
+        }
+    }
+
+    //Number communities from 0
+    let mut renumber: HashMap<usize, usize> = HashMap::new();
+    for c in community.iter_mut() {
+        let next = renumber.len();
+        *c = *renumber.entry(*c).or_insert(next);
+    }
+    (community, improved)
+}
+
+
+////////////////////////////////////////////////////////////
+/// Graph with one node per community. Edges within a community become a self-loop
+fn aggregate_graph(graph: &WeightedGraph, community: &Vec<usize>, num_communities: usize) -> WeightedGraph {
+    let mut list_adj: Vec<HashMap<usize, f64>> = vec![HashMap::new(); num_communities];
+    for (i, adj) in graph.iter().enumerate() {
+        for (j, w) in adj.iter() {
+            *list_adj[community[i]].entry(community[*j]).or_insert(0.0) += w;
+        }
+    }
+    list_adj.into_iter().map(|adj| {
+        let mut adj: Vec<(usize, f64)> = adj.into_iter().collect();
+        adj.sort_by_key(|(j, _)| *j);
+        adj
+    }).collect()
+}
+
+
+////////////////////////////////////////////////////////////
+/// Find communities in a graph using the Louvain algorithm (Blondel et al. 2008): nodes are moved
+/// between communities while modularity increases, then each community is merged into a node,
+/// and this is repeated until nothing moves
+pub fn louvain(graph: &WeightedGraph) -> LouvainResult {
+    let n = graph.len();
+    let mut node_community: Vec<usize> = (0..n).collect();
+    let mut num_communities = n;
+    let mut num_levels = 0;
+
+    let mut level_graph = graph.clone();
+    loop {
+        let (community, improved) = louvain_local_moves(&level_graph);
+        if !improved {
+            break;
+        }
+        num_levels += 1;
+        num_communities = community.iter().max().map(|c| c + 1).unwrap_or(0);
+        for c in node_community.iter_mut() {
+            *c = community[*c];
+        }
+        level_graph = aggregate_graph(&level_graph, &community, num_communities);
+    }
+
+    //Largest community first, as for categories
+    let mut sizes: Vec<(usize, usize)> = (0..num_communities).map(|c| (0, c)).collect();
+    for c in node_community.iter() {
+        sizes[*c].0 += 1;
+    }
+    sizes.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    let mut order = vec![0; num_communities];
+    for (rank, (_, c)) in sizes.iter().enumerate() {
+        order[*c] = rank;
+    }
+    let node_community: Vec<usize> = node_community.iter().map(|c| order[*c]).collect();
+
+    LouvainResult {
+        modularity: modularity(graph, &node_community, num_communities),
+        communities: node_community.iter().map(|c| *c as u32).collect(),
+        num_communities: num_communities,
+        num_levels: num_levels,
+    }
+}
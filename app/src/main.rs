@@ -6,13 +6,69 @@ pub mod camera;
 pub mod component_reduction_main;
 pub mod component_reduction_left;
 pub mod component_reduction_right;
+pub mod component_reduction_correlation;
+pub mod component_cross_projection;
+pub mod component_batch_correction;
+pub mod component_reduction_params;
+pub mod component_cell_compare;
+pub mod component_cell_metadata;
+pub mod component_cell_table;
+pub mod component_correlation_matrix;
+pub mod component_cluster_purity;
+pub mod component_sankey;
+pub mod component_pseudotime_chart;
+pub mod component_grn;
+pub mod component_parallel_coords;
+pub mod component_cellstate_heatmap;
+pub mod component_collab;
+pub mod component_markers;
+pub mod component_command_palette;
 pub mod component_about_model;
 pub mod component_gbrowser_model;
 
+pub mod cellgroups;
 pub mod closestpoint;
+pub mod density;
+pub mod cluster_proportion;
 pub mod appstate;
 pub mod resize;
 pub mod histogram;
+pub mod column_summary;
+pub mod palette_gen;
+pub mod colorblind_sim;
+pub mod colorscale;
+pub mod pointlod;
+pub mod layout_radial;
+pub mod gpu_relax;
+pub mod local_umap;
+pub mod correlation;
+pub mod ot;
+pub mod violin;
+pub mod purity;
+pub mod louvain;
+pub mod smoothing;
+pub mod sankey;
+pub mod parallel_coords;
+pub mod heatmap;
+pub mod perfstats;
+pub mod collab;
+pub mod trajectory;
+pub mod spline;
+pub mod color_table;
+pub mod subsample;
+pub mod legend;
+pub mod download;
+pub mod fetch_retry;
+pub mod svg_export;
+pub mod csv_export;
+pub mod state_snapshot;
+pub mod url_state;
+pub mod geojson_mask;
+pub mod landmarks;
+pub mod biplot;
+pub mod selection_context;
+pub mod metadata_cache;
+pub mod grn_layout;
 
 use crate::core_model::*;
 
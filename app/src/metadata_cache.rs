@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+
+use indexmap::IndexMap;
+use my_web_app::CountFileMetaColumnData;
+
+use crate::appstate::{AsyncData, PerCellDataSource};
+
+
+////////////////////////////////////////////////////////////
+/// Number of loaded columns kept before the least recently used is dropped
+pub const DEFAULT_METADATA_CACHE_CAPACITY: usize = 64;
+
+
+////////////////////////////////////////////////////////////
+/// How to make room in the cache once it is full
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EvictionPolicy {
+    LeastRecentlyUsed,
+    Never,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Loaded metadata columns and features, in order of last use; least recently used first.
+/// Once more than capacity columns are loaded, columns that can be fetched again from the
+/// server are evicted, setting them back to NotLoaded. Columns computed locally are kept
+pub struct MetadataCache {
+    entries: IndexMap<PerCellDataSource, AsyncData<CountFileMetaColumnData>>,
+    pub capacity: usize,
+    pub policy: EvictionPolicy,
+    pub num_hits: usize,
+    pub num_misses: usize,
+    pub num_evicted: usize,
+}
+impl MetadataCache {
+
+    ////////////////////////////////////////////////////////////
+    /// Create an empty cache
+    pub fn new(capacity: usize, policy: EvictionPolicy) -> MetadataCache {
+        MetadataCache {
+            entries: IndexMap::new(),
+            capacity: capacity,
+            policy: policy,
+            num_hits: 0,
+            num_misses: 0,
+            num_evicted: 0,
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Get a column without counting it as used
+    pub fn get(&self, k: &PerCellDataSource) -> Option<&AsyncData<CountFileMetaColumnData>> {
+        self.entries.get(k)
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Get a column about to be used, marking it as the most recently used. Counts as a hit
+    /// if it is loaded or loading, otherwise as a miss that the caller is expected to fetch
+    pub fn lookup(&mut self, k: &PerCellDataSource) -> Option<&AsyncData<CountFileMetaColumnData>> {
+        match self.entries.get_index_of(k) {
+            Some(i) if matches!(self.entries[i], AsyncData::Loaded(_) | AsyncData::Loading) => {
+                self.num_hits += 1;
+                let last = self.entries.len() - 1;
+                self.entries.move_index(i, last);
+                self.entries.get_index(last).map(|(_, v)| v)
+            },
+            entry => {
+                self.num_misses += 1;
+                entry.map(move |i| &self.entries[i])
+            }
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Store a column as the most recently used, evicting others if the cache is full
+    pub fn insert(&mut self, k: PerCellDataSource, v: AsyncData<CountFileMetaColumnData>) {
+        self.entries.shift_remove(&k);
+        self.entries.insert(k, v);
+        self.evict();
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Drop the least recently used columns that can be fetched again, until at most capacity
+    /// columns are loaded. The most recently used column is always kept
+    fn evict(&mut self) {
+        if self.policy == EvictionPolicy::Never {
+            return;
+        }
+        let mut num_loaded = self.entries.values().filter(|v| matches!(v, AsyncData::Loaded(_))).count();
+        let last = self.entries.len().saturating_sub(1);
+        for (i, (k, v)) in self.entries.iter_mut().enumerate() {
+            if num_loaded <= self.capacity || i == last {
+                break;
+            }
+            let can_fetch = matches!(k, PerCellDataSource::Metadata(_) | PerCellDataSource::Counts(_, _));
+            if can_fetch && matches!(v, AsyncData::Loaded(_)) {
+                log::debug!("Evicting {} from the metadata cache", k);
+                *v = AsyncData::NotLoaded;
+                num_loaded -= 1;
+                self.num_evicted += 1;
+            }
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Keep only the columns for which the predicate is true
+    pub fn retain(&mut self, mut keep: impl FnMut(&PerCellDataSource, &AsyncData<CountFileMetaColumnData>) -> bool) {
+        self.entries.retain(|k, v| keep(k, v));
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// All columns, least recently used first
+    pub fn iter(&self) -> impl Iterator<Item = (&PerCellDataSource, &AsyncData<CountFileMetaColumnData>)> {
+        self.entries.iter()
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Data of all columns, least recently used first
+    pub fn values(&self) -> impl Iterator<Item = &AsyncData<CountFileMetaColumnData>> {
+        self.entries.values()
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Copy of the load state of each column, e.g. for properties of a component
+    pub fn to_hashmap(&self) -> HashMap<PerCellDataSource, AsyncData<CountFileMetaColumnData>> {
+        self.entries.iter().map(|(k, v)| (k.clone(), v.clone())).collect()
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Summary of the cache, for diagnostics
+    pub fn describe_stats(&self) -> String {
+        let num_loaded = self.entries.values().filter(|v| matches!(v, AsyncData::Loaded(_))).count();
+        format!("Metadata cache: {}/{} columns loaded, {} hits, {} misses, {} evicted",
+            num_loaded, self.capacity, self.num_hits, self.num_misses, self.num_evicted)
+    }
+
+}
@@ -0,0 +1,15 @@
+precision mediump float;
+
+// Second pass of order-independent transparency: average color of the points covering each pixel
+
+varying vec2 v_uv;
+
+uniform sampler2D u_accum;
+
+void main() {
+    vec4 accum = texture2D(u_accum, v_uv);
+    if (accum.a <= 0.0) {
+        discard;
+    }
+    gl_FragColor = vec4(accum.rgb / accum.a, min(accum.a, 1.0));
+}
@@ -0,0 +1,11 @@
+precision mediump float;
+
+attribute vec2 a_position;
+
+varying vec2 v_uv;
+
+void main() {
+    // Full-screen quad; a_position is already in [-1,1] clip coordinates
+    v_uv = a_position * 0.5 + 0.5;
+    gl_Position = vec4(a_position, 0.0, 1.0);
+}
@@ -0,0 +1,45 @@
+////////////////////////////////////////////////////////////
+/// Wasserstein-1 distance (Earth Mover's Distance) between the distributions of values in two groups.
+///
+/// In 1D, the optimal transport plan moves mass in sorted order, so the distance is the area between
+/// the two empirical CDFs. Both are step functions changing only at the values, so the area is summed
+/// over the intervals between consecutive values of the merged, sorted groups.
+/// None if either group has no finite values
+pub fn wasserstein_1d(a: &Vec<f32>, b: &Vec<f32>) -> Option<f32> {
+    let sorted = |values: &Vec<f32>| {
+        let mut values: Vec<f32> = values.iter().cloned().filter(|v| v.is_finite()).collect();
+        values.sort_by(|x, y| x.total_cmp(y));
+        values
+    };
+    let a = sorted(a);
+    let b = sorted(b);
+    if a.is_empty() || b.is_empty() {
+        return None;
+    }
+
+    let (na, nb) = (a.len() as f64, b.len() as f64);
+    let (mut i, mut j) = (0, 0);
+    let mut prev = a[0].min(b[0]) as f64;
+    let mut distance = 0.0;
+    while i < a.len() || j < b.len() {
+        let x = match (a.get(i), b.get(j)) {
+            (Some(va), Some(vb)) => va.min(*vb),
+            (Some(va), None) => *va,
+            (None, Some(vb)) => *vb,
+            (None, None) => break,
+        } as f64;
+
+        //CDFs are constant since the previous value
+        distance += (i as f64/na - j as f64/nb).abs()*(x - prev);
+        prev = x;
+
+        //Step past all values equal to x, in both groups
+        while i < a.len() && a[i] as f64 == x {
+            i += 1;
+        }
+        while j < b.len() && b[j] as f64 == x {
+            j += 1;
+        }
+    }
+    Some(distance as f32)
+}
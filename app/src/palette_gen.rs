@@ -0,0 +1,243 @@
+////////////////////////////////////////////////////////////
+/// RGB color, 0...1
+type Color3f = (f32,f32,f32);
+
+////////////////////////////////////////////////////////////
+/// CIELAB color: L, a, b
+type ColorLab = (f32,f32,f32);
+
+
+////////////////////////////////////////////////////////////
+/// Generate n colors that are maximally apart in CIELAB space.
+///
+/// A grid of candidate colors is laid out in CIELAB space. Colors are then
+/// picked one at a time, each time taking the candidate furthest away from all
+/// colors picked so far (farthest-point sampling). The white background counts
+/// as already picked, so that no color disappears into it
+pub fn generate_palette(n: usize) -> Vec<Color3f> {
+
+    //Candidate colors. Keep only those that sRGB can show
+    let mut list_cand: Vec<ColorLab> = Vec::new();
+    for l in (25..=90).step_by(5) {
+        for a in (-80..=80).step_by(8) {
+            for b in (-80..=80).step_by(8) {
+                let lab = (l as f32, a as f32, b as f32);
+                if lab_in_srgb_gamut(lab) {
+                    list_cand.push(lab);
+                }
+            }
+        }
+    }
+
+    //Distance from each candidate to the closest color picked so far
+    let white: ColorLab = (100.0, 0.0, 0.0);
+    let mut list_mindist: Vec<f32> = list_cand.iter().map(|c| dist2_lab(*c, white)).collect();
+
+    let mut list_picked = Vec::with_capacity(n);
+    while list_picked.len() < n && !list_cand.is_empty() {
+
+        //Pick the candidate furthest away from all previous colors
+        let mut best_i = 0;
+        for (i, d) in list_mindist.iter().enumerate() {
+            if *d > list_mindist[best_i] {
+                best_i = i;
+            }
+        }
+        let picked = list_cand[best_i];
+        list_picked.push(lab2srgb(picked));
+
+        //Update distances given the new color
+        for (c, d) in list_cand.iter().zip(list_mindist.iter_mut()) {
+            *d = d.min(dist2_lab(*c, picked));
+        }
+    }
+
+    //If more colors were requested than there are candidates, repeat colors
+    let num_unique = list_picked.len();
+    while list_picked.len() < n && num_unique > 0 {
+        list_picked.push(list_picked[list_picked.len() % num_unique]);
+    }
+    list_picked
+}
+
+
+////////////////////////////////////////////////////////////
+/// Compute squared distance between two CIELAB colors (CIE76)
+fn dist2_lab(c1: ColorLab, c2: ColorLab) -> f32 {
+    let dl = c1.0 - c2.0;
+    let da = c1.1 - c2.1;
+    let db = c1.2 - c2.2;
+    dl*dl + da*da + db*db
+}
+
+
+////////////////////////////////////////////////////////////
+/// Convert from CIELAB (D65 white point) to linear sRGB. The result may be outside 0...1
+fn lab2linear_rgb(c: ColorLab) -> Color3f {
+    let (l, a, b) = c;
+
+    //CIELAB to XYZ
+    let fy = (l + 16.0)/116.0;
+    let fx = fy + a/500.0;
+    let fz = fy - b/200.0;
+
+    fn finv(t: f32) -> f32 {
+        let delta = 6.0/29.0;
+        if t > delta {
+            t*t*t
+        } else {
+            3.0*delta*delta*(t - 4.0/29.0)
+        }
+    }
+
+    let x = 0.95047*finv(fx);
+    let y = 1.00000*finv(fy);
+    let z = 1.08883*finv(fz);
+
+    //XYZ to linear sRGB
+    (
+         3.2404542*x - 1.5371385*y - 0.4985314*z,
+        -0.9692660*x + 1.8760108*y + 0.0415560*z,
+         0.0556434*x - 0.2040259*y + 1.0572252*z,
+    )
+}
+
+
+////////////////////////////////////////////////////////////
+/// Check if a CIELAB color can be shown in sRGB
+fn lab_in_srgb_gamut(c: ColorLab) -> bool {
+    let (r, g, b) = lab2linear_rgb(c);
+    let inside = |v: f32| v >= 0.0 && v <= 1.0;
+    inside(r) && inside(g) && inside(b)
+}
+
+
+////////////////////////////////////////////////////////////
+/// Convert from CIELAB to sRGB, 0...1. Colors outside the gamut are clamped
+fn lab2srgb(c: ColorLab) -> Color3f {
+
+    fn gamma(v: f32) -> f32 {
+        let v = v.clamp(0.0, 1.0);
+        if v <= 0.0031308 {
+            12.92*v
+        } else {
+            1.055*v.powf(1.0/2.4) - 0.055
+        }
+    }
+
+    let (r, g, b) = lab2linear_rgb(c);
+    (gamma(r), gamma(g), gamma(b))
+}
+
+
+////////////////////////////////////////////////////////////
+/// Convert from sRGB, 0...1, to CIELAB (D65 white point)
+fn srgb2lab(c: Color3f) -> ColorLab {
+
+    fn linear(v: f32) -> f32 {
+        if v <= 0.04045 {
+            v/12.92
+        } else {
+            ((v + 0.055)/1.055).powf(2.4)
+        }
+    }
+
+    //Linear sRGB to XYZ, relative to the white point
+    let (r, g, b) = (linear(c.0), linear(c.1), linear(c.2));
+    let x = (0.4124564*r + 0.3575761*g + 0.1804375*b)/0.95047;
+    let y =  0.2126729*r + 0.7151522*g + 0.0721750*b;
+    let z = (0.0193339*r + 0.1191920*g + 0.9503041*b)/1.08883;
+
+    fn f(t: f32) -> f32 {
+        let delta = 6.0/29.0;
+        if t > delta*delta*delta {
+            t.cbrt()
+        } else {
+            t/(3.0*delta*delta) + 4.0/29.0
+        }
+    }
+
+    //XYZ to CIELAB
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    (116.0*fy - 16.0, 500.0*(fx - fy), 200.0*(fy - fz))
+}
+
+
+////////////////////////////////////////////////////////////
+/// Sort colors by hue, the angle in the a-b plane of CIELAB. Assigned to categories in
+/// this order, each category gets a color close to those of the categories next to it
+pub fn sort_palette_by_hue(palette: &Vec<Color3f>) -> Vec<Color3f> {
+    let hue = |c: &Color3f| {
+        let (_l, a, b) = srgb2lab(*c);
+        b.atan2(a)
+    };
+    let mut sorted = palette.clone();
+    sorted.sort_by(|c1, c2| hue(c1).total_cmp(&hue(c2)));
+    sorted
+}
+
+
+////////////////////////////////////////////////////////////
+/// Smooth path through the colors of a palette sorted by hue, going around in a loop.
+/// Between two adjacent colors, the path is a cubic Bezier curve in CIELAB space,
+/// with control points placed as for a Catmull-Rom spline so that it is smooth
+/// where the curves meet
+pub struct HueGradient {
+    list_lab: Vec<ColorLab>,
+}
+impl HueGradient {
+
+    ////////////////////////////////////////////////////////////
+    /// Create the path through the colors of a palette, already sorted by hue
+    pub fn new(palette: &Vec<Color3f>) -> HueGradient {
+        HueGradient {
+            list_lab: palette.iter().map(|c| srgb2lab(*c)).collect(),
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Color a fraction t of the way from the color of a category to the next (forward)
+    /// or previous category in hue
+    pub fn color(&self, cat: usize, forward: bool, t: f32) -> Color3f {
+        let n = self.list_lab.len();
+        if n < 2 {
+            return lab2srgb(self.list_lab.first().copied().unwrap_or((0.0, 0.0, 0.0)));
+        }
+
+        //Four colors around the segment, in the direction of travel
+        let step = |i: usize, k: isize| {
+            let k = if forward { k } else { -k };
+            self.list_lab[(i as isize + k).rem_euclid(n as isize) as usize]
+        };
+        let (c0, c1, c2, c3) = (step(cat, -1), step(cat, 0), step(cat, 1), step(cat, 2));
+
+        //Bezier control points, from the tangents at either end
+        let p1 = lab_add(c1, lab_scale(lab_sub(c2, c0), 1.0/6.0));
+        let p2 = lab_sub(c2, lab_scale(lab_sub(c3, c1), 1.0/6.0));
+
+        let t = t.clamp(0.0, 1.0);
+        let s = 1.0 - t;
+        let lab = lab_add(
+            lab_add(lab_scale(c1, s*s*s), lab_scale(p1, 3.0*s*s*t)),
+            lab_add(lab_scale(p2, 3.0*s*t*t), lab_scale(c2, t*t*t))
+        );
+        lab2srgb(lab)
+    }
+
+}
+
+
+////////////////////////////////////////////////////////////
+/// Component-wise operations on CIELAB colors
+fn lab_add(c1: ColorLab, c2: ColorLab) -> ColorLab {
+    (c1.0 + c2.0, c1.1 + c2.1, c1.2 + c2.2)
+}
+
+fn lab_sub(c1: ColorLab, c2: ColorLab) -> ColorLab {
+    (c1.0 - c2.0, c1.1 - c2.1, c1.2 - c2.2)
+}
+
+fn lab_scale(c: ColorLab, s: f32) -> ColorLab {
+    (c.0*s, c.1*s, c.2*s)
+}
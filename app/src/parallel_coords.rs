@@ -0,0 +1,101 @@
+use my_web_app::CountFileMetaColumnData;
+
+use crate::component_reduction_main::{get_palette_for_categories, Color3f, ReductionColoringWithData};
+use crate::appstate::AsyncData;
+
+
+////////////////////////////////////////////////////////////
+/// Largest number of cells drawn as lines; more would not be readable anyway
+pub const MAX_PARALLEL_COORDS_CELLS: usize = 500;
+
+
+////////////////////////////////////////////////////////////
+/// Vertical axis of a parallel coordinates plot, for one numeric column
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParallelAxis {
+    pub name: String,
+    pub min: f32,   // over all cells, not only those drawn
+    pub max: f32,
+    pub values: Vec<f32>,   // for each cell drawn
+}
+
+
+////////////////////////////////////////////////////////////
+/// Build an axis of a numeric column for the given cells. Sparse columns are 0 where not listed.
+/// None for categorical columns
+pub fn build_parallel_axis(name: &str, data: &CountFileMetaColumnData, cells: &Vec<usize>, num_point: usize) -> Option<ParallelAxis> {
+    let (values, list_data, has_implicit_zero): (Vec<f32>, &Vec<f32>, bool) = match data {
+        CountFileMetaColumnData::Categorical(_, _) => return None,
+        CountFileMetaColumnData::Numeric(list_data) => (
+            cells.iter().map(|c| list_data.get(*c).copied().unwrap_or(f32::NAN)).collect(),
+            list_data,
+            false,
+        ),
+        CountFileMetaColumnData::SparseNumeric(list_indices, list_data) => (
+            cells.iter().map(|c| list_indices.iter().position(|i| *i as usize == *c).map(|p| list_data[p]).unwrap_or(0.0)).collect(),
+            list_data,
+            list_indices.len() < num_point,
+        ),
+    };
+
+    let mut min = if has_implicit_zero { 0.0 } else { f32::INFINITY };
+    let mut max = if has_implicit_zero { 0.0 } else { f32::NEG_INFINITY };
+    for v in list_data.iter().filter(|v| v.is_finite()) {
+        min = min.min(*v);
+        max = max.max(*v);
+    }
+    if min > max {
+        return None;
+    }
+    Some(ParallelAxis {
+        name: name.to_string(),
+        min: min,
+        max: max,
+        values: values,
+    })
+}
+
+
+////////////////////////////////////////////////////////////
+/// Points of the line of a cell, as for an SVG polyline. Axes are spread evenly over the width,
+/// with the minimum of each at the bottom. Missing values are left out
+pub fn parallel_polyline_points(axes: &Vec<ParallelAxis>, cell_pos: usize, width: f32, height: f32) -> String {
+    let step = if axes.len() > 1 { width / (axes.len() - 1) as f32 } else { 0.0 };
+    axes.iter().enumerate().filter_map(|(k, axis)| {
+        let v = *axis.values.get(cell_pos)?;
+        if !v.is_finite() {
+            return None;
+        }
+        let t = if axis.max > axis.min { (v - axis.min)/(axis.max - axis.min) } else { 0.5 };
+        Some(format!("{:.1},{:.1}", k as f32*step, height - t*height))
+    }).collect::<Vec<String>>().join(" ")
+}
+
+
+////////////////////////////////////////////////////////////
+/// Color of each cell, as drawn in the reduction. Numeric values are shown on the same red scale
+pub fn cell_line_colors(coloring: &ReductionColoringWithData, cells: &Vec<usize>) -> Vec<Color3f> {
+    let gray = (0.5, 0.5, 0.5);
+    match coloring {
+        ReductionColoringWithData::ByMeta(_, AsyncData::Loaded(data)) => match data.as_ref() {
+            CountFileMetaColumnData::Categorical(vec_data, vec_cats) => {
+                let palette = get_palette_for_categories(vec_cats.len());
+                cells.iter().map(|c| vec_data.get(*c).and_then(|cat| palette.get(*cat as usize)).copied().unwrap_or(gray)).collect()
+            },
+            CountFileMetaColumnData::Numeric(_) | CountFileMetaColumnData::SparseNumeric(_, _) => {
+                //Scaled by the largest value, as when drawing the reduction
+                let Some(axis) = build_parallel_axis("", data, cells, 0) else {
+                    return vec![gray; cells.len()];
+                };
+                axis.values.iter().map(|v| {
+                    let t = if axis.max > 0.0 && v.is_finite() { (v/axis.max).clamp(0.0, 1.0) } else { 0.0 };
+                    (t, 0.0, 0.0)
+                }).collect()
+            },
+        },
+        ReductionColoringWithData::Custom(colors) => {
+            cells.iter().map(|c| colors.get(*c).copied().unwrap_or(gray)).collect()
+        },
+        _ => vec![gray; cells.len()]
+    }
+}
@@ -0,0 +1,68 @@
+////////////////////////////////////////////////////////////
+/// Weight of the newest frame in the smoothed frame rate
+const FPS_SMOOTHING: f64 = 0.1;
+
+
+////////////////////////////////////////////////////////////
+/// Timings of the last rendered frame, for the performance overlay.
+/// All times are in milliseconds, from js_sys::Date::now()
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderStats {
+    pub last_frame_time: Option<f64>,
+    pub fps: f64,          // smoothed over recent frames
+    pub build_ms: f64,     // building the vertex buffer
+    pub gl_ms: f64,        // issuing GL calls. The GPU may still be working afterwards
+    pub draw_calls: usize,
+    pub upload_ms: f64,    // uploading the vertex buffer to the GPU; included in gl_ms
+    pub upload_bytes: usize,
+}
+impl RenderStats {
+
+    ////////////////////////////////////////////////////////////
+    /// Stats before anything has been rendered
+    pub fn new() -> RenderStats {
+        RenderStats {
+            last_frame_time: None,
+            fps: 0.0,
+            build_ms: 0.0,
+            gl_ms: 0.0,
+            draw_calls: 0,
+            upload_ms: 0.0,
+            upload_bytes: 0,
+        }
+    }
+
+    ////////////////////////////////////////////////////////////
+    /// Record a frame. Times are when rendering started, when the vertex buffer was ready, and when done
+    pub fn record_frame(&mut self, t_start: f64, t_built: f64, t_end: f64, draw_calls: usize) {
+        if let Some(last) = self.last_frame_time {
+            let interval = t_start - last;
+            if interval > 0.0 {
+                let fps = 1000.0 / interval;
+                self.fps = if self.fps > 0.0 { self.fps*(1.0 - FPS_SMOOTHING) + fps*FPS_SMOOTHING } else { fps };
+            }
+        }
+        self.last_frame_time = Some(t_start);
+        self.build_ms = t_built - t_start;
+        self.gl_ms = t_end - t_built;
+        self.draw_calls = draw_calls;
+    }
+
+    ////////////////////////////////////////////////////////////
+    /// Record the size of the vertex buffer uploaded for the frame, and how long it took
+    pub fn record_upload(&mut self, t_begin: f64, t_end: f64, bytes: usize) {
+        self.upload_ms = t_end - t_begin;
+        self.upload_bytes = bytes;
+    }
+
+    ////////////////////////////////////////////////////////////
+    /// Text shown in the overlay. The second line splits the render time, the third is about the vertex buffer
+    pub fn overlay_text(&self) -> String {
+        format!(
+            "FPS: {:.0} | Render: {:.0}ms | Calls: {}\nVertices: {:.0}ms | GL: {:.0}ms\nBuffer: {:.1}MB in {:.0}ms",
+            self.fps, self.build_ms + self.gl_ms, self.draw_calls, self.build_ms, self.gl_ms,
+            self.upload_bytes as f64 / 1e6, self.upload_ms
+        )
+    }
+
+}
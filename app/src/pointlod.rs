@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+
+use crate::camera::Camera2D;
+use crate::component_reduction_main::ReductionViewData;
+
+////////////////////////////////////////////////////////////
+/// Finest level of detail; the reduction is divided into 2^level cells along each axis
+const MAX_LOD_LEVEL: u32 = 12;
+
+////////////////////////////////////////////////////////////
+/// Points closer than this on screen, in pixels, can be merged without visible loss
+const MERGE_PIXELS: f32 = 2.0;
+
+////////////////////////////////////////////////////////////
+/// Only merge points when zoomed out such that the reduction covers less than this fraction of the canvas
+const MAX_SPAN_FRACTION: f32 = 0.25;
+
+
+////////////////////////////////////////////////////////////
+/// Pick the size of cells to merge points in, given the zoom level.
+/// This is the largest cell that is still small on screen. Nothing is merged
+/// unless zoomed out, or if even the finest level of detail is visible
+pub fn choose_lod_cell_size(camera: &Camera2D, canvas_w: f32, datapoints: &ReductionViewData) -> Option<f32> {
+    let span = (datapoints.max_x - datapoints.min_x).max(datapoints.max_y - datapoints.min_y);
+    if !(span > 0.0) {
+        return None;
+    }
+
+    //Size of one world unit on screen
+    let pixels_per_unit = camera.zoom_x.abs() * canvas_w / 2.0;
+    if span * pixels_per_unit > canvas_w * MAX_SPAN_FRACTION {
+        return None;
+    }
+
+    for level in 1..=MAX_LOD_LEVEL {
+        let cell_size = span / ((1u32 << level) as f32);
+        if cell_size * pixels_per_unit <= MERGE_PIXELS {
+            return Some(cell_size);
+        }
+    }
+    None
+}
+
+
+////////////////////////////////////////////////////////////
+/// Merge all points in each cell into one representative point at the centroid,
+/// with the mean color. Input vertices start with x,y,z,r,g,b. Output vertices are
+/// x,y,count,r,g,b, padded to the same size. Returns the vertices and the number of clusters
+pub fn cluster_points(vec_vertex: &Vec<f32>, vec_vertex_size: usize, datapoints: &ReductionViewData, cell_size: f32) -> (Vec<f32>, usize) {
+
+    //Sum up position, count and color for each cell
+    let mut cells: HashMap<(i32,i32), [f32; 6]> = HashMap::new();
+    for i in 0..vec_vertex.len()/vec_vertex_size {
+        let base = vec_vertex_size*i;
+        let x = vec_vertex[base + 0];
+        let y = vec_vertex[base + 1];
+        let cell_id = (
+            ((x - datapoints.min_x)/cell_size) as i32,
+            ((y - datapoints.min_y)/cell_size) as i32,
+        );
+
+        let sum = cells.entry(cell_id).or_insert([0.0; 6]);
+        sum[0] += x;
+        sum[1] += y;
+        sum[2] += 1.0;
+        sum[3] += vec_vertex[base + 3];
+        sum[4] += vec_vertex[base + 4];
+        sum[5] += vec_vertex[base + 5];
+    }
+
+    //Turn sums into means
+    let mut vec_cluster: Vec<f32> = Vec::with_capacity(cells.len()*vec_vertex_size);
+    for sum in cells.values() {
+        let n = sum[2];
+        vec_cluster.extend_from_slice(&[
+            sum[0]/n, sum[1]/n, n,
+            sum[3]/n, sum[4]/n, sum[5]/n
+        ]);
+        //A cluster is not a single cell; any further component, such as the cell index, is -1
+        vec_cluster.extend(std::iter::repeat(-1.0).take(vec_vertex_size - 6));
+    }
+    (vec_cluster, cells.len())
+}
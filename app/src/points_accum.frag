@@ -0,0 +1,12 @@
+precision mediump float;
+
+// First pass of order-independent transparency: accumulate premultiplied color and alpha.
+// Blending is additive, so the order points are drawn in does not matter
+
+varying lowp vec3 color;
+
+uniform float u_alpha;
+
+void main() {
+    gl_FragColor = vec4(color * u_alpha, u_alpha);
+}
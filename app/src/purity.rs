@@ -0,0 +1,48 @@
+use my_web_app::CountFileMetaColumnData;
+
+
+////////////////////////////////////////////////////////////
+/// Ground-truth labels of the cells in each predicted cluster
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClusterPurity {
+    pub cluster_names: Vec<String>,
+    pub label_names: Vec<String>,
+    pub counts: Vec<Vec<usize>>,   // for each cluster, number of cells with each label
+}
+impl ClusterPurity {
+
+    ////////////////////////////////////////////////////////////
+    /// Fraction of cells of a cluster having its most common label. None for empty clusters
+    pub fn purity(&self, cluster: usize) -> Option<f32> {
+        let counts = &self.counts[cluster];
+        let total: usize = counts.iter().sum();
+        if total > 0 {
+            Some(*counts.iter().max().unwrap_or(&0) as f32 / total as f32)
+        } else {
+            None
+        }
+    }
+
+}
+
+
+////////////////////////////////////////////////////////////
+/// Count the ground-truth labels of the cells in each predicted cluster.
+/// None unless both columns are categorical
+pub fn compute_cluster_purity(predicted: &CountFileMetaColumnData, ground_truth: &CountFileMetaColumnData) -> Option<ClusterPurity> {
+    if let (CountFileMetaColumnData::Categorical(pred_codes, pred_names), CountFileMetaColumnData::Categorical(gt_codes, gt_names)) = (predicted, ground_truth) {
+        let mut counts = vec![vec![0; gt_names.len()]; pred_names.len()];
+        for (p, g) in pred_codes.iter().zip(gt_codes.iter()) {
+            if let Some(c) = counts.get_mut(*p as usize).and_then(|row| row.get_mut(*g as usize)) {
+                *c += 1;
+            }
+        }
+        Some(ClusterPurity {
+            cluster_names: pred_names.clone(),
+            label_names: gt_names.clone(),
+            counts: counts,
+        })
+    } else {
+        None
+    }
+}
@@ -0,0 +1,9 @@
+#version 300 es
+precision mediump float;
+
+//Nothing is drawn; only the positions written by the vertex shader are used
+out vec4 color;
+
+void main() {
+    color = vec4(0.0);
+}
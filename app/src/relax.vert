@@ -0,0 +1,39 @@
+#version 300 es
+precision highp float;
+precision highp int;
+
+in vec2 a_pos;      // current position of this point
+in vec2 a_anchor;   // position given by the server
+
+uniform highp sampler2D u_positions;   // current positions of all points, row by row
+uniform int u_tex_width;
+uniform int u_num_point;
+uniform int u_num_samples;
+uniform int u_offset;      // changes each iteration, so that other points are sampled
+uniform float u_spring;
+uniform float u_alpha;     // learning rate, decreasing over the iterations
+uniform float u_max_step;
+
+out vec2 v_pos;
+
+void main() {
+    //Points are pulled back to where the server put them, so the overall shape is kept
+    vec2 force = u_spring*(a_anchor - a_pos);
+
+    //UMAP repulsion with a=b=1, from points spread evenly over the list
+    int stride = max(u_num_point/u_num_samples, 1);
+    vec2 repulsion = vec2(0.0);
+    for (int k=0; k<u_num_samples; k++) {
+        int j = (gl_VertexID + u_offset + k*stride) % u_num_point;
+        if (j == gl_VertexID) {
+            continue;
+        }
+        vec2 other = texelFetch(u_positions, ivec2(j % u_tex_width, j / u_tex_width), 0).xy;
+        vec2 d = a_pos - other;
+        float dist2 = dot(d, d);
+        repulsion += clamp(2.0*d/((0.001 + dist2)*(1.0 + dist2)), -u_max_step, u_max_step);
+    }
+    force += repulsion/float(u_num_samples);
+
+    v_pos = a_pos + clamp(force, -u_max_step, u_max_step)*u_alpha;
+}
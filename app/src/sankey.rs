@@ -0,0 +1,141 @@
+use my_web_app::CountFileMetaColumnData;
+
+
+////////////////////////////////////////////////////////////
+/// Number of cells going from each category of one column to each category of another,
+/// e.g. from an annotation at one time point to the next
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransitionCounts {
+    pub from_names: Vec<String>,
+    pub to_names: Vec<String>,
+    pub counts: Vec<Vec<usize>>,   // for each from-category, number of cells in each to-category
+}
+
+
+////////////////////////////////////////////////////////////
+/// Count the cells of each pair of categories. None unless both columns are categorical
+pub fn compute_transitions(from: &CountFileMetaColumnData, to: &CountFileMetaColumnData) -> Option<TransitionCounts> {
+    if let (CountFileMetaColumnData::Categorical(from_codes, from_names), CountFileMetaColumnData::Categorical(to_codes, to_names)) = (from, to) {
+        let mut counts = vec![vec![0; to_names.len()]; from_names.len()];
+        for (a, b) in from_codes.iter().zip(to_codes.iter()) {
+            if let Some(c) = counts.get_mut(*a as usize).and_then(|row| row.get_mut(*b as usize)) {
+                *c += 1;
+            }
+        }
+        Some(TransitionCounts {
+            from_names: from_names.clone(),
+            to_names: to_names.clone(),
+            counts: counts,
+        })
+    } else {
+        None
+    }
+}
+
+
+////////////////////////////////////////////////////////////
+/// Bar of a category in a Sankey diagram
+#[derive(Debug, Clone, PartialEq)]
+pub struct SankeyNode {
+    pub name: String,
+    pub x: f32,
+    pub y: f32,
+    pub height: f32,
+    pub count: usize,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Flow between two categories, as an SVG path
+#[derive(Debug, Clone, PartialEq)]
+pub struct SankeyLink {
+    pub from: usize,
+    pub to: usize,
+    pub count: usize,
+    pub path: String,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Positions of the bars and flows of a Sankey diagram
+#[derive(Debug, Clone, PartialEq)]
+pub struct SankeyLayout {
+    pub from_nodes: Vec<SankeyNode>,
+    pub to_nodes: Vec<SankeyNode>,
+    pub links: Vec<SankeyLink>,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Closed path of a flow of varying height between two vertical spans, with the edges
+/// following cubic bezier curves. Control points halfway between give horizontal tangents at both ends
+pub fn bezier_ribbon_path(x0: f32, y0_top: f32, y0_bottom: f32, x1: f32, y1_top: f32, y1_bottom: f32) -> String {
+    let xm = (x0 + x1)/2.0;
+    format!(
+        "M{:.1},{:.1} C{:.1},{:.1} {:.1},{:.1} {:.1},{:.1} L{:.1},{:.1} C{:.1},{:.1} {:.1},{:.1} {:.1},{:.1} Z",
+        x0, y0_top, xm, y0_top, xm, y1_top, x1, y1_top,
+        x1, y1_bottom, xm, y1_bottom, xm, y0_bottom, x0, y0_bottom
+    )
+}
+
+
+////////////////////////////////////////////////////////////
+/// Stack the categories of each column as bars, with heights proportional to the number of cells,
+/// and connect them with flows. Flows leave each bar in the order of the destination, and enter
+/// in the order of the source, so that they do not cross at the bars
+pub fn layout_sankey(transitions: &TransitionCounts, width: f32, height: f32, node_w: f32, gap: f32) -> SankeyLayout {
+    let from_totals: Vec<usize> = transitions.counts.iter().map(|row| row.iter().sum()).collect();
+    let to_totals: Vec<usize> = (0..transitions.to_names.len())
+        .map(|j| transitions.counts.iter().map(|row| row[j]).sum())
+        .collect();
+    let total: usize = from_totals.iter().sum();
+
+    //Same scale for both columns, so that the flows keep their width
+    let max_gaps = (transitions.from_names.len().max(transitions.to_names.len()) as f32 - 1.0).max(0.0);
+    let scale = if total > 0 { (height - gap*max_gaps).max(0.0) / total as f32 } else { 0.0 };
+
+    let stack_nodes = |names: &Vec<String>, totals: &Vec<usize>, x: f32| {
+        let mut y = 0.0;
+        names.iter().zip(totals.iter()).map(|(name, count)| {
+            let node = SankeyNode {
+                name: name.clone(),
+                x: x,
+                y: y,
+                height: *count as f32 * scale,
+                count: *count,
+            };
+            y += node.height + gap;
+            node
+        }).collect::<Vec<SankeyNode>>()
+    };
+    let from_nodes = stack_nodes(&transitions.from_names, &from_totals, 0.0);
+    let to_nodes = stack_nodes(&transitions.to_names, &to_totals, width - node_w);
+
+    //Where the next flow leaves or enters each bar
+    let mut from_offset: Vec<f32> = from_nodes.iter().map(|n| n.y).collect();
+    let mut to_offset: Vec<f32> = to_nodes.iter().map(|n| n.y).collect();
+
+    let mut links = Vec::new();
+    for (i, row) in transitions.counts.iter().enumerate() {
+        for (j, count) in row.iter().enumerate() {
+            if *count == 0 {
+                continue;
+            }
+            let h = *count as f32 * scale;
+            links.push(SankeyLink {
+                from: i,
+                to: j,
+                count: *count,
+                path: bezier_ribbon_path(node_w, from_offset[i], from_offset[i] + h, width - node_w, to_offset[j], to_offset[j] + h),
+            });
+            from_offset[i] += h;
+            to_offset[j] += h;
+        }
+    }
+
+    SankeyLayout {
+        from_nodes: from_nodes,
+        to_nodes: to_nodes,
+        links: links,
+    }
+}
@@ -0,0 +1,14 @@
+use yew::Callback;
+
+
+////////////////////////////////////////////////////////////
+/// Hovered and selected cells, shared through a Yew context with all components below the provider.
+/// Any component can read it, and change it using the callbacks, without the state being passed
+/// through the properties of each component in between
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelectionContext {
+    pub hovered: Option<usize>,
+    pub selection: Vec<usize>,
+    pub set_hovered: Callback<Option<usize>>,
+    pub set_selection: Callback<Vec<usize>>,
+}
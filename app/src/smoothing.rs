@@ -0,0 +1,80 @@
+use crate::louvain::WeightedGraph;
+
+
+////////////////////////////////////////////////////////////
+/// Number of neighbors of each cell that values are spread to
+pub const DEFAULT_SMOOTH_NEIGHBORS: usize = 15;
+
+////////////////////////////////////////////////////////////
+/// Number of propagation steps. Each step spreads values one more neighbor away
+pub const DEFAULT_SMOOTH_ITERATIONS: usize = 10;
+
+////////////////////////////////////////////////////////////
+/// Weight of the neighbors in each step; the rest is taken from the original value
+pub const DEFAULT_SMOOTH_ALPHA: f32 = 0.5;
+
+
+////////////////////////////////////////////////////////////
+/// Row-normalized adjacency matrix D^-1 A, in compressed sparse row format
+pub struct TransitionMatrix {
+    pub row_start: Vec<usize>,   // one more than the number of rows
+    pub col: Vec<usize>,
+    pub weight: Vec<f32>,
+}
+impl TransitionMatrix {
+
+    ////////////////////////////////////////////////////////////
+    /// Build from a graph. Rows of nodes without edges are empty
+    pub fn from_graph(graph: &WeightedGraph) -> TransitionMatrix {
+        let mut row_start = Vec::with_capacity(graph.len() + 1);
+        let mut col = Vec::new();
+        let mut weight = Vec::new();
+        row_start.push(0);
+        for adj in graph.iter() {
+            let degree: f64 = adj.iter().map(|(_, w)| w).sum();
+            if degree > 0.0 {
+                for (j, w) in adj.iter() {
+                    col.push(*j);
+                    weight.push((w/degree) as f32);
+                }
+            }
+            row_start.push(col.len());
+        }
+        TransitionMatrix {
+            row_start: row_start,
+            col: col,
+            weight: weight,
+        }
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Mean of the values of the neighbors of a node, or None if it has none
+    fn mean_of_neighbors(&self, i: usize, values: &Vec<f32>) -> Option<f32> {
+        let (start, end) = (self.row_start[i], self.row_start[i+1]);
+        if start == end {
+            None
+        } else {
+            Some((start..end).map(|e| self.weight[e]*values[self.col[e]]).sum())
+        }
+    }
+
+}
+
+
+////////////////////////////////////////////////////////////
+/// Smooth values over a graph by label propagation: each step, a node takes alpha of the mean of its
+/// neighbors and 1-alpha of its original value. Nodes without neighbors keep their value
+pub fn smooth_over_graph(matrix: &TransitionMatrix, values: &Vec<f32>, iterations: usize, alpha: f32) -> Vec<f32> {
+    let alpha = alpha.clamp(0.0, 1.0);
+    let mut current = values.clone();
+    for _ in 0..iterations {
+        current = (0..values.len()).map(|i| {
+            match matrix.mean_of_neighbors(i, &current) {
+                Some(mean) => alpha*mean + (1.0 - alpha)*values[i],
+                None => values[i]
+            }
+        }).collect();
+    }
+    current
+}
@@ -0,0 +1,14 @@
+precision mediump float;
+
+uniform float u_alpha;
+uniform float u_edge_alpha;   // fraction of u_alpha kept at the control points
+uniform float u_edge_ramp;    // part of each segment, at either end, over which alpha ramps
+uniform vec3 u_color;
+
+varying float v_control_dist;
+
+void main() {
+    //Fade towards the control points, so segments blend into each other rather than overlap hard
+    float ramp = mix(u_edge_alpha, 1.0, smoothstep(0.0, u_edge_ramp, v_control_dist));
+    gl_FragColor = vec4(u_color, u_alpha * ramp);
+}
@@ -0,0 +1,54 @@
+////////////////////////////////////////////////////////////
+/// Number of points drawn per segment between two control points
+pub const SPLINE_SUBDIVISIONS: usize = 16;
+
+
+////////////////////////////////////////////////////////////
+/// Point at t in [0,1] on the uniform Catmull-Rom segment from p1 to p2. p0 and p3 are
+/// the control points before and after, which set the tangents at the ends of the segment
+pub fn catmull_rom_point(p0: (f32,f32), p1: (f32,f32), p2: (f32,f32), p3: (f32,f32), t: f32) -> (f32,f32) {
+    let t2 = t*t;
+    let t3 = t2*t;
+    let f = |a: f32, b: f32, c: f32, d: f32| {
+        0.5*(2.0*b + (c - a)*t + (2.0*a - 5.0*b + 4.0*c - d)*t2 + (3.0*b - a - 3.0*c + d)*t3)
+    };
+    (f(p0.0, p1.0, p2.0, p3.0), f(p0.1, p1.1, p2.1, p3.1))
+}
+
+
+////////////////////////////////////////////////////////////
+/// Curve passing through all the given points, with the given number of steps per segment.
+/// The first and last points are repeated as control points, so the curve starts and ends on them
+pub fn catmull_rom_path(points: &Vec<(f32,f32)>, subdivisions: usize) -> Vec<(f32,f32)> {
+    if points.len() < 2 || subdivisions == 0 {
+        return points.clone();
+    }
+    let n = points.len();
+    let mut out = Vec::with_capacity((n - 1)*subdivisions + 1);
+    for i in 0..(n - 1) {
+        let p0 = points[i.saturating_sub(1)];
+        let p1 = points[i];
+        let p2 = points[i + 1];
+        let p3 = points[(i + 2).min(n - 1)];
+        for step in 0..subdivisions {
+            out.push(catmull_rom_point(p0, p1, p2, p3, step as f32/subdivisions as f32));
+        }
+    }
+    out.push(points[n - 1]);
+    out
+}
+
+
+////////////////////////////////////////////////////////////
+/// For each point of catmull_rom_path, the distance along its segment to the closest control point;
+/// 0 on a control point, and 0.5 half way between two. Varies linearly between consecutive points
+pub fn control_point_distances(num_points: usize, subdivisions: usize) -> Vec<f32> {
+    (0..num_points).map(|k| {
+        if subdivisions == 0 {
+            0.0
+        } else {
+            let s = (k % subdivisions) as f32/subdivisions as f32;
+            s.min(1.0 - s)
+        }
+    }).collect()
+}
@@ -0,0 +1,24 @@
+precision mediump float;
+
+attribute vec2 a_position;
+attribute float a_control_dist;   // along the segment, to the closest control point; 0 to 0.5
+
+uniform float u_camera_x;
+uniform float u_camera_y;
+
+uniform float u_camera_zoom_x;
+uniform float u_camera_zoom_y;
+
+varying float v_control_dist;
+
+
+void main() {
+
+    //Transform from world coordinates to [-1,1] camera coordinates. Same as umap.vert
+    vec2 a_cam_pos = vec2(u_camera_x, u_camera_y);
+    vec2 u_camera_zoom = vec2(u_camera_zoom_x, u_camera_zoom_y);
+    vec2 scaled = (a_position - a_cam_pos) * u_camera_zoom;
+
+    gl_Position = vec4(scaled.x, -scaled.y, 0.0, 1.0);
+    v_control_dist = a_control_dist;
+}
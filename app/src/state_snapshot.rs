@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::camera::Camera2D;
+use crate::component_reduction_main::{Color3f, CurrentTool, ReductionColoring};
+
+
+////////////////////////////////////////////////////////////
+/// Version of the snapshot format. Increase when fields change meaning; new fields should
+/// instead get a default, so that older snapshots can still be read
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+
+////////////////////////////////////////////////////////////
+/// What the user is looking at, saved to a JSON file so that the view can be restored later
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppStateSnapshot {
+    pub version: u32,
+    #[serde(default)]
+    pub current_reduction: Option<String>,
+    #[serde(default)]
+    pub color_umap_by: ReductionColoring,
+    #[serde(default)]
+    pub custom_colors: Vec<Color3f>,   // used if colored by an imported table
+    #[serde(default)]
+    pub camera: Option<Camera2D>,
+    #[serde(default)]
+    pub selection: Vec<usize>,
+    #[serde(default)]
+    pub cell_annotations: HashMap<usize, String>,   // cells bookmarked with a label
+    #[serde(default)]
+    pub tool: Option<CurrentTool>,   // None if never changed
+}
+
+
+////////////////////////////////////////////////////////////
+/// Reason a snapshot could not be read
+#[derive(Debug, Clone, PartialEq)]
+pub enum SnapshotError {
+    InvalidJson(String),
+    MissingVersion,
+    UnsupportedVersion(u32),
+}
+impl std::fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::InvalidJson(e) => write!(f, "not a valid snapshot: {}", e),
+            SnapshotError::MissingVersion => write!(f, "the snapshot has no version"),
+            SnapshotError::UnsupportedVersion(v) => write!(f, "the snapshot has version {}, but only up to {} is supported", v, SNAPSHOT_VERSION),
+        }
+    }
+}
+
+
+////////////////////////////////////////////////////////////
+/// Write a snapshot as JSON
+pub fn snapshot_to_json(snapshot: &AppStateSnapshot) -> String {
+    serde_json::to_string_pretty(snapshot).expect("snapshot can always be serialized")
+}
+
+
+////////////////////////////////////////////////////////////
+/// Read a snapshot from JSON. The version is checked first, so that snapshots from newer
+/// versions of the app give a clear error rather than a confusing one about some field
+pub fn parse_snapshot(json: &str) -> Result<AppStateSnapshot, SnapshotError> {
+    let value: serde_json::Value = serde_json::from_str(json).map_err(|e| SnapshotError::InvalidJson(e.to_string()))?;
+    let version = value.get("version").and_then(|v| v.as_u64()).ok_or(SnapshotError::MissingVersion)?;
+    if version > SNAPSHOT_VERSION as u64 {
+        return Err(SnapshotError::UnsupportedVersion(version as u32));
+    }
+    serde_json::from_value(value).map_err(|e| SnapshotError::InvalidJson(e.to_string()))
+}
@@ -0,0 +1,78 @@
+use std::collections::HashSet;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+
+////////////////////////////////////////////////////////////
+/// Seed for choosing the cells to display. Fixed so that the same cells are shown on every render
+const SUBSAMPLE_SEED: u64 = 42;
+
+
+////////////////////////////////////////////////////////////
+/// Random subset of cells to display instead of all of them. The same subset is returned
+/// for the same number of points. Indices are sorted, so the drawing order is kept
+pub fn subsample_indices(num_point: usize, max_points: usize) -> Vec<usize> {
+    if num_point <= max_points {
+        return (0..num_point).collect();
+    }
+    let mut rng = StdRng::seed_from_u64(SUBSAMPLE_SEED);
+    let mut indices = rand::seq::index::sample(&mut rng, num_point, max_points).into_vec();
+    indices.sort_unstable();
+    indices
+}
+
+
+////////////////////////////////////////////////////////////
+/// Random subset of cells with the same number of cells from each sample, so that larger
+/// samples do not dominate the view. Each sample keeps cells_per_sample cells, or by default
+/// as many as the smallest sample has. Samples with fewer cells keep all of them
+pub fn sample_normalized_indices(sample_id: &Vec<u32>, num_samples: usize, cells_per_sample: Option<usize>) -> HashSet<usize> {
+    let mut cells_of_sample: Vec<Vec<usize>> = vec![Vec::new(); num_samples];
+    for (i, s) in sample_id.iter().enumerate() {
+        if let Some(cells) = cells_of_sample.get_mut(*s as usize) {
+            cells.push(i);
+        }
+    }
+    let cells_per_sample = cells_per_sample.unwrap_or_else(|| {
+        cells_of_sample.iter().map(|cells| cells.len()).filter(|n| *n > 0).min().unwrap_or(0)
+    });
+
+    let mut rng = StdRng::seed_from_u64(SUBSAMPLE_SEED);
+    let mut subset = HashSet::new();
+    for cells in cells_of_sample.iter() {
+        if cells.len() <= cells_per_sample {
+            subset.extend(cells.iter().copied());
+        } else {
+            subset.extend(rand::seq::index::sample(&mut rng, cells.len(), cells_per_sample).into_iter().map(|k| cells[k]));
+        }
+    }
+    subset
+}
+
+
+////////////////////////////////////////////////////////////
+/// Keep only the given points of a vertex array
+pub fn subsample_vertices(vec_vertex: &Vec<f32>, vec_vertex_size: usize, indices: &Vec<usize>) -> Vec<f32> {
+    let mut out = Vec::with_capacity(indices.len()*vec_vertex_size);
+    for i in indices.iter() {
+        let base = i*vec_vertex_size;
+        out.extend_from_slice(&vec_vertex[base..base+vec_vertex_size]);
+    }
+    out
+}
+
+
+////////////////////////////////////////////////////////////
+/// Format a count with spaces between groups of thousands, e.g. "1 000 000"
+pub fn format_count(n: usize) -> String {
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            out.push(' ');
+        }
+        out.push(c);
+    }
+    out
+}
@@ -0,0 +1,48 @@
+use std::fmt::Write;
+
+use crate::camera::Camera2D;
+use crate::component_reduction_main::rgbvec2string;
+
+
+////////////////////////////////////////////////////////////
+/// Radius of exported points, in pixels. Same size as drawn by GL
+const SVG_POINT_RADIUS: f32 = 2.5;
+
+
+////////////////////////////////////////////////////////////
+/// Build an SVG of the points in view. Each point is a circle with the color it is drawn with;
+/// vertices are x,y,z,r,g,b. For numeric coloring, the color scale is included as a gradient
+pub fn build_svg(
+    vec_vertex: &Vec<f32>, 
+    vec_vertex_size: usize, 
+    camera: &Camera2D, 
+    canvas_w: f32, 
+    canvas_h: f32, 
+    color_range: Option<(f32,f32)>
+) -> String {
+    let mut svg = String::new();
+    let _ = writeln!(svg, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">"#, canvas_w, canvas_h, canvas_w, canvas_h);
+    let _ = writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#);
+
+    for vertex in vec_vertex.chunks_exact(vec_vertex_size) {
+        let (cx, cy) = camera.world2cam(vertex[0], vertex[1]);
+        if cx.abs() > 1.0 || cy.abs() > 1.0 {
+            continue;
+        }
+        let x = cx*canvas_w/2.0 + canvas_w/2.0;
+        let y = cy*canvas_h/2.0 + canvas_h/2.0;
+        let fill = rgbvec2string((vertex[3], vertex[4], vertex[5]));
+        let _ = writeln!(svg, r#"<circle cx="{:.1}" cy="{:.1}" r="{}" fill="{}"/>"#, x, y, SVG_POINT_RADIUS, fill);
+    }
+
+    //Color scale for numeric data; same as the legend drawn over the canvas
+    if let Some((min_val, max_val)) = color_range {
+        let _ = writeln!(svg, r#"<defs><linearGradient id="colorscale" x1="0" y1="0" x2="0" y2="1"><stop offset="0" stop-color="red"/><stop offset="1" stop-color="black"/></linearGradient></defs>"#);
+        let _ = writeln!(svg, r#"<rect x="13" y="72" width="15" height="180" fill="url(#colorscale)" stroke="black"/>"#);
+        let _ = writeln!(svg, r#"<text x="32" y="72" dy="0.3em" font-size="10">{:.2}</text>"#, max_val);
+        let _ = writeln!(svg, r#"<text x="32" y="252" dy="0.3em" font-size="10">{:.2}</text>"#, min_val);
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
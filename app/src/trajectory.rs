@@ -0,0 +1,173 @@
+use crate::component_reduction_main::ReductionViewData;
+use crate::spline::{catmull_rom_path, SPLINE_SUBDIVISIONS};
+
+
+////////////////////////////////////////////////////////////
+/// Polyline through the cells of a trajectory, parameterized by arc length
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrajectoryPath {
+    pub points: Vec<(f32,f32)>,
+    pub cumulative_length: Vec<f32>,  // arc length at each point; starts at 0
+}
+impl TrajectoryPath {
+
+    ////////////////////////////////////////////////////////////
+    /// Build the path through the given cells, in order. Cells not in the reduction are skipped
+    pub fn new(reduction: &ReductionViewData, cells: &Vec<usize>) -> TrajectoryPath {
+        let points: Vec<(f32,f32)> = cells.iter()
+            .filter(|i| **i < reduction.num_point)
+            .map(|i| (reduction.data[i*2+0], reduction.data[i*2+1]))
+            .collect();
+        TrajectoryPath::from_points(points)
+    }
+
+    ////////////////////////////////////////////////////////////
+    /// Build a smooth path through the given cells, in order: a Catmull-Rom spline with
+    /// SPLINE_SUBDIVISIONS points per pair of cells. Cells not in the reduction are skipped
+    pub fn smoothed(reduction: &ReductionViewData, cells: &Vec<usize>) -> TrajectoryPath {
+        let path = TrajectoryPath::new(reduction, cells);
+        TrajectoryPath::from_points(catmull_rom_path(&path.points, SPLINE_SUBDIVISIONS))
+    }
+
+    ////////////////////////////////////////////////////////////
+    /// Build the polyline through the given points
+    fn from_points(points: Vec<(f32,f32)>) -> TrajectoryPath {
+        let mut cumulative_length = Vec::with_capacity(points.len());
+        let mut total = 0.0;
+        for (i, p) in points.iter().enumerate() {
+            if i > 0 {
+                let prev = points[i-1];
+                total += ((p.0 - prev.0).powi(2) + (p.1 - prev.1).powi(2)).sqrt();
+            }
+            cumulative_length.push(total);
+        }
+
+        TrajectoryPath {
+            points: points,
+            cumulative_length: cumulative_length,
+        }
+    }
+
+    ////////////////////////////////////////////////////////////
+    /// Total arc length of the path
+    pub fn length(&self) -> f32 {
+        self.cumulative_length.last().cloned().unwrap_or(0.0)
+    }
+
+    ////////////////////////////////////////////////////////////
+    /// Position at arc length t, clamped to the ends of the path
+    pub fn point_at(&self, t: f32) -> Option<(f32,f32)> {
+        if self.points.is_empty() {
+            return None;
+        }
+        let seg = self.cumulative_length.partition_point(|l| *l <= t);
+        if seg == 0 {
+            return self.points.first().cloned();
+        }
+        if seg >= self.points.len() {
+            return self.points.last().cloned();
+        }
+        let (l0, l1) = (self.cumulative_length[seg-1], self.cumulative_length[seg]);
+        let f = if l1 > l0 { (t - l0)/(l1 - l0) } else { 0.0 };
+        let (p0, p1) = (self.points[seg-1], self.points[seg]);
+        Some((p0.0 + f*(p1.0 - p0.0), p0.1 + f*(p1.1 - p0.1)))
+    }
+
+    ////////////////////////////////////////////////////////////
+    /// Closest point on the path to a position: its arc length, and the distance to it
+    pub fn project(&self, x: f32, y: f32) -> Option<(f32,f32)> {
+        if self.points.len() == 1 {
+            let p = self.points[0];
+            return Some((0.0, ((x - p.0).powi(2) + (y - p.1).powi(2)).sqrt()));
+        }
+        self.points.windows(2).enumerate().map(|(seg, pair)| {
+            let (p0, p1) = (pair[0], pair[1]);
+            let (dx, dy) = (p1.0 - p0.0, p1.1 - p0.1);
+            let len2 = dx*dx + dy*dy;
+            let f = if len2 > 0.0 { (((x - p0.0)*dx + (y - p0.1)*dy)/len2).clamp(0.0, 1.0) } else { 0.0 };
+            let (px, py) = (p0.0 + f*dx, p0.1 + f*dy);
+            let t = self.cumulative_length[seg] + f*len2.sqrt();
+            (t, ((x - px).powi(2) + (y - py).powi(2)).sqrt())
+        }).min_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+}
+
+
+////////////////////////////////////////////////////////////
+/// Order selected cells into a trajectory following a numeric value, e.g. pseudotime.
+/// Cells are binned by value; each bin is represented by the cell closest to the bin centroid,
+/// which gives a smoother path than visiting every cell
+pub fn trajectory_from_selection(reduction: &ReductionViewData, selection: &Vec<usize>, values: &Vec<f32>, num_bins: usize) -> Vec<usize> {
+    let mut cells: Vec<usize> = selection.iter().cloned()
+        .filter(|i| *i < reduction.num_point && *i < values.len() && values[*i].is_finite())
+        .collect();
+    cells.sort_by(|a, b| values[*a].partial_cmp(&values[*b]).unwrap_or(std::cmp::Ordering::Equal));
+    if cells.is_empty() || num_bins == 0 {
+        return Vec::new();
+    }
+
+    //Bins with the same number of cells each
+    let bin_size = ((cells.len() + num_bins - 1) / num_bins).max(1);
+    cells.chunks(bin_size).map(|bin| {
+        let n = bin.len() as f32;
+        let cx = bin.iter().map(|i| reduction.data[i*2+0]).sum::<f32>() / n;
+        let cy = bin.iter().map(|i| reduction.data[i*2+1]).sum::<f32>() / n;
+        let dist2 = |i: &usize| (reduction.data[i*2+0] - cx).powi(2) + (reduction.data[i*2+1] - cy).powi(2);
+        *bin.iter()
+            .min_by(|a, b| dist2(a).partial_cmp(&dist2(b)).unwrap_or(std::cmp::Ordering::Equal))
+            .expect("bins are not empty")
+    }).collect()
+}
+
+
+////////////////////////////////////////////////////////////
+/// Summary of the values of the cells in one bin of pseudotime
+#[derive(Debug, Clone, PartialEq)]
+pub struct PseudotimeBin {
+    pub mean: f32,
+    pub sd: f32,
+    pub num_cells: usize,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Mean and standard deviation of a value, e.g. expression of a gene, along a trajectory. Cells within
+/// max_distance of the path are placed at the closest point on it, and binned by arc length into
+/// num_bins bins of equal length. Bins without cells are None
+pub fn expression_along_path(reduction: &ReductionViewData, path: &TrajectoryPath, values: &Vec<f32>, num_bins: usize, max_distance: f32) -> Vec<Option<PseudotimeBin>> {
+    let length = path.length();
+    let mut sums = vec![(0.0f64, 0.0f64, 0usize); num_bins];
+    if num_bins == 0 {
+        return Vec::new();
+    }
+    for i in 0..reduction.num_point.min(values.len()) {
+        if !values[i].is_finite() {
+            continue;
+        }
+        let Some((t, distance)) = path.project(reduction.data[i*2+0], reduction.data[i*2+1]) else {
+            break;
+        };
+        if distance > max_distance {
+            continue;
+        }
+        let bin = if length > 0.0 { ((t/length*num_bins as f32) as usize).min(num_bins - 1) } else { 0 };
+        let v = values[i] as f64;
+        sums[bin].0 += v;
+        sums[bin].1 += v*v;
+        sums[bin].2 += 1;
+    }
+
+    sums.iter().map(|(sum, sum2, n)| {
+        if *n == 0 {
+            return None;
+        }
+        let mean = sum / *n as f64;
+        let var = (sum2 / *n as f64 - mean*mean).max(0.0);
+        Some(PseudotimeBin {
+            mean: mean as f32,
+            sd: var.sqrt() as f32,
+            num_cells: *n,
+        })
+    }).collect()
+}
@@ -3,7 +3,10 @@ precision mediump float;
 // consider adapting https://github.com/chanzuckerberg/cellxgene/blob/main/client/src/components/scatterplot/drawPointsRegl.js
 
 varying lowp vec3 color;
+varying lowp float alpha;
+
+uniform float u_alpha;
 
 void main() {
-    gl_FragColor = vec4(color, 1);
+    gl_FragColor = vec4(color, u_alpha*alpha);
 }
\ No newline at end of file
@@ -3,9 +3,12 @@ precision mediump float;
 
 attribute vec2 a_position;
 attribute vec3 a_color;
+attribute float a_idx;       // index of the cell; -1 for clusters and groups
+attribute float a_fade;      // how much a crowded cell is faded out; 0 if the attribute is not enabled
 
 
 varying highp vec3 color;
+varying lowp float alpha;
 ///// attribute vec3 color;
 
 
@@ -18,12 +21,25 @@ uniform float u_camera_zoom_y;
 uniform float u_display_w;
 uniform float u_display_h;
 
+uniform float u_point_size;
+uniform float u_zoom_scale;   // 1 when all points fit in view; larger when zoomed in
+
+uniform vec2 u_pos_scale;    // positions may be quantized to [-1,1]; this maps them back to world coordinates
+uniform vec2 u_pos_offset;
+
+uniform int u_hover_idx;     // cell under the mouse, or -1
+
+attribute float a_born_at;   // time a new cell arrived, in ms since the same start as u_time
+attribute vec2 a_start_pos;  // camera position a new cell flies in from
+uniform float u_time;
+uniform float u_arrival_ms;  // duration of the arrival of new cells; 0 if none are arriving
+
 
 void main() {
 
     //Transform from world coordinates to [-1,1] camera coordinates
     vec2 a_cam_pos = vec2(u_camera_x, u_camera_y);
-    vec2 a_position2 = vec2(a_position.x, a_position.y);
+    vec2 a_position2 = a_position * u_pos_scale + u_pos_offset;
     vec2 a_position3 = a_position2 - a_cam_pos;
 
     vec2 u_camera_zoom = vec2(u_camera_zoom_x, u_camera_zoom_y);
@@ -31,12 +47,26 @@ void main() {
 
     gl_Position = vec4(scaled.x, -scaled.y, 0.0, 1.0);   // Invert camera y to match 
 
-    //Set size of points
-    gl_PointSize = 5.0;
+    //New cells spring from the edge of the view to their place, overshooting a little before settling
+    if (u_arrival_ms > 0.0) {
+        float t = clamp((u_time - a_born_at) / u_arrival_ms, 0.0, 1.0);
+        float s = 1.0 - (1.0 - t) * exp(-4.0 * t) * cos(3.0 * 3.14159265 * t);
+        gl_Position.xy = mix(a_start_pos, gl_Position.xy, s);
+    }
+
+    //Set size of points. Smaller when zoomed in, so that dense regions can be told apart
+    gl_PointSize = clamp(u_point_size / u_zoom_scale, 1.0, 20.0);
 
     //Set color based on lookup. 99 colors
 //    color = vec3(0.0, 0.0, 0.0);
     color = a_color;
+    alpha = 1.0 - clamp(a_fade, 0.0, 1.0);
+
+    //Make the hovered cell stand out
+    if (u_hover_idx >= 0 && a_idx >= 0.0 && int(a_idx + 0.5) == u_hover_idx) {
+        color = min(color * 1.5, vec3(1.0));
+        gl_PointSize += 4.0;
+    }
 
 }
 
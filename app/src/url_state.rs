@@ -0,0 +1,162 @@
+use crate::appstate::PerCellDataSource;
+use crate::camera::Camera2D;
+use crate::component_reduction_main::{CurrentTool, ReductionColoring};
+use crate::state_snapshot::{AppStateSnapshot, SNAPSHOT_VERSION};
+
+
+////////////////////////////////////////////////////////////
+/// Larger selections are left out of the URL, to keep it a manageable length
+pub const MAX_URL_SELECTION: usize = 10_000;
+
+////////////////////////////////////////////////////////////
+/// Alphabet of URL-safe base64, which needs no escaping in a query string
+const BASE64_URL_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+
+////////////////////////////////////////////////////////////
+/// Write the parts of a snapshot that are useful to share as a URL query, without the leading "?":
+/// the reduction, the column colored by, the tool, the camera and the selection.
+/// Also returns false if the selection was left out, being larger than MAX_URL_SELECTION
+pub fn snapshot_to_query(snapshot: &AppStateSnapshot) -> (String, bool) {
+    let mut params: Vec<String> = Vec::new();
+    if let Some(reduction) = &snapshot.current_reduction {
+        params.push(format!("reduction={}", String::from(js_sys::encode_uri_component(reduction))));
+    }
+    if let ReductionColoring::ByMeta(column) = &snapshot.color_umap_by {
+        let json = serde_json::to_string(column).expect("column can always be serialized");
+        params.push(format!("color={}", String::from(js_sys::encode_uri_component(&json))));
+    }
+    if let Some(tool) = &snapshot.tool {
+        let name = match tool {
+            CurrentTool::Zoom => "zoom",
+            CurrentTool::ZoomAll => "zoom",
+            CurrentTool::Select => "select",
+            CurrentTool::Lasso => "lasso",
+            CurrentTool::Explore => "explore",
+        };
+        params.push(format!("tool={}", name));
+    }
+    if let Some(camera) = &snapshot.camera {
+        params.push(format!("cam={},{},{},{},{}", camera.x, camera.y, camera.zoom_x, camera.zoom_y, camera.fit_zoom_x));
+    }
+
+    let selection_fits = snapshot.selection.len() <= MAX_URL_SELECTION;
+    if selection_fits && !snapshot.selection.is_empty() {
+        params.push(format!("sel={}", encode_selection(&snapshot.selection)));
+    }
+    (params.join("&"), selection_fits)
+}
+
+
+////////////////////////////////////////////////////////////
+/// Read a snapshot from a URL query, with or without the leading "?". Parameters that cannot be
+/// read are skipped. None if there are no parameters of a snapshot
+pub fn parse_query(query: &str) -> Option<AppStateSnapshot> {
+    let mut snapshot = AppStateSnapshot {
+        version: SNAPSHOT_VERSION,
+        current_reduction: None,
+        color_umap_by: ReductionColoring::None,
+        custom_colors: Vec::new(),
+        camera: None,
+        selection: Vec::new(),
+        cell_annotations: Default::default(),
+        tool: None,
+    };
+    let mut found = false;
+    for param in query.trim_start_matches('?').split('&') {
+        let Some((key, value)) = param.split_once('=') else {
+            continue;
+        };
+        let decode = || js_sys::decode_uri_component(value).ok().map(String::from);
+        match key {
+            "reduction" => {
+                snapshot.current_reduction = decode();
+            },
+            "color" => {
+                if let Some(column) = decode().and_then(|json| serde_json::from_str::<PerCellDataSource>(&json).ok()) {
+                    snapshot.color_umap_by = ReductionColoring::ByMeta(column);
+                }
+            },
+            "tool" => {
+                snapshot.tool = match value {
+                    "zoom" => Some(CurrentTool::Zoom),
+                    "select" => Some(CurrentTool::Select),
+                    "lasso" => Some(CurrentTool::Lasso),
+                    "explore" => Some(CurrentTool::Explore),
+                    _ => None
+                };
+            },
+            "cam" => {
+                let v: Vec<f32> = value.split(',').filter_map(|s| s.parse().ok()).collect();
+                if v.len() == 5 && v.iter().all(|x| x.is_finite()) {
+                    let mut camera = Camera2D::new();
+                    (camera.x, camera.y, camera.zoom_x, camera.zoom_y, camera.fit_zoom_x) = (v[0], v[1], v[2], v[3], v[4]);
+                    snapshot.camera = Some(camera);
+                }
+            },
+            "sel" => {
+                snapshot.selection = decode_selection(value).unwrap_or_default();
+            },
+            _ => continue
+        }
+        found = true;
+    }
+    if found { Some(snapshot) } else { None }
+}
+
+
+////////////////////////////////////////////////////////////
+/// Encode cell indices as a bitset in URL-safe base64. Bytes before the first selected cell are
+/// skipped, and their number written first: "<bytes skipped>~<base64>"
+fn encode_selection(cells: &Vec<usize>) -> String {
+    let first_byte = cells.iter().min().map(|c| c/8).unwrap_or(0);
+    let last_byte = cells.iter().max().map(|c| c/8).unwrap_or(0);
+    let mut bits = vec![0u8; last_byte + 1 - first_byte];
+    for c in cells {
+        bits[c/8 - first_byte] |= 1 << (c % 8);
+    }
+
+    let mut out = String::with_capacity(bits.len()*4/3 + 8);
+    for chunk in bits.chunks(3) {
+        let n = (chunk[0] as u32) << 16 | (*chunk.get(1).unwrap_or(&0) as u32) << 8 | *chunk.get(2).unwrap_or(&0) as u32;
+        for k in 0..=chunk.len() {
+            out.push(BASE64_URL_CHARS[(n >> (18 - 6*k) & 63) as usize] as char);
+        }
+    }
+    format!("{}~{}", first_byte, out)
+}
+
+
+////////////////////////////////////////////////////////////
+/// Decode cell indices written by encode_selection. None if the text is not valid, or if it holds
+/// more than MAX_URL_SELECTION cells
+fn decode_selection(text: &str) -> Option<Vec<usize>> {
+    let (first_byte, encoded) = text.split_once('~')?;
+    let first_byte: usize = first_byte.parse().ok()?;
+
+    let values = encoded.bytes().map(|b| BASE64_URL_CHARS.iter().position(|c| *c == b).map(|v| v as u32)).collect::<Option<Vec<u32>>>()?;
+    let mut bits: Vec<u8> = Vec::with_capacity(values.len()*3/4);
+    for chunk in values.chunks(4) {
+        if chunk.len() < 2 {
+            return None;
+        }
+        let n = chunk.iter().enumerate().fold(0u32, |n, (k, v)| n | v << (18 - 6*k));
+        for k in 0..chunk.len() - 1 {
+            bits.push((n >> (16 - 8*k)) as u8);
+        }
+    }
+
+    let mut cells = Vec::new();
+    for (i, byte) in bits.iter().enumerate() {
+        for bit in 0..8 {
+            if byte & (1 << bit) != 0 {
+                if cells.len() == MAX_URL_SELECTION {
+                    return None;
+                }
+                let cell = first_byte.checked_add(i)?.checked_mul(8)?.checked_add(bit)?;
+                cells.push(cell);
+            }
+        }
+    }
+    Some(cells)
+}
@@ -0,0 +1,16 @@
+precision mediump float;
+
+uniform float u_alpha;
+uniform float u_hollow;   // 1.0 to draw points as hollow circles
+uniform vec3 u_color;     // black unless set
+
+void main() {
+    if (u_hollow > 0.5) {
+        //Keep only a ring of the point square
+        float r = length(gl_PointCoord - vec2(0.5, 0.5));
+        if (r > 0.5 || r < 0.3) {
+            discard;
+        }
+    }
+    gl_FragColor = vec4(u_color, u_alpha);
+}
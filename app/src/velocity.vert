@@ -0,0 +1,25 @@
+precision mediump float;
+
+attribute vec2 a_position;
+
+uniform float u_camera_x;
+uniform float u_camera_y;
+
+uniform float u_camera_zoom_x;
+uniform float u_camera_zoom_y;
+
+uniform float u_point_size;
+
+
+void main() {
+
+    //Transform from world coordinates to [-1,1] camera coordinates. Same as umap.vert
+    vec2 a_cam_pos = vec2(u_camera_x, u_camera_y);
+    vec2 u_camera_zoom = vec2(u_camera_zoom_x, u_camera_zoom_y);
+    vec2 scaled = (a_position - a_cam_pos) * u_camera_zoom;
+
+    gl_Position = vec4(scaled.x, -scaled.y, 0.0, 1.0);
+
+    //Only used when drawing points
+    gl_PointSize = u_point_size;
+}
@@ -0,0 +1,55 @@
+////////////////////////////////////////////////////////////
+/// Number of values each violin is evaluated at, spanning the range of the column
+pub const VIOLIN_NUM_POINTS: usize = 40;
+
+
+////////////////////////////////////////////////////////////
+/// Kernel density estimate with a Gaussian kernel, evaluated at the given points.
+/// The bandwidth is chosen using Silverman's rule of thumb. Empty input gives all zeros
+pub fn gaussian_kde(values: &Vec<f32>, points: &Vec<f32>) -> Vec<f32> {
+    let n = values.len();
+    if n == 0 {
+        return vec![0.0; points.len()];
+    }
+
+    let mean = values.iter().map(|v| *v as f64).sum::<f64>() / n as f64;
+    let var = values.iter().map(|v| (*v as f64 - mean).powi(2)).sum::<f64>() / n as f64;
+
+    //Constant values would give a zero bandwidth; use a narrow kernel instead
+    let span = points.last().zip(points.first()).map(|(hi, lo)| (hi - lo) as f64).unwrap_or(1.0);
+    let bandwidth = (1.06*var.sqrt()*(n as f64).powf(-0.2)).max(span*0.01).max(1e-6);
+
+    let norm = 1.0/(n as f64*bandwidth*(2.0*std::f64::consts::PI).sqrt());
+    points.iter().map(|x| {
+        let sum: f64 = values.iter().map(|v| {
+            let u = (*x as f64 - *v as f64)/bandwidth;
+            (-0.5*u*u).exp()
+        }).sum();
+        (sum*norm) as f32
+    }).collect()
+}
+
+
+////////////////////////////////////////////////////////////
+/// Density of a numeric column within each category, evaluated at VIOLIN_NUM_POINTS values
+/// spanning the range of the column over all cells. Categories without cells give all zeros
+pub fn category_violins(categories: &Vec<u32>, num_categories: usize, values: &Vec<f32>) -> Vec<Vec<f32>> {
+    let mut per_category: Vec<Vec<f32>> = vec![Vec::new(); num_categories];
+    for (category, v) in categories.iter().zip(values.iter()) {
+        if let Some(list) = per_category.get_mut(*category as usize) {
+            if v.is_finite() {
+                list.push(*v);
+            }
+        }
+    }
+
+    let (min, max) = values.iter().filter(|v| v.is_finite()).fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), v| (lo.min(*v), hi.max(*v)));
+    if min > max {
+        return vec![vec![0.0; VIOLIN_NUM_POINTS]; num_categories];
+    }
+    let points: Vec<f32> = (0..VIOLIN_NUM_POINTS)
+        .map(|k| min + (max - min)*k as f32/(VIOLIN_NUM_POINTS - 1) as f32)
+        .collect();
+
+    per_category.iter().map(|list| gaussian_kde(list, &points)).collect()
+}
@@ -1,20 +1,54 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{collections::{BTreeMap, HashMap}, path::PathBuf};
 use hdf5::File;
 //use ndarray::{arr2, s};
 
 use my_web_app::countfile_struct::CountFileMat;
 use my_web_app::countfile_struct::CountFileMetaColumnDesc;
 use my_web_app::countfile_struct::CountFileRed;
+use my_web_app::CellIdsResponse;
+use my_web_app::CellTopGenesResponse;
 use my_web_app::CountFileMetaColumnData;
 use my_web_app::DatasetDescResponse;
+use my_web_app::FindMarkersResponse;
+use my_web_app::GrnResponse;
+use my_web_app::MarkerGene;
+use my_web_app::markers::{welch_t_test, FeatureSums};
 use my_web_app::MetadataColumnResponse;
+use my_web_app::MetadataColumnSchema;
+use my_web_app::MetadataSchemaResponse;
+use my_web_app::SampleAnnotation;
+use my_web_app::SampleAnnotationResponse;
+use my_web_app::SearchCellsResponse;
+use my_web_app::ReductionDelta;
 use my_web_app::ReductionResponse;
+use my_web_app::local_umap::{fit_umap_curve, knn_graph, optimize_layout_with_curve, DEFAULT_MIN_DIST, DEFAULT_N_NEIGHBORS};
 
 use ndarray::Axis;
 
 use anyhow::Context;
 
 
+////////////////////////////////////////////////////////////
+/// Largest number of cells of a reduction that can be recomputed. The neighbour search is brute force
+pub const MAX_RECOMPUTE_CELLS: usize = 5000;
+
+////////////////////////////////////////////////////////////
+/// Largest number of dimensions of a PCA used to find neighbours when recomputing a reduction
+const MAX_RECOMPUTE_DIMS: usize = 50;
+
+////////////////////////////////////////////////////////////
+/// Number of epochs when recomputing a reduction
+const RECOMPUTE_EPOCHS: u32 = 200;
+
+////////////////////////////////////////////////////////////
+/// Number of computed versions kept per reduction, to send deltas from. The version in the file is always kept
+const MAX_STORED_REDUCTION_VERSIONS: usize = 8;
+
+////////////////////////////////////////////////////////////
+/// Number of entries of a count matrix read at a time when extracting the counts of a feature
+const COLUMN_READ_CHUNK: usize = 1 << 20;
+
+
 ////////////////////////////////////////////////////////////
 /// Reader of an HDF5 file, with a format similar to anndata
 pub struct CountFile {
@@ -22,6 +56,7 @@ pub struct CountFile {
     pub matrices: HashMap<String, CountFileMat>,
     pub reductions: HashMap<String, CountFileRed>,    
     pub meta: HashMap<String, CountFileMetaColumnDesc>,
+    pub reduction_history: HashMap<String, BTreeMap<u32, ReductionResponse>>,  // Computed versions, not in the file
 }
 impl CountFile {
 
@@ -30,26 +65,8 @@ impl CountFile {
     /// Retrieve all feature counts for a given cell
     pub fn get_counts_for_cell(&self, count_name: &String, row: u32) -> anyhow::Result<MetadataColumnResponse> {
 
-        let group_counts = self.file.group("/counts")?; 
-        let group_cnt = group_counts.group(&count_name)?;
-
         let cnt = self.matrices.get(count_name.into()).context("err0")?;//.ok_or("Could not get matrix")?; // .expect("could not get matrix");  // D
-
-        let row_start = *cnt.list_indptr.get(row as usize).context("err1")? as usize;
-        let row_end = *cnt.list_indptr.get(1 + row as usize).context("err2")? as usize;
-
-        let df_data = group_cnt.dataset("data")?;
-        let df_indices = group_cnt.dataset("indices")?;
-        
-        //Get column indices
-        let ret_indices = df_indices.read_slice_1d::<u32, _>(
-            row_start..row_end
-        )?.iter().map(|x| *x).collect::<Vec<_>>();        
-
-        //Get values at given columns
-        let ret_data = df_data.read_slice_1d::<f32, _>(
-            row_start..row_end
-        )?.iter().map(|x| *x).collect::<Vec<_>>();
+        let (ret_indices, ret_data) = read_sparse_row(&self.file, count_name, cnt, row)?;
 
         let v = CountFileMetaColumnData::SparseNumeric(
             ret_indices,
@@ -63,15 +80,105 @@ impl CountFile {
 
 
 
+    ////////////////////////////////////////////////////////////
+    /// Get the features with the highest counts in a given cell
+    pub fn get_top_features_for_cell(&self, count_name: &String, row: u32, num_genes: usize) -> anyhow::Result<CellTopGenesResponse> {
+
+        let cnt = self.matrices.get(count_name.into()).context("err0")?;
+
+        let counts = self.get_counts_for_cell(count_name, row)?;
+        let mut list_counts = if let CountFileMetaColumnData::SparseNumeric(indices, data) = counts.data {
+            indices.into_iter().zip(data.into_iter()).collect::<Vec<_>>()
+        } else {
+            anyhow::bail!("Unexpected format of counts");
+        };
+
+        //Highest counts first
+        list_counts.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+        let mut genes = Vec::new();
+        for (feature_index, value) in list_counts.into_iter().take(num_genes) {
+            let feature_name = cnt.list_feature_names.get(feature_index as usize).context("feature index out of range")?;
+            genes.push((feature_name.clone(), value));
+        }
+
+        Ok(CellTopGenesResponse {
+            genes
+        })
+    }
+
+
+
+    ////////////////////////////////////////////////////////////
+    /// Get the features with the highest mean count over a group of cells, highest first
+    pub fn get_top_features_for_cells(&self, count_name: &String, cells: &Vec<usize>, num_genes: usize) -> anyhow::Result<CellTopGenesResponse> {
+        if cells.is_empty() {
+            anyhow::bail!("No cells given");
+        }
+        let cnt = self.matrices.get(count_name.into()).context("err0")?;
+
+        //Sum the sparse rows of the cells
+        let mut sums = vec![0.0f64; cnt.list_feature_names.len()];
+        for row in cells.iter() {
+            if let CountFileMetaColumnData::SparseNumeric(indices, data) = self.get_counts_for_cell(count_name, *row as u32)?.data {
+                for (i, v) in indices.iter().zip(data.iter()) {
+                    if let Some(s) = sums.get_mut(*i as usize) {
+                        *s += *v as f64;
+                    }
+                }
+            }
+        }
+
+        //Highest means first
+        let mut list_sums = sums.into_iter().enumerate().filter(|(_, s)| *s > 0.0).collect::<Vec<_>>();
+        list_sums.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+        let num_cells = cells.len() as f64;
+        let genes = list_sums.into_iter().take(num_genes).map(|(feature_index, sum)| {
+            (cnt.list_feature_names[feature_index].clone(), (sum/num_cells) as f32)
+        }).collect();
+
+        Ok(CellTopGenesResponse {
+            genes
+        })
+    }
+
+
+
+    ////////////////////////////////////////////////////////////
+    /// Get what is needed to read a count matrix without holding the count file, see find_markers
+    /// and read_sparse_column: the file, and the layout of the count matrix
+    pub fn get_matrix_input(&self, count_name: &String) -> anyhow::Result<(File, CountFileMat)> {
+        let cnt = self.matrices.get(count_name).context("err0")?;
+        Ok((self.file.clone(), cnt.clone()))
+    }
+
+
+
     ////////////////////////////////////////////////////////////
     /// Read the reduction coordinates from the file
     pub fn get_reduction(&self, reduction_name: &String) -> anyhow::Result<ReductionResponse> {
+        let red = self.reductions.get(reduction_name).context("0")?;
+        self.get_reduction_version(reduction_name, red.version)
+    }
+
+
+
+    ////////////////////////////////////////////////////////////
+    /// Get a given version of a reduction. Version 0 is the one in the file, later ones have been computed
+    pub fn get_reduction_version(&self, reduction_name: &String, version: u32) -> anyhow::Result<ReductionResponse> {
+        if version != 0 {
+            let red = self.reduction_history.get(reduction_name)
+                .and_then(|history| history.get(&version))
+                .with_context(|| format!("Version {} of reduction {} is no longer stored", version, reduction_name))?;
+            return Ok(red.clone());
+        }
 
         let group_counts = self.file.group("/reductions")?; 
         let df_thisred = group_counts.dataset(&reduction_name)?;
 
-        //Check that it is there. Need no further info right now
-        let _red = self.reductions.get(reduction_name.into()).context("0")?;
+        //Check that it is there
+        self.reductions.get(reduction_name).context("0")?;
 
         let my_array = df_thisred.read_2d::<f32>()?;
 
@@ -86,13 +193,78 @@ impl CountFile {
         //println!("got {:?}",x);
 
         let out = ReductionResponse {
-            x,y
+            x,y,
+            version: 0,
         };
         Ok(out)
     }
 
 
 
+    ////////////////////////////////////////////////////////////
+    /// Store a computed reduction as the next version of a reduction, so that clients can fetch it
+    /// as a delta. Only the latest versions are kept. Returns the new version
+    pub fn store_reduction_version(&mut self, reduction_name: &String, mut reduction: ReductionResponse) -> anyhow::Result<ReductionResponse> {
+        let red = self.reductions.get_mut(reduction_name).context("0")?;
+        red.version += 1;
+        reduction.version = red.version;
+
+        let history = self.reduction_history.entry(reduction_name.clone()).or_default();
+        history.insert(reduction.version, reduction.clone());
+        while history.len() > MAX_STORED_REDUCTION_VERSIONS {
+            history.pop_first();
+        }
+        Ok(reduction)
+    }
+
+
+
+    ////////////////////////////////////////////////////////////
+    /// Read what is needed to recompute a reduction: its current coordinates, used as a starting point,
+    /// and the features to find neighbours with. These are from a PCA if the file has one, otherwise
+    /// the coordinates of the reduction itself
+    pub fn get_recompute_input(&self, reduction_name: &String) -> anyhow::Result<(ReductionResponse, Vec<Vec<f32>>)> {
+        let red = self.get_reduction(reduction_name)?;
+        if red.x.len() > MAX_RECOMPUTE_CELLS {
+            anyhow::bail!("Reduction {} has {} cells; at most {} can be recomputed", reduction_name, red.x.len(), MAX_RECOMPUTE_CELLS);
+        }
+
+        let pca_name = self.reductions.keys().find(|name| *name != reduction_name && name.to_lowercase().contains("pca"));
+        let features = if let Some(pca_name) = pca_name {
+            let group_reds = self.file.group("/reductions")?;
+            let my_array = group_reds.dataset(pca_name)?.read_2d::<f32>()?;
+            my_array.outer_iter()
+                .take(MAX_RECOMPUTE_DIMS)
+                .map(|dim| dim.iter().cloned().collect::<Vec<f32>>())
+                .filter(|dim| dim.len() == red.x.len())
+                .collect::<Vec<_>>()
+        } else {
+            Vec::new()
+        };
+        let features = if features.is_empty() {
+            vec![red.x.clone(), red.y.clone()]
+        } else {
+            features
+        };
+        Ok((red, features))
+    }
+
+
+
+    ////////////////////////////////////////////////////////////
+    /// Get the change of reduction coordinates between two versions.
+    /// Fails if either version is no longer stored; the client then fetches the whole reduction
+    pub fn get_reduction_delta(&self, reduction_name: &String, from_version: u32, to_version: u32) -> anyhow::Result<ReductionDelta> {
+        let from = self.get_reduction_version(reduction_name, from_version)?;
+        let to = self.get_reduction_version(reduction_name, to_version)?;
+        if from.x.len() != to.x.len() {
+            anyhow::bail!("Versions {} and {} of reduction {} differ in size", from_version, to_version, reduction_name);
+        }
+        Ok(ReductionDelta::compute(&from, &to))
+    }
+
+
+
     ////////////////////////////////////////////////////////////
     /// Get all values for a metadata column
     pub fn get_metacolumn(&self, column_name: &String) -> anyhow::Result<MetadataColumnResponse> {
@@ -141,6 +313,131 @@ impl CountFile {
     }    
 
 
+    ////////////////////////////////////////////////////////////
+    /// Get which sample each cell comes from. The sample is taken from the first
+    /// categorical metadata column with a conventional name
+    pub fn get_sample_annotation(&self) -> anyhow::Result<SampleAnnotationResponse> {
+        for sample_column in SAMPLE_COLUMN_NAMES {
+            let column_name = self.meta.iter()
+                .find(|(name, desc)| name.eq_ignore_ascii_case(sample_column) && matches!(desc, CountFileMetaColumnDesc::Categorical(_)))
+                .map(|(name, _desc)| name.clone());
+            if let Some(column_name) = column_name {
+                if let CountFileMetaColumnData::Categorical(sample_id, sample_names) = self.get_metacolumn(&column_name)?.data {
+                    return Ok(SampleAnnotationResponse {
+                        annotation: Some(SampleAnnotation {
+                            sample_id,
+                            sample_names,
+                        })
+                    });
+                }
+            }
+        }
+        Ok(SampleAnnotationResponse {
+            annotation: None
+        })
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Get the regulators and targets of a feature in the gene regulatory network, the
+    /// strongest edges first. Empty if the dataset has no network
+    pub fn get_grn(&self, gene: &String, max_edges: usize) -> anyhow::Result<GrnResponse> {
+        let Ok(group_grn) = self.file.group(GRN_GROUP) else {
+            return Ok(GrnResponse {
+                regulators: Vec::new(),
+                targets: Vec::new(),
+            });
+        };
+        let list_regulator = read_hdf5_stringvec(&group_grn.dataset("regulator")?)?;
+        let list_target = read_hdf5_stringvec(&group_grn.dataset("target")?)?;
+        let list_weight = read_hdf5_f32vec(&group_grn.dataset("weight")?)?;
+
+        let mut regulators = Vec::new();
+        let mut targets = Vec::new();
+        for ((regulator, target), weight) in list_regulator.iter().zip(list_target.iter()).zip(list_weight.iter()) {
+            if target == gene {
+                regulators.push((regulator.clone(), *weight));
+            }
+            if regulator == gene {
+                targets.push((target.clone(), *weight));
+            }
+        }
+
+        //Strongest edges first, whether activating or repressing
+        for edges in [&mut regulators, &mut targets] {
+            edges.sort_by(|a, b| b.1.abs().partial_cmp(&a.1.abs()).unwrap_or(std::cmp::Ordering::Equal));
+            edges.truncate(max_edges);
+        }
+        Ok(GrnResponse {
+            regulators,
+            targets
+        })
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Get the ID (barcode) of each cell. These are stored as the index of /obs
+    pub fn get_cell_ids(&self) -> anyhow::Result<CellIdsResponse> {
+        let group_meta = self.file.group("/obs")?; 
+        let cell_ids = read_hdf5_stringvec(&group_meta.dataset(CELL_ID_DATASET)?)?;
+        Ok(CellIdsResponse {
+            cell_ids: cell_ids
+        })
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Find cells having a category containing the query in any categorical metadata column. Case insensitive
+    pub fn search_cells(&self, query: &String) -> anyhow::Result<SearchCellsResponse> {
+        let query = query.to_lowercase();
+        let mut found = Vec::new();
+        for (column_name, desc) in self.meta.iter() {
+            if let CountFileMetaColumnDesc::Categorical(categories) = desc {
+                let matching: Vec<bool> = categories.iter().map(|c| c.to_lowercase().contains(&query)).collect();
+                if !matching.contains(&true) {
+                    continue;
+                }
+                if let CountFileMetaColumnData::Categorical(codes, _) = self.get_metacolumn(column_name)?.data {
+                    for (i, code) in codes.iter().enumerate() {
+                        if matching.get(*code as usize).copied().unwrap_or(false) {
+                            found.push(i);
+                        }
+                    }
+                }
+            }
+        }
+        found.sort();
+        found.dedup();
+        Ok(SearchCellsResponse {
+            cells: found
+        })
+    }
+
+
+    ////////////////////////////////////////////////////////////
+    /// Get names and types of all metadata columns
+    pub fn get_metadata_schema(&self) -> MetadataSchemaResponse {
+        let mut columns: Vec<MetadataColumnSchema> = self.meta.iter().map(|(name, desc)| {
+            match desc {
+                CountFileMetaColumnDesc::Numeric() => MetadataColumnSchema {
+                    name: name.clone(),
+                    is_categorical: false,
+                    num_categories: 0,
+                },
+                CountFileMetaColumnDesc::Categorical(categories) => MetadataColumnSchema {
+                    name: name.clone(),
+                    is_categorical: true,
+                    num_categories: categories.len(),
+                },
+            }
+        }).collect();
+        columns.sort_by(|a, b| a.name.cmp(&b.name));
+        MetadataSchemaResponse {
+            columns: columns
+        }
+    }
+
+
 }
 
 
@@ -148,6 +445,20 @@ impl CountFile {
 
 
 
+////////////////////////////////////////////////////////////
+/// Names of metadata columns that hold the sample of each cell, in order of preference
+const SAMPLE_COLUMN_NAMES: [&str; 4] = ["sample", "sample_id", "batch", "orig.ident"];
+
+////////////////////////////////////////////////////////////
+/// Dataset in /obs with the ID of each cell, as written by anndata
+const CELL_ID_DATASET: &str = "_index";
+
+////////////////////////////////////////////////////////////
+/// Group with the edges of the gene regulatory network, if any: datasets "regulator",
+/// "target" and "weight", one entry per edge
+const GRN_GROUP: &str = "/grn";
+
+
 ////////////////////////////////////////////////////////////
 /// Read a count file and figure out the contents for later rapid response
 pub fn index_countfile(p: &PathBuf) -> anyhow::Result<CountFile> {
@@ -199,6 +510,7 @@ pub fn index_countfile(p: &PathBuf) -> anyhow::Result<CountFile> {
         let c = CountFileRed {
             num_sample: num_sample,
             num_dim: num_dim,
+            version: 0, //No version history in the file
         };
         map_reductions.insert(red_name.clone(), c);
 
@@ -211,6 +523,11 @@ pub fn index_countfile(p: &PathBuf) -> anyhow::Result<CountFile> {
     let meta_names = group_meta.member_names()?;
     println!("Indexing Metadata columns {:?}", meta_names);
     for meta_name in meta_names {
+        //The index holds cell IDs, not a metadata column
+        if meta_name == CELL_ID_DATASET {
+            continue;
+        }
+
         let ds_thismeta = group_meta.dataset(&meta_name);
 
         let desc = if let Ok(_ds_thismeta) = ds_thismeta {
@@ -234,11 +551,148 @@ pub fn index_countfile(p: &PathBuf) -> anyhow::Result<CountFile> {
         matrices: map_matrices,
         reductions: map_reductions,
         meta: map_meta,
+        reduction_history: HashMap::new(),
     })
 }
 
 
 
+////////////////////////////////////////////////////////////
+/// Read the counts of one cell, as the indices of the features with counts and their values
+pub fn read_sparse_row(file: &File, count_name: &String, cnt: &CountFileMat, row: u32) -> anyhow::Result<(Vec<u32>, Vec<f32>)> {
+    let group_counts = file.group("/counts")?; 
+    let group_cnt = group_counts.group(count_name)?;
+
+    let row_start = *cnt.list_indptr.get(row as usize).context("err1")? as usize;
+    let row_end = *cnt.list_indptr.get(1 + row as usize).context("err2")? as usize;
+
+    let df_data = group_cnt.dataset("data")?;
+    let df_indices = group_cnt.dataset("indices")?;
+    
+    //Get column indices
+    let ret_indices = df_indices.read_slice_1d::<u32, _>(
+        row_start..row_end
+    )?.iter().map(|x| *x).collect::<Vec<_>>();        
+
+    //Get values at given columns
+    let ret_data = df_data.read_slice_1d::<f32, _>(
+        row_start..row_end
+    )?.iter().map(|x| *x).collect::<Vec<_>>();
+
+    Ok((ret_indices, ret_data))
+}
+
+
+
+////////////////////////////////////////////////////////////
+/// Read the counts of one feature across all cells. Rows of the matrix are cells, so the whole
+/// matrix is scanned, a chunk at a time. Takes the input from CountFile::get_matrix_input
+pub fn read_sparse_column(file: &File, count_name: &String, cnt: &CountFileMat, col: u32) -> anyhow::Result<MetadataColumnResponse> {
+    if col as usize >= cnt.list_feature_names.len() {
+        anyhow::bail!("Feature index {} out of range", col);
+    }
+
+    let group_counts = file.group("/counts")?; 
+    let group_cnt = group_counts.group(count_name)?;
+    let df_data = group_cnt.dataset("data")?;
+    let df_indices = group_cnt.dataset("indices")?;
+
+    let num_entries = *cnt.list_indptr.last().context("err1")? as usize;
+    let mut rows = Vec::new();
+    let mut values = Vec::new();
+    let mut chunk_start = 0;
+    while chunk_start < num_entries {
+        let chunk_end = (chunk_start + COLUMN_READ_CHUNK).min(num_entries);
+        let indices = df_indices.read_slice_1d::<u32, _>(chunk_start..chunk_end)?.to_vec();
+        let data = df_data.read_slice_1d::<f32, _>(chunk_start..chunk_end)?.to_vec();
+        cnt.collect_column(chunk_start, &indices, &data, col, &mut rows, &mut values);
+        chunk_start = chunk_end;
+    }
+
+    Ok(MetadataColumnResponse {
+        data: CountFileMetaColumnData::SparseNumeric(rows, values)
+    })
+}
+
+
+
+////////////////////////////////////////////////////////////
+/// Compare the counts of each feature between two groups of cells. Features without counts in
+/// either group are left out. Sorted by p-value, then by the size of the fold change.
+/// Takes the input from CountFile::get_matrix_input, so that the count file need not be locked meanwhile
+pub fn find_markers(file: &File, count_name: &String, cnt: &CountFileMat, group_a: &Vec<usize>, group_b: &Vec<usize>) -> anyhow::Result<FindMarkersResponse> {
+    if group_a.is_empty() || group_b.is_empty() {
+        anyhow::bail!("Both groups need cells");
+    }
+    let num_features = cnt.list_feature_names.len();
+
+    //Sum the sparse rows of each group
+    let sum_group = |group: &Vec<usize>| -> anyhow::Result<Vec<FeatureSums>> {
+        let mut sums = vec![FeatureSums::default(); num_features];
+        for row in group.iter() {
+            let (indices, data) = read_sparse_row(file, count_name, cnt, *row as u32)?;
+            for (i, v) in indices.iter().zip(data.iter()) {
+                if let Some(s) = sums.get_mut(*i as usize) {
+                    s.add(*v as f64);
+                }
+            }
+        }
+        Ok(sums)
+    };
+    let sums_a = sum_group(group_a)?;
+    let sums_b = sum_group(group_b)?;
+
+    let mut markers = Vec::new();
+    for (feature_index, feature_name) in cnt.list_feature_names.iter().enumerate() {
+        let (a, b) = (&sums_a[feature_index], &sums_b[feature_index]);
+        if a.sum == 0.0 && b.sum == 0.0 {
+            continue;
+        }
+        let (log_fc, p_value) = welch_t_test(a, group_a.len(), b, group_b.len());
+        markers.push(MarkerGene {
+            gene: feature_name.clone(),
+            log_fc: log_fc as f32,
+            p_value: p_value as f32,
+        });
+    }
+    markers.sort_by(|x, y| x.p_value.partial_cmp(&y.p_value)
+        .unwrap_or(std::cmp::Ordering::Equal)
+        .then(y.log_fc.abs().partial_cmp(&x.log_fc.abs()).unwrap_or(std::cmp::Ordering::Equal)));
+
+    Ok(FindMarkersResponse {
+        markers
+    })
+}
+
+
+
+////////////////////////////////////////////////////////////
+/// Lay out cells again using UMAP, starting from their current coordinates. Parameters are n_neighbors
+/// and min_dist; for t-SNE, neighbours are instead taken as 3 x perplexity, as in Barnes-Hut t-SNE.
+/// Missing parameters take their default value
+pub fn recompute_reduction(red: &ReductionResponse, features: &[Vec<f32>], params: &HashMap<String, f32>) -> ReductionResponse {
+    let num_point = red.x.len();
+    let n_neighbors = match (params.get("n_neighbors"), params.get("perplexity")) {
+        (Some(n_neighbors), _) => *n_neighbors,
+        (None, Some(perplexity)) => 3.0*perplexity,
+        (None, None) => DEFAULT_N_NEIGHBORS,
+    };
+    let min_dist = params.get("min_dist").cloned().unwrap_or(DEFAULT_MIN_DIST);
+
+    let graph = knn_graph(features, num_point, n_neighbors.round().max(1.0) as usize);
+    let (umap_a, umap_b) = fit_umap_curve(min_dist, 1.0);
+    let mut positions: Vec<f32> = red.x.iter().zip(red.y.iter()).flat_map(|(x, y)| [*x, *y]).collect();
+    optimize_layout_with_curve(&mut positions, &graph, RECOMPUTE_EPOCHS, umap_a, umap_b);
+
+    ReductionResponse {
+        x: positions.iter().step_by(2).cloned().collect(),
+        y: positions.iter().skip(1).step_by(2).cloned().collect(),
+        version: red.version + 1,  //Final once stored, see store_reduction_version
+    }
+}
+
+
+
 ////////////////////////////////////////////////////////////
 /// Read a HDF5 string vector
 pub fn read_hdf5_stringvec(ds: &hdf5::Dataset) -> anyhow::Result<Vec<String>>{
@@ -10,14 +10,23 @@ use std::io::BufReader;
 use actix_files::Files;
 use actix_web::http::header::ContentType;
 use actix_web::web::Json;
-use actix_web::{web, web::Data, App, HttpResponse, HttpServer, post};
-use my_web_app::{FeatureCountsRequest, DatasetDescRequest, MetadataColumnRequest, ReductionRequest};
+use actix_web::{web, web::Data, App, HttpRequest, HttpResponse, HttpServer, post};
+use my_web_app::local_umap::{MAX_LOCAL_UMAP_CELLS, MAX_LOCAL_UMAP_EPOCHS};
+use my_web_app::{CellIdsRequest, CellTopGenesRequest, ClusterTopGenesRequest, FeatureColumnRequest, FeatureCountsRequest, DatasetDescRequest, FindMarkersRequest, GrnRequest, LocalUmapRequest, LocalUmapResponse, MetadataColumnRequest, MetadataSchemaRequest, RecomputeReductionRequest, ReductionDeltaRequest, ReductionRequest, SampleAnnotationRequest, SearchCellsRequest};
 use serde::Deserialize;
 use serde::Serialize;
 
 use crate::err::MyError;
 use crate::index::{index_bascet_dir, BascetDir};
 
+////////////////////////////////////////////////////////////
+/// Content type of MessagePack responses
+const MSGPACK_CONTENT_TYPE: &str = "application/msgpack";
+
+////////////////////////////////////////////////////////////
+/// Largest JSON request body. Local UMAP requests carry a neighbour graph
+const MAX_JSON_REQUEST_SIZE: usize = 32*1024*1024;
+
 ////////////////////////////////////////////////////////////
 /// Backend state
 pub struct ServerData {
@@ -53,15 +62,116 @@ async fn get_featurecounts(server_data: Data<Mutex<ServerData>>, req_body: web::
 }
 
 ////////////////////////////////////////////////////////////
-/// REST entry point: Get coordinates for a reduction
+/// REST entry point: Get the counts of a feature across all cells.
+/// The count file is only locked to get what is needed; the scan is run on a thread pool
+#[post("/get_featurecolumn")]
+async fn get_featurecolumn(server_data: Data<Mutex<ServerData>>, req_body: web::Json<FeatureColumnRequest>) -> Result<HttpResponse, MyError> { 
+
+    println!("get_featurecolumn {:?}",req_body);
+    let Json(req) = req_body;
+
+    let (file, cnt) = {
+        let server_data =server_data.lock().unwrap();
+        server_data.bdir.counts.get_matrix_input(&req.counts_name)?
+    };
+    let mat = web::block(move || crate::countfile::read_sparse_column(&file, &req.counts_name, &cnt, req.col)).await??;
+    let ser_out = serde_cbor::to_vec(&mat)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::octet_stream())
+        .body(ser_out))
+}
+
+////////////////////////////////////////////////////////////
+/// REST entry point: Get the most highly expressed features for a given cell
+#[post("/get_cell_top_genes")]
+async fn get_cell_top_genes(server_data: Data<Mutex<ServerData>>, req_body: web::Json<CellTopGenesRequest>) -> Result<HttpResponse, MyError> { 
+
+    println!("get_cell_top_genes {:?}",req_body);
+    let Json(req) = req_body;
+
+    let server_data =server_data.lock().unwrap();
+    let mat = server_data.bdir.counts.get_top_features_for_cell(&req.counts_name.into(), req.row, req.num_genes)?;
+    let ser_out = serde_cbor::to_vec(&mat)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::octet_stream())
+        .body(ser_out))
+}
+
+////////////////////////////////////////////////////////////
+/// REST entry point: Get the features with the highest mean expression over a group of cells
+#[post("/cluster_top_genes")]
+async fn get_cluster_top_genes(server_data: Data<Mutex<ServerData>>, req_body: web::Json<ClusterTopGenesRequest>) -> Result<HttpResponse, MyError> { 
+
+    let Json(req) = req_body;
+    println!("cluster_top_genes {} cells", req.cells.len());
+
+    let server_data =server_data.lock().unwrap();
+    let mat = server_data.bdir.counts.get_top_features_for_cells(&req.counts_name, &req.cells, req.num_genes)?;
+    let ser_out = serde_cbor::to_vec(&mat)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::octet_stream())
+        .body(ser_out))
+}
+
+////////////////////////////////////////////////////////////
+/// REST entry point: Get the regulators and targets of a feature in the gene regulatory network
+#[post("/grn")]
+async fn get_grn(server_data: Data<Mutex<ServerData>>, req_body: web::Json<GrnRequest>) -> Result<HttpResponse, MyError> { 
+
+    println!("grn {:?}",req_body);
+    let Json(req) = req_body;
+
+    let server_data =server_data.lock().unwrap();
+    let mat = server_data.bdir.counts.get_grn(&req.gene, req.max_edges)?;
+    let ser_out = serde_cbor::to_vec(&mat)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::octet_stream())
+        .body(ser_out))
+}
+
+////////////////////////////////////////////////////////////
+/// REST entry point: Get coordinates for a reduction.
+/// Sent as MessagePack if the client accepts it, otherwise as CBOR
 #[post("/get_reduction")]
-async fn get_reduction(server_data: Data<Mutex<ServerData>>, req_body: web::Json<ReductionRequest>) -> Result<HttpResponse, MyError> { 
+async fn get_reduction(server_data: Data<Mutex<ServerData>>, http_req: HttpRequest, req_body: web::Json<ReductionRequest>) -> Result<HttpResponse, MyError> { 
 
     println!("get_reduction {:?}",req_body);
     let Json(req) = req_body;
 
     let server_data =server_data.lock().unwrap();
     let mat = server_data.bdir.counts.get_reduction(&req.reduction_name.into())?;
+
+    let accept_msgpack = http_req.headers().get("Accept")
+        .and_then(|h| h.to_str().ok())
+        .map(|h| h.contains(MSGPACK_CONTENT_TYPE))
+        .unwrap_or(false);
+    if accept_msgpack {
+        let ser_out = rmp_serde::to_vec(&mat)?;
+        Ok(HttpResponse::Ok()
+            .content_type(MSGPACK_CONTENT_TYPE)
+            .body(ser_out))
+    } else {
+        let ser_out = serde_cbor::to_vec(&mat)?;
+        Ok(HttpResponse::Ok()
+            .content_type(ContentType::octet_stream())
+            .body(ser_out))
+    }
+}
+
+////////////////////////////////////////////////////////////
+/// REST entry point: Get the change of a reduction between two versions
+#[post("/get_reduction_delta")]
+async fn get_reduction_delta(server_data: Data<Mutex<ServerData>>, req_body: web::Json<ReductionDeltaRequest>) -> Result<HttpResponse, MyError> { 
+
+    println!("get_reduction_delta {:?}",req_body);
+    let Json(req) = req_body;
+
+    let server_data =server_data.lock().unwrap();
+    let mat = server_data.bdir.counts.get_reduction_delta(&req.reduction_name.into(), req.from_version, req.to_version)?;
     let ser_out = serde_cbor::to_vec(&mat)?;
 
     Ok(HttpResponse::Ok()
@@ -103,6 +213,156 @@ async fn get_dataset_desc(server_data: Data<Mutex<ServerData>>, req_body: web::J
 }
 
 
+////////////////////////////////////////////////////////////
+/// REST entry point: Get names and types of metadata columns, without their data
+#[post("/get_metadata_schema")]
+async fn get_metadata_schema(server_data: Data<Mutex<ServerData>>, req_body: web::Json<MetadataSchemaRequest>) -> Result<HttpResponse, MyError> { 
+
+    println!("get_metadata_schema {:?}",req_body);
+
+    let server_data =server_data.lock().unwrap();
+    let mat = server_data.bdir.counts.get_metadata_schema(); 
+    let ser_out = serde_cbor::to_vec(&mat)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::octet_stream())
+        .body(ser_out))
+}
+
+
+////////////////////////////////////////////////////////////
+/// REST entry point: Get the sample of each cell
+#[post("/get_sample_annotation")]
+async fn get_sample_annotation(server_data: Data<Mutex<ServerData>>, req_body: web::Json<SampleAnnotationRequest>) -> Result<HttpResponse, MyError> { 
+
+    println!("get_sample_annotation {:?}",req_body);
+
+    let server_data =server_data.lock().unwrap();
+    let mat = server_data.bdir.counts.get_sample_annotation()?; 
+    let ser_out = serde_cbor::to_vec(&mat)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::octet_stream())
+        .body(ser_out))
+}
+
+
+////////////////////////////////////////////////////////////
+/// REST entry point: Get the ID (barcode) of each cell
+#[post("/get_cell_ids")]
+async fn get_cell_ids(server_data: Data<Mutex<ServerData>>, req_body: web::Json<CellIdsRequest>) -> Result<HttpResponse, MyError> { 
+
+    println!("get_cell_ids {:?}",req_body);
+
+    let server_data =server_data.lock().unwrap();
+    let mat = server_data.bdir.counts.get_cell_ids()?; 
+    let ser_out = serde_cbor::to_vec(&mat)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::octet_stream())
+        .body(ser_out))
+}
+
+
+////////////////////////////////////////////////////////////
+/// REST entry point: Find cells by metadata value
+#[post("/search_cells")]
+async fn search_cells(server_data: Data<Mutex<ServerData>>, req_body: web::Json<SearchCellsRequest>) -> Result<HttpResponse, MyError> { 
+
+    println!("search_cells {:?}",req_body);
+    let Json(req) = req_body;
+
+    let server_data =server_data.lock().unwrap();
+    let mat = server_data.bdir.counts.search_cells(&req.query)?; 
+    let ser_out = serde_cbor::to_vec(&mat)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::octet_stream())
+        .body(ser_out))
+}
+
+
+////////////////////////////////////////////////////////////
+/// REST entry point: Find the features that differ the most between two groups of cells.
+/// The count file is only locked to get what is needed; the scan is run on a thread pool
+#[post("/find_markers")]
+async fn find_markers(server_data: Data<Mutex<ServerData>>, req_body: web::Json<FindMarkersRequest>) -> Result<HttpResponse, MyError> { 
+
+    let Json(req) = req_body;
+    println!("find_markers {} vs {} cells", req.group_a.len(), req.group_b.len());
+
+    let (file, cnt) = {
+        let server_data =server_data.lock().unwrap();
+        server_data.bdir.counts.get_matrix_input(&req.counts_name)?
+    };
+    let mat = web::block(move || crate::countfile::find_markers(&file, &req.counts_name, &cnt, &req.group_a, &req.group_b)).await??;
+    let ser_out = serde_cbor::to_vec(&mat)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::octet_stream())
+        .body(ser_out))
+}
+
+
+////////////////////////////////////////////////////////////
+/// REST entry point: Compute a reduction again with other hyperparameters.
+/// The data is read first, and the layout run on a thread pool, so that other requests are not held up.
+/// The result is stored as the next version of the reduction
+#[post("/recompute_reduction")]
+async fn recompute_reduction(server_data: Data<Mutex<ServerData>>, req_body: web::Json<RecomputeReductionRequest>) -> Result<HttpResponse, MyError> { 
+
+    let Json(req) = req_body;
+    println!("recompute_reduction {} {:?}", req.reduction_name, req.params);
+
+    let (red, features) = {
+        let server_data =server_data.lock().unwrap();
+        server_data.bdir.counts.get_recompute_input(&req.reduction_name)?
+    };
+    let params = req.params;
+    let mat = web::block(move || crate::countfile::recompute_reduction(&red, &features, &params)).await?;
+    let mat = {
+        let mut server_data =server_data.lock().unwrap();
+        server_data.bdir.counts.store_reduction_version(&req.reduction_name, mat)?
+    };
+    let ser_out = serde_cbor::to_vec(&mat)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::octet_stream())
+        .body(ser_out))
+}
+
+
+////////////////////////////////////////////////////////////
+/// REST entry point: Lay out a neighbour graph using UMAP, for clients without WebGPU.
+/// The layout is run on a thread pool so that other requests are not held up
+#[post("/run_local_umap")]
+async fn run_local_umap(req_body: web::Json<LocalUmapRequest>) -> Result<HttpResponse, MyError> { 
+
+    let Json(req) = req_body;
+    println!("run_local_umap {} cells, {} epochs", req.graph.num_point(), req.n_epochs);
+
+    if !req.graph.is_valid() || req.positions.len() != req.graph.num_point()*2 {
+        return Err(MyError::InternalError);
+    }
+    if req.graph.num_point() > MAX_LOCAL_UMAP_CELLS || req.n_epochs > MAX_LOCAL_UMAP_EPOCHS {
+        return Err(anyhow::anyhow!("At most {} cells and {} epochs can be laid out", MAX_LOCAL_UMAP_CELLS, MAX_LOCAL_UMAP_EPOCHS).into());
+    }
+
+    let LocalUmapRequest { mut positions, graph, n_epochs } = req;
+    let positions = web::block(move || {
+        my_web_app::local_umap::optimize_layout(&mut positions, &graph, n_epochs);
+        positions
+    }).await?;
+    let ser_out = serde_cbor::to_vec(&LocalUmapResponse {
+        positions: positions
+    })?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::octet_stream())
+        .body(ser_out))
+}
+
+
 ////////////////////////////////////////////////////////////
 /// Backend entry point
 #[actix_web::main]
@@ -128,11 +388,24 @@ async fn main() -> std::io::Result<()> {
     HttpServer::new(move || {
         App::new()
             .app_data(data.clone())
+            .app_data(web::JsonConfig::default().limit(MAX_JSON_REQUEST_SIZE))
             .wrap(actix_web::middleware::Logger::default())  //for debugging
             .service(get_featurecounts)
+            .service(get_featurecolumn)
+            .service(get_cell_top_genes)
+            .service(get_cluster_top_genes)
+            .service(get_grn)
             .service(get_reduction)
+            .service(get_reduction_delta)
             .service(get_metacolumn)
             .service(get_dataset_desc)
+            .service(get_metadata_schema)
+            .service(get_sample_annotation)
+            .service(get_cell_ids)
+            .service(search_cells)
+            .service(run_local_umap)
+            .service(find_markers)
+            .service(recompute_reduction)
             .service(Files::new("/", "./dist/").index_file("index.html"))
             //.service(get_)
             .default_service(
@@ -8,6 +8,27 @@ pub struct CountFileMat {
     pub list_feature_names: Vec<String>,  // Compact, but would a hashmap be better? or treemap ideally?
     pub list_indptr: Vec<u32>,
 }
+impl CountFileMat {
+
+    ////////////////////////////////////////////////////////////
+    /// Collect the entries of one column from a chunk of the sparse matrix, which starts at position offset
+    /// of its indices and data. Rows are cells, so a column holds the counts of one feature across cells
+    pub fn collect_column(&self, offset: usize, indices: &[u32], data: &[f32], col: u32, rows: &mut Vec<u32>, values: &mut Vec<f32>) {
+        //Row holding the first entry of the chunk. Empty rows share their start with the next row
+        let mut row = self.list_indptr.partition_point(|p| (*p as usize) <= offset).saturating_sub(1);
+        for (i, (index, value)) in indices.iter().zip(data.iter()).enumerate() {
+            if *index == col {
+                let pos = offset + i;
+                while row + 1 < self.list_indptr.len() && (self.list_indptr[row + 1] as usize) <= pos {
+                    row += 1;
+                }
+                rows.push(row as u32);
+                values.push(*value);
+            }
+        }
+    }
+
+}
 
 
 
@@ -18,6 +39,7 @@ pub struct CountFileMat {
 pub struct CountFileRed {
     pub num_sample: usize,
     pub num_dim: usize,
+    pub version: u32,
 }
 
 
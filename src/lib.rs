@@ -1,12 +1,16 @@
 use std::collections::HashMap;
 
+use half::f16;
 use serde::{Deserialize, Serialize};
 
 pub mod countfile_struct;
+pub mod local_umap;
+pub mod markers;
 
 use countfile_struct::CountFileMat;
 use countfile_struct::CountFileMetaColumnDesc;
 use countfile_struct::CountFileRed;
+use local_umap::NeighborGraph;
 
 
 
@@ -25,6 +29,56 @@ pub struct ReductionRequest {
 pub struct ReductionResponse {
     pub x: Vec<f32>,
     pub y: Vec<f32>,
+    pub version: u32,
+}
+
+
+////////////////////////////////////////////////////////////
+/// 
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ReductionDeltaRequest {
+    pub reduction_name: String,
+    pub from_version: u32,
+    pub to_version: u32,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Change of reduction coordinates between two versions. Only points
+/// that moved are included, and offsets are sent at half precision
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReductionDelta {
+    pub moved_indices: Vec<u32>,
+    pub delta_x: Vec<f16>,
+    pub delta_y: Vec<f16>,
+}
+impl ReductionDelta {
+
+    ////////////////////////////////////////////////////////////
+    /// Compute the delta needed to go from one version of a reduction to another
+    pub fn compute(from: &ReductionResponse, to: &ReductionResponse) -> ReductionDelta {
+        let mut moved_indices = Vec::new();
+        let mut delta_x = Vec::new();
+        let mut delta_y = Vec::new();
+
+        let num_point = from.x.len().min(to.x.len());
+        for i in 0..num_point {
+            let dx = to.x[i] - from.x[i];
+            let dy = to.y[i] - from.y[i];
+            if dx != 0.0 || dy != 0.0 {
+                moved_indices.push(i as u32);
+                delta_x.push(f16::from_f32(dx));
+                delta_y.push(f16::from_f32(dy));
+            }
+        }
+
+        ReductionDelta {
+            moved_indices,
+            delta_x,
+            delta_y,
+        }
+    }
+
 }
 
 
@@ -38,6 +92,40 @@ pub struct FeatureCountsRequest {
     pub row: u32,
 }
 
+////////////////////////////////////////////////////////////
+/// Request for the counts of one feature across all cells. The feature is given by its index in the count matrix
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FeatureColumnRequest {
+    pub counts_name: String,
+    pub col: u32,
+}
+
+////////////////////////////////////////////////////////////
+/// 
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CellTopGenesRequest {
+    pub counts_name: String,
+    pub row: u32,
+    pub num_genes: usize,
+}
+
+////////////////////////////////////////////////////////////
+/// Request for the features with the highest mean expression over a group of cells
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ClusterTopGenesRequest {
+    pub counts_name: String,
+    pub cells: Vec<usize>,
+    pub num_genes: usize,
+}
+
+////////////////////////////////////////////////////////////
+/// Most highly expressed features in a cell, highest first.
+/// Also sent for a group of cells, with the mean expression over the group
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct CellTopGenesResponse {
+    pub genes: Vec<(String, f32)>,
+}
+
 ////////////////////////////////////////////////////////////
 /// 
 #[derive(Debug, Deserialize, Serialize)]
@@ -81,6 +169,178 @@ pub struct MetadataColumnResponse {
 
 
 
+////////////////////////////////////////////////////////////
+/// 
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MetadataSchemaRequest {
+}
+
+
+////////////////////////////////////////////////////////////
+/// Name and type of a metadata column, without any data
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct MetadataColumnSchema {
+    pub name: String,
+    pub is_categorical: bool,
+    pub num_categories: usize,
+}
+
+
+////////////////////////////////////////////////////////////
+/// All metadata columns, sorted by name
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MetadataSchemaResponse {
+    pub columns: Vec<MetadataColumnSchema>,
+}
+
+
+
+////////////////////////////////////////////////////////////
+/// Sample (or batch) that each cell comes from
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct SampleAnnotation {
+    pub sample_id: Vec<u32>,          // for each cell, index into sample_names
+    pub sample_names: Vec<String>,
+}
+impl SampleAnnotation {
+
+    ////////////////////////////////////////////////////////////
+    /// Number of cells in each sample
+    pub fn count_cells(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.sample_names.len()];
+        for id in self.sample_id.iter() {
+            if let Some(c) = counts.get_mut(*id as usize) {
+                *c += 1;
+            }
+        }
+        counts
+    }
+
+}
+
+
+////////////////////////////////////////////////////////////
+/// 
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SampleAnnotationRequest {
+}
+
+
+////////////////////////////////////////////////////////////
+/// Sample annotation, if the dataset has one
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SampleAnnotationResponse {
+    pub annotation: Option<SampleAnnotation>,
+}
+
+
+////////////////////////////////////////////////////////////
+/// 
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CellIdsRequest {
+}
+
+
+////////////////////////////////////////////////////////////
+/// ID (barcode) of each cell, in the order of the cells in the count file
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CellIdsResponse {
+    pub cell_ids: Vec<String>,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Request for cells with a metadata value containing the query, in any categorical column. Case insensitive
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SearchCellsRequest {
+    pub query: String,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Cells found by a search
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SearchCellsResponse {
+    pub cells: Vec<usize>,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Request to compute a reduction again with other hyperparameters, e.g. n_neighbors and min_dist.
+/// Parameters not given take their default value. The response is a ReductionResponse
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RecomputeReductionRequest {
+    pub reduction_name: String,
+    pub params: HashMap<String, f32>,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Request for the regulators and targets of a feature in the gene regulatory network
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GrnRequest {
+    pub gene: String,
+    pub max_edges: usize,   // of each kind
+}
+
+
+////////////////////////////////////////////////////////////
+/// Neighbours of a feature in the gene regulatory network, with the weight of each edge,
+/// strongest first. Empty if the dataset has no network
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct GrnResponse {
+    pub regulators: Vec<(String, f32)>,
+    pub targets: Vec<(String, f32)>,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Request for the features that differ the most between two groups of cells
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FindMarkersRequest {
+    pub counts_name: String,
+    pub group_a: Vec<usize>,
+    pub group_b: Vec<usize>,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Difference of one feature between two groups of cells
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct MarkerGene {
+    pub gene: String,
+    pub log_fc: f32,    // log2 of mean in group A over mean in group B
+    pub p_value: f32,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Features expressed in either group, most significant first
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct FindMarkersResponse {
+    pub markers: Vec<MarkerGene>,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Request to lay out a neighbour graph using UMAP. Used when the browser cannot do it using WebGPU
+#[derive(Debug, Deserialize, Serialize)]
+pub struct LocalUmapRequest {
+    pub positions: Vec<f32>,  // initial x,y pairs
+    pub graph: NeighborGraph,
+    pub n_epochs: u32,
+}
+
+
+////////////////////////////////////////////////////////////
+/// Optimized layout, as x,y pairs
+#[derive(Debug, Deserialize, Serialize)]
+pub struct LocalUmapResponse {
+    pub positions: Vec<f32>,
+}
+
+
+
 
 
 
@@ -0,0 +1,218 @@
+use serde::{Deserialize, Serialize};
+
+
+////////////////////////////////////////////////////////////
+/// Curve parameters of UMAP, for min_dist=0.1 and spread=1.0
+pub const UMAP_A: f32 = 1.577;
+pub const UMAP_B: f32 = 0.895;
+
+////////////////////////////////////////////////////////////
+/// Hyperparameters used when recomputing a reduction, unless others are given
+pub const DEFAULT_N_NEIGHBORS: f32 = 15.0;
+pub const DEFAULT_MIN_DIST: f32 = 0.1;
+pub const DEFAULT_PERPLEXITY: f32 = 30.0;
+
+////////////////////////////////////////////////////////////
+/// Number of random cells each cell is pushed away from, per epoch
+pub const NUM_NEGATIVE_SAMPLES: u32 = 5;
+
+////////////////////////////////////////////////////////////
+/// Largest move of a coordinate due to one neighbour, as in UMAP
+const GRADIENT_CLIP: f32 = 4.0;
+
+////////////////////////////////////////////////////////////
+/// Largest number of cells, and of epochs, of a layout the server runs for a client
+pub const MAX_LOCAL_UMAP_CELLS: usize = 5000;
+pub const MAX_LOCAL_UMAP_EPOCHS: u32 = 1000;
+
+
+////////////////////////////////////////////////////////////
+/// Symmetric k-nearest neighbour graph, in compressed sparse row format.
+/// The neighbours of cell i are neighbors[offsets[i]..offsets[i+1]]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct NeighborGraph {
+    pub offsets: Vec<u32>,
+    pub neighbors: Vec<u32>,
+}
+impl NeighborGraph {
+
+    ////////////////////////////////////////////////////////////
+    /// Number of cells in the graph
+    pub fn num_point(&self) -> usize {
+        self.offsets.len().saturating_sub(1)
+    }
+
+    ////////////////////////////////////////////////////////////
+    /// Check that the graph is consistent, e.g. after being received from a client
+    pub fn is_valid(&self) -> bool {
+        let n = self.num_point() as u32;
+        self.offsets.windows(2).all(|w| w[0] <= w[1]) &&
+            self.offsets.last().map(|last| *last as usize == self.neighbors.len()).unwrap_or(false) &&
+            self.neighbors.iter().all(|j| *j < n)
+    }
+
+}
+
+
+////////////////////////////////////////////////////////////
+/// Build the k-nearest neighbour graph of cells. Features are given as one vector per dimension.
+/// Each dimension is scaled to unit variance first, so that no single feature dominates.
+///
+/// This is a brute force search, only meant for a few thousand cells
+pub fn knn_graph(features: &[Vec<f32>], num_point: usize, n_neighbors: usize) -> NeighborGraph {
+
+    //Scale each dimension
+    let features: Vec<Vec<f32>> = features.iter().map(|dim| {
+        let n = dim.len().max(1) as f32;
+        let mean = dim.iter().sum::<f32>() / n;
+        let var = dim.iter().map(|v| (v - mean)*(v - mean)).sum::<f32>() / n;
+        let sd = if var > 0.0 { var.sqrt() } else { 1.0 };
+        dim.iter().map(|v| (v - mean)/sd).collect()
+    }).collect();
+
+    //Find nearest neighbours of each cell
+    let k = n_neighbors.min(num_point.saturating_sub(1));
+    let mut list_adj: Vec<Vec<u32>> = vec![Vec::new(); num_point];
+    for i in 0..num_point {
+        let mut list_dist: Vec<(f32, usize)> = (0..num_point)
+            .filter(|j| *j != i)
+            .map(|j| {
+                let d2: f32 = features.iter().map(|dim| (dim[i] - dim[j])*(dim[i] - dim[j])).sum();
+                (d2, j)
+            })
+            .collect();
+        list_dist.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+        for (_d2, j) in list_dist.iter().take(k) {
+            list_adj[i].push(*j as u32);
+            list_adj[*j].push(i as u32);
+        }
+    }
+
+    //Flatten, removing duplicate edges
+    let mut offsets = Vec::with_capacity(num_point + 1);
+    let mut neighbors = Vec::new();
+    offsets.push(0);
+    for mut adj in list_adj {
+        adj.sort();
+        adj.dedup();
+        neighbors.extend(adj);
+        offsets.push(neighbors.len() as u32);
+    }
+
+    NeighborGraph {
+        offsets,
+        neighbors,
+    }
+}
+
+
+////////////////////////////////////////////////////////////
+/// Hash used to pick negative samples. Must be the same as in the WebGPU shader, local_umap.js
+pub fn hash_u32(x: u32) -> u32 {
+    let state = x.wrapping_mul(747796405).wrapping_add(2891336453);
+    let word = ((state >> ((state >> 28) + 4)) ^ state).wrapping_mul(277803737);
+    (word >> 22) ^ word
+}
+
+
+////////////////////////////////////////////////////////////
+/// Index of a negative sample of cell i. Must be the same as in the WebGPU shader, local_umap.js
+pub fn negative_sample(i: u32, epoch: u32, s: u32, num_point: u32) -> u32 {
+    hash_u32(i.wrapping_mul(2654435769) ^ hash_u32(epoch.wrapping_mul(NUM_NEGATIVE_SAMPLES).wrapping_add(s))) % num_point
+}
+
+
+////////////////////////////////////////////////////////////
+/// Optimize a 2D layout of the graph using the UMAP cost function. Positions are x,y pairs.
+///
+/// All cells are moved at the same time in each epoch, using the positions of the previous epoch.
+/// This differs from the reference implementation but allows the same steps to run on a GPU
+pub fn optimize_layout(positions: &mut [f32], graph: &NeighborGraph, n_epochs: u32) {
+    optimize_layout_with_curve(positions, graph, n_epochs, UMAP_A, UMAP_B);
+}
+
+
+////////////////////////////////////////////////////////////
+/// Fit the curve 1/(1 + a*d^(2b)) used by UMAP to how close cells should be placed: 1 up to min_dist,
+/// then decaying exponentially with the given spread. Returns (a, b).
+///
+/// The reference implementation uses least squares; here a coarse grid search is enough
+pub fn fit_umap_curve(min_dist: f32, spread: f32) -> (f32, f32) {
+    let num_sample = 300;
+    let list_d: Vec<f32> = (1..=num_sample).map(|i| 3.0*spread*(i as f32)/(num_sample as f32)).collect();
+    let list_target: Vec<f32> = list_d.iter().map(|d| if *d < min_dist { 1.0 } else { (-(d - min_dist)/spread).exp() }).collect();
+
+    let mut best = (UMAP_A, UMAP_B, f32::INFINITY);
+    for bi in 0..=170 {
+        let b = 0.3 + 0.01*(bi as f32);
+        for ai in 0..=200 {
+            let a = (10.0f32).powf(-1.0 + 3.0*(ai as f32)/200.0);
+            let err: f32 = list_d.iter().zip(list_target.iter())
+                .map(|(d, t)| {
+                    let v = 1.0/(1.0 + a*d.powf(2.0*b));
+                    (v - t)*(v - t)
+                })
+                .sum();
+            if err < best.2 {
+                best = (a, b, err);
+            }
+        }
+    }
+    (best.0, best.1)
+}
+
+
+////////////////////////////////////////////////////////////
+/// Optimize a 2D layout of the graph using the UMAP cost function, with given curve parameters
+/// as from fit_umap_curve. See optimize_layout
+pub fn optimize_layout_with_curve(positions: &mut [f32], graph: &NeighborGraph, n_epochs: u32, umap_a: f32, umap_b: f32) {
+    let num_point = graph.num_point().min(positions.len()/2);
+    if num_point < 2 {
+        return;
+    }
+
+    let clip = |v: f32| v.clamp(-GRADIENT_CLIP, GRADIENT_CLIP);
+    let mut current_positions = positions.to_vec();
+    let mut next_positions = positions.to_vec();
+
+    for epoch in 0..n_epochs {
+        let alpha = 1.0 - (epoch as f32)/(n_epochs as f32);
+
+        for i in 0..num_point {
+            let (xi, yi) = (current_positions[i*2], current_positions[i*2+1]);
+            let (mut fx, mut fy) = (0.0, 0.0);
+
+            //Pull towards neighbours
+            for j in &graph.neighbors[graph.offsets[i] as usize..graph.offsets[i+1] as usize] {
+                let j = *j as usize;
+                let (dx, dy) = (xi - current_positions[j*2], yi - current_positions[j*2+1]);
+                let d2 = dx*dx + dy*dy;
+                if d2 > 0.0 {
+                    let coeff = -2.0*umap_a*umap_b*d2.powf(umap_b - 1.0) / (1.0 + umap_a*d2.powf(umap_b));
+                    fx += clip(coeff*dx);
+                    fy += clip(coeff*dy);
+                }
+            }
+
+            //Push away from random cells
+            for s in 0..NUM_NEGATIVE_SAMPLES {
+                let k = negative_sample(i as u32, epoch, s, num_point as u32) as usize;
+                if k == i {
+                    continue;
+                }
+                let (dx, dy) = (xi - current_positions[k*2], yi - current_positions[k*2+1]);
+                let d2 = dx*dx + dy*dy;
+                if d2 > 0.0 {
+                    let coeff = 2.0*umap_b / ((0.001 + d2)*(1.0 + umap_a*d2.powf(umap_b)));
+                    fx += clip(coeff*dx);
+                    fy += clip(coeff*dy);
+                }
+            }
+
+            next_positions[i*2] = xi + alpha*fx;
+            next_positions[i*2+1] = yi + alpha*fy;
+        }
+        std::mem::swap(&mut current_positions, &mut next_positions);
+    }
+    positions.copy_from_slice(&current_positions);
+}
@@ -0,0 +1,78 @@
+////////////////////////////////////////////////////////////
+/// Added to both means before taking the fold change, so that features barely
+/// expressed in either group do not get extreme fold changes
+pub const FOLD_CHANGE_PSEUDOCOUNT: f64 = 1.0;
+
+
+////////////////////////////////////////////////////////////
+/// Sums of the values of one feature over a group of cells. Cells without counts add zeros
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FeatureSums {
+    pub sum: f64,
+    pub sum_sq: f64,
+}
+impl FeatureSums {
+
+    ////////////////////////////////////////////////////////////
+    /// No values yet
+    pub fn new() -> FeatureSums {
+        FeatureSums {
+            sum: 0.0,
+            sum_sq: 0.0,
+        }
+    }
+
+    ////////////////////////////////////////////////////////////
+    /// Add the value of one cell
+    pub fn add(&mut self, v: f64) {
+        self.sum += v;
+        self.sum_sq += v*v;
+    }
+
+    ////////////////////////////////////////////////////////////
+    /// Mean and sample variance over n cells
+    pub fn mean_var(&self, n: usize) -> (f64, f64) {
+        let n = n as f64;
+        let mean = self.sum/n;
+        let var = if n > 1.0 { ((self.sum_sq - n*mean*mean)/(n - 1.0)).max(0.0) } else { 0.0 };
+        (mean, var)
+    }
+
+}
+
+
+////////////////////////////////////////////////////////////
+/// Fold change and p-value of Welch's t-test, comparing group A of n_a cells with group B of n_b cells.
+/// The t distribution is approximated by a normal distribution, which is fine for groups of tens of cells
+pub fn welch_t_test(a: &FeatureSums, n_a: usize, b: &FeatureSums, n_b: usize) -> (f64, f64) {
+    let (mean_a, var_a) = a.mean_var(n_a);
+    let (mean_b, var_b) = b.mean_var(n_b);
+    let log_fc = ((mean_a + FOLD_CHANGE_PSEUDOCOUNT)/(mean_b + FOLD_CHANGE_PSEUDOCOUNT)).log2();
+
+    let se = (var_a/(n_a as f64) + var_b/(n_b as f64)).sqrt();
+    let p_value = if se > 0.0 {
+        let t = (mean_a - mean_b)/se;
+        2.0*(1.0 - normal_cdf(t.abs()))
+    } else if mean_a == mean_b {
+        1.0
+    } else {
+        0.0   // constant within each group, but different between them
+    };
+    (log_fc, p_value.clamp(0.0, 1.0))
+}
+
+
+////////////////////////////////////////////////////////////
+/// Cumulative distribution function of the standard normal distribution.
+/// Uses the approximation 7.1.26 of Abramowitz and Stegun for erf, accurate to about 1e-7
+pub fn normal_cdf(x: f64) -> f64 {
+    let z = x.abs()/std::f64::consts::SQRT_2;
+    let t = 1.0/(1.0 + 0.3275911*z);
+    let poly = t*(0.254829592 + t*(-0.284496736 + t*(1.421413741 + t*(-1.453152027 + t*1.061405429))));
+    let erf = 1.0 - poly*(-z*z).exp();
+    if x >= 0.0 {
+        0.5*(1.0 + erf)
+    } else {
+        0.5*(1.0 - erf)
+    }
+}